@@ -0,0 +1,340 @@
+// Copyright (c) 2021, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{collections::BTreeMap, io};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use crate::{
+    core::{
+        data::AutoSectionData,
+        decoder::{headers_end, load_section1, read_section_header_table},
+        error::{CorruptionContext, ReadError},
+        header::{MainHeader, SectionHeader, Struct, SIZE_MAIN_HEADER, SIZE_SECTION_HEADER},
+        section::SectionEntry,
+        DEFAULT_MAX_SECTIONS
+    },
+    ContainerTag,
+    Handle
+};
+
+/// An asynchronous read-only BPX container.
+///
+/// Headers and raw section data are read using async I/O while decompression, decryption and
+/// checksum verification run on the blocking thread pool of the tokio runtime.
+///
+/// *Sections are not cached: each call to [load](AsyncContainer::load) reads and decodes the
+/// section again.*
+pub struct AsyncContainer<T>
+{
+    backend: T,
+    main_header: MainHeader,
+    sections: BTreeMap<u32, SectionEntry>,
+    //Identifies the handles created by this container
+    tag: ContainerTag
+}
+
+async fn read_fill<T: AsyncRead + Unpin>(backend: &mut T, buf: &mut [u8]) -> io::Result<usize>
+{
+    let mut len = 0;
+    while len < buf.len() {
+        let res = backend.read(&mut buf[len..]).await?;
+        if res == 0 {
+            break;
+        }
+        len += res;
+    }
+    Ok(len)
+}
+
+impl<T: AsyncRead + AsyncSeek + Unpin> AsyncContainer<T>
+{
+    /// Loads a BPX container from the given asynchronous `backend`.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend`: An [AsyncRead](tokio::io::AsyncRead) + [AsyncSeek](tokio::io::AsyncSeek) backend to use for reading the BPX container.
+    ///
+    /// returns: Result<AsyncContainer<T>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if some headers
+    /// could not be read or if the header data is corrupted.
+    pub async fn open(backend: T) -> Result<AsyncContainer<T>, ReadError>
+    {
+        AsyncContainer::open_with_max_sections(backend, DEFAULT_MAX_SECTIONS).await
+    }
+
+    /// Loads a BPX container from the given asynchronous `backend`, accepting at most
+    /// `max_sections` sections.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend`: An [AsyncRead](tokio::io::AsyncRead) + [AsyncSeek](tokio::io::AsyncSeek) backend to use for reading the BPX container.
+    /// * `max_sections`: the maximum number of sections the main header may declare.
+    ///
+    /// returns: Result<AsyncContainer<T>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if some headers
+    /// could not be read, if the header data is corrupted or if the main header declares
+    /// more than `max_sections` sections.
+    pub async fn open_with_max_sections(
+        mut backend: T,
+        max_sections: u32
+    ) -> Result<AsyncContainer<T>, ReadError>
+    {
+        let mut buffer = [0; SIZE_MAIN_HEADER];
+        read_fill(&mut backend, &mut buffer).await?;
+        let (checksum, header) = MainHeader::from_bytes(buffer)?;
+        if header.section_num > max_sections {
+            return Err(ReadError::TooManySections(header.section_num, max_sections));
+        }
+        let file_size = backend.seek(io::SeekFrom::End(0)).await?;
+        if headers_end(&header) > file_size {
+            //Checked before allocating the table so that huge section counts fail early
+            return Err(ReadError::Corrupted(CorruptionContext::SectionTable));
+        }
+        backend
+            .seek(io::SeekFrom::Start(SIZE_MAIN_HEADER as u64))
+            .await?;
+        let mut table = vec![0; header.section_num as usize * SIZE_SECTION_HEADER];
+        let len = read_fill(&mut backend, &mut table).await?;
+        let (_, sections) = read_section_header_table(
+            &mut &table[..len],
+            &header,
+            checksum,
+            max_sections,
+            Some(file_size)
+        )?;
+        Ok(AsyncContainer {
+            backend,
+            main_header: header,
+            sections,
+            tag: ContainerTag::new()
+        })
+    }
+
+    /// Reads and decodes a section.
+    ///
+    /// The raw section data is read asynchronously; decompression, decryption and checksum
+    /// verification are then performed on the blocking thread pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: Result<AutoSectionData, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if the section could not be
+    /// read or decoded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid or if this is not called from within a
+    /// tokio runtime.
+    pub async fn load(&mut self, handle: Handle) -> Result<AutoSectionData, ReadError>
+    {
+        let entry = &self.sections[&handle.0];
+        let mut header = entry.header;
+        let entry1 = entry.entry1.clone();
+        let mut raw = vec![0; header.csize as usize];
+        self.backend
+            .seek(io::SeekFrom::Start(header.pointer))
+            .await?;
+        let len = read_fill(&mut self.backend, &mut raw).await?;
+        raw.truncate(len);
+        header.pointer = 0;
+        tokio::task::spawn_blocking(move || {
+            load_section1(&mut io::Cursor::new(raw), &header, &entry1)
+        })
+        .await
+        .map_err(|e| ReadError::Io(io::Error::other(e)))?
+    }
+}
+
+impl<T> AsyncContainer<T>
+{
+    /// Returns a read-only reference to the BPX main header.
+    pub fn get_main_header(&self) -> &MainHeader
+    {
+        &self.main_header
+    }
+
+    /// Returns the header of a section.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: &SectionHeader
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    pub fn get_section_header(&self, handle: Handle) -> &SectionHeader
+    {
+        &self.sections[&handle.0].header
+    }
+
+    /// Returns the header of a section, without panicking on invalid handles.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: Result<&SectionHeader, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// [InvalidHandle](crate::core::error::ReadError::InvalidHandle) is returned if the handle
+    /// does not match the key of any section of this container or, in debug builds, if it was
+    /// created by another container.
+    pub fn try_header(&self, handle: Handle) -> Result<&SectionHeader, ReadError>
+    {
+        self.sections
+            .get(&handle.key_in(self.tag)?)
+            .map(|v| &v.header)
+            .ok_or(ReadError::InvalidHandle(handle))
+    }
+
+    /// Returns the index of a section in the file, without panicking on invalid handles.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: Result<u32, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// [InvalidHandle](crate::core::error::ReadError::InvalidHandle) is returned if the handle
+    /// does not match the key of any section of this container or, in debug builds, if it was
+    /// created by another container.
+    pub fn try_index(&self, handle: Handle) -> Result<u32, ReadError>
+    {
+        self.sections
+            .get(&handle.key_in(self.tag)?)
+            .map(|v| v.index)
+            .ok_or(ReadError::InvalidHandle(handle))
+    }
+
+    /// Searches for the first section of a given type.
+    /// Returns None if no section could be found.
+    ///
+    /// # Arguments
+    ///
+    /// * `ty`: section type byte.
+    ///
+    /// returns: Option<Handle>
+    pub fn find_section_by_type(&self, ty: u8) -> Option<Handle>
+    {
+        self.sections
+            .iter()
+            .find(|(_, entry)| entry.header.ty == ty)
+            .map(|(handle, _)| Handle(*handle, self.tag))
+    }
+
+    /// Locates a section by its index in the file.
+    /// Returns None if the section does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: the section index to search for.
+    ///
+    /// returns: Option<Handle>
+    pub fn find_section_by_index(&self, index: u32) -> Option<Handle>
+    {
+        self.sections
+            .iter()
+            .find(|(_, entry)| entry.index == index)
+            .map(|(handle, _)| Handle(*handle, self.tag))
+    }
+
+    /// Returns the number of sections in this container.
+    pub fn len(&self) -> usize
+    {
+        self.sections.len()
+    }
+
+    /// Returns true if this container has no sections.
+    pub fn is_empty(&self) -> bool
+    {
+        self.sections.is_empty()
+    }
+
+    /// Sets the maximum size in bytes of a section to keep in memory when loading; larger
+    /// sections are decoded to a temporary file instead.
+    ///
+    /// *By default, this is [DEFAULT_MEMORY_THRESHOLD](crate::core::DEFAULT_MEMORY_THRESHOLD).*
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes`: the memory threshold in bytes.
+    pub fn set_memory_threshold(&mut self, bytes: usize)
+    {
+        for entry in self.sections.values_mut() {
+            entry.entry1.memory_threshold = bytes;
+        }
+    }
+
+    /// Enables or disables checksum verification when loading sections.
+    ///
+    /// # Arguments
+    ///
+    /// * `skip`: true to disable checksum verification, false to enable it (the default).
+    pub fn set_skip_checksum(&mut self, skip: bool)
+    {
+        for entry in self.sections.values_mut() {
+            entry.entry1.skip_checksum = skip;
+        }
+    }
+
+    /// Sets the AES-256-GCM key used to decrypt encrypted sections.
+    ///
+    /// # Arguments
+    ///
+    /// * `key`: the 256 bits key.
+    #[cfg(feature = "encryption")]
+    pub fn set_encryption_key(&mut self, key: [u8; 32])
+    {
+        for entry in self.sections.values_mut() {
+            entry.entry1.key = Some(key);
+        }
+    }
+
+    /// Consumes this container and returns the underlying backend.
+    pub fn into_inner(self) -> T
+    {
+        self.backend
+    }
+}
@@ -28,17 +28,28 @@
 
 //! High-level utilities to generate low-level file headers.
 
-use crate::core::header::{
-    MainHeader,
-    SectionHeader,
-    Struct,
-    FLAG_CHECK_CRC32,
-    FLAG_CHECK_WEAK,
-    FLAG_COMPRESS_XZ,
-    FLAG_COMPRESS_ZLIB
-};
+use std::collections::BTreeMap;
 
-const COMPRESSION_THRESHOLD: u32 = 65536;
+#[cfg(feature = "encryption")]
+use crate::core::header::FLAG_ENCRYPT_AES_GCM;
+use crate::core::{
+    container::{
+        DEFAULT_COMPRESSION_THRESHOLD,
+        DEFAULT_MAX_SECTIONS,
+        UNTRUSTED_MAX_SECTION_SIZE,
+        UNTRUSTED_MAX_TOTAL_MEMORY
+    },
+    header::{
+        MainHeader,
+        SectionHeader,
+        SectionType,
+        Struct,
+        FLAG_CHECK_CRC32,
+        FLAG_CHECK_WEAK,
+        FLAG_COMPRESS_XZ,
+        FLAG_COMPRESS_ZLIB
+    }
+};
 
 /// The compression method to use for a section.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -55,6 +66,37 @@ pub enum CompressionMethod
     Zlib
 }
 
+/// The compression level to use for a section.
+///
+/// *The level only affects writing: it is not stored in the BPX file.*
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CompressionLevel
+{
+    /// Favor compression speed over compression ratio.
+    Fast,
+
+    /// A trade-off between compression speed and compression ratio.
+    Balanced,
+
+    /// Favor compression ratio over compression speed.
+    Best,
+
+    /// Use an explicit preset of the compression algorithm (0 to 9, higher values are clamped).
+    Preset(u8)
+}
+
+/// The encryption method to use for a section.
+#[cfg(feature = "encryption")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EncryptionMethod
+{
+    /// Use AES-256 in Galois/Counter Mode.
+    ///
+    /// *The key must be given to the [Container](crate::core::Container) before saving or
+    /// loading the section.*
+    Aes256Gcm
+}
+
 /// The checksum algorithm to use for a section
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Checksum
@@ -72,10 +114,31 @@ pub enum Checksum
     Crc32
 }
 
+/// The settings of a section to create, generated by a [SectionHeaderBuilder].
+///
+/// Unlike a [SectionHeader](crate::core::header::SectionHeader), the settings also carry the
+/// compression level, which is not stored in the BPX file.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SectionOptions
+{
+    /// The header of the section.
+    pub header: SectionHeader,
+
+    /// The compression level of the section, None for the default level of the compression
+    /// algorithm.
+    pub level: Option<CompressionLevel>,
+
+    /// The compression threshold of the section, None for the default threshold of the
+    /// container.
+    pub threshold: Option<u32>
+}
+
 /// Utility to easily generate a [SectionHeader](crate::core::header::SectionHeader).
 pub struct SectionHeaderBuilder
 {
-    header: SectionHeader
+    header: SectionHeader,
+    level: Option<CompressionLevel>,
+    threshold: Option<u32>
 }
 
 impl Default for SectionHeaderBuilder
@@ -92,7 +155,9 @@ impl SectionHeaderBuilder
     pub fn new() -> SectionHeaderBuilder
     {
         SectionHeaderBuilder {
-            header: SectionHeader::new()
+            header: SectionHeader::new(),
+            level: None,
+            threshold: None
         }
     }
 
@@ -149,6 +214,30 @@ impl SectionHeaderBuilder
         self
     }
 
+    /// Defines the type byte of the section from a known [SectionType](crate::core::header::SectionType).
+    ///
+    /// # Arguments
+    ///
+    /// * `ty`: the type of the section.
+    ///
+    /// returns: SectionHeaderBuilder
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::SectionHeaderBuilder;
+    /// use bpx::core::header::{SectionType, SECTION_TYPE_STRING};
+    ///
+    /// let header = SectionHeaderBuilder::new()
+    ///     .section_type(SectionType::Strings)
+    ///     .build();
+    /// assert_eq!(header.ty, SECTION_TYPE_STRING);
+    /// ```
+    pub fn section_type(&mut self, ty: SectionType) -> &mut Self
+    {
+        self.ty(ty.into())
+    }
+
     /// Defines the compression algorithm to use when compressing the section.
     ///
     /// *The default is to not perform any compression at all.*
@@ -176,7 +265,65 @@ impl SectionHeaderBuilder
             CompressionMethod::Xz => self.header.flags |= FLAG_COMPRESS_XZ,
             CompressionMethod::Zlib => self.header.flags |= FLAG_COMPRESS_ZLIB
         }
-        self.header.csize = COMPRESSION_THRESHOLD;
+        self.header.csize = DEFAULT_COMPRESSION_THRESHOLD;
+        self
+    }
+
+    /// Defines the encryption method to use when saving the section.
+    ///
+    /// *The default is to not encrypt the section. Encryption is applied after compression
+    /// and the section checksum is computed over the encrypted data.*
+    ///
+    /// # Arguments
+    ///
+    /// * `method`: the [EncryptionMethod](self::EncryptionMethod) to use for saving this section.
+    ///
+    /// returns: SectionHeaderBuilder
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{EncryptionMethod, SectionHeaderBuilder};
+    /// use bpx::core::header::FLAG_ENCRYPT_AES_GCM;
+    ///
+    /// let header = SectionHeaderBuilder::new()
+    ///     .encryption(EncryptionMethod::Aes256Gcm)
+    ///     .build();
+    /// assert_ne!(header.flags & FLAG_ENCRYPT_AES_GCM, 0);
+    /// ```
+    #[cfg(feature = "encryption")]
+    pub fn encryption(&mut self, method: EncryptionMethod) -> &mut Self
+    {
+        match method {
+            EncryptionMethod::Aes256Gcm => self.header.flags |= FLAG_ENCRYPT_AES_GCM
+        }
+        self
+    }
+
+    /// Defines the compression level to use when compressing the section.
+    ///
+    /// *The default is to use the xz extreme preset 0 and the default zlib level.*
+    ///
+    /// # Arguments
+    ///
+    /// * `level`: the [CompressionLevel](self::CompressionLevel) to use for saving this section.
+    ///
+    /// returns: SectionHeaderBuilder
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{CompressionLevel, CompressionMethod, SectionHeaderBuilder};
+    ///
+    /// let options = SectionHeaderBuilder::new()
+    ///     .compression(CompressionMethod::Xz)
+    ///     .level(CompressionLevel::Best)
+    ///     .options();
+    /// assert_eq!(options.level, Some(CompressionLevel::Best));
+    /// ```
+    pub fn level(&mut self, level: CompressionLevel) -> &mut Self
+    {
+        self.level = Some(level);
         self
     }
 
@@ -207,6 +354,7 @@ impl SectionHeaderBuilder
     pub fn threshold(&mut self, threshold: u32) -> &mut Self
     {
         self.header.csize = threshold;
+        self.threshold = Some(threshold);
         self
     }
 
@@ -267,6 +415,17 @@ impl SectionHeaderBuilder
     {
         self.header
     }
+
+    /// Returns the generated [SectionOptions], including the settings which are not part of
+    /// the [SectionHeader](crate::core::header::SectionHeader).
+    pub fn options(&self) -> SectionOptions
+    {
+        SectionOptions {
+            header: self.header,
+            level: self.level,
+            threshold: self.threshold
+        }
+    }
 }
 
 /// Utility to easily generate a [MainHeader](crate::core::header::MainHeader).
@@ -398,6 +557,342 @@ impl MainHeaderBuilder
     }
 }
 
+/// Utility to describe the new compression and checksum settings of sections when repacking
+/// a [Container](crate::core::Container).
+///
+/// Only the flags, compression threshold and compression level of the given section settings
+/// are used.
+pub struct RepackOptions
+{
+    all: Option<SectionOptions>,
+    types: BTreeMap<u8, SectionOptions>
+}
+
+impl Default for RepackOptions
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl RepackOptions
+{
+    /// Creates new repack options which keep the current settings of all sections.
+    pub fn new() -> RepackOptions
+    {
+        RepackOptions {
+            all: None,
+            types: BTreeMap::new()
+        }
+    }
+
+    /// Defines the settings to apply to all sections without settings for their type.
+    ///
+    /// # Arguments
+    ///
+    /// * `header`: the [SectionOptions] to take the settings from.
+    ///
+    /// returns: RepackOptions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{CompressionMethod, RepackOptions, SectionHeaderBuilder};
+    /// use bpx::core::header::FLAG_COMPRESS_ZLIB;
+    ///
+    /// let mut options = RepackOptions::new();
+    /// options.all(SectionHeaderBuilder::new().compression(CompressionMethod::Zlib));
+    /// assert_eq!(options.get(1).unwrap().header.flags, FLAG_COMPRESS_ZLIB);
+    /// ```
+    pub fn all<H: Into<SectionOptions>>(&mut self, header: H) -> &mut Self
+    {
+        self.all = Some(header.into());
+        self
+    }
+
+    /// Defines the settings to apply to all sections of a given type.
+    ///
+    /// # Arguments
+    ///
+    /// * `ty`: the type byte of the sections.
+    /// * `header`: the [SectionOptions] to take the settings from.
+    ///
+    /// returns: RepackOptions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{Checksum, RepackOptions, SectionHeaderBuilder};
+    /// use bpx::core::header::FLAG_CHECK_CRC32;
+    ///
+    /// let mut options = RepackOptions::new();
+    /// options.ty(1, SectionHeaderBuilder::new().checksum(Checksum::Crc32));
+    /// assert_eq!(options.get(1).unwrap().header.flags, FLAG_CHECK_CRC32);
+    /// assert!(options.get(2).is_none());
+    /// ```
+    pub fn ty<H: Into<SectionOptions>>(&mut self, ty: u8, header: H) -> &mut Self
+    {
+        self.types.insert(ty, header.into());
+        self
+    }
+
+    /// Returns the settings to apply to sections of the given type, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `ty`: the type byte of the sections.
+    ///
+    /// returns: Option<SectionOptions>
+    pub fn get(&self, ty: u8) -> Option<SectionOptions>
+    {
+        self.types.get(&ty).or(self.all.as_ref()).copied()
+    }
+}
+
+/// Utility to describe how a [Container](crate::core::Container) is opened.
+pub struct OpenOptions
+{
+    max_sections: u32,
+    reject_trailing_data: bool,
+    skip_validation: bool,
+    skip_checksum: bool,
+    verify_trailer: bool,
+    max_section_size: Option<u32>,
+    max_total_memory: Option<u64>
+}
+
+impl Default for OpenOptions
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl OpenOptions
+{
+    /// Creates new open options with the default settings.
+    pub fn new() -> OpenOptions
+    {
+        OpenOptions {
+            max_sections: DEFAULT_MAX_SECTIONS,
+            reject_trailing_data: false,
+            skip_validation: false,
+            skip_checksum: false,
+            verify_trailer: true,
+            max_section_size: None,
+            max_total_memory: None
+        }
+    }
+
+    /// Creates new open options suitable for untrusted files.
+    ///
+    /// The size of a section is limited to
+    /// [UNTRUSTED_MAX_SECTION_SIZE](crate::core::UNTRUSTED_MAX_SECTION_SIZE) and the total
+    /// size of loaded sections to
+    /// [UNTRUSTED_MAX_TOTAL_MEMORY](crate::core::UNTRUSTED_MAX_TOTAL_MEMORY).
+    pub fn untrusted() -> OpenOptions
+    {
+        OpenOptions {
+            max_section_size: Some(UNTRUSTED_MAX_SECTION_SIZE),
+            max_total_memory: Some(UNTRUSTED_MAX_TOTAL_MEMORY),
+            ..OpenOptions::new()
+        }
+    }
+
+    /// Defines the maximum number of sections the main header may declare.
+    ///
+    /// *By default, this is [DEFAULT_MAX_SECTIONS](crate::core::DEFAULT_MAX_SECTIONS).*
+    ///
+    /// # Arguments
+    ///
+    /// * `max_sections`: the maximum number of sections.
+    ///
+    /// returns: OpenOptions
+    pub fn max_sections(&mut self, max_sections: u32) -> &mut Self
+    {
+        self.max_sections = max_sections;
+        self
+    }
+
+    /// Enables or disables rejection of files with data after the end of the last section.
+    ///
+    /// Extra bytes may indicate a corrupted file or an appended payload. A whole-file
+    /// integrity trailer written by [save_with_trailer](crate::core::Container::save_with_trailer)
+    /// is not considered as trailing data.
+    ///
+    /// *By default, trailing data is ignored. Saving in place never truncates the backend: once
+    /// an in-place [save](crate::core::Container::save) shrunk the file, the bytes left over
+    /// from its previous version are trailing data. This option is therefore only meant for
+    /// files written to a new backend, for example with
+    /// [save_to](crate::core::Container::save_to).*
+    ///
+    /// # Arguments
+    ///
+    /// * `reject`: true to fail with [TrailingData](crate::core::error::ReadError::TrailingData)
+    ///   when the file has trailing data.
+    ///
+    /// returns: OpenOptions
+    pub fn reject_trailing_data(&mut self, reject: bool) -> &mut Self
+    {
+        self.reject_trailing_data = reject;
+        self
+    }
+
+    /// Enables or disables validation of the headers against the size of the file.
+    ///
+    /// When enabled, opening fails with [Corrupted](crate::core::error::ReadError::Corrupted)
+    /// if the section header table or the data of a section extends past the end of the file
+    /// or if the data of a section overlaps the headers. Recovery tools may disable
+    /// validation to access the intact sections of a damaged file.
+    ///
+    /// *By default, headers are validated.*
+    ///
+    /// # Arguments
+    ///
+    /// * `skip`: true to disable validation.
+    ///
+    /// returns: OpenOptions
+    pub fn skip_validation(&mut self, skip: bool) -> &mut Self
+    {
+        self.skip_validation = skip;
+        self
+    }
+
+    /// Enables or disables checksum verification of sections.
+    ///
+    /// This is the same as calling [set_skip_checksum](crate::core::Container::set_skip_checksum)
+    /// on the opened container, except that it also applies to sections loaded while opening,
+    /// such as memory-mapped sections.
+    ///
+    /// *By default, checksums are verified.*
+    ///
+    /// # Arguments
+    ///
+    /// * `skip`: true to disable checksum verification.
+    ///
+    /// returns: OpenOptions
+    pub fn skip_checksum(&mut self, skip: bool) -> &mut Self
+    {
+        self.skip_checksum = skip;
+        self
+    }
+
+    /// Enables or disables verification of the whole-file integrity trailer written by
+    /// [save_with_trailer](crate::core::Container::save_with_trailer).
+    ///
+    /// When enabled and the file has a trailer, the whole file is read when opening and
+    /// opening fails with [Corrupted](crate::core::error::ReadError::Corrupted) if it does not
+    /// match the trailer. Files without a trailer are not affected. The trailer is never
+    /// verified when [skip_validation](OpenOptions::skip_validation) is enabled.
+    ///
+    /// *By default, the trailer is verified.*
+    ///
+    /// # Arguments
+    ///
+    /// * `verify`: false to skip verification of the trailer.
+    ///
+    /// returns: OpenOptions
+    pub fn verify_trailer(&mut self, verify: bool) -> &mut Self
+    {
+        self.verify_trailer = verify;
+        self
+    }
+
+    /// Defines the maximum size in bytes of a section to load.
+    ///
+    /// *By default, there is no limit.*
+    ///
+    /// # Arguments
+    ///
+    /// * `limit`: the maximum size of a section, None for no limit.
+    ///
+    /// returns: OpenOptions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, OpenOptions, SectionHeaderBuilder};
+    /// use bpx::core::error::ReadError;
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new());
+    /// file.get_mut(handle).open().unwrap().write_all(&[0; 64]).unwrap();
+    /// file.save().unwrap();
+    /// let mut buf = file.into_inner();
+    /// buf.set_position(0);
+    /// let mut file = Container::open_with_options(buf, OpenOptions::new().max_section_size(Some(16))).unwrap();
+    /// let handle = file.find_section_by_index(0).unwrap();
+    /// assert!(matches!(file.get_mut(handle).load(), Err(ReadError::SectionTooLarge(64, 16))));
+    /// ```
+    pub fn max_section_size(&mut self, limit: Option<u32>) -> &mut Self
+    {
+        self.max_section_size = limit;
+        self
+    }
+
+    /// Defines the maximum total size in bytes of the sections loaded at the same time.
+    ///
+    /// *By default, there is no limit.*
+    ///
+    /// # Arguments
+    ///
+    /// * `limit`: the maximum total size of loaded sections, None for no limit.
+    ///
+    /// returns: OpenOptions
+    pub fn max_total_memory(&mut self, limit: Option<u64>) -> &mut Self
+    {
+        self.max_total_memory = limit;
+        self
+    }
+
+    /// Returns the maximum number of sections the main header may declare.
+    pub fn get_max_sections(&self) -> u32
+    {
+        self.max_sections
+    }
+
+    /// Returns true if files with trailing data are rejected.
+    pub fn get_reject_trailing_data(&self) -> bool
+    {
+        self.reject_trailing_data
+    }
+
+    /// Returns true if validation of the headers is disabled.
+    pub fn get_skip_validation(&self) -> bool
+    {
+        self.skip_validation
+    }
+
+    /// Returns true if checksum verification of sections is disabled.
+    pub fn get_skip_checksum(&self) -> bool
+    {
+        self.skip_checksum
+    }
+
+    /// Returns true if the whole-file integrity trailer is verified.
+    pub fn get_verify_trailer(&self) -> bool
+    {
+        self.verify_trailer
+    }
+
+    /// Returns the maximum size of a section to load.
+    pub fn get_max_section_size(&self) -> Option<u32>
+    {
+        self.max_section_size
+    }
+
+    /// Returns the maximum total size of the sections loaded at the same time.
+    pub fn get_max_total_memory(&self) -> Option<u64>
+    {
+        self.max_total_memory
+    }
+}
+
 impl From<&mut MainHeaderBuilder> for MainHeader
 {
     fn from(builder: &mut MainHeaderBuilder) -> Self
@@ -429,3 +924,31 @@ impl From<SectionHeaderBuilder> for SectionHeader
         builder.build()
     }
 }
+
+impl From<&mut SectionHeaderBuilder> for SectionOptions
+{
+    fn from(builder: &mut SectionHeaderBuilder) -> Self
+    {
+        builder.options()
+    }
+}
+
+impl From<SectionHeaderBuilder> for SectionOptions
+{
+    fn from(builder: SectionHeaderBuilder) -> Self
+    {
+        builder.options()
+    }
+}
+
+impl From<SectionHeader> for SectionOptions
+{
+    fn from(header: SectionHeader) -> Self
+    {
+        SectionOptions {
+            header,
+            level: None,
+            threshold: Some(header.csize)
+        }
+    }
+}
@@ -40,15 +40,17 @@ pub struct Crc32Checksum
 
 impl Crc32Checksum
 {
-    pub fn new() -> Crc32Checksum
+    fn with_rounds(rounds: usize) -> Crc32Checksum
     {
         let mut table = Vec::with_capacity(256);
         for i in 0..256 {
             let mut val = i as u32;
-            if (val & 0x1) != 0 {
-                val = (val >> 1) ^ POLYNOMIAL;
-            } else {
-                val >>= 1;
+            for _ in 0..rounds {
+                if (val & 0x1) != 0 {
+                    val = (val >> 1) ^ POLYNOMIAL;
+                } else {
+                    val >>= 1;
+                }
             }
             table.push(val);
         }
@@ -57,6 +59,18 @@ impl Crc32Checksum
             current: 0xFFFFFFFF
         }
     }
+
+    pub fn new() -> Crc32Checksum
+    {
+        Crc32Checksum::with_rounds(8)
+    }
+
+    /// Creates the CRC32 written by older revisions of this library, which built their table
+    /// with a single round instead of 8.
+    pub fn legacy() -> Crc32Checksum
+    {
+        Crc32Checksum::with_rounds(1)
+    }
 }
 
 impl Checksum for Crc32Checksum
@@ -35,10 +35,13 @@ mod zlib;
 
 pub use crc32chksum::Crc32Checksum;
 pub use weakchksum::WeakChecksum;
-pub use xz::XzCompressionMethod;
-pub use zlib::ZlibCompressionMethod;
+pub use xz::{XzCompressionMethod, XzDecoder};
+pub use zlib::{ZlibCompressionMethod, ZlibDecoder};
 
-use crate::core::error::{DeflateError, InflateError};
+use crate::core::{
+    builder::CompressionLevel,
+    error::{DeflateError, InflateError}
+};
 
 pub trait Checksum
 {
@@ -62,6 +65,7 @@ pub trait Deflater
         input: TRead,
         output: TWrite,
         inflated_size: usize,
+        level: Option<CompressionLevel>,
         chksum: &mut TChecksum
     ) -> Result<usize, DeflateError>;
 }
@@ -29,6 +29,7 @@
 use std::io::{Read, Write};
 
 use lzma_sys::{
+    lzma_action,
     lzma_code,
     lzma_easy_encoder,
     lzma_end,
@@ -44,6 +45,7 @@ use lzma_sys::{
     LZMA_MEM_ERROR,
     LZMA_OK,
     LZMA_OPTIONS_ERROR,
+    LZMA_PRESET_DEFAULT,
     LZMA_PRESET_EXTREME,
     LZMA_RUN,
     LZMA_STREAM_END,
@@ -52,6 +54,7 @@ use lzma_sys::{
 
 use crate::{
     core::{
+        builder::CompressionLevel,
         compression::{Checksum, Deflater, Inflater},
         error::{DeflateError, InflateError}
     },
@@ -62,8 +65,20 @@ const THREADS_MAX: u32 = 8;
 const ENCODER_BUF_SIZE: usize = 8192;
 const DECODER_BUF_SIZE: usize = ENCODER_BUF_SIZE * 2;
 
-fn new_encoder() -> Result<lzma_stream, DeflateError>
+fn get_preset(level: Option<CompressionLevel>) -> u32
 {
+    match level {
+        None => LZMA_PRESET_EXTREME,
+        Some(CompressionLevel::Fast) => 0,
+        Some(CompressionLevel::Balanced) => LZMA_PRESET_DEFAULT,
+        Some(CompressionLevel::Best) => 9 | LZMA_PRESET_EXTREME,
+        Some(CompressionLevel::Preset(v)) => std::cmp::min(v as u32, 9)
+    }
+}
+
+fn new_encoder(level: Option<CompressionLevel>) -> Result<lzma_stream, DeflateError>
+{
+    let preset = get_preset(level);
     unsafe {
         let mut stream: lzma_stream = std::mem::zeroed();
         let mut mt: lzma_mt = std::mem::zeroed();
@@ -71,13 +86,13 @@ fn new_encoder() -> Result<lzma_stream, DeflateError>
         mt.flags = 0;
         mt.block_size = 0;
         mt.timeout = 0;
-        mt.preset = LZMA_PRESET_EXTREME;
+        mt.preset = preset;
         mt.filters = std::ptr::null();
         mt.check = LZMA_CHECK_NONE;
         mt.threads = num_cpus::get() as u32;
         let res;
         if mt.threads == 0 || mt.threads == 1 {
-            res = lzma_easy_encoder(&mut stream, LZMA_PRESET_EXTREME, LZMA_CHECK_NONE);
+            res = lzma_easy_encoder(&mut stream, preset, LZMA_CHECK_NONE);
         } else {
             if mt.threads > THREADS_MAX {
                 mt.threads = THREADS_MAX;
@@ -225,10 +240,11 @@ impl Deflater for XzCompressionMethod
         input: TRead,
         output: TWrite,
         inflated_size: usize,
+        level: Option<CompressionLevel>,
         chksum: &mut TChecksum
     ) -> Result<usize, DeflateError>
     {
-        let mut stream = new_encoder()?;
+        let mut stream = new_encoder(level)?;
         let res = do_deflate(&mut stream, input, output, inflated_size, chksum);
         unsafe {
             lzma_end(&mut stream);
@@ -254,3 +270,86 @@ impl Inflater for XzCompressionMethod
         res
     }
 }
+
+/// A pull based xz decoder which inflates a compressed stream block by block.
+pub struct XzDecoder
+{
+    stream: lzma_stream,
+    // Boxed as the stream keeps a pointer to the input buffer.
+    inbuf: Box<[u8; ENCODER_BUF_SIZE]>,
+    action: lzma_action,
+    remaining: usize,
+    end: bool
+}
+
+impl XzDecoder
+{
+    pub fn new(deflated_size: usize) -> Result<XzDecoder, InflateError>
+    {
+        let mut stream = new_decoder()?;
+        stream.avail_in = 0;
+        Ok(XzDecoder {
+            stream,
+            inbuf: Box::new([0; ENCODER_BUF_SIZE]),
+            action: match deflated_size {
+                0 => LZMA_FINISH,
+                _ => LZMA_RUN
+            },
+            remaining: deflated_size,
+            end: false
+        })
+    }
+
+    /// Inflates some data into `out`; returns 0 once the end of the stream is reached.
+    pub fn decode<TRead: Read>(
+        &mut self,
+        mut input: TRead,
+        out: &mut [u8]
+    ) -> Result<usize, InflateError>
+    {
+        if self.end || out.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if self.stream.avail_in == 0 && self.remaining > 0 {
+                let len = input.read_fill(
+                    &mut self.inbuf[0..std::cmp::min(ENCODER_BUF_SIZE, self.remaining)]
+                )?;
+                if len == 0 {
+                    //The compressed stream is truncated
+                    return Err(InflateError::Data);
+                }
+                self.remaining -= len;
+                self.stream.avail_in = len;
+                self.stream.next_in = self.inbuf.as_ptr();
+                if self.remaining == 0 {
+                    self.action = LZMA_FINISH;
+                }
+            }
+            self.stream.avail_out = out.len();
+            self.stream.next_out = out.as_mut_ptr();
+            let res = unsafe { lzma_code(&mut self.stream, self.action) };
+            let len = out.len() - self.stream.avail_out;
+            match res {
+                LZMA_OK => (),
+                LZMA_STREAM_END => self.end = true,
+                LZMA_MEM_ERROR => return Err(InflateError::Memory),
+                LZMA_DATA_ERROR | LZMA_BUF_ERROR => return Err(InflateError::Data),
+                _ => return Err(InflateError::Unknown)
+            }
+            if len > 0 || self.end {
+                return Ok(len);
+            }
+        }
+    }
+}
+
+impl Drop for XzDecoder
+{
+    fn drop(&mut self)
+    {
+        unsafe {
+            lzma_end(&mut self.stream);
+        }
+    }
+}
@@ -36,6 +36,9 @@ use libz_sys::{
     inflateEnd,
     inflateInit_,
     z_stream,
+    Z_BEST_COMPRESSION,
+    Z_BEST_SPEED,
+    Z_BUF_ERROR,
     Z_DATA_ERROR,
     Z_DEFAULT_COMPRESSION,
     Z_FINISH,
@@ -43,12 +46,14 @@ use libz_sys::{
     Z_NEED_DICT,
     Z_NO_FLUSH,
     Z_OK,
+    Z_STREAM_END,
     Z_STREAM_ERROR,
     Z_VERSION_ERROR
 };
 
 use crate::{
     core::{
+        builder::CompressionLevel,
         compression::{Checksum, Deflater, Inflater},
         error::{DeflateError, InflateError}
     },
@@ -57,6 +62,7 @@ use crate::{
 
 const ENCODER_BUF_SIZE: usize = 8192;
 const DECODER_BUF_SIZE: usize = ENCODER_BUF_SIZE * 2;
+const ZLIB_VERSION: &[u8] = b"1.1.3\0";
 
 // Needed to bypass rust new "feature" to prevent users from using std::mem::zeroed() on UB types.
 // Because this z_stream struct is repr(C) rust must guarantee ABI compatibility with C.
@@ -68,18 +74,30 @@ unsafe fn zstream_zeroed() -> z_stream
     std::mem::transmute(arr)
 }
 
-fn new_encoder() -> Result<z_stream, DeflateError>
+// zlib keeps a pointer back to the z_stream it was initialized with, so the stream must be
+// initialized in place and never moved afterwards.
+fn get_level(level: Option<CompressionLevel>) -> std::os::raw::c_int
+{
+    match level {
+        None => Z_DEFAULT_COMPRESSION,
+        Some(CompressionLevel::Fast) => Z_BEST_SPEED,
+        Some(CompressionLevel::Balanced) => 6,
+        Some(CompressionLevel::Best) => Z_BEST_COMPRESSION,
+        Some(CompressionLevel::Preset(v)) => std::cmp::min(v as _, Z_BEST_COMPRESSION)
+    }
+}
+
+fn new_encoder(stream: &mut z_stream, level: Option<CompressionLevel>) -> Result<(), DeflateError>
 {
     unsafe {
-        let mut stream: z_stream = zstream_zeroed();
         let err = deflateInit_(
-            &mut stream as _,
-            Z_DEFAULT_COMPRESSION,
-            "1.1.3".as_ptr() as _,
+            stream as _,
+            get_level(level),
+            ZLIB_VERSION.as_ptr() as _,
             std::mem::size_of::<z_stream>() as _
         );
         if err == Z_OK {
-            return Ok(stream);
+            return Ok(());
         }
         match err {
             Z_MEM_ERROR => Err(DeflateError::Memory),
@@ -90,17 +108,16 @@ fn new_encoder() -> Result<z_stream, DeflateError>
     }
 }
 
-fn new_decoder() -> Result<z_stream, InflateError>
+fn new_decoder(stream: &mut z_stream) -> Result<(), InflateError>
 {
     unsafe {
-        let mut stream: z_stream = zstream_zeroed();
         let err = inflateInit_(
-            &mut stream as _,
-            "1.1.3".as_ptr() as _,
+            stream as _,
+            ZLIB_VERSION.as_ptr() as _,
             std::mem::size_of::<z_stream>() as _
         );
         if err == Z_OK {
-            return Ok(stream);
+            return Ok(());
         }
         match err {
             Z_MEM_ERROR => Err(InflateError::Memory),
@@ -125,12 +142,13 @@ fn do_deflate<TRead: Read, TWrite: Write, TChecksum: Checksum>(
     let mut csize: usize = 0;
 
     loop {
-        let len = input.read_fill(&mut inbuf)?;
+        let len = input
+            .read_fill(&mut inbuf[0..std::cmp::min(ENCODER_BUF_SIZE, inflated_size - count)])?;
         count += len;
         chksum.push(&inbuf[0..len]);
         stream.avail_in = len as _;
         let action = {
-            if count == inflated_size {
+            if count == inflated_size || len == 0 {
                 Z_FINISH
             } else {
                 Z_NO_FLUSH
@@ -142,7 +160,8 @@ fn do_deflate<TRead: Read, TWrite: Write, TChecksum: Checksum>(
             stream.next_out = outbuf.as_mut_ptr();
             unsafe {
                 let err = deflate(stream, action);
-                if err != Z_OK {
+                // Z_BUF_ERROR only means no progress was possible; it is not fatal.
+                if err != Z_OK && err != Z_STREAM_END && err != Z_BUF_ERROR {
                     return match err {
                         Z_MEM_ERROR => Err(DeflateError::Memory),
                         Z_STREAM_ERROR => Err(DeflateError::Unsupported("compression level")),
@@ -154,7 +173,7 @@ fn do_deflate<TRead: Read, TWrite: Write, TChecksum: Checksum>(
             let len = ENCODER_BUF_SIZE - stream.avail_out as usize;
             output.write_all(&outbuf[0..len])?;
             csize += len;
-            if stream.avail_out == 0 {
+            if stream.avail_out != 0 {
                 break;
             }
         }
@@ -177,23 +196,26 @@ fn do_inflate<TRead: Read, TWrite: Write, TChecksum: Checksum>(
     let mut outbuf: [u8; DECODER_BUF_SIZE] = [0; DECODER_BUF_SIZE];
     let mut remaining = deflated_size;
 
-    loop {
-        let len = input.read_fill(&mut inbuf)?;
-        remaining -= len;
-        if remaining == 0 && len == 0 {
-            break;
+    while remaining > 0 {
+        let len = input.read_fill(&mut inbuf[0..std::cmp::min(DECODER_BUF_SIZE, remaining)])?;
+        if len == 0 {
+            //The compressed stream is truncated
+            return Err(InflateError::Data);
         }
+        remaining -= len;
         stream.avail_in = len as _;
         stream.next_in = inbuf.as_mut_ptr();
+        let mut err;
         loop {
             stream.avail_out = DECODER_BUF_SIZE as _;
             stream.next_out = outbuf.as_mut_ptr();
             unsafe {
-                let err = inflate(stream, Z_NO_FLUSH);
+                err = inflate(stream, Z_NO_FLUSH);
                 match err {
                     Z_MEM_ERROR => return Err(InflateError::Memory),
                     Z_DATA_ERROR => return Err(InflateError::Data),
                     Z_NEED_DICT => return Err(InflateError::Data),
+                    Z_STREAM_ERROR => return Err(InflateError::Data),
                     Z_VERSION_ERROR => return Err(InflateError::Unsupported("version")),
                     _ => ()
                 }
@@ -201,10 +223,13 @@ fn do_inflate<TRead: Read, TWrite: Write, TChecksum: Checksum>(
             let len = DECODER_BUF_SIZE - stream.avail_out as usize;
             chksum.push(&outbuf[0..len]);
             output.write_all(&outbuf[0..len])?;
-            if stream.avail_out == 0 {
+            if stream.avail_out != 0 || err == Z_STREAM_END {
                 break;
             }
         }
+        if err == Z_STREAM_END {
+            break;
+        }
     }
     Ok(())
 }
@@ -217,10 +242,12 @@ impl Deflater for ZlibCompressionMethod
         input: TRead,
         output: TWrite,
         inflated_size: usize,
+        level: Option<CompressionLevel>,
         chksum: &mut TChecksum
     ) -> Result<usize, DeflateError>
     {
-        let mut encoder = new_encoder()?;
+        let mut encoder = unsafe { zstream_zeroed() };
+        new_encoder(&mut encoder, level)?;
         let res = do_deflate(&mut encoder, input, output, inflated_size, chksum);
         unsafe {
             deflateEnd(&mut encoder);
@@ -238,7 +265,8 @@ impl Inflater for ZlibCompressionMethod
         chksum: &mut TChecksum
     ) -> Result<(), InflateError>
     {
-        let mut decoder = new_decoder()?;
+        let mut decoder = unsafe { zstream_zeroed() };
+        new_decoder(&mut decoder)?;
         let res = do_inflate(&mut decoder, input, output, deflated_size, chksum);
         unsafe {
             inflateEnd(&mut decoder);
@@ -246,3 +274,87 @@ impl Inflater for ZlibCompressionMethod
         res
     }
 }
+
+/// A pull based zlib decoder which inflates a compressed stream block by block.
+pub struct ZlibDecoder
+{
+    // Boxed as zlib keeps pointers to both the stream and the input buffer.
+    stream: Box<z_stream>,
+    inbuf: Box<[u8; DECODER_BUF_SIZE]>,
+    remaining: usize,
+    end: bool
+}
+
+impl ZlibDecoder
+{
+    pub fn new(deflated_size: usize) -> Result<ZlibDecoder, InflateError>
+    {
+        let mut stream = Box::new(unsafe { zstream_zeroed() });
+        new_decoder(&mut stream)?;
+        Ok(ZlibDecoder {
+            stream,
+            inbuf: Box::new([0; DECODER_BUF_SIZE]),
+            remaining: deflated_size,
+            end: false
+        })
+    }
+
+    /// Inflates some data into `out`; returns 0 once the end of the stream is reached.
+    pub fn decode<TRead: Read>(
+        &mut self,
+        mut input: TRead,
+        out: &mut [u8]
+    ) -> Result<usize, InflateError>
+    {
+        let size = std::cmp::min(out.len(), u32::MAX as usize);
+        if self.end || size == 0 {
+            return Ok(0);
+        }
+        loop {
+            if self.stream.avail_in == 0 {
+                if self.remaining == 0 {
+                    self.end = true;
+                    return Ok(0);
+                }
+                let len = input.read_fill(
+                    &mut self.inbuf[0..std::cmp::min(DECODER_BUF_SIZE, self.remaining)]
+                )?;
+                if len == 0 {
+                    //The compressed stream is truncated
+                    return Err(InflateError::Data);
+                }
+                self.remaining -= len;
+                self.stream.avail_in = len as _;
+                self.stream.next_in = self.inbuf.as_mut_ptr();
+            }
+            self.stream.avail_out = size as _;
+            self.stream.next_out = out.as_mut_ptr();
+            let err = unsafe { inflate(&mut *self.stream, Z_NO_FLUSH) };
+            match err {
+                Z_MEM_ERROR => return Err(InflateError::Memory),
+                Z_DATA_ERROR => return Err(InflateError::Data),
+                Z_NEED_DICT => return Err(InflateError::Data),
+                Z_STREAM_ERROR => return Err(InflateError::Data),
+                Z_VERSION_ERROR => return Err(InflateError::Unsupported("version")),
+                _ => ()
+            }
+            let len = size - self.stream.avail_out as usize;
+            if err == Z_STREAM_END {
+                self.end = true;
+            }
+            if len > 0 || self.end {
+                return Ok(len);
+            }
+        }
+    }
+}
+
+impl Drop for ZlibDecoder
+{
+    fn drop(&mut self)
+    {
+        unsafe {
+            inflateEnd(&mut *self.stream);
+        }
+    }
+}
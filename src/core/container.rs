@@ -27,21 +27,68 @@
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use std::{
-    collections::{BTreeMap, Bound},
-    io
+    collections::BTreeMap,
+    io::{self, Seek, Write},
+    sync::atomic::{AtomicU64, Ordering}
 };
 
+#[cfg(feature = "mmap")]
+use crate::core::{data::new_mmap_section, decoder::loaded_checksum_status};
+#[cfg(feature = "multithreading")]
+use crate::core::encoder::internal_save_parallel;
 use crate::{
     core::{
-        data::AutoSectionData,
-        decoder::read_section_header_table,
-        encoder::{internal_save, internal_save_last},
-        error::{ReadError, WriteError},
-        header::{MainHeader, SectionHeader, Struct},
-        section::{new_section, new_section_mut, SectionEntry, SectionEntry1},
+        builder::{Checksum, OpenOptions, RepackOptions, SectionOptions},
+        compare,
+        data::new_section_data,
+        decoder::{
+            backend_size,
+            checksum_data,
+            checksum_headers,
+            checksum_section,
+            checksum_section_with,
+            load_section1,
+            load_section_unchecked,
+            read_section_header_table
+        },
+        encoder::{internal_save, internal_save_last, internal_save_to},
+        error::{ReadError, RepackError, WriteError},
+        header::{
+            MainHeader,
+            SectionHeader,
+            Struct,
+            FLAG_COMPRESS_XZ,
+            FLAG_COMPRESS_ZLIB,
+            FLAG_ENCRYPT_AES_GCM,
+            SIZE_MAIN_HEADER,
+            SIZE_SECTION_HEADER,
+            SIZE_TRAILER
+        },
+        reader::{new_loaded_section_reader, new_section_reader},
+        reserved::{self, Conflict, RESERVED_SECTION_TYPES},
+        section::{
+            new_section,
+            new_section_mut,
+            sorted_entries,
+            SectionEntry,
+            SectionEntry1
+        },
+        trailer::{
+            check_trailer,
+            has_trailer,
+            strip_trailer,
+            trailing_data,
+            verify_trailer,
+            write_trailer
+        },
+        AutoSectionData,
         Section,
-        SectionMut
+        SectionCursor,
+        SectionData,
+        SectionMut,
+        SectionReader
     },
+    ContainerTag,
     Handle
 };
 
@@ -50,11 +97,33 @@ use crate::{
 /// *Used as default compression threshold when a section is marked as compressible.*
 pub const DEFAULT_COMPRESSION_THRESHOLD: u32 = 65536;
 
+/// The default maximum size of section data kept in memory.
+///
+/// *Section data exceeding this size is moved to a temporary file.*
+pub const DEFAULT_MEMORY_THRESHOLD: usize = 32 * 1024 * 1024;
+
+/// The default maximum number of sections accepted when opening a container.
+///
+/// *Protects against main headers declaring a huge number of sections.*
+pub const DEFAULT_MAX_SECTIONS: u32 = 1 << 20;
+
+/// The maximum size of a section loaded from an untrusted container.
+///
+/// *See [OpenOptions::untrusted](crate::core::builder::OpenOptions::untrusted).*
+pub const UNTRUSTED_MAX_SECTION_SIZE: u32 = 256 * 1024 * 1024;
+
+/// The maximum total size of the sections loaded at the same time from an untrusted container.
+///
+/// *See [OpenOptions::untrusted](crate::core::builder::OpenOptions::untrusted).*
+pub const UNTRUSTED_MAX_TOTAL_MEMORY: u64 = 1024 * 1024 * 1024;
+
 /// Mutable iterator over [SectionMut](crate::core::SectionMut) for a [Container](crate::core::Container).
 pub struct IterMut<'a, T>
 {
     backend: &'a mut T,
-    sections: std::collections::btree_map::IterMut<'a, u32, SectionEntry>
+    sections: std::vec::IntoIter<(&'a u32, &'a mut SectionEntry)>,
+    available: &'a AtomicU64,
+    tag: ContainerTag
 }
 
 impl<'a, T> Iterator for IterMut<'a, T>
@@ -66,7 +135,7 @@ impl<'a, T> Iterator for IterMut<'a, T>
         let (h, v) = self.sections.next()?;
         unsafe {
             let ptr = self.backend as *mut T;
-            Some(new_section_mut(&mut *ptr, v, Handle(*h)))
+            Some(new_section_mut(&mut *ptr, v, Handle(*h, self.tag), self.available))
         }
     }
 }
@@ -74,7 +143,8 @@ impl<'a, T> Iterator for IterMut<'a, T>
 /// Iterator over [Section](crate::core::Section) for a [Container](crate::core::Container).
 pub struct Iter<'a>
 {
-    sections: std::collections::btree_map::Iter<'a, u32, SectionEntry>
+    sections: std::vec::IntoIter<(&'a u32, &'a SectionEntry)>,
+    tag: ContainerTag
 }
 
 impl<'a> Iterator for Iter<'a>
@@ -84,10 +154,155 @@ impl<'a> Iterator for Iter<'a>
     fn next(&mut self) -> Option<Self::Item>
     {
         let (h, v) = self.sections.next()?;
-        Some(new_section(v, Handle(*h)))
+        Some(new_section(v, Handle(*h, self.tag)))
+    }
+}
+
+/// A progress report sent after a section is written by
+/// [save_with_progress](crate::core::Container::save_with_progress).
+#[derive(Copy, Clone, Debug)]
+pub struct SaveProgress
+{
+    /// The handle of the section.
+    pub handle: Handle,
+
+    /// The index of the section in the file.
+    pub index: u32,
+
+    /// The size in bytes of the section.
+    pub size: u32,
+
+    /// The size in bytes of the section after compression.
+    pub csize: u32,
+
+    /// The number of bytes of section data processed so far, including this section.
+    pub bytes: u64,
+
+    /// The total number of bytes of section data to process.
+    pub total: u64
+}
+
+fn log_section(_event: SaveProgress)
+{
+    #[cfg(feature = "debug-log")]
+    println!(
+        "Writing section #{}: Size = {}, Size after compression = {}, Handle = {}",
+        _event.index,
+        _event.size,
+        _event.csize,
+        _event.handle.into_raw()
+    );
+}
+
+/// The result of the checksum verification of a section.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ChecksumStatus
+{
+    /// The section has no checksum.
+    None,
+
+    /// The checksum matches the section data.
+    Valid,
+
+    /// The checksum matches the section data only with the faulty CRC32 table used by older
+    /// revisions of this library.
+    Legacy,
+
+    /// The checksum does not match the section data.
+    ///
+    /// # Arguments
+    /// * expected checksum value.
+    /// * actual checksum value.
+    Mismatch(u32, u32)
+}
+
+impl ChecksumStatus
+{
+    /// Returns false if the checksum did not match.
+    pub fn is_valid(&self) -> bool
+    {
+        !matches!(self, ChecksumStatus::Mismatch(_, _))
     }
 }
 
+/// Information about a section removed from a BPX container.
+///
+/// *Sections located after the removed section are shifted down by one index. References
+/// to section indices stored inside of sections (such as the start section of objects in a
+/// BPX Package) must be updated by the caller.*
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct RemovedSection
+{
+    /// The index the removed section had in the file.
+    pub old_index: u32
+}
+
+/// A report of the integrity of a BPX container.
+#[derive(Clone, Debug)]
+pub struct VerifyReport
+{
+    /// The status of the main header checksum.
+    pub main_header: ChecksumStatus,
+
+    /// The status of each section in the container.
+    pub sections: Vec<(Handle, ChecksumStatus)>
+}
+
+impl VerifyReport
+{
+    /// Returns true if all checksums in this report are valid.
+    pub fn is_valid(&self) -> bool
+    {
+        self.main_header.is_valid() && self.sections.iter().all(|(_, v)| v.is_valid())
+    }
+}
+
+/// The compression codec of a section as stored in its flags.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum CompressionKind
+{
+    /// The section is not compressed.
+    None,
+
+    /// The section is compressed with zlib.
+    Zlib,
+
+    /// The section is compressed with xz.
+    Xz
+}
+
+impl CompressionKind
+{
+    /// Returns the compression codec described by a set of section flags.
+    ///
+    /// # Arguments
+    ///
+    /// * `flags`: the flags of a section header.
+    ///
+    /// returns: CompressionKind
+    pub fn from_flags(flags: u8) -> CompressionKind
+    {
+        if flags & FLAG_COMPRESS_XZ != 0 {
+            CompressionKind::Xz
+        } else if flags & FLAG_COMPRESS_ZLIB != 0 {
+            CompressionKind::Zlib
+        } else {
+            CompressionKind::None
+        }
+    }
+}
+
+/// The compression and checksum policy applied to a section on save.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SectionPolicy
+{
+    /// The requested flags (see FLAG_* constants).
+    pub flags: u8,
+
+    /// The maximum size in bytes to keep the section uncompressed.
+    pub threshold: u32
+}
+
 /// The main BPX container implementation.
 pub struct Container<T>
 {
@@ -95,7 +310,19 @@ pub struct Container<T>
     main_header: MainHeader,
     sections: BTreeMap<u32, SectionEntry>,
     next_handle: u32,
-    modified: bool
+    modified: bool,
+    memory_threshold: usize,
+    key: Option<[u8; 32]>,
+    skip_checksum: bool,
+    save_memory_budget: Option<usize>,
+    max_section_size: Option<u32>,
+    max_total_memory: Option<u64>,
+    available_memory: AtomicU64,
+    default_compression_threshold: u32,
+    //True if the backend holds a trailer located right after the data of the last section
+    trailer: bool,
+    //Identifies the handles created by this container
+    tag: ContainerTag
 }
 
 impl<T> Container<T>
@@ -123,12 +350,49 @@ impl<T> Container<T>
     {
         for (handle, entry) in &self.sections {
             if entry.header.ty == ty {
-                return Some(Handle(*handle));
+                return Some(Handle(*handle, self.tag));
             }
         }
         None
     }
 
+    /// Searches for all sections of a given type.
+    ///
+    /// *Sections are returned in the order they appear in the file (by section index). Only
+    /// the sections of the given type are sorted.*
+    ///
+    /// # Arguments
+    ///
+    /// * `ty`: section type byte.
+    ///
+    /// returns: impl Iterator<Item = Handle>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let first = file.create_section(SectionHeaderBuilder::new().ty(1));
+    /// file.create_section(SectionHeaderBuilder::new().ty(2));
+    /// let second = file.create_section(SectionHeaderBuilder::new().ty(1));
+    /// let all: Vec<_> = file.find_all_sections_by_type(1).collect();
+    /// assert_eq!(all, vec![first, second]);
+    /// ```
+    pub fn find_all_sections_by_type(&self, ty: u8) -> impl Iterator<Item = Handle> + '_
+    {
+        //Only matching sections need to be sorted by index
+        sorted_entries(
+            self.sections
+                .iter()
+                .filter(move |(_, entry)| entry.header.ty == ty)
+        )
+        .into_iter()
+        .map(|(handle, _)| Handle(*handle, self.tag))
+    }
+
     /// Locates a section by its index in the file.
     /// Returns None if the section does not exist.
     ///
@@ -150,12 +414,10 @@ impl<T> Container<T>
     /// ```
     pub fn find_section_by_index(&self, index: u32) -> Option<Handle>
     {
-        for (idx, handle) in self.sections.keys().enumerate() {
-            if idx as u32 == index {
-                return Some(Handle(*handle));
-            }
-        }
-        None
+        self.sections
+            .iter()
+            .find(|(_, entry)| entry.index == index)
+            .map(|(handle, _)| Handle(*handle, self.tag))
     }
 
     /// Sets the BPX Main Header.
@@ -225,12 +487,72 @@ impl<T> Container<T>
     /// // Default section type is 0x0.
     /// assert_eq!(section.ty, 0x0);
     /// ```
-    pub fn get(&self, handle: Handle) -> Section
+    pub fn get(&self, handle: Handle) -> Section<'_>
+    {
+        self.try_get(handle).expect("attempt to use invalid handle")
+    }
+
+    /// Obtains read-only access to a given section, without panicking on invalid handles.
+    ///
+    /// *Handles are never reused: a handle to a removed section stays invalid even after
+    /// new sections are created. In debug builds, a handle obtained from another container is
+    /// also invalid; in release builds it is only detected when no section of this container
+    /// has the same key.*
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the wanted section.
+    ///
+    /// returns: Result<Section, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if the handle does not match
+    /// the key of any section of this container or, in debug builds, if it was created by
+    /// another container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::error::ReadError;
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let section = file.create_section(SectionHeaderBuilder::new());
+    /// assert!(file.try_get(section).is_ok());
+    /// file.remove_section(section);
+    /// file.create_section(SectionHeaderBuilder::new());
+    /// assert!(matches!(file.try_get(section), Err(ReadError::InvalidHandle(_))));
+    /// ```
+    pub fn try_get(&self, handle: Handle) -> Result<Section<'_>, ReadError>
     {
         self.sections
-            .get(&handle.0)
+            .get(&handle.key_in(self.tag)?)
             .map(|v| new_section(v, handle))
-            .expect("attempt to use invalid handle")
+            .ok_or(ReadError::InvalidHandle(handle))
+    }
+
+    /// Creates a new read cursor over a given loaded section.
+    ///
+    /// Returns None if the section is not loaded.
+    ///
+    /// *Each cursor has its own position: any number of cursors may read the same section at
+    /// the same time.*
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the wanted section.
+    ///
+    /// returns: Option<SectionCursor>
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    pub fn open_cursor(&self, handle: Handle) -> Option<SectionCursor<'_>>
+    {
+        self.get(handle).cursor()
     }
 
     /// Obtains mutable access to a given section.
@@ -258,19 +580,102 @@ impl<T> Container<T>
     /// let buf = section.open().unwrap().load_in_memory().unwrap();
     /// assert_eq!(buf.len(), 0);
     /// ```
-    pub fn get_mut(&mut self, handle: Handle) -> SectionMut<T>
+    pub fn get_mut(&mut self, handle: Handle) -> SectionMut<'_, T>
     {
-        self.sections
-            .get_mut(&handle.0)
-            .map(|v| new_section_mut(&mut self.backend, v, handle))
+        self.try_get_mut(handle)
             .expect("attempt to use invalid handle")
     }
 
+    /// Obtains mutable access to a given section, without panicking on invalid handles.
+    ///
+    /// *See [try_get](Container::try_get) for the handles which are detected.*
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the wanted section.
+    ///
+    /// returns: Result<SectionMut, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if the handle does not match
+    /// the key of any section of this container or, in debug builds, if it was created by
+    /// another container.
+    pub fn try_get_mut(&mut self, handle: Handle) -> Result<SectionMut<'_, T>, ReadError>
+    {
+        let key = handle.key_in(self.tag)?;
+        if !self.sections.contains_key(&key) {
+            return Err(ReadError::InvalidHandle(handle));
+        }
+        self.update_available_memory();
+        self.sections
+            .get_mut(&key)
+            .map(|v| new_section_mut(&mut self.backend, v, handle, &self.available_memory))
+            .ok_or(ReadError::InvalidHandle(handle))
+    }
+
+    /// Returns the header of a section, without panicking on invalid handles.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: Result<&SectionHeader, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// [InvalidHandle](crate::core::error::ReadError::InvalidHandle) is returned if the handle
+    /// does not match the key of any section of this container or, in debug builds, if it was
+    /// created by another container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let section = file.create_section(SectionHeaderBuilder::new().ty(1));
+    /// assert_eq!(file.try_header(section).unwrap().ty, 1);
+    /// file.remove_section(section);
+    /// assert!(file.try_header(section).is_err());
+    /// ```
+    pub fn try_header(&self, handle: Handle) -> Result<&SectionHeader, ReadError>
+    {
+        self.sections
+            .get(&handle.key_in(self.tag)?)
+            .map(|v| &v.header)
+            .ok_or(ReadError::InvalidHandle(handle))
+    }
+
+    /// Returns the index of a section in the file, without panicking on invalid handles.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: Result<u32, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// [InvalidHandle](crate::core::error::ReadError::InvalidHandle) is returned if the handle
+    /// does not match the key of any section of this container or, in debug builds, if it was
+    /// created by another container.
+    pub fn try_index(&self, handle: Handle) -> Result<u32, ReadError>
+    {
+        self.sections
+            .get(&handle.key_in(self.tag)?)
+            .map(|v| v.index)
+            .ok_or(ReadError::InvalidHandle(handle))
+    }
+
     /// Creates a new section in the BPX
     ///
     /// # Arguments
     ///
-    /// * `header`: the [SectionHeader](crate::core::header::SectionHeader) of the new section.
+    /// * `header`: the [SectionOptions](crate::core::builder::SectionOptions) of the new section,
+    ///   usually a [SectionHeaderBuilder](crate::core::builder::SectionHeaderBuilder).
     ///
     /// returns: Handle
     ///
@@ -286,30 +691,85 @@ impl<T> Container<T>
     /// file.create_section(SectionHeaderBuilder::new());
     /// assert_eq!(file.get_main_header().section_num, 1);
     /// ```
-    pub fn create_section<H: Into<SectionHeader>>(&mut self, header: H) -> Handle
+    pub fn create_section<H: Into<SectionOptions>>(&mut self, header: H) -> Handle
+    {
+        self.create_section_at(self.main_header.section_num, header)
+    }
+
+    /// Creates a new section in the BPX at a given index.
+    ///
+    /// Sections at or after `index` are shifted by one.
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: the index of the new section in the file.
+    /// * `header`: the [SectionOptions](crate::core::builder::SectionOptions) of the new section,
+    ///   usually a [SectionHeaderBuilder](crate::core::builder::SectionHeaderBuilder).
+    ///
+    /// returns: Handle
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of sections in this container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let last = file.create_section(SectionHeaderBuilder::new().ty(1));
+    /// let first = file.create_section_at(0, SectionHeaderBuilder::new().ty(2));
+    /// assert_eq!(file.get(first).index(), 0);
+    /// assert_eq!(file.get(last).index(), 1);
+    /// assert_eq!(file.find_section_by_index(0), Some(first));
+    /// ```
+    pub fn create_section_at<H: Into<SectionOptions>>(&mut self, index: u32, header: H) -> Handle
     {
+        assert!(
+            index <= self.main_header.section_num,
+            "section index out of bounds"
+        );
+        self.sections
+            .values_mut()
+            .filter(|v| v.index >= index)
+            .for_each(|v| v.index += 1);
         self.modified = true;
         self.main_header.section_num += 1;
         let r = self.next_handle;
-        let section = AutoSectionData::new();
-        let h = header.into();
+        let section = new_section_data(None, self.memory_threshold)
+            .expect("in-memory section data must not fail to initialize");
+        let options = header.into();
+        let h = options.header;
         let entry = SectionEntry {
             header: h,
             data: Some(section),
+            raw: None,
             modified: false,
-            index: self.main_header.section_num - 1,
+            index,
             entry1: SectionEntry1 {
-                threshold: h.csize,
-                flags: h.flags
+                threshold: options
+                    .threshold
+                    .unwrap_or(self.default_compression_threshold),
+                flags: h.flags,
+                memory_threshold: self.memory_threshold,
+                level: options.level,
+                key: self.key,
+                skip_checksum: self.skip_checksum,
+                max_size: self.max_section_size
             }
         };
         self.sections.insert(r, entry);
         self.next_handle += 1;
-        Handle(r)
+        Handle(r, self.tag)
     }
 
     /// Removes a section from this BPX.
     ///
+    /// Sections located after the removed section are shifted down by one index.
+    ///
     /// # Panics
     ///
     /// Panics if the given section handle is invalid.
@@ -318,6 +778,8 @@ impl<T> Container<T>
     ///
     /// * `handle`: a handle to the section.
     ///
+    /// returns: RemovedSection
+    ///
     /// # Examples
     ///
     /// ```
@@ -329,109 +791,1657 @@ impl<T> Container<T>
     /// let section = file.create_section(SectionHeaderBuilder::new());
     /// file.save();
     /// assert_eq!(file.get_main_header().section_num, 1);
-    /// file.remove_section(section);
+    /// assert_eq!(file.remove_section(section).old_index, 0);
     /// file.save();
     /// assert_eq!(file.get_main_header().section_num, 0);
     /// ```
-    pub fn remove_section(&mut self, handle: Handle)
+    pub fn remove_section(&mut self, handle: Handle) -> RemovedSection
     {
-        self.sections.remove(&handle.0);
+        let entry = handle
+            .key_in(self.tag)
+            .ok()
+            .and_then(|key| self.sections.remove(&key))
+            .expect("attempt to use invalid handle");
         self.main_header.section_num -= 1;
         self.modified = true;
         self.sections
-            .range_mut((Bound::Included(handle.0), Bound::Unbounded))
-            .for_each(|(_, v)| {
-                v.index -= 1;
-            });
-    }
-
-    /// Creates an immutable iterator over each [Section](crate::core::Section) in this container.
-    pub fn iter(&self) -> Iter
-    {
-        Iter {
-            sections: self.sections.iter()
-        }
-    }
-
-    /// Creates a mutable iterator over each [SectionMut](crate::core::SectionMut) in this container.
-    pub fn iter_mut(&mut self) -> IterMut<T>
-    {
-        IterMut {
-            backend: &mut self.backend,
-            sections: self.sections.iter_mut()
+            .values_mut()
+            .filter(|v| v.index > entry.index)
+            .for_each(|v| v.index -= 1);
+        RemovedSection {
+            old_index: entry.index
         }
     }
 
-    /// Consumes this BPX container and returns the inner IO backend.
-    pub fn into_inner(self) -> T
-    {
-        self.backend
-    }
-}
-
-impl<'a, T> IntoIterator for &'a Container<T>
-{
-    type Item = Section<'a>;
-    type IntoIter = Iter<'a>;
-
-    fn into_iter(self) -> Self::IntoIter
-    {
-        self.iter()
-    }
-}
-
-impl<'a, T> IntoIterator for &'a mut Container<T>
-{
-    type Item = SectionMut<'a, T>;
-    type IntoIter = IterMut<'a, T>;
-
-    fn into_iter(self) -> Self::IntoIter
-    {
-        self.iter_mut()
-    }
-}
-
-impl<T: io::Read + io::Seek> Container<T>
-{
-    /// Loads a BPX container from the given `backend`.
+    /// Moves all sections of another container at the end of this container.
+    ///
+    /// Sections of `other` which are not yet loaded are loaded first. Returns the new handles
+    /// of the sections in the order they appear in `other`.
+    ///
+    /// *Data inside of sections (such as string offsets) is copied as is.*
     ///
     /// # Arguments
     ///
-    /// * `backend`: A [Read](std::io::Read) + [Seek](std::io::Seek) backend to use for reading the BPX container.
+    /// * `other`: the container to merge into this container.
     ///
-    /// returns: Result<Decoder<TBackend>, Error>
+    /// returns: Result<Vec<Handle>, ReadError>
     ///
     /// # Errors
     ///
-    /// A [ReadError](crate::core::error::ReadError) is returned if some headers
-    /// could not be read or if the header data is corrupted.
+    /// A [ReadError](crate::core::error::ReadError) is returned if a section of `other`
+    /// could not be loaded.
     ///
     /// # Examples
     ///
     /// ```
-    /// use bpx::core::builder::MainHeaderBuilder;
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// file.create_section(SectionHeaderBuilder::new().ty(1));
+    /// let mut other = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// other.create_section(SectionHeaderBuilder::new().ty(2));
+    /// let handles = file.merge(other).unwrap();
+    /// assert_eq!(file.get_main_header().section_num, 2);
+    /// assert_eq!(file.get(handles[0]).ty, 2);
+    /// assert_eq!(file.get(handles[0]).index(), 1);
+    /// ```
+    pub fn merge<U: io::Read + io::Seek>(
+        &mut self,
+        mut other: Container<U>
+    ) -> Result<Vec<Handle>, ReadError>
+    {
+        for mut section in other.iter_mut() {
+            section.load()?;
+        }
+        let mut entries: Vec<SectionEntry> = other.sections.into_values().collect();
+        entries.sort_by_key(|entry| entry.index);
+        let mut handles = Vec::with_capacity(entries.len());
+        for mut entry in entries {
+            entry.index = self.main_header.section_num;
+            entry.modified = true;
+            self.sections.insert(self.next_handle, entry);
+            handles.push(Handle(self.next_handle, self.tag));
+            self.next_handle += 1;
+            self.main_header.section_num += 1;
+        }
+        self.modified = true;
+        Ok(handles)
+    }
+
+    /// Copies a section of another container at the end of this container.
+    ///
+    /// The section of `src` is loaded if needed and its decoded data is copied. The type byte,
+    /// flags, compression threshold and compression level of the section are preserved, so the
+    /// copy is re-compressed according to these settings when this container is saved.
+    ///
+    /// # Arguments
+    ///
+    /// * `src`: the container to copy the section from.
+    /// * `handle`: a handle to the section in `src`.
+    ///
+    /// returns: Result<Handle, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if the section could not be
+    /// loaded or copied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::{Container, SectionData};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut src = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = src.create_section(SectionHeaderBuilder::new().ty(3));
+    /// src.get_mut(handle).open().unwrap().write_all(b"test").unwrap();
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let copy = file.copy_section_from(&mut src, handle).unwrap();
+    /// assert_eq!(file.get(copy).ty, 3);
+    /// let mut section = file.get_mut(copy);
+    /// assert_eq!(section.open().unwrap().load_in_memory().unwrap(), b"test");
+    /// ```
+    pub fn copy_section_from<U: io::Read + io::Seek>(
+        &mut self,
+        src: &mut Container<U>,
+        handle: Handle
+    ) -> Result<Handle, ReadError>
+    {
+        let key = handle.key_in(src.tag).expect("attempt to use invalid handle");
+        let entry = &src.sections[&key];
+        let header = SectionOptions {
+            header: SectionHeader {
+                pointer: 0,
+                csize: entry.entry1.threshold,
+                size: 0,
+                chksum: 0,
+                ty: entry.header.ty,
+                flags: entry.entry1.flags
+            },
+            level: entry.entry1.level,
+            threshold: Some(entry.entry1.threshold)
+        };
+        let mut section = src.get_mut(handle);
+        let data = section.load()?;
+        let pos = data.stream_position()?;
+        data.seek(io::SeekFrom::Start(0))?;
+        let copy = self.create_section(header);
+        let res = io::copy(data, self.get_mut(copy).open().unwrap());
+        data.seek(io::SeekFrom::Start(pos))?;
+        res?;
+        Ok(copy)
+    }
+
+    /// Creates an immutable iterator over each [Section](crate::core::Section) in this container.
+    pub fn iter(&self) -> Iter<'_>
+    {
+        Iter {
+            sections: sorted_entries(self.sections.iter()).into_iter(),
+            tag: self.tag
+        }
+    }
+
+    /// Counts how many sections use each compression codec.
+    ///
+    /// Only section flags are read: no section data is loaded. *Flags are updated on save,
+    /// so sections which were never saved are reported as not compressed.*
+    ///
+    /// returns: BTreeMap<CompressionKind, usize>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::{CompressionKind, Container};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// file.create_section(SectionHeaderBuilder::new());
+    /// let summary = file.compression_summary();
+    /// assert_eq!(summary[&CompressionKind::None], 1);
+    /// assert!(!summary.contains_key(&CompressionKind::Xz));
+    /// ```
+    pub fn compression_summary(&self) -> BTreeMap<CompressionKind, usize>
+    {
+        let mut summary = BTreeMap::new();
+        for entry in self.sections.values() {
+            *summary
+                .entry(CompressionKind::from_flags(entry.header.flags))
+                .or_insert(0) += 1;
+        }
+        summary
+    }
+
+    /// Lists sections sorted by descending compressed size.
+    ///
+    /// Only section headers are read: no section data is loaded. *Sizes are updated on save,
+    /// so sections which were never saved are reported with a size of 0.*
+    ///
+    /// returns: Vec<(Handle, u32)>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    ///
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let small = file.create_section(SectionHeaderBuilder::new());
+    /// let big = file.create_section(SectionHeaderBuilder::new());
+    /// file.get_mut(small).open().unwrap().write_all(b"small").unwrap();
+    /// file.get_mut(big).open().unwrap().write_all(b"much bigger").unwrap();
+    /// file.save().unwrap();
+    /// let sizes = file.sections_by_size();
+    /// assert_eq!(sizes, vec![(big, 11), (small, 5)]);
+    /// ```
+    pub fn sections_by_size(&self) -> Vec<(Handle, u32)>
+    {
+        let mut sizes: Vec<(Handle, u32)> = self
+            .sections
+            .iter()
+            .map(|(k, v)| (Handle(*k, self.tag), v.header.csize))
+            .collect();
+        sizes.sort_by_key(|v| std::cmp::Reverse(v.1));
+        sizes
+    }
+
+    /// Lists sections whose type collides with a section type reserved by an official variant
+    /// other than `my_main_type`.
+    ///
+    /// This check is advisory only: nothing prevents a custom variant from reusing a
+    /// reserved section type.
+    ///
+    /// # Arguments
+    ///
+    /// * `my_main_type`: the main type of the BPX variant this container implements.
+    ///
+    /// returns: Vec<Conflict>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::reserved::{MAIN_TYPE_SHADER, SHADER_SYMBOL_TABLE};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new().ty(b'X'));
+    /// let handle = file.create_section(SectionHeaderBuilder::new().ty(0x2));
+    /// let conflicts = file.check_reserved_conflicts(b'X');
+    /// assert!(conflicts
+    ///     .iter()
+    ///     .any(|v| v.handle == handle && v.reserved == SHADER_SYMBOL_TABLE));
+    /// assert!(file
+    ///     .check_reserved_conflicts(MAIN_TYPE_SHADER)
+    ///     .iter()
+    ///     .all(|v| v.reserved.main_type != MAIN_TYPE_SHADER));
+    /// ```
+    pub fn check_reserved_conflicts(&self, my_main_type: u8) -> Vec<Conflict>
+    {
+        let mut conflicts = Vec::new();
+        for section in self.iter() {
+            conflicts.extend(
+                RESERVED_SECTION_TYPES
+                    .iter()
+                    .filter(|v| v.main_type != my_main_type && v.section_type == section.ty)
+                    .map(|v| Conflict {
+                        handle: section.handle(),
+                        reserved: *v
+                    })
+            );
+        }
+        conflicts
+    }
+
+    /// Checks if a section type is a standard core section type or is reserved by any
+    /// official variant.
+    ///
+    /// *See [is_reserved](crate::core::reserved::is_reserved).*
+    ///
+    /// # Arguments
+    ///
+    /// * `ty`: the section type byte.
+    ///
+    /// returns: bool
+    pub fn is_reserved_type(&self, ty: u8) -> bool
+    {
+        reserved::is_reserved(ty)
+    }
+
+    /// Sets the maximum size of section data to keep in memory.
+    ///
+    /// Section data which exceeds this size is transparently moved to a temporary file.
+    /// This applies to sections created or loaded after this call; already loaded sections
+    /// keep their current threshold.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes`: the new threshold in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::MainHeaderBuilder;
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// file.set_memory_threshold(1024);
+    /// assert_eq!(file.get_memory_threshold(), 1024);
+    /// ```
+    pub fn set_memory_threshold(&mut self, bytes: usize)
+    {
+        self.memory_threshold = bytes;
+        for entry in self.sections.values_mut() {
+            entry.entry1.memory_threshold = bytes;
+        }
+    }
+
+    /// Sets the compression threshold of sections created after this call whose header
+    /// does not define an explicit threshold.
+    ///
+    /// A section is only compressed if its size is greater than its threshold.
+    /// *By default, the threshold is [DEFAULT_COMPRESSION_THRESHOLD](self::DEFAULT_COMPRESSION_THRESHOLD).*
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold`: the compression threshold in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{CompressionMethod, MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// file.set_default_compression_threshold(1024);
+    /// let handle = file.create_section(SectionHeaderBuilder::new().compression(CompressionMethod::Zlib));
+    /// assert_eq!(file.get_section_policy(handle).threshold, 1024);
+    /// ```
+    pub fn set_default_compression_threshold(&mut self, threshold: u32)
+    {
+        self.default_compression_threshold = threshold;
+    }
+
+    /// Sets the maximum size in bytes of a section to load.
+    ///
+    /// Loading a larger section fails with
+    /// [SectionTooLarge](crate::core::error::ReadError::SectionTooLarge) before any data is
+    /// allocated. Sections decoding to more data than their declared size also fail to load.
+    ///
+    /// *By default, there is no limit.*
+    ///
+    /// # Arguments
+    ///
+    /// * `limit`: the maximum size of a section, None for no limit.
+    pub fn set_max_section_size(&mut self, limit: Option<u32>)
+    {
+        self.max_section_size = limit;
+        for entry in self.sections.values_mut() {
+            entry.entry1.max_size = limit;
+        }
+    }
+
+    /// Sets the maximum total size in bytes of the sections loaded at the same time.
+    ///
+    /// Loading a section which would exceed this limit fails with
+    /// [SectionTooLarge](crate::core::error::ReadError::SectionTooLarge) before any data is
+    /// allocated. Sections decoding to more data than their declared size also fail to load.
+    ///
+    /// *By default, there is no limit.*
+    ///
+    /// # Arguments
+    ///
+    /// * `limit`: the maximum total size of loaded sections, None for no limit.
+    pub fn set_max_total_memory(&mut self, limit: Option<u64>)
+    {
+        self.max_total_memory = limit;
+    }
+
+    fn update_available_memory(&self)
+    {
+        let available = match self.max_total_memory {
+            Some(limit) => {
+                let used: u64 = self
+                    .sections
+                    .values()
+                    .filter_map(|v| v.data.as_ref())
+                    .map(|v| v.size() as u64)
+                    .sum();
+                limit.saturating_sub(used)
+            },
+            None => u64::MAX
+        };
+        self.available_memory.store(available, Ordering::Relaxed);
+    }
+
+    /// Enables or disables checksum verification when loading sections.
+    ///
+    /// When checksum verification is disabled, sections whose checksum does not match are
+    /// loaded anyway instead of failing with [Checksum](crate::core::error::ReadError::Checksum).
+    /// This allows recovering data from slightly corrupted containers. Use
+    /// [verify_section](Container::verify_section) to explicitly check the integrity of a section.
+    ///
+    /// *The checksum of the section header table is always verified when opening a container.*
+    ///
+    /// # Arguments
+    ///
+    /// * `skip`: true to disable checksum verification, false to enable it (the default).
+    pub fn set_skip_checksum(&mut self, skip: bool)
+    {
+        self.skip_checksum = skip;
+        for entry in self.sections.values_mut() {
+            entry.entry1.skip_checksum = skip;
+        }
+    }
+
+    /// Sets the maximum total size of in-memory section data to keep across all sections.
+    ///
+    /// [save](Container::save) writes sections one at a time and moves each written section
+    /// to a temporary file while the budget is exceeded; `save_parallel` does the same and
+    /// also keeps the compressed data of each section in memory only up to the budget. To
+    /// also bound memory usage while filling sections, call
+    /// [spill_sections](Container::spill_sections) after writing each large section.
+    ///
+    /// *By default, there is no budget and each section only switches to a temporary file once
+    /// it exceeds the [memory threshold](Container::set_memory_threshold).*
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes`: the budget in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// file.set_save_memory_budget(4);
+    /// let first = file.create_section(SectionHeaderBuilder::new());
+    /// file.get_mut(first).open().unwrap().write_all(b"first").unwrap();
+    /// let second = file.create_section(SectionHeaderBuilder::new());
+    /// file.get_mut(second).open().unwrap().write_all(b"2nd").unwrap();
+    /// file.spill_sections().unwrap();
+    /// assert!(!file.get(first).open().unwrap().is_in_memory());
+    /// assert!(file.get(second).open().unwrap().is_in_memory());
+    /// ```
+    pub fn set_save_memory_budget(&mut self, bytes: usize)
+    {
+        self.save_memory_budget = Some(bytes);
+    }
+
+    /// Moves the largest in-memory sections to temporary files until the remaining in-memory
+    /// sections fit in the [save memory budget](Container::set_save_memory_budget).
+    ///
+    /// *Does nothing if no budget is set.*
+    ///
+    /// # Errors
+    ///
+    /// A [WriteError](crate::core::error::WriteError) is returned if a section could not be
+    /// moved to a temporary file; sections moved before the error stay in their temporary file.
+    pub fn spill_sections(&mut self) -> Result<(), WriteError>
+    {
+        let budget = match self.save_memory_budget {
+            Some(v) => v,
+            None => return Ok(())
+        };
+        let mut in_memory: Vec<(usize, &mut AutoSectionData)> = self
+            .sections
+            .values_mut()
+            .filter_map(|v| v.data.as_mut())
+            .filter(|v| v.is_in_memory())
+            .map(|v| (v.size(), v))
+            .collect();
+        let mut total: usize = in_memory.iter().map(|(size, _)| size).sum();
+        in_memory.sort_by_key(|(size, _)| std::cmp::Reverse(*size));
+        for (size, data) in in_memory {
+            if total <= budget {
+                break;
+            }
+            data.spill()?;
+            total -= size;
+        }
+        Ok(())
+    }
+
+    /// Returns the maximum size of section data to keep in memory.
+    pub fn get_memory_threshold(&self) -> usize
+    {
+        self.memory_threshold
+    }
+
+    /// Sets the AES-256 key used to decrypt and encrypt sections flagged with
+    /// [FLAG_ENCRYPT_AES_GCM](crate::core::header::FLAG_ENCRYPT_AES_GCM).
+    ///
+    /// The key applies to all sections of this container, including sections created after
+    /// this call. The key is never written to the container.
+    ///
+    /// # Arguments
+    ///
+    /// * `key`: the 32 bytes key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{EncryptionMethod, MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::{Container, SectionData};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// file.set_encryption_key([42; 32]);
+    /// let handle = file.create_section(
+    ///     SectionHeaderBuilder::new().encryption(EncryptionMethod::Aes256Gcm)
+    /// );
+    /// file.get_mut(handle).open().unwrap().write_all(b"secret").unwrap();
+    /// file.save().unwrap();
+    ///
+    /// let mut buf = file.into_inner();
+    /// buf.set_position(0);
+    /// let mut file = Container::open(buf).unwrap();
+    /// let handle = file.find_section_by_index(0).unwrap();
+    /// assert!(file.get_mut(handle).load().is_err());
+    /// file.set_encryption_key([42; 32]);
+    /// let data = file.get_mut(handle).load().unwrap().load_in_memory().unwrap();
+    /// assert_eq!(data, b"secret");
+    /// ```
+    #[cfg(feature = "encryption")]
+    pub fn set_encryption_key(&mut self, key: [u8; 32])
+    {
+        self.key = Some(key);
+        for entry in self.sections.values_mut() {
+            entry.entry1.key = Some(key);
+        }
+    }
+
+    /// Returns the compression and checksum policy applied to a section on save.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: SectionPolicy
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    pub fn get_section_policy(&self, handle: Handle) -> SectionPolicy
+    {
+        let entry = self
+            .sections
+            .get(&handle.0)
+            .expect("attempt to use invalid handle");
+        SectionPolicy {
+            flags: entry.entry1.flags,
+            threshold: entry.entry1.threshold
+        }
+    }
+
+    /// Returns the decoded size in bytes of a section without loading it.
+    ///
+    /// *For a loaded section this is the size of the in-memory data, which includes changes
+    /// not saved yet; otherwise it is the size recorded in the section header.*
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: u64
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new());
+    /// file.get_mut(handle).open().unwrap().write_all(b"test").unwrap();
+    /// assert_eq!(file.decoded_size(handle), 4);
+    /// assert_eq!(file.compressed_size(handle), 0);
+    /// file.save().unwrap();
+    /// assert_eq!(file.compressed_size(handle), 4);
+    /// ```
+    pub fn decoded_size(&self, handle: Handle) -> u64
+    {
+        let entry = self
+            .sections
+            .get(&handle.0)
+            .expect("attempt to use invalid handle");
+        match &entry.data {
+            Some(data) => data.size() as u64,
+            None => entry.header.size as u64
+        }
+    }
+
+    /// Returns the size in bytes of a section as stored in the file, after compression and
+    /// encryption, without loading it.
+    ///
+    /// *This is the size recorded in the section header: it is only updated on save so it
+    /// does not reflect changes not saved yet and is 0 for sections never saved.*
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: u64
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    pub fn compressed_size(&self, handle: Handle) -> u64
+    {
+        self.sections
+            .get(&handle.0)
+            .map(|v| v.header.csize as u64)
+            .expect("attempt to use invalid handle")
+    }
+
+    /// Creates a mutable iterator over each [SectionMut](crate::core::SectionMut) in this container.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T>
+    {
+        self.update_available_memory();
+        IterMut {
+            backend: &mut self.backend,
+            sections: sorted_entries(self.sections.iter_mut()).into_iter(),
+            available: &self.available_memory,
+            tag: self.tag
+        }
+    }
+
+    /// Consumes this BPX container and returns the inner IO backend.
+    pub fn into_inner(self) -> T
+    {
+        self.backend
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Container<T>
+{
+    type Item = Section<'a>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter
+    {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Container<T>
+{
+    type Item = SectionMut<'a, T>;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter
+    {
+        self.iter_mut()
+    }
+}
+
+impl<T: io::Read + io::Seek> Container<T>
+{
+    /// Loads a BPX container from the given `backend`.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend`: A [Read](std::io::Read) + [Seek](std::io::Seek) backend to use for reading the BPX container.
+    ///
+    /// returns: Result<Decoder<TBackend>, Error>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if some headers
+    /// could not be read or if the header data is corrupted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::MainHeaderBuilder;
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// file.save().unwrap();
+    /// let mut buf = file.into_inner();
+    /// buf.set_position(0);
+    /// let file = Container::open(buf).unwrap();
+    /// //Default BPX variant/type is 'P'
+    /// assert_eq!(file.get_main_header().ty, 'P' as u8);
+    /// ```
+    pub fn open(backend: T) -> Result<Container<T>, ReadError>
+    {
+        Container::open_with_max_sections(backend, DEFAULT_MAX_SECTIONS)
+    }
+
+    /// Loads a BPX container from the given `backend`, accepting at most `max_sections`
+    /// sections.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend`: A [Read](std::io::Read) + [Seek](std::io::Seek) backend to use for reading the BPX container.
+    /// * `max_sections`: the maximum number of sections the main header may declare.
+    ///
+    /// returns: Result<Container<T>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if some headers
+    /// could not be read, if the header data is corrupted or if the main header declares
+    /// more than `max_sections` sections.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::error::ReadError;
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// file.create_section(SectionHeaderBuilder::new());
+    /// file.create_section(SectionHeaderBuilder::new());
+    /// file.save().unwrap();
+    /// let mut buf = file.into_inner();
+    /// buf.set_position(0);
+    /// let res = Container::open_with_max_sections(buf, 1);
+    /// assert!(matches!(res, Err(ReadError::TooManySections(2, 1))));
+    /// ```
+    pub fn open_with_max_sections(backend: T, max_sections: u32)
+        -> Result<Container<T>, ReadError>
+    {
+        Container::open_with_options(backend, OpenOptions::new().max_sections(max_sections))
+    }
+
+    /// Loads a BPX container from an untrusted `backend`, limiting the memory used by loaded
+    /// sections.
+    ///
+    /// This is a shortcut for [open_with_options](Container::open_with_options) with
+    /// [OpenOptions::untrusted](crate::core::builder::OpenOptions::untrusted).
+    ///
+    /// # Arguments
+    ///
+    /// * `backend`: A [Read](std::io::Read) + [Seek](std::io::Seek) backend to use for reading the BPX container.
+    ///
+    /// returns: Result<Container<T>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if some headers
+    /// could not be read or if the header data is corrupted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new());
+    /// file.get_mut(handle).open().unwrap().write_all(b"test").unwrap();
+    /// file.save().unwrap();
+    /// let mut buf = file.into_inner();
+    /// buf.set_position(0);
+    /// let mut file = Container::open_untrusted(buf).unwrap();
+    /// let handle = file.find_section_by_index(0).unwrap();
+    /// assert!(file.get_mut(handle).load().is_ok());
+    /// ```
+    pub fn open_untrusted(backend: T) -> Result<Container<T>, ReadError>
+    {
+        Container::open_with_options(backend, &OpenOptions::untrusted())
+    }
+
+    /// Loads a BPX container from the given `backend` with the given [OpenOptions].
+    ///
+    /// # Arguments
+    ///
+    /// * `backend`: A [Read](std::io::Read) + [Seek](std::io::Seek) backend to use for reading the BPX container.
+    /// * `options`: the [OpenOptions] to use.
+    ///
+    /// returns: Result<Container<T>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if some headers
+    /// could not be read, if the header data is corrupted or if the file does not satisfy
+    /// the given options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Seek, SeekFrom, Write};
+    /// use bpx::core::builder::{MainHeaderBuilder, OpenOptions, SectionHeaderBuilder};
+    /// use bpx::core::error::ReadError;
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// file.create_section(SectionHeaderBuilder::new());
+    /// file.save().unwrap();
+    /// let mut buf = file.into_inner();
+    /// buf.seek(SeekFrom::End(0)).unwrap();
+    /// buf.write_all(b"junk").unwrap();
+    /// buf.set_position(0);
+    /// let res = Container::open_with_options(buf, OpenOptions::new().reject_trailing_data(true));
+    /// assert!(matches!(res, Err(ReadError::TrailingData(4))));
+    /// ```
+    pub fn open_with_options(
+        mut backend: T,
+        options: &OpenOptions
+    ) -> Result<Container<T>, ReadError>
+    {
+        let (checksum, header) = MainHeader::read(&mut backend)?;
+        let file_size = match options.get_skip_validation() {
+            true => None,
+            false => Some(backend_size(&mut backend)?)
+        };
+        let (next_handle, mut sections) = read_section_header_table(
+            &mut backend,
+            &header,
+            checksum,
+            options.get_max_sections(),
+            file_size
+        )?;
+        if let Some(len) = file_size {
+            //Only files long enough to contain a trailer are probed
+            if options.get_verify_trailer()
+                && len.saturating_sub(header.file_size) >= SIZE_TRAILER as u64
+            {
+                verify_trailer(&mut backend, header.file_size)?;
+            }
+        }
+        let len = match file_size {
+            Some(len) => len,
+            None => backend_size(&mut backend)?
+        };
+        //Only files long enough to contain a trailer are probed
+        let trailer = len.saturating_sub(header.file_size) >= SIZE_TRAILER as u64
+            && has_trailer(&mut backend, header.file_size)?;
+        for entry in sections.values_mut() {
+            entry.entry1.max_size = options.get_max_section_size();
+            entry.entry1.skip_checksum = options.get_skip_checksum();
+        }
+        if options.get_reject_trailing_data() {
+            let data_end = sections
+                .values()
+                .map(|v| v.header.pointer + v.header.csize as u64)
+                .fold(
+                    SIZE_MAIN_HEADER as u64 + SIZE_SECTION_HEADER as u64 * sections.len() as u64,
+                    u64::max
+                );
+            let bytes = trailing_data(&mut backend, data_end)?;
+            if bytes > 0 {
+                return Err(ReadError::TrailingData(bytes));
+            }
+        }
+        Ok(Container {
+            backend,
+            main_header: header,
+            sections,
+            next_handle,
+            modified: false,
+            memory_threshold: DEFAULT_MEMORY_THRESHOLD,
+            key: None,
+            skip_checksum: options.get_skip_checksum(),
+            save_memory_budget: None,
+            max_section_size: options.get_max_section_size(),
+            max_total_memory: options.get_max_total_memory(),
+            available_memory: AtomicU64::new(u64::MAX),
+            default_compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            trailer,
+            tag: ContainerTag::new()
+        })
+    }
+
+    /// Verifies the checksums of the main header and all sections against the data stored
+    /// in the backend.
+    ///
+    /// Section data is streamed from the backend and never stored: this does not load any
+    /// section. *Compressed sections still have to be decompressed as BPX checksums are computed
+    /// on uncompressed data.*
+    ///
+    /// Only data already written to the backend is verified; unsaved changes are not taken
+    /// into account.
+    ///
+    /// returns: Result<VerifyReport, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if some data could not be read
+    /// or a section could not be decompressed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{Checksum, MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::{ChecksumStatus, Container};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new().checksum(Checksum::Crc32));
+    /// file.get_mut(handle).open().unwrap().write_all(b"test").unwrap();
+    /// file.save().unwrap();
+    /// let report = file.verify().unwrap();
+    /// assert!(report.is_valid());
+    /// assert_eq!(report.sections[0], (handle, ChecksumStatus::Valid));
+    /// ```
+    pub fn verify(&mut self) -> Result<VerifyReport, ReadError>
+    {
+        let (expected, actual) = checksum_headers(&mut self.backend)?;
+        let main_header = match expected == actual {
+            true => ChecksumStatus::Valid,
+            false => ChecksumStatus::Mismatch(expected, actual)
+        };
+        let mut sections = Vec::with_capacity(self.sections.len());
+        for (handle, entry) in sorted_entries(self.sections.iter()) {
+            let status = checksum_section(
+                &mut self.backend,
+                &entry.header,
+                entry.entry1.key.as_ref()
+            )?;
+            sections.push((Handle(*handle, self.tag), status));
+        }
+        Ok(VerifyReport {
+            main_header,
+            sections
+        })
+    }
+
+    /// Verifies the checksum of a single section against the data stored in the backend.
+    ///
+    /// Section data is streamed from the backend and never stored: this does not load the
+    /// section. Sections without a checksum are always considered valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: Result<(), ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [Checksum](crate::core::error::ReadError::Checksum) error is returned if the section
+    /// is damaged. Any other [ReadError](crate::core::error::ReadError) is returned if some data
+    /// could not be read or decompressed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{Checksum, MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new().checksum(Checksum::Crc32));
+    /// file.get_mut(handle).open().unwrap().write_all(b"test").unwrap();
+    /// file.save().unwrap();
+    /// assert!(file.verify_section(handle).is_ok());
+    /// ```
+    pub fn verify_section(&mut self, handle: Handle) -> Result<(), ReadError>
+    {
+        let entry = self
+            .sections
+            .get(&handle.0)
+            .expect("attempt to use invalid handle");
+        match checksum_section(&mut self.backend, &entry.header, entry.entry1.key.as_ref())? {
+            ChecksumStatus::Mismatch(expected, actual) => {
+                Err(ReadError::Checksum(actual, expected))
+            },
+            _ => Ok(())
+        }
+    }
+
+    /// Appends bytes at the end of a section, loading the section first if needed.
+    ///
+    /// *The cursor of the section is left unchanged by this function.*
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    /// * `bytes`: the bytes to append.
+    ///
+    /// returns: Result<(), ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if the section could not be
+    /// loaded or written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::{Container, SectionData};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new());
+    /// file.append_to_section(handle, b"Hello ").unwrap();
+    /// file.append_to_section(handle, b"world").unwrap();
+    /// let data = file.get_mut(handle).load().unwrap().load_in_memory().unwrap();
+    /// assert_eq!(data, b"Hello world");
+    /// ```
+    pub fn append_to_section(&mut self, handle: Handle, bytes: &[u8]) -> Result<(), ReadError>
+    {
+        let mut section = self.get_mut(handle);
+        let data = section.load()?;
+        let pos = data.stream_position()?;
+        data.seek(io::SeekFrom::End(0))?;
+        let res = data.write_all(bytes);
+        data.seek(io::SeekFrom::Start(pos))?;
+        res?;
+        Ok(())
+    }
+
+    /// Unloads all loaded sections of a given type whose data is identical to the data stored
+    /// in the backend, freeing their memory or temporary files.
+    ///
+    /// Unloaded sections are transparently loaded again on the next call to
+    /// [load](crate::core::SectionMut::load). Returns the number of unloaded sections.
+    ///
+    /// *Sections written to since they were loaded or last saved, or whose flags changed, are
+    /// never unloaded. Nothing is unloaded while the container itself has unsaved changes,
+    /// such as newly created or removed sections.*
+    ///
+    /// # Arguments
+    ///
+    /// * `ty`: the section type byte.
+    ///
+    /// returns: usize
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new().ty(1));
+    /// file.get_mut(handle).open().unwrap().write_all(b"test").unwrap();
+    /// assert_eq!(file.unload_type(1), 0);
+    /// file.save().unwrap();
+    /// assert_eq!(file.unload_type(1), 1);
+    /// assert!(file.get(handle).open().is_none());
+    /// ```
+    pub fn unload_type(&mut self, ty: u8) -> usize
+    {
+        if self.modified {
+            return 0;
+        }
+        let mut count = 0;
+        for entry in self.sections.values_mut() {
+            if entry.header.ty == ty && !entry.is_modified() && entry.data.is_some() {
+                entry.data = None;
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Checks the whole-file integrity trailer written by
+    /// [save_with_trailer](Container::save_with_trailer).
+    ///
+    /// Returns false if the file has no trailer, if the trailer is incomplete or if the file
+    /// does not match the CRC32 stored in the trailer. Files without a trailer can still be
+    /// used normally.
+    ///
+    /// *The trailer is already verified by [open](Container::open) unless disabled in
+    /// [OpenOptions](crate::core::builder::OpenOptions); this function reads the whole file
+    /// again and also covers changes made to the backend after opening.*
+    ///
+    /// returns: Result<bool, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if some data could not be read.
+    pub fn has_valid_trailer(&mut self) -> Result<bool, ReadError>
+    {
+        check_trailer(&mut self.backend, self.main_header.file_size)
+    }
+
+    /// Computes the checksum of the uncompressed content of a section with the given algorithm.
+    ///
+    /// Loaded sections are checksummed from memory; other sections are streamed from the backend
+    /// and stay unloaded. The checksum always covers decoded section data, even for encrypted
+    /// sections whose stored checksum covers the encrypted data.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    /// * `algo`: the [Checksum](crate::core::builder::Checksum) algorithm to use.
+    ///
+    /// returns: Result<u32, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if the section data could not
+    /// be read or decompressed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{Checksum, MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new());
+    /// file.get_mut(handle).open().unwrap().write_all(b"test").unwrap();
+    /// let sum = b"test".iter().map(|v| *v as u32).sum::<u32>();
+    /// assert_eq!(file.checksum_of(handle, Checksum::Weak).unwrap(), sum);
+    /// ```
+    pub fn checksum_of(&mut self, handle: Handle, algo: Checksum) -> Result<u32, ReadError>
+    {
+        let entry = self
+            .sections
+            .get_mut(&handle.0)
+            .expect("attempt to use invalid handle");
+        match &mut entry.data {
+            Some(data) => checksum_data(data, algo),
+            //The stored checksum of encrypted sections covers the encrypted data
+            None if entry.header.flags & FLAG_ENCRYPT_AES_GCM != 0 => {
+                let (mut data, _) = load_section_unchecked(
+                    &mut self.backend,
+                    &entry.header,
+                    entry.entry1.memory_threshold,
+                    entry.entry1.key.as_ref(),
+                    false
+                )?;
+                checksum_data(&mut data, algo)
+            },
+            None => checksum_section_with(
+                &mut self.backend,
+                &entry.header,
+                algo,
+                entry.entry1.key.as_ref()
+            )
+        }
+    }
+
+    /// Loads all sections of this container which are not yet loaded.
+    ///
+    /// *[open](Container::open) only reads the main header and the section header table:
+    /// section data is read when sections are loaded.*
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError::Section](crate::core::error::ReadError::Section) is returned with the
+    /// handle of the first section which could not be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new());
+    /// file.get_mut(handle).open().unwrap().write_all(b"test").unwrap();
+    /// file.save().unwrap();
+    /// let mut buf = file.into_inner();
+    /// buf.set_position(0);
+    /// let mut file = Container::open(buf).unwrap();
+    /// let handle = file.find_section_by_index(0).unwrap();
+    /// assert!(file.get(handle).open().is_none());
+    /// file.load_all().unwrap();
+    /// assert!(file.get(handle).open().is_some());
+    /// ```
+    pub fn load_all(&mut self) -> Result<(), ReadError>
+    {
+        for mut section in self.iter_mut() {
+            let handle = section.handle();
+            section
+                .load()
+                .map_err(|e| ReadError::Section(handle, Box::new(e)))?;
+        }
+        Ok(())
+    }
+
+    /// Reads the stored bytes of all sections which are not loaded, so that the next save
+    /// copies them as is instead of requiring them to be loaded.
+    ///
+    /// Adding or removing sections re-writes the whole container as section data follows the
+    /// section header table. Staged sections are neither decoded nor compressed again: their
+    /// stored bytes, headers and checksums are preserved verbatim.
+    ///
+    /// *Staged bytes are kept in memory up to the memory threshold of this container and in a
+    /// temporary file beyond.*
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if the stored bytes of some
+    /// section could not be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new());
+    /// file.get_mut(handle).open().unwrap().write_all(b"test").unwrap();
+    /// file.save().unwrap();
+    /// let mut buf = file.into_inner();
+    /// buf.set_position(0);
+    /// let mut file = Container::open(buf).unwrap();
+    /// file.stage_unloaded().unwrap();
+    /// file.create_section(SectionHeaderBuilder::new());
+    /// file.save().unwrap();
+    /// assert_eq!(file.get_main_header().section_num, 2);
+    /// ```
+    pub fn stage_unloaded(&mut self) -> Result<(), ReadError>
+    {
+        for entry in self.sections.values_mut() {
+            if entry.data.is_some() || entry.raw.is_some() {
+                continue;
+            }
+            let mut raw = new_section_data(Some(entry.header.csize), self.memory_threshold)?;
+            self.backend
+                .seek(io::SeekFrom::Start(entry.header.pointer))?;
+            let count = io::copy(
+                &mut io::Read::take(&mut self.backend, entry.header.csize as u64),
+                &mut raw
+            )?;
+            if count != entry.header.csize as u64 {
+                return Err(ReadError::Io(io::ErrorKind::UnexpectedEof.into()));
+            }
+            entry.raw = Some(raw);
+        }
+        Ok(())
+    }
+
+    /// Loads all sections of this container which are not yet loaded, skipping sections
+    /// which fail to load.
+    ///
+    /// Returns the handle and error of each section which could not be loaded.
+    ///
+    /// returns: Vec<(Handle, ReadError)>
+    pub fn load_all_tolerant(&mut self) -> Vec<(Handle, ReadError)>
+    {
+        let mut errors = Vec::new();
+        for mut section in self.iter_mut() {
+            if let Err(e) = section.load() {
+                errors.push((section.handle(), e));
+            }
+        }
+        errors
+    }
+
+    /// Checks if two sections of this container have the same content.
+    ///
+    /// Both sections are loaded if needed. See [compare](crate::core::compare).
+    ///
+    /// # Arguments
+    ///
+    /// * `h1`: a handle to the first section.
+    /// * `h2`: a handle to the second section.
+    ///
+    /// returns: Result<bool, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if a section could not be
+    /// loaded or read.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the given section handles is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let h1 = file.create_section(SectionHeaderBuilder::new());
+    /// let h2 = file.create_section(SectionHeaderBuilder::new());
+    /// file.get_mut(h1).open().unwrap().write_all(b"test").unwrap();
+    /// assert!(!file.sections_equal(h1, h2).unwrap());
+    /// file.get_mut(h2).open().unwrap().write_all(b"test").unwrap();
+    /// assert!(file.sections_equal(h1, h2).unwrap());
+    /// ```
+    pub fn sections_equal(&mut self, h1: Handle, h2: Handle) -> Result<bool, ReadError>
+    {
+        self.get_mut(h1).load()?;
+        if h1 == h2 {
+            return Ok(true);
+        }
+        self.get_mut(h2).load()?;
+        let mut a = self.sections.get_mut(&h1.0).unwrap().data.take().unwrap();
+        let res = compare(
+            &mut a,
+            self.sections.get_mut(&h2.0).unwrap().data.as_mut().unwrap()
+        );
+        self.sections.get_mut(&h1.0).unwrap().data = Some(a);
+        Ok(res?)
+    }
+
+    /// Checks if a section of this container has the same content as a section of another
+    /// container.
+    ///
+    /// Both sections are loaded if needed. See [compare](crate::core::compare).
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section in this container.
+    /// * `other`: the other container.
+    /// * `other_handle`: a handle to the section in `other`.
+    ///
+    /// returns: Result<bool, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if a section could not be
+    /// loaded or read.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the given section handles is invalid.
+    pub fn sections_equal_with<U: io::Read + io::Seek>(
+        &mut self,
+        handle: Handle,
+        other: &mut Container<U>,
+        other_handle: Handle
+    ) -> Result<bool, ReadError>
+    {
+        let mut section = self.get_mut(handle);
+        let a = section.load()?;
+        let mut section = other.get_mut(other_handle);
+        let b = section.load()?;
+        Ok(compare(a, b)?)
+    }
+
+    /// Opens a forward-only reader over the content of a section.
+    ///
+    /// Unlike [load](crate::core::SectionMut::load), the section is decompressed on the fly
+    /// while reading and is never entirely decoded in memory. The checksum of the section is
+    /// verified when the end of the section is reached.
+    ///
+    /// *If the section is already loaded, the reader reads the loaded data instead and moves
+    /// its cursor.*
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: Result<SectionReader<T>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if the backend could not be
+    /// positioned or the decompressor could not be initialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use bpx::core::builder::{CompressionMethod, MainHeaderBuilder, SectionHeaderBuilder};
     /// use bpx::core::Container;
     /// use bpx::utils::new_byte_buf;
     ///
     /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(
+    ///     SectionHeaderBuilder::new()
+    ///         .compression(CompressionMethod::Zlib)
+    ///         .threshold(0)
+    /// );
+    /// file.get_mut(handle).open().unwrap().write_all(b"test").unwrap();
     /// file.save().unwrap();
-    /// let mut buf = file.into_inner();
-    /// buf.set_position(0);
-    /// let file = Container::open(buf).unwrap();
-    /// //Default BPX variant/type is 'P'
-    /// assert_eq!(file.get_main_header().ty, 'P' as u8);
+    /// let mut bpx = file.into_inner();
+    /// bpx.set_position(0);
+    /// let mut file = Container::open(bpx).unwrap();
+    /// let handle = file.find_section_by_index(0).unwrap();
+    /// let mut buf = Vec::new();
+    /// file.section_reader(handle).unwrap().read_to_end(&mut buf).unwrap();
+    /// assert_eq!(buf, b"test");
+    /// assert!(file.get(handle).open().is_none());
     /// ```
-    pub fn open(mut backend: T) -> Result<Container<T>, ReadError>
+    pub fn section_reader(&mut self, handle: Handle) -> Result<SectionReader<'_, T>, ReadError>
     {
-        let (checksum, header) = MainHeader::read(&mut backend)?;
-        let (next_handle, sections) = read_section_header_table(&mut backend, &header, checksum)?;
-        Ok(Container {
-            backend,
-            main_header: header,
-            sections,
-            next_handle,
-            modified: false
-        })
+        let entry = self
+            .sections
+            .get_mut(&handle.0)
+            .expect("attempt to use invalid handle");
+        if entry.data.is_none() && entry.header.flags & FLAG_ENCRYPT_AES_GCM != 0 {
+            //Encrypted sections can't be streamed: the authentication tag must be verified
+            // before any data is returned
+            entry.data = Some(load_section1(
+                &mut self.backend,
+                &entry.header,
+                &entry.entry1
+            )?);
+        }
+        match &mut entry.data {
+            Some(data) => new_loaded_section_reader(data),
+            None => new_section_reader(&mut self.backend, &entry.header)
+        }
+    }
+
+    /// Writes this container to a different IO backend.
+    ///
+    /// Sections which were never loaded are streamed from the original backend. Neither the
+    /// original backend nor the state of this container are changed: a later call to
+    /// [save](Container::save) still writes all pending changes to the original backend.
+    ///
+    /// # Arguments
+    ///
+    /// * `out`: A [Write](std::io::Write) + [Seek](std::io::Seek) backend to write the container to.
+    ///
+    /// returns: Result<(), WriteError>
+    ///
+    /// # Errors
+    ///
+    /// A [WriteError](crate::core::error::WriteError) is returned if some data could
+    /// not be read from the original backend or written to `out`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new());
+    /// file.get_mut(handle).open().unwrap().write_all(b"test").unwrap();
+    /// let mut out = new_byte_buf(0);
+    /// file.save_to(&mut out).unwrap();
+    /// out.set_position(0);
+    /// let file = Container::open(out).unwrap();
+    /// assert_eq!(file.get_main_header().section_num, 1);
+    /// ```
+    pub fn save_to<W: io::Write + io::Seek>(&mut self, out: W) -> Result<(), WriteError>
+    {
+        internal_save_to(
+            &mut self.backend,
+            out,
+            &mut self.sections,
+            &self.main_header
+        )
+    }
+
+    /// Sets the compression and checksum flags to apply to a section on the next save.
+    ///
+    /// *The section is loaded if it is not already, as it has to be re-written.*
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    /// * `flags`: the new flags (see FLAG_* constants).
+    ///
+    /// returns: Result<(), ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if the section could not
+    /// be loaded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::header::FLAG_COMPRESS_XZ;
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new());
+    /// file.set_flags(handle, FLAG_COMPRESS_XZ).unwrap();
+    /// assert_eq!(file.get_section_policy(handle).flags, FLAG_COMPRESS_XZ);
+    /// ```
+    pub fn set_flags(&mut self, handle: Handle, flags: u8) -> Result<(), ReadError>
+    {
+        self.get_mut(handle).load()?;
+        let entry = self
+            .sections
+            .get_mut(&handle.0)
+            .expect("attempt to use invalid handle");
+        entry.entry1.flags = flags;
+        entry.modified = true;
+        Ok(())
+    }
+
+    /// Sets the maximum size in bytes to keep a section uncompressed on the next save.
+    ///
+    /// *The section is loaded if it is not already, as it has to be re-written.*
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    /// * `threshold`: the new compression threshold.
+    ///
+    /// returns: Result<(), ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if the section could not
+    /// be loaded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    pub fn set_compression_threshold(
+        &mut self,
+        handle: Handle,
+        threshold: u32
+    ) -> Result<(), ReadError>
+    {
+        self.get_mut(handle).load()?;
+        let entry = self
+            .sections
+            .get_mut(&handle.0)
+            .expect("attempt to use invalid handle");
+        entry.entry1.threshold = threshold;
+        entry.modified = true;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl Container<std::fs::File>
+{
+    /// Loads a BPX container from the given file, mapping it in memory.
+    ///
+    /// Uncompressed sections are not copied: they are served directly from the mapping and are
+    /// only copied into a writable buffer on the first write. Compressed sections are still
+    /// decoded the usual way when loaded.
+    ///
+    /// *The checksums of memory-mapped sections are verified when opening, which reads
+    /// their whole content once.*
+    ///
+    /// # Arguments
+    ///
+    /// * `file`: the file to map.
+    ///
+    /// returns: Result<Container<File>, Error>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if the file could not be mapped,
+    /// if some headers could not be read, if the header data is corrupted or if the checksum
+    /// of a memory-mapped section does not match.
+    ///
+    /// # Safety
+    ///
+    /// The underlying file must not be modified or truncated, by this process or any other,
+    /// as long as the container or any section loaded from it is alive. In particular, saving
+    /// the container back into the same file is not supported. A modification made after
+    /// opening is not detected by checksum verification.
+    pub unsafe fn open_mmap(file: std::fs::File) -> Result<Container<std::fs::File>, ReadError>
+    {
+        Container::open_mmap_with_options(file, &OpenOptions::new())
+    }
+
+    /// Loads a BPX container from the given file with the given [OpenOptions], mapping it in
+    /// memory.
+    ///
+    /// Checksums of memory-mapped sections are not verified when
+    /// [skip_checksum](OpenOptions::skip_checksum) is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `file`: the file to map.
+    /// * `options`: the [OpenOptions] to use.
+    ///
+    /// returns: Result<Container<File>, Error>
+    ///
+    /// # Errors
+    ///
+    /// See [open_mmap](Container::open_mmap) and
+    /// [open_with_options](Container::open_with_options).
+    ///
+    /// # Safety
+    ///
+    /// See [open_mmap](Container::open_mmap).
+    pub unsafe fn open_mmap_with_options(
+        file: std::fs::File,
+        options: &OpenOptions
+    ) -> Result<Container<std::fs::File>, ReadError>
+    {
+        let map = std::sync::Arc::new(memmap2::Mmap::map(&file)?);
+        let mut container = Container::open_with_options(file, options)?;
+        for entry in container.sections.values_mut() {
+            let header = &entry.header;
+            let end = header.pointer + header.csize as u64;
+            if header.flags & (FLAG_COMPRESS_XZ | FLAG_COMPRESS_ZLIB | FLAG_ENCRYPT_AES_GCM) != 0
+                || header.csize != header.size
+                || end > map.len() as u64
+            {
+                continue;
+            }
+            let mut data = new_mmap_section(
+                map.clone(),
+                header.pointer as usize,
+                header.size as usize
+            );
+            if let (Some(algo), false) = (header.checksum(), entry.entry1.skip_checksum) {
+                let actual = checksum_data(&mut data, algo)?;
+                if let ChecksumStatus::Mismatch(expected, actual) =
+                    loaded_checksum_status(&mut data, header, Some(actual))?
+                {
+                    return Err(ReadError::Checksum(actual, expected));
+                }
+            }
+            entry.data = Some(data);
+        }
+        Ok(container)
     }
 }
 
@@ -465,12 +2475,124 @@ impl<T: io::Write + io::Seek> Container<T>
             modified: true,
             main_header: header.into(),
             next_handle: 0,
-            sections: BTreeMap::new()
+            sections: BTreeMap::new(),
+            memory_threshold: DEFAULT_MEMORY_THRESHOLD,
+            key: None,
+            skip_checksum: false,
+            save_memory_budget: None,
+            max_section_size: None,
+            max_total_memory: None,
+            available_memory: AtomicU64::new(u64::MAX),
+            default_compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            trailer: false,
+            tag: ContainerTag::new()
         }
     }
 
+    /// Writes all sections to the underlying IO backend, compressing sections in parallel.
+    ///
+    /// Sections are compressed on the rayon thread pool, then written in order: the output is
+    /// identical to [save](Container::save) regardless of the number of threads. Unlike
+    /// [save](Container::save), all sections are always re-written. Like
+    /// [save](Container::save), this removes a whole-file trailer.
+    ///
+    /// *Sections are compressed by batches of one section per thread: only the compressed data
+    /// of the current batch is buffered. The memory budget set with
+    /// [set_save_memory_budget](Container::set_save_memory_budget) applies like in
+    /// [save](Container::save).*
+    ///
+    /// # Errors
+    ///
+    /// A [WriteError](crate::core::error::WriteError) is returned if some data could
+    /// not be written or if a section is not loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{CompressionMethod, MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// for _ in 0..4 {
+    ///     let handle = file.create_section(
+    ///         SectionHeaderBuilder::new()
+    ///             .compression(CompressionMethod::Zlib)
+    ///             .threshold(0)
+    ///     );
+    ///     file.get_mut(handle).open().unwrap().write_all(b"test").unwrap();
+    /// }
+    /// file.save_parallel().unwrap();
+    /// let mut buf = file.into_inner();
+    /// buf.set_position(0);
+    /// let file = Container::open(buf).unwrap();
+    /// assert_eq!(file.get_main_header().section_num, 4);
+    /// ```
+    #[cfg(feature = "multithreading")]
+    pub fn save_parallel(&mut self) -> Result<(), WriteError>
+    {
+        self.save_parallel_with_progress(log_section)
+    }
+
+    /// Writes all sections to the underlying IO backend like
+    /// [save_parallel](Container::save_parallel) and reports progress after each written
+    /// section.
+    ///
+    /// Progress is reported in index order, once a section has been written to the backend.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress`: the function to call after each section is written.
+    ///
+    /// # Errors
+    ///
+    /// A [WriteError](crate::core::error::WriteError) is returned if some data could
+    /// not be written or if a section is not loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// for data in [&b"first"[..], &b"second"[..]] {
+    ///     let section = file.create_section(SectionHeaderBuilder::new());
+    ///     file.get_mut(section).open().unwrap().write_all(data).unwrap();
+    /// }
+    /// let mut events = Vec::new();
+    /// file.save_parallel_with_progress(|event| events.push((event.index, event.bytes)))
+    ///     .unwrap();
+    /// assert_eq!(events, vec![(0, 5), (1, 11)]);
+    /// ```
+    #[cfg(feature = "multithreading")]
+    pub fn save_parallel_with_progress<P: FnMut(SaveProgress)>(
+        &mut self,
+        mut progress: P
+    ) -> Result<(), WriteError>
+    {
+        trace_span!("bpx::save", sections = self.main_header.section_num);
+        let file_size = self.main_header.file_size;
+        self.modified = false;
+        internal_save_parallel(
+            &mut self.backend,
+            &mut self.sections,
+            &mut self.main_header,
+            self.save_memory_budget,
+            self.tag,
+            &mut progress
+        )?;
+        self.discard_trailer(file_size)
+    }
+
     /// Writes all sections to the underlying IO backend.
     ///
+    /// A whole-file trailer written by [save_with_trailer](Container::save_with_trailer) is
+    /// removed if any data is written.
+    ///
     /// **This function prints some information to standard output as a way
     /// to debug data compression issues unless the `debug-log` feature
     /// is disabled.**
@@ -494,29 +2616,209 @@ impl<T: io::Write + io::Seek> Container<T>
     /// ```
     pub fn save(&mut self) -> Result<(), WriteError>
     {
-        let mut filter = self.sections.iter().filter(|(_, entry)| entry.modified);
-        let count = filter.by_ref().count();
+        self.save_with_progress(log_section)
+    }
+
+    /// Writes all sections to the underlying IO backend and reports progress after each
+    /// written section.
+    ///
+    /// The total number of bytes is the sum of the sizes of all written sections. When only
+    /// the last section was modified, only that section is written and reported.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress`: the function to call after each section is written.
+    ///
+    /// # Errors
+    ///
+    /// A [WriteError](crate::core::error::WriteError) is returned if some data could
+    /// not be written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let section = file.create_section(SectionHeaderBuilder::new());
+    /// file.get_mut(section).open().unwrap().write_all(b"data").unwrap();
+    /// let mut events = Vec::new();
+    /// file.save_with_progress(|event| events.push((event.index, event.bytes, event.total)))
+    ///     .unwrap();
+    /// assert_eq!(events, vec![(0, 4, 4)]);
+    /// ```
+    pub fn save_with_progress<P: FnMut(SaveProgress)>(
+        &mut self,
+        mut progress: P
+    ) -> Result<(), WriteError>
+    {
+        trace_span!("bpx::save", sections = self.main_header.section_num);
+        let mut filter = self
+            .sections
+            .iter()
+            .filter(|(_, entry)| entry.is_modified())
+            .map(|(handle, _)| *handle);
+        let first = filter.next();
+        let count = first.map(|_| 1 + filter.count()).unwrap_or(0);
+        let file_size = self.main_header.file_size;
         if self.modified || count > 1 {
             self.modified = false;
-            internal_save(&mut self.backend, &mut self.sections, &mut self.main_header)
-        } else if !self.modified && count == 1 {
-            let (handle, _) = filter.last().unwrap();
-            if *handle == self.next_handle - 1 {
+            internal_save(
+                &mut self.backend,
+                &mut self.sections,
+                &mut self.main_header,
+                self.save_memory_budget,
+                self.tag,
+                &mut progress
+            )?;
+        } else if let Some(handle) = first {
+            if self.sections[&handle].index + 1 == self.main_header.section_num {
                 //Save only the last section (no need to re-write every other section
                 internal_save_last(
                     &mut self.backend,
                     &mut self.sections,
                     &mut self.main_header,
-                    self.next_handle - 1
-                )
+                    handle
+                )?;
+                let entry = &self.sections[&handle];
+                progress(SaveProgress {
+                    handle: Handle(handle, self.tag),
+                    index: entry.index,
+                    size: entry.header.size,
+                    csize: entry.header.csize,
+                    bytes: entry.header.size as u64,
+                    total: entry.header.size as u64
+                });
             } else {
                 //Unfortunately the modified section is not the last one so we can't safely
                 //expand/reduce the file size without corrupting other sections
                 self.modified = false;
-                internal_save(&mut self.backend, &mut self.sections, &mut self.main_header)
+                internal_save(
+                    &mut self.backend,
+                    &mut self.sections,
+                    &mut self.main_header,
+                    self.save_memory_budget,
+                    self.tag,
+                    &mut progress
+                )?;
             }
         } else {
-            Ok(())
+            //Nothing was written: the trailer is still valid
+            return Ok(());
+        }
+        self.discard_trailer(file_size)
+    }
+
+    /// Removes the trailer which was located at `file_size` before saving as it no longer
+    /// matches the content of the file.
+    fn discard_trailer(&mut self, file_size: u64) -> Result<(), WriteError>
+    {
+        //A trailer located before the new end of the data was already overwritten
+        if self.trailer && file_size >= self.main_header.file_size {
+            strip_trailer(&mut self.backend, file_size)?;
+        }
+        self.trailer = false;
+        Ok(())
+    }
+}
+
+impl<T: io::Read + io::Write + io::Seek> Container<T>
+{
+    /// Decodes every section of this container and saves it again with new settings.
+    ///
+    /// Sections for which `options` has no settings keep their current flags, compression
+    /// threshold and compression level.
+    ///
+    /// # Arguments
+    ///
+    /// * `options`: the new settings of sections.
+    ///
+    /// returns: Result<(), RepackError>
+    ///
+    /// # Errors
+    ///
+    /// A [RepackError](crate::core::error::RepackError) is returned if some sections could
+    /// not be loaded or if the container could not be saved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, RepackOptions, SectionHeaderBuilder, CompressionMethod};
+    /// use bpx::core::header::FLAG_COMPRESS_XZ;
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new().ty(1));
+    /// file.get_mut(handle).open().unwrap().write_all(&[0; 256]).unwrap();
+    /// file.save().unwrap();
+    /// file.repack(RepackOptions::new().all(
+    ///     SectionHeaderBuilder::new()
+    ///         .compression(CompressionMethod::Xz)
+    ///         .threshold(0)
+    /// )).unwrap();
+    /// assert_eq!(file.get(handle).flags, FLAG_COMPRESS_XZ);
+    /// ```
+    pub fn repack(&mut self, options: &RepackOptions) -> Result<(), RepackError>
+    {
+        for mut section in self.iter_mut() {
+            section.load()?;
         }
+        for entry in self.sections.values_mut() {
+            if let Some(settings) = options.get(entry.header.ty) {
+                entry.entry1.flags = settings.header.flags;
+                entry.entry1.threshold = settings
+                    .threshold
+                    .unwrap_or(self.default_compression_threshold);
+                entry.entry1.level = settings.level;
+            }
+            entry.modified = true;
+        }
+        self.modified = true;
+        self.save()?;
+        Ok(())
+    }
+
+    /// Writes all sections to the underlying IO backend like [save](Container::save), then
+    /// appends a trailer containing the CRC32 of the whole file.
+    ///
+    /// The trailer detects any truncation or corruption of the file, including in sections
+    /// which have no checksum. Readers which do not know about the trailer ignore it.
+    ///
+    /// *A subsequent call to [save](Container::save) removes the trailer, as it would no longer
+    /// match the file; call this function instead to keep a valid trailer.*
+    ///
+    /// # Errors
+    ///
+    /// A [WriteError](crate::core::error::WriteError) is returned if some data could
+    /// not be written or read back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::builder::{MainHeaderBuilder, SectionHeaderBuilder};
+    /// use bpx::core::Container;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    /// let handle = file.create_section(SectionHeaderBuilder::new());
+    /// file.get_mut(handle).open().unwrap().write_all(b"test").unwrap();
+    /// file.save_with_trailer().unwrap();
+    /// let mut buf = file.into_inner();
+    /// buf.set_position(0);
+    /// let mut file = Container::open(buf).unwrap();
+    /// assert!(file.has_valid_trailer().unwrap());
+    /// ```
+    pub fn save_with_trailer(&mut self) -> Result<(), WriteError>
+    {
+        self.save()?;
+        write_trailer(&mut self.backend, self.main_header.file_size)?;
+        self.trailer = true;
+        Ok(())
     }
 }
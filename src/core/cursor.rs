@@ -0,0 +1,118 @@
+// Copyright (c) 2021, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom};
+
+use crate::core::{data::AutoSectionData, SectionData};
+
+/// A read cursor over a loaded section.
+///
+/// Each cursor holds its own position and reads the section data at that position without
+/// moving the cursor of the section itself, so that several cursors can read the same section
+/// at the same time.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{Read, Seek, SeekFrom, Write};
+/// use bpx::core::{builder::{MainHeaderBuilder, SectionHeaderBuilder}, Container};
+/// use bpx::utils::new_byte_buf;
+///
+/// let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+/// let handle = container.create_section(SectionHeaderBuilder::new());
+/// container.get_mut(handle).open().unwrap().write_all(b"Hello world").unwrap();
+/// let mut a = container.open_cursor(handle).unwrap();
+/// let mut b = container.open_cursor(handle).unwrap();
+/// b.seek(SeekFrom::Start(6)).unwrap();
+/// let mut buf = [0; 5];
+/// b.read_exact(&mut buf).unwrap();
+/// assert_eq!(&buf, b"world");
+/// a.read_exact(&mut buf).unwrap();
+/// assert_eq!(&buf, b"Hello");
+/// ```
+pub struct SectionCursor<'a>
+{
+    data: &'a AutoSectionData,
+    pos: u64
+}
+
+impl<'a> SectionCursor<'a>
+{
+    /// Creates a new cursor at the start of the given section data.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: the section data to read.
+    ///
+    /// returns: SectionCursor
+    pub fn new(data: &'a AutoSectionData) -> SectionCursor<'a>
+    {
+        SectionCursor { data, pos: 0 }
+    }
+
+    /// Returns the current position of this cursor.
+    pub fn position(&self) -> u64
+    {
+        self.pos
+    }
+
+    /// Returns the size of the underlying section data.
+    pub fn size(&self) -> usize
+    {
+        self.data.size()
+    }
+}
+
+impl<'a> Read for SectionCursor<'a>
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize>
+    {
+        let len = self.data.read_at(self.pos, buf)?;
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl<'a> Seek for SectionCursor<'a>
+{
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64>
+    {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(offset) => (self.data.size() as u64).checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset)
+        };
+        self.pos = pos.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position"
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
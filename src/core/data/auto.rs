@@ -30,28 +30,38 @@ use std::io::{Read, Seek, SeekFrom, Write};
 
 use tempfile::tempfile;
 
+#[cfg(feature = "mmap")]
+use crate::core::data::mmap::MmapSection;
 use crate::core::{
     data::{file::FileBasedSection, memory::InMemorySection},
-    SectionData
+    SectionData,
+    DEFAULT_MEMORY_THRESHOLD
 };
 
-const MEMORY_THRESHOLD: u32 = 100000000;
 const INIT_BUF_SIZE: usize = 512;
 
 #[allow(clippy::large_enum_variant)] // This is always used behind a Box
 enum DynSectionData
 {
     File(FileBasedSection),
-    Memory(InMemorySection)
+    Memory(InMemorySection),
+    #[cfg(feature = "mmap")]
+    Mmap(MmapSection)
 }
 
 /// Automatic section data implementation.
 ///
 /// *This automatically switches an in-memory section data into a file backed section data
-/// when the size of the data exceeds 100Mb.*
+/// when the size of the data exceeds a threshold (by default
+/// [DEFAULT_MEMORY_THRESHOLD](crate::core::DEFAULT_MEMORY_THRESHOLD)).*
+///
+/// *When the `mmap` feature is enabled, this may also be a read-only view into a memory-mapped
+/// file which is transparently copied into a writable buffer on the first write.*
 pub struct AutoSectionData
 {
-    inner: Box<DynSectionData>
+    inner: Box<DynSectionData>,
+    threshold: usize,
+    written: bool
 }
 
 impl Default for AutoSectionData
@@ -68,7 +78,38 @@ impl AutoSectionData
     pub fn new() -> AutoSectionData
     {
         AutoSectionData {
-            inner: Box::new(DynSectionData::Memory(InMemorySection::new(512)))
+            inner: Box::new(DynSectionData::Memory(InMemorySection::new(INIT_BUF_SIZE))),
+            threshold: DEFAULT_MEMORY_THRESHOLD,
+            written: false
+        }
+    }
+
+    /// Creates a new section data which switches to a file once its size exceeds `threshold`.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold`: the maximum size in bytes to keep in memory.
+    ///
+    /// returns: AutoSectionData
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Read, Seek, SeekFrom, Write};
+    /// use bpx::core::{AutoSectionData, SectionData};
+    ///
+    /// let mut data = AutoSectionData::new_with_threshold(16);
+    /// data.write_all(&[1; 64]).unwrap();
+    /// assert_eq!(data.size(), 64);
+    /// data.seek(SeekFrom::Start(0)).unwrap();
+    /// assert_eq!(data.load_in_memory().unwrap(), vec![1; 64]);
+    /// ```
+    pub fn new_with_threshold(threshold: usize) -> AutoSectionData
+    {
+        AutoSectionData {
+            inner: Box::new(DynSectionData::Memory(InMemorySection::new(INIT_BUF_SIZE))),
+            threshold,
+            written: false
         }
     }
 
@@ -86,35 +127,153 @@ impl AutoSectionData
     /// given the size constraint, but failed to initialize.
     pub fn new_with_size(size: u32) -> std::io::Result<AutoSectionData>
     {
-        if size >= MEMORY_THRESHOLD {
-            let file = FileBasedSection::new(tempfile()?);
-            Ok(AutoSectionData {
-                inner: Box::new(DynSectionData::File(file))
-            })
-        } else {
-            Ok(AutoSectionData {
-                inner: Box::new(DynSectionData::Memory(InMemorySection::new(size as usize)))
-            })
-        }
+        new_section_data(Some(size), DEFAULT_MEMORY_THRESHOLD)
     }
 
     unsafe fn move_to_file(&mut self) -> std::io::Result<()>
     {
         let mut file = FileBasedSection::new(tempfile()?);
         match &mut *self.inner {
-            DynSectionData::Memory(m) => std::io::copy(m, &mut file),
+            DynSectionData::Memory(m) => {
+                let pos = m.stream_position()?;
+                m.seek(SeekFrom::Start(0))?;
+                std::io::copy(m, &mut file)?;
+                file.seek(SeekFrom::Start(pos))
+            },
             //SAFETY: If the section is not an InMemorySection then move_to_file is not supposed to have been called,
             // and that is an unrecoverable internal BPX error
-            DynSectionData::File(_) => std::hint::unreachable_unchecked()
+            DynSectionData::File(_) => std::hint::unreachable_unchecked(),
+            #[cfg(feature = "mmap")]
+            DynSectionData::Mmap(_) => std::hint::unreachable_unchecked()
         }?;
-        self.inner = Box::new(DynSectionData::File(file));
+        *self.inner = DynSectionData::File(file);
+        Ok(())
+    }
+
+    /// Returns true if the data of this section is held in an in-memory buffer.
+    pub fn is_in_memory(&self) -> bool
+    {
+        matches!(&*self.inner, DynSectionData::Memory(_))
+    }
+
+    /// Reserves capacity for at least `additional` more bytes in the in-memory buffer of
+    /// this section.
+    ///
+    /// *Has no effect if the section is not held in memory or if the reservation would
+    /// exceed the memory threshold of this section.*
+    ///
+    /// # Arguments
+    ///
+    /// * `additional`: the number of bytes to reserve.
+    pub fn reserve(&mut self, additional: usize)
+    {
+        let size = self.size();
+        if let DynSectionData::Memory(m) = &mut *self.inner {
+            if size.saturating_add(additional) <= self.threshold {
+                m.reserve(additional);
+            }
+        }
+    }
+
+    /// Consumes this section data and returns its whole content.
+    ///
+    /// *The in-memory buffer is returned as is, without copying it; the content of other
+    /// section data is read from the start.*
+    ///
+    /// # Errors
+    ///
+    /// An [Error](std::io::Error) is returned if the section data could not be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use bpx::core::{AutoSectionData, SectionData};
+    ///
+    /// let mut data = AutoSectionData::new();
+    /// data.write_all(b"test").unwrap();
+    /// let ptr = data.as_slice().unwrap().as_ptr();
+    /// let buf = data.into_vec().unwrap();
+    /// assert_eq!(buf, b"test");
+    /// assert_eq!(buf.as_ptr(), ptr);
+    /// ```
+    pub fn into_vec(mut self) -> std::io::Result<Vec<u8>>
+    {
+        match *self.inner {
+            DynSectionData::Memory(m) => Ok(m.into_vec()),
+            _ => {
+                self.seek(SeekFrom::Start(0))?;
+                self.load_in_memory()
+            }
+        }
+    }
+
+    /// Moves the data of this section to a temporary file if it is held in an in-memory buffer.
+    ///
+    /// *Has no effect if the section is not held in memory.*
+    ///
+    /// # Errors
+    ///
+    /// An [Error](std::io::Error) is returned if the temporary file could not be created or
+    /// written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Read, Seek, SeekFrom, Write};
+    /// use bpx::core::{AutoSectionData, SectionData};
+    ///
+    /// let mut data = AutoSectionData::new();
+    /// data.write_all(b"test").unwrap();
+    /// assert!(data.is_in_memory());
+    /// data.spill().unwrap();
+    /// assert!(!data.is_in_memory());
+    /// data.seek(SeekFrom::Start(0)).unwrap();
+    /// assert_eq!(data.load_in_memory().unwrap(), b"test");
+    /// ```
+    pub fn spill(&mut self) -> std::io::Result<()>
+    {
+        if self.is_in_memory() {
+            unsafe {
+                self.move_to_file()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy-on-write: moves a memory-mapped section into a writable buffer.
+    #[cfg(feature = "mmap")]
+    fn detach_mmap(&mut self) -> std::io::Result<()>
+    {
+        if let DynSectionData::Mmap(m) = &*self.inner {
+            let data = m.as_slice();
+            let pos = m.position();
+            let mut new = new_section_data(Some(data.len() as u32), self.threshold)?;
+            new.write_all(data)?;
+            new.seek(SeekFrom::Start(pos))?;
+            *self = new;
+        }
         Ok(())
     }
 
     /// Clears this section data and resets to a default dynamically sized in-memory buffer.
     pub fn clear(&mut self)
     {
-        self.inner = Box::new(DynSectionData::Memory(InMemorySection::new(INIT_BUF_SIZE)))
+        *self.inner = DynSectionData::Memory(InMemorySection::new(INIT_BUF_SIZE));
+        self.written = true;
+    }
+
+    /// Returns true if this section data was changed since it was created or since the last
+    /// call to [clear_written](AutoSectionData::clear_written).
+    pub(crate) fn is_written(&self) -> bool
+    {
+        self.written
+    }
+
+    /// Marks the current content of this section data as unchanged.
+    pub(crate) fn clear_written(&mut self)
+    {
+        self.written = false;
     }
 }
 
@@ -124,7 +283,9 @@ impl Read for AutoSectionData
     {
         match &mut *self.inner {
             DynSectionData::File(f) => f.read(buf),
-            DynSectionData::Memory(m) => m.read(buf)
+            DynSectionData::Memory(m) => m.read(buf),
+            #[cfg(feature = "mmap")]
+            DynSectionData::Mmap(m) => m.read(buf)
         }
     }
 }
@@ -133,17 +294,22 @@ impl Write for AutoSectionData
 {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize>
     {
+        #[cfg(feature = "mmap")]
+        self.detach_mmap()?;
+        self.written = true;
         match &mut *self.inner {
             DynSectionData::File(f) => f.write(buf),
             DynSectionData::Memory(m) => {
                 let size = m.write(buf)?;
-                if m.size() >= MEMORY_THRESHOLD as usize {
+                if m.size() > self.threshold {
                     unsafe {
                         self.move_to_file()?;
                     }
                 }
                 Ok(size)
-            }
+            },
+            #[cfg(feature = "mmap")]
+            DynSectionData::Mmap(m) => m.write(buf)
         }
     }
 
@@ -151,7 +317,9 @@ impl Write for AutoSectionData
     {
         match &mut *self.inner {
             DynSectionData::File(f) => f.flush(),
-            DynSectionData::Memory(m) => m.flush()
+            DynSectionData::Memory(m) => m.flush(),
+            #[cfg(feature = "mmap")]
+            DynSectionData::Mmap(m) => m.flush()
         }
     }
 }
@@ -162,7 +330,9 @@ impl Seek for AutoSectionData
     {
         match &mut *self.inner {
             DynSectionData::File(f) => f.seek(pos),
-            DynSectionData::Memory(m) => m.seek(pos)
+            DynSectionData::Memory(m) => m.seek(pos),
+            #[cfg(feature = "mmap")]
+            DynSectionData::Mmap(m) => m.seek(pos)
         }
     }
 }
@@ -173,7 +343,9 @@ impl SectionData for AutoSectionData
     {
         match &mut *self.inner {
             DynSectionData::File(f) => f.load_in_memory(),
-            DynSectionData::Memory(m) => m.load_in_memory()
+            DynSectionData::Memory(m) => m.load_in_memory(),
+            #[cfg(feature = "mmap")]
+            DynSectionData::Mmap(m) => m.load_in_memory()
         }
     }
 
@@ -181,7 +353,73 @@ impl SectionData for AutoSectionData
     {
         match &*self.inner {
             DynSectionData::File(f) => f.size(),
-            DynSectionData::Memory(m) => m.size()
+            DynSectionData::Memory(m) => m.size(),
+            #[cfg(feature = "mmap")]
+            DynSectionData::Mmap(m) => m.size()
         }
     }
+
+    fn read_at(&self, pos: u64, buf: &mut [u8]) -> std::io::Result<usize>
+    {
+        match &*self.inner {
+            DynSectionData::File(f) => f.read_at(pos, buf),
+            DynSectionData::Memory(m) => m.read_at(pos, buf),
+            #[cfg(feature = "mmap")]
+            DynSectionData::Mmap(m) => m.read_at(pos, buf)
+        }
+    }
+
+    fn as_slice(&self) -> Option<&[u8]>
+    {
+        match &*self.inner {
+            DynSectionData::File(_) => None,
+            DynSectionData::Memory(m) => m.as_slice(),
+            #[cfg(feature = "mmap")]
+            DynSectionData::Mmap(m) => SectionData::as_slice(m)
+        }
+    }
+
+    fn truncate(&mut self, size: usize) -> std::io::Result<()>
+    {
+        self.written = true;
+        match &mut *self.inner {
+            DynSectionData::File(f) => f.truncate(size),
+            DynSectionData::Memory(m) => m.truncate(size),
+            #[cfg(feature = "mmap")]
+            DynSectionData::Mmap(m) => m.truncate(size)
+        }
+    }
+}
+
+/// Creates a new section data which switches to a file once its size exceeds `threshold`.
+///
+/// When `size` is known in advance and already exceeds `threshold` the section data starts
+/// directly as a file backed section.
+pub fn new_section_data(size: Option<u32>, threshold: usize) -> std::io::Result<AutoSectionData>
+{
+    let size = size.map(|v| v as usize).unwrap_or(INIT_BUF_SIZE);
+    let inner = if size > threshold {
+        DynSectionData::File(FileBasedSection::new(tempfile()?))
+    } else {
+        DynSectionData::Memory(InMemorySection::new(size))
+    };
+    Ok(AutoSectionData {
+        inner: Box::new(inner),
+        threshold,
+        written: false
+    })
+}
+
+#[cfg(feature = "mmap")]
+pub fn new_mmap_section(
+    map: std::sync::Arc<memmap2::Mmap>,
+    start: usize,
+    len: usize
+) -> AutoSectionData
+{
+    AutoSectionData {
+        inner: Box::new(DynSectionData::Mmap(MmapSection::new(map, start, len))),
+        threshold: DEFAULT_MEMORY_THRESHOLD,
+        written: false
+    }
 }
@@ -0,0 +1,159 @@
+// Copyright (c) 2021, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::io::{Cursor, Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+
+use crate::core::SectionData;
+
+enum Buffer<'a>
+{
+    ReadOnly(Cursor<&'a [u8]>),
+    ReadWrite(Cursor<&'a mut [u8]>)
+}
+
+/// A section data borrowing a caller-owned buffer.
+///
+/// *The size of the section is the size of the borrowed buffer: writes past the end of the
+/// buffer fail and writes to a read-only section are rejected.*
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+/// use bpx::core::{BorrowedSection, SectionData};
+///
+/// let data = b"Hello world";
+/// let mut section = BorrowedSection::new(data);
+/// assert_eq!(section.size(), 11);
+/// let mut s = String::new();
+/// section.read_to_string(&mut s).unwrap();
+/// assert_eq!(s, "Hello world");
+/// ```
+pub struct BorrowedSection<'a>
+{
+    buffer: Buffer<'a>
+}
+
+impl<'a> BorrowedSection<'a>
+{
+    /// Creates a new read-only section data over the given buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: the buffer to borrow.
+    ///
+    /// returns: BorrowedSection
+    pub fn new(data: &'a [u8]) -> BorrowedSection<'a>
+    {
+        BorrowedSection {
+            buffer: Buffer::ReadOnly(Cursor::new(data))
+        }
+    }
+
+    /// Creates a new read/write section data over the given buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: the buffer to borrow.
+    ///
+    /// returns: BorrowedSection
+    pub fn new_mut(data: &'a mut [u8]) -> BorrowedSection<'a>
+    {
+        BorrowedSection {
+            buffer: Buffer::ReadWrite(Cursor::new(data))
+        }
+    }
+
+    /// Returns the borrowed buffer.
+    pub fn as_slice(&self) -> &[u8]
+    {
+        match &self.buffer {
+            Buffer::ReadOnly(c) => c.get_ref(),
+            Buffer::ReadWrite(c) => c.get_ref()
+        }
+    }
+}
+
+impl<'a> Read for BorrowedSection<'a>
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize>
+    {
+        match &mut self.buffer {
+            Buffer::ReadOnly(c) => c.read(buf),
+            Buffer::ReadWrite(c) => c.read(buf)
+        }
+    }
+}
+
+impl<'a> Write for BorrowedSection<'a>
+{
+    fn write(&mut self, buf: &[u8]) -> Result<usize>
+    {
+        match &mut self.buffer {
+            Buffer::ReadOnly(_) => Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "borrowed section is read-only"
+            )),
+            Buffer::ReadWrite(c) => c.write(buf)
+        }
+    }
+
+    fn flush(&mut self) -> Result<()>
+    {
+        Ok(())
+    }
+}
+
+impl<'a> Seek for BorrowedSection<'a>
+{
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64>
+    {
+        match &mut self.buffer {
+            Buffer::ReadOnly(c) => c.seek(pos),
+            Buffer::ReadWrite(c) => c.seek(pos)
+        }
+    }
+}
+
+impl<'a> SectionData for BorrowedSection<'a>
+{
+    fn load_in_memory(&mut self) -> Result<Vec<u8>>
+    {
+        Ok(self.as_slice().to_vec())
+    }
+
+    fn size(&self) -> usize
+    {
+        self.as_slice().len()
+    }
+
+    fn as_slice(&self) -> Option<&[u8]>
+    {
+        Some(BorrowedSection::as_slice(self))
+    }
+}
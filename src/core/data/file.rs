@@ -28,13 +28,35 @@
 
 use std::{
     fs::File,
-    io::{Read, Result, Seek, SeekFrom, Write}
+    io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write}
 };
 
 use crate::core::SectionData;
 
 const READ_BLOCK_SIZE: usize = 8192;
 
+//Positioned reads do not rely on the file cursor as reads and writes always seek first
+#[cfg(unix)]
+fn read_file_at(file: &File, pos: u64, buf: &mut [u8]) -> Result<usize>
+{
+    std::os::unix::fs::FileExt::read_at(file, buf, pos)
+}
+
+#[cfg(windows)]
+fn read_file_at(file: &File, pos: u64, buf: &mut [u8]) -> Result<usize>
+{
+    std::os::windows::fs::FileExt::seek_read(file, buf, pos)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn read_file_at(_: &File, _: u64, _: &mut [u8]) -> Result<usize>
+{
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "positioned reads are not supported on this platform"
+    ))
+}
+
 pub struct FileBasedSection
 {
     data: File,
@@ -53,11 +75,17 @@ impl FileBasedSection
             data,
             buffer: [0; READ_BLOCK_SIZE],
             written: 0,
-            cursor: usize::MAX,
+            cursor: 0,
             cur_size: 0,
             seek_ptr: 0
         }
     }
+
+    fn invalidate_buffer(&mut self)
+    {
+        self.written = 0;
+        self.cursor = 0;
+    }
 }
 
 impl Read for FileBasedSection
@@ -66,16 +94,21 @@ impl Read for FileBasedSection
     {
         let mut cnt: usize = 0;
 
-        for byte in data {
+        while cnt < data.len() {
             if self.cursor >= self.written {
+                //The read buffer always starts at the current seek pointer
+                self.data.seek(SeekFrom::Start(self.seek_ptr))?;
                 self.cursor = 0;
                 self.written = self.data.read(&mut self.buffer)?;
+                if self.written == 0 {
+                    break;
+                }
             }
-            if self.cursor < self.written {
-                *byte = self.buffer[self.cursor];
-                self.cursor += 1;
-                cnt += 1;
-            }
+            let len = std::cmp::min(data.len() - cnt, self.written - self.cursor);
+            data[cnt..cnt + len].copy_from_slice(&self.buffer[self.cursor..self.cursor + len]);
+            self.cursor += len;
+            self.seek_ptr += len as u64;
+            cnt += len;
         }
         Ok(cnt)
     }
@@ -85,18 +118,22 @@ impl Write for FileBasedSection
 {
     fn write(&mut self, data: &[u8]) -> Result<usize>
     {
+        self.invalidate_buffer();
+        if self.seek_ptr > self.cur_size as u64 {
+            //Extending the file zero-fills the gap between the current end and the cursor
+            self.data.set_len(self.seek_ptr)?;
+        }
+        self.data.seek(SeekFrom::Start(self.seek_ptr))?;
         let len = self.data.write(data)?;
-        if self.seek_ptr >= self.cur_size as u64 {
-            self.cur_size += len;
-            self.seek_ptr += len as u64;
+        self.seek_ptr += len as u64;
+        if self.seek_ptr > self.cur_size as u64 {
+            self.cur_size = self.seek_ptr as usize;
         }
         Ok(len)
     }
 
     fn flush(&mut self) -> Result<()>
     {
-        self.data.seek(SeekFrom::Current(self.cursor as i64))?;
-        self.cursor = usize::MAX;
         self.data.flush()
     }
 }
@@ -105,7 +142,21 @@ impl Seek for FileBasedSection
 {
     fn seek(&mut self, state: SeekFrom) -> Result<u64>
     {
-        self.seek_ptr = self.data.seek(state)?;
+        let pos = match state {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(offset) => (self.cur_size as u64).checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.seek_ptr.checked_add_signed(offset)
+        };
+        let pos = pos.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position"
+            )
+        })?;
+        if pos != self.seek_ptr {
+            self.invalidate_buffer();
+            self.seek_ptr = pos;
+        }
         Ok(self.seek_ptr)
     }
 }
@@ -116,4 +167,35 @@ impl SectionData for FileBasedSection
     {
         self.cur_size
     }
+
+    fn read_at(&self, pos: u64, buf: &mut [u8]) -> Result<usize>
+    {
+        if pos >= self.cur_size as u64 {
+            return Ok(0);
+        }
+        let len = std::cmp::min(buf.len() as u64, self.cur_size as u64 - pos) as usize;
+        let mut cnt = 0;
+        while cnt < len {
+            let val = read_file_at(&self.data, pos + cnt as u64, &mut buf[cnt..len])?;
+            if val == 0 {
+                break;
+            }
+            cnt += val;
+        }
+        Ok(cnt)
+    }
+
+    fn truncate(&mut self, size: usize) -> Result<()>
+    {
+        if size >= self.cur_size {
+            return Ok(());
+        }
+        self.data.set_len(size as u64)?;
+        self.cur_size = size;
+        self.invalidate_buffer();
+        if self.seek_ptr > size as u64 {
+            self.seek_ptr = size as u64;
+        }
+        Ok(())
+    }
 }
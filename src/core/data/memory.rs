@@ -45,6 +45,18 @@ impl InMemorySection
             cur_size: 0
         }
     }
+
+    pub fn reserve(&mut self, additional: usize)
+    {
+        self.byte_buf.get_mut().reserve(additional);
+    }
+
+    pub fn into_vec(self) -> Vec<u8>
+    {
+        let mut data = self.byte_buf.into_inner();
+        data.truncate(self.cur_size);
+        data
+    }
 }
 
 impl Read for InMemorySection
@@ -59,6 +71,11 @@ impl Write for InMemorySection
 {
     fn write(&mut self, buf: &[u8]) -> Result<usize>
     {
+        let pos = self.byte_buf.position() as usize;
+        if pos > self.cur_size {
+            //Explicitly zero-fill the gap between the current end and the cursor
+            self.byte_buf.get_mut().resize(pos, 0);
+        }
         let len = self.byte_buf.write(buf)?;
         if self.byte_buf.position() as usize >= self.cur_size {
             self.cur_size = self.byte_buf.position() as usize;
@@ -84,11 +101,29 @@ impl SectionData for InMemorySection
 {
     fn load_in_memory(&mut self) -> Result<Vec<u8>>
     {
-        return Ok(self.byte_buf.get_ref().clone());
+        Ok(self.byte_buf.get_ref()[..self.cur_size].to_vec())
     }
 
     fn size(&self) -> usize
     {
         self.cur_size
     }
+
+    fn as_slice(&self) -> Option<&[u8]>
+    {
+        Some(&self.byte_buf.get_ref()[..self.cur_size])
+    }
+
+    fn truncate(&mut self, size: usize) -> Result<()>
+    {
+        if size >= self.cur_size {
+            return Ok(());
+        }
+        self.byte_buf.get_mut().truncate(size);
+        self.cur_size = size;
+        if self.byte_buf.position() > size as u64 {
+            self.byte_buf.set_position(size as u64);
+        }
+        Ok(())
+    }
 }
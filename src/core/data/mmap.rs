@@ -0,0 +1,150 @@
+// Copyright (c) 2021, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write},
+    sync::Arc
+};
+
+use memmap2::Mmap;
+
+use crate::core::SectionData;
+
+/// A read-only view over a range of a memory-mapped BPX file.
+///
+/// *Writes are not supported by this type; [AutoSectionData](crate::core::AutoSectionData)
+/// takes care of moving the data to an in-memory buffer before writing.*
+pub struct MmapSection
+{
+    map: Arc<Mmap>,
+    start: usize,
+    len: usize,
+    cursor: usize
+}
+
+impl MmapSection
+{
+    pub fn new(map: Arc<Mmap>, start: usize, len: usize) -> MmapSection
+    {
+        MmapSection {
+            map,
+            start,
+            len,
+            cursor: 0
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8]
+    {
+        &self.map[self.start..self.start + self.len]
+    }
+
+    pub fn position(&self) -> u64
+    {
+        self.cursor as u64
+    }
+}
+
+impl Read for MmapSection
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize>
+    {
+        if self.cursor >= self.len {
+            return Ok(0);
+        }
+        let len = std::cmp::min(buf.len(), self.len - self.cursor);
+        let start = self.start + self.cursor;
+        buf[0..len].copy_from_slice(&self.map[start..start + len]);
+        self.cursor += len;
+        Ok(len)
+    }
+}
+
+impl Write for MmapSection
+{
+    fn write(&mut self, _: &[u8]) -> Result<usize>
+    {
+        Err(Error::new(
+            ErrorKind::PermissionDenied,
+            "memory-mapped sections are read-only"
+        ))
+    }
+
+    fn flush(&mut self) -> Result<()>
+    {
+        Ok(())
+    }
+}
+
+impl Seek for MmapSection
+{
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64>
+    {
+        let pos = match pos {
+            SeekFrom::Start(v) => v as i64,
+            SeekFrom::End(v) => self.len as i64 + v,
+            SeekFrom::Current(v) => self.cursor as i64 + v
+        };
+        if pos < 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek to a negative position"
+            ));
+        }
+        self.cursor = pos as usize;
+        Ok(self.cursor as u64)
+    }
+}
+
+impl SectionData for MmapSection
+{
+    fn load_in_memory(&mut self) -> Result<Vec<u8>>
+    {
+        Ok(self.as_slice().to_vec())
+    }
+
+    fn size(&self) -> usize
+    {
+        self.len
+    }
+
+    fn as_slice(&self) -> Option<&[u8]>
+    {
+        Some(MmapSection::as_slice(self))
+    }
+
+    fn truncate(&mut self, size: usize) -> Result<()>
+    {
+        //Shrinking the view does not modify the underlying file
+        if size < self.len {
+            self.len = size;
+            self.cursor = std::cmp::min(self.cursor, size);
+        }
+        Ok(())
+    }
+}
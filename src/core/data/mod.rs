@@ -29,15 +29,21 @@
 //! Utilities to manipulate the content of sections.
 
 mod auto;
+mod borrowed;
 mod file;
 mod memory;
+#[cfg(feature = "mmap")]
+mod mmap;
 
 use std::{
-    io::{Read, Result, Seek, Write},
+    io::{Read, Result, Seek, SeekFrom, Write},
     vec::Vec
 };
 
 /// Opaque variant intended to manipulate section data in the form of standard IO operations.
+///
+/// *Seeking past the end of a section is allowed; writing past the end of a section fills
+/// the gap between the previous end and the cursor with zeros.*
 pub trait SectionData: Read + Write + Seek
 {
     /// Loads this section into memory.
@@ -54,6 +60,136 @@ pub trait SectionData: Read + Write + Seek
 
     /// Returns the current size of this section.
     fn size(&self) -> usize;
+
+    /// Returns the whole content of this section without copying it, if this section is
+    /// held in memory.
+    ///
+    /// Returns None if the data of this section is not directly addressable (for example if
+    /// it is backed by a file).
+    fn as_slice(&self) -> Option<&[u8]>
+    {
+        None
+    }
+
+    /// Reads bytes at the given position without moving the cursor of this section.
+    ///
+    /// Returns the number of bytes read, which is 0 if `pos` is at or past the end of this
+    /// section.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos`: the position in bytes to read from.
+    /// * `buf`: the buffer to read into.
+    ///
+    /// returns: Result<usize, Error>
+    ///
+    /// # Errors
+    ///
+    /// An [Error](std::io::Error) is returned if the data could not be read. The default
+    /// implementation returns an error of kind
+    /// [Unsupported](std::io::ErrorKind::Unsupported) if this section is not held in memory.
+    fn read_at(&self, pos: u64, buf: &mut [u8]) -> Result<usize>
+    {
+        let data = self.as_slice().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "section data does not support positioned reads"
+            )
+        })?;
+        let start = std::cmp::min(pos, data.len() as u64) as usize;
+        let len = std::cmp::min(buf.len(), data.len() - start);
+        buf[..len].copy_from_slice(&data[start..start + len]);
+        Ok(len)
+    }
+
+    /// Shrinks this section to the given size.
+    ///
+    /// Does nothing if `size` is greater than or equal to the current size of this section.
+    /// If the cursor is past the new end of this section, it is moved to the new end.
+    ///
+    /// # Arguments
+    ///
+    /// * `size`: the new size of this section.
+    ///
+    /// returns: Result<(), Error>
+    ///
+    /// # Errors
+    ///
+    /// An [Error](std::io::Error) is returned if this section could not be truncated. The
+    /// default implementation always returns an error of kind
+    /// [Unsupported](std::io::ErrorKind::Unsupported).
+    fn truncate(&mut self, size: usize) -> Result<()>
+    {
+        let _ = size;
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "section data does not support truncation"
+        ))
+    }
+}
+
+const COMPARE_BUF_SIZE: usize = 8192;
+
+/// Checks if two section data have the same content.
+///
+/// The content is compared by chunks and the comparison stops at the first difference.
+/// The cursors of both section data are restored before returning.
+///
+/// # Arguments
+///
+/// * `a`: the first section data.
+/// * `b`: the second section data.
+///
+/// returns: Result<bool, Error>
+///
+/// # Errors
+///
+/// An [Error](std::io::Error) is returned if one of the section data could not be read.
+///
+/// # Examples
+///
+/// ```
+/// use bpx::core::{compare, BorrowedSection};
+///
+/// let mut a = BorrowedSection::new(b"test");
+/// let mut b = BorrowedSection::new(b"test");
+/// assert!(compare(&mut a, &mut b).unwrap());
+/// let mut c = BorrowedSection::new(b"tost");
+/// assert!(!compare(&mut a, &mut c).unwrap());
+/// ```
+pub fn compare(a: &mut dyn SectionData, b: &mut dyn SectionData) -> Result<bool>
+{
+    if a.size() != b.size() {
+        return Ok(false);
+    }
+    let pos_a = a.stream_position()?;
+    let pos_b = b.stream_position()?;
+    let res = compare_content(a, b);
+    a.seek(SeekFrom::Start(pos_a))?;
+    b.seek(SeekFrom::Start(pos_b))?;
+    res
+}
+
+fn compare_content(a: &mut dyn SectionData, b: &mut dyn SectionData) -> Result<bool>
+{
+    let mut buf_a = [0; COMPARE_BUF_SIZE];
+    let mut buf_b = [0; COMPARE_BUF_SIZE];
+    let mut remaining = a.size();
+    a.seek(SeekFrom::Start(0))?;
+    b.seek(SeekFrom::Start(0))?;
+    while remaining > 0 {
+        let len = std::cmp::min(remaining, COMPARE_BUF_SIZE);
+        a.read_exact(&mut buf_a[..len])?;
+        b.read_exact(&mut buf_b[..len])?;
+        if buf_a[..len] != buf_b[..len] {
+            return Ok(false);
+        }
+        remaining -= len;
+    }
+    Ok(true)
 }
 
-pub use auto::AutoSectionData;
+#[cfg(feature = "mmap")]
+pub(crate) use auto::new_mmap_section;
+pub use auto::{new_section_data, AutoSectionData};
+pub use borrowed::BorrowedSection;
@@ -36,6 +36,7 @@ use std::{
 
 use crate::{
     core::{
+        builder::Checksum as ChecksumAlgo,
         compression::{
             Checksum,
             Crc32Checksum,
@@ -44,8 +45,9 @@ use crate::{
             XzCompressionMethod,
             ZlibCompressionMethod
         },
-        data::AutoSectionData,
-        error::ReadError,
+        data::{new_section_data, AutoSectionData, SectionData},
+        encryption::decrypt_section,
+        error::{CorruptionContext, ReadError},
         header::{
             MainHeader,
             SectionHeader,
@@ -53,22 +55,95 @@ use crate::{
             FLAG_CHECK_CRC32,
             FLAG_CHECK_WEAK,
             FLAG_COMPRESS_XZ,
-            FLAG_COMPRESS_ZLIB
+            FLAG_COMPRESS_ZLIB,
+            FLAG_ENCRYPT_AES_GCM,
+            SIZE_MAIN_HEADER,
+            SIZE_SECTION_HEADER
         },
         section::{SectionEntry, SectionEntry1},
-        DEFAULT_COMPRESSION_THRESHOLD
+        ChecksumStatus,
+        DEFAULT_COMPRESSION_THRESHOLD,
+        DEFAULT_MEMORY_THRESHOLD
     },
     utils::ReadFill
 };
 
 const READ_BLOCK_SIZE: usize = 8192;
 
+/// Returns the offset of the end of the section header table declared by `main_header`.
+pub fn headers_end(main_header: &MainHeader) -> u64
+{
+    SIZE_MAIN_HEADER as u64 + SIZE_SECTION_HEADER as u64 * main_header.section_num as u64
+}
+
+/// Checks that the section header table declared by `main_header` fits in a file of
+/// `file_size` bytes.
+fn check_section_table(main_header: &MainHeader, file_size: u64) -> Result<(), ReadError>
+{
+    if headers_end(main_header) > file_size {
+        return Err(ReadError::Corrupted(CorruptionContext::SectionTable));
+    }
+    Ok(())
+}
+
+/// Checks that the data of each section lies between the end of the headers and the end of
+/// a file of `file_size` bytes.
+fn check_sections(
+    main_header: &MainHeader,
+    sections: &BTreeMap<u32, SectionEntry>,
+    file_size: u64
+) -> Result<(), ReadError>
+{
+    let headers_end = headers_end(main_header);
+    for entry in sections.values() {
+        if entry.header.csize == 0 {
+            continue;
+        }
+        if entry.header.pointer < headers_end {
+            return Err(ReadError::Corrupted(
+                CorruptionContext::SectionOverlapsHeaders(entry.index)
+            ));
+        }
+        let end = entry.header.pointer.checked_add(entry.header.csize as u64);
+        if end.map(|v| v > file_size).unwrap_or(true) {
+            return Err(ReadError::Corrupted(CorruptionContext::SectionPastEnd(
+                entry.index
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the size of the backend without moving its cursor.
+pub fn backend_size<T: Seek>(backend: &mut T) -> io::Result<u64>
+{
+    let pos = backend.stream_position()?;
+    let len = backend.seek(io::SeekFrom::End(0))?;
+    backend.seek(io::SeekFrom::Start(pos))?;
+    Ok(len)
+}
+
+/// Reads the section header table following the main header.
+///
+/// When `file_size` is given, headers are validated against the size of the file so that
+/// untrusted sizes and pointers are never used to allocate or read data.
 pub fn read_section_header_table<T: Read>(
     mut backend: &mut T,
     main_header: &MainHeader,
-    checksum: u32
+    checksum: u32,
+    max_sections: u32,
+    file_size: Option<u64>
 ) -> Result<(u32, BTreeMap<u32, SectionEntry>), ReadError>
 {
+    if main_header.section_num > max_sections {
+        return Err(ReadError::TooManySections(
+            main_header.section_num,
+            max_sections
+        ));
+    }
+    if let Some(len) = file_size {
+        check_section_table(main_header, len)?;
+    }
     let mut sections = BTreeMap::new();
     let mut final_checksum = checksum;
     let mut hdl: u32 = 0;
@@ -81,11 +156,17 @@ pub fn read_section_header_table<T: Read>(
             SectionEntry {
                 header,
                 data: None,
+                raw: None,
                 modified: false,
                 index: i,
                 entry1: SectionEntry1 {
                     flags: header.flags,
-                    threshold: DEFAULT_COMPRESSION_THRESHOLD
+                    threshold: DEFAULT_COMPRESSION_THRESHOLD,
+                    memory_threshold: DEFAULT_MEMORY_THRESHOLD,
+                    level: None,
+                    key: None,
+                    skip_checksum: false,
+                    max_size: None
                 }
             }
         );
@@ -94,48 +175,266 @@ pub fn read_section_header_table<T: Read>(
     if final_checksum != main_header.chksum {
         return Err(ReadError::Checksum(final_checksum, main_header.chksum));
     }
+    if let Some(len) = file_size {
+        check_sections(main_header, &sections, len)?;
+    }
     Ok((hdl, sections))
 }
 
 pub fn load_section1<T: io::Read + io::Seek>(
     file: &mut T,
-    section: &SectionHeader
+    section: &SectionHeader,
+    entry1: &SectionEntry1
 ) -> Result<AutoSectionData, ReadError>
 {
-    let mut data = AutoSectionData::new_with_size(section.size)?;
+    if let Some(limit) = entry1.max_size {
+        if section.size > limit {
+            return Err(ReadError::SectionTooLarge(section.size, limit));
+        }
+    }
+    let (mut data, chksum) = load_section_unchecked(
+        file,
+        section,
+        entry1.memory_threshold,
+        entry1.key.as_ref(),
+        entry1.max_size.is_some()
+    )?;
+    if !entry1.skip_checksum {
+        if let ChecksumStatus::Mismatch(expected, actual) =
+            loaded_checksum_status(&mut data, section, chksum)?
+        {
+            return Err(ReadError::Checksum(actual, expected));
+        }
+    }
+    //The decoded data is identical to the data stored in the backend
+    data.clear_written();
+    Ok(data)
+}
+
+/// A writer failing once more than the declared size of a section is written, so that
+/// decompression stops early on sections lying about their size.
+struct SizeLimit<W>
+{
+    inner: W,
+    remaining: u64
+}
+
+impl<W: Write> Write for SizeLimit<W>
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize>
+    {
+        if buf.len() as u64 > self.remaining {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "section data exceeds its declared size"
+            ));
+        }
+        let len = self.inner.write(buf)?;
+        self.remaining -= len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+        self.inner.flush()
+    }
+}
+
+/// Loads a section without checking its checksum; the computed checksum is returned instead
+/// if the section has one.
+///
+/// When `strict` is true, loading fails as soon as more data than the declared size of the
+/// section is decoded.
+pub fn load_section_unchecked<T: io::Read + io::Seek>(
+    file: &mut T,
+    section: &SectionHeader,
+    memory_threshold: usize,
+    key: Option<&[u8; 32]>,
+    strict: bool
+) -> Result<(AutoSectionData, Option<u32>), ReadError>
+{
+    let mut data = new_section_data(Some(section.size), memory_threshold)?;
     data.seek(io::SeekFrom::Start(0))?;
-    if section.flags & FLAG_CHECK_WEAK != 0 {
+    let remaining = match strict {
+        true => section.size as u64,
+        false => u64::MAX
+    };
+    let mut out = SizeLimit {
+        inner: &mut data,
+        remaining
+    };
+    let chksum = if section.flags & FLAG_CHECK_WEAK != 0 {
         let mut chksum = WeakChecksum::new();
-        //TODO: Check
-        load_section_checked(file, section, &mut data, &mut chksum)?;
-        let v = chksum.finish();
-        if v != section.chksum {
-            return Err(ReadError::Checksum(v, section.chksum));
-        }
+        load_section_checked(file, section, &mut out, &mut chksum, key)?;
+        Some(chksum.finish())
     } else if section.flags & FLAG_CHECK_CRC32 != 0 {
         let mut chksum = Crc32Checksum::new();
-        //TODO: Check
-        load_section_checked(file, section, &mut data, &mut chksum)?;
-        let v = chksum.finish();
-        if v != section.chksum {
-            return Err(ReadError::Checksum(v, section.chksum));
-        }
+        load_section_checked(file, section, &mut out, &mut chksum, key)?;
+        Some(chksum.finish())
     } else {
         let mut chksum = WeakChecksum::new();
-        //TODO: Check
-        load_section_checked(file, section, &mut data, &mut chksum)?;
+        load_section_checked(file, section, &mut out, &mut chksum, key)?;
+        None
+    };
+    data.seek(io::SeekFrom::Start(0))?;
+    Ok((data, chksum))
+}
+
+/// Compares the checksum computed by [load_section_unchecked] with the one stored in the
+/// section header.
+///
+/// A mismatching CRC32 of an unencrypted section is computed again over the loaded data with
+/// the legacy table.
+pub fn loaded_checksum_status(
+    data: &mut AutoSectionData,
+    section: &SectionHeader,
+    chksum: Option<u32>
+) -> Result<ChecksumStatus, ReadError>
+{
+    match chksum {
+        None => Ok(ChecksumStatus::None),
+        Some(v) if v == section.chksum => Ok(ChecksumStatus::Valid),
+        Some(v) => {
+            if section.flags & FLAG_CHECK_CRC32 != 0 && section.flags & FLAG_ENCRYPT_AES_GCM == 0 {
+                let pos = data.stream_position()?;
+                data.seek(io::SeekFrom::Start(0))?;
+                let header = SectionHeader {
+                    size: data.size() as u32,
+                    ..SectionHeader::new()
+                };
+                let mut legacy = Crc32Checksum::legacy();
+                load_section_uncompressed(data, &header, io::sink(), &mut legacy)?;
+                data.seek(io::SeekFrom::Start(pos))?;
+                if legacy.finish() == section.chksum {
+                    return Ok(ChecksumStatus::Legacy);
+                }
+            }
+            Ok(ChecksumStatus::Mismatch(section.chksum, v))
+        }
     }
+}
+
+/// Verifies the checksum of a section from the backend without keeping the decoded data.
+///
+/// A mismatching CRC32 is computed again with the legacy table.
+pub fn checksum_section<T: io::Read + io::Seek>(
+    file: &mut T,
+    section: &SectionHeader,
+    key: Option<&[u8; 32]>
+) -> Result<ChecksumStatus, ReadError>
+{
+    let actual = if section.flags & FLAG_CHECK_WEAK != 0 {
+        checksum_section_with(file, section, ChecksumAlgo::Weak, key)?
+    } else if section.flags & FLAG_CHECK_CRC32 != 0 {
+        checksum_section_with(file, section, ChecksumAlgo::Crc32, key)?
+    } else {
+        return Ok(ChecksumStatus::None);
+    };
+    if actual == section.chksum {
+        return Ok(ChecksumStatus::Valid);
+    }
+    if section.flags & FLAG_CHECK_CRC32 != 0
+        && legacy_crc32_section(file, section, key)? == section.chksum
+    {
+        return Ok(ChecksumStatus::Legacy);
+    }
+    Ok(ChecksumStatus::Mismatch(section.chksum, actual))
+}
+
+/// Computes the CRC32 of a section from the backend with the legacy table used by older
+/// revisions of this library.
+pub fn legacy_crc32_section<T: io::Read + io::Seek>(
+    file: &mut T,
+    section: &SectionHeader,
+    key: Option<&[u8; 32]>
+) -> Result<u32, ReadError>
+{
+    let mut chksum = Crc32Checksum::legacy();
+    load_section_checked(file, section, io::sink(), &mut chksum, key)?;
+    Ok(chksum.finish())
+}
+
+/// Computes the checksum of a section from the backend with the given algorithm without
+/// keeping the decoded data.
+pub fn checksum_section_with<T: io::Read + io::Seek>(
+    file: &mut T,
+    section: &SectionHeader,
+    algo: ChecksumAlgo,
+    key: Option<&[u8; 32]>
+) -> Result<u32, ReadError>
+{
+    trace_span!("bpx::checksum", size = section.size, algorithm = ?algo);
+    match algo {
+        ChecksumAlgo::Weak => {
+            let mut chksum = WeakChecksum::new();
+            load_section_checked(file, section, io::sink(), &mut chksum, key)?;
+            Ok(chksum.finish())
+        },
+        ChecksumAlgo::Crc32 => {
+            let mut chksum = Crc32Checksum::new();
+            load_section_checked(file, section, io::sink(), &mut chksum, key)?;
+            Ok(chksum.finish())
+        }
+    }
+}
+
+/// Computes the checksum of already loaded section data; the cursor of the data is preserved.
+pub fn checksum_data(data: &mut AutoSectionData, algo: ChecksumAlgo) -> Result<u32, ReadError>
+{
+    let pos = data.stream_position()?;
     data.seek(io::SeekFrom::Start(0))?;
-    Ok(data)
+    let header = SectionHeader {
+        size: data.size() as u32,
+        ..SectionHeader::new()
+    };
+    let res = match algo {
+        ChecksumAlgo::Weak => {
+            let mut chksum = WeakChecksum::new();
+            load_section_uncompressed(data, &header, io::sink(), &mut chksum)?;
+            chksum.finish()
+        },
+        ChecksumAlgo::Crc32 => {
+            let mut chksum = Crc32Checksum::new();
+            load_section_uncompressed(data, &header, io::sink(), &mut chksum)?;
+            chksum.finish()
+        }
+    };
+    data.seek(io::SeekFrom::Start(pos))?;
+    Ok(res)
+}
+
+/// Re-reads the main header and section header table from the backend and returns the
+/// stored and computed main header checksums.
+pub fn checksum_headers<T: io::Read + io::Seek>(file: &mut T) -> Result<(u32, u32), ReadError>
+{
+    file.seek(io::SeekFrom::Start(0))?;
+    let (mut checksum, header) = MainHeader::read(&mut *file)?;
+    for _ in 0..header.section_num {
+        let (v, _) = SectionHeader::read(&mut *file)?;
+        checksum += v;
+    }
+    Ok((header.chksum, checksum))
 }
 
 fn load_section_checked<TBackend: io::Read + io::Seek, TWrite: Write, TChecksum: Checksum>(
     file: &mut TBackend,
     section: &SectionHeader,
     out: TWrite,
-    chksum: &mut TChecksum
+    chksum: &mut TChecksum,
+    key: Option<&[u8; 32]>
 ) -> Result<(), ReadError>
 {
+    if section.flags & FLAG_ENCRYPT_AES_GCM != 0 {
+        //The checksum of encrypted sections covers the stored encrypted data
+        let (mut data, header) = decrypt_section(file, section, key, chksum)?;
+        return load_section_checked(&mut data, &header, out, &mut WeakChecksum::new(), None);
+    }
+    trace_span!(
+        "bpx::decompress",
+        size = section.size,
+        csize = section.csize,
+        compression = ?crate::core::CompressionKind::from_flags(section.flags)
+    );
     if section.flags & FLAG_COMPRESS_XZ != 0 {
         load_section_compressed::<XzCompressionMethod, _, _, _>(file, section, out, chksum)?;
     } else if section.flags & FLAG_COMPRESS_ZLIB != 0 {
@@ -160,6 +459,9 @@ fn load_section_uncompressed<TBackend: io::Read + io::Seek, TWrite: Write, TChec
     bpx.seek(io::SeekFrom::Start(header.pointer))?;
     while count < header.size as usize {
         let res = bpx.read_fill(&mut idata[0..std::cmp::min(READ_BLOCK_SIZE, remaining)])?;
+        if res == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
         output.write_all(&idata[0..res])?;
         chksum.push(&idata[0..res]);
         count += res;
@@ -181,6 +483,6 @@ fn load_section_compressed<
 ) -> Result<(), ReadError>
 {
     bpx.seek(io::SeekFrom::Start(header.pointer))?;
-    XzCompressionMethod::inflate(bpx, output, header.csize as usize, chksum)?;
+    TMethod::inflate(bpx, output, header.csize as usize, chksum)?;
     Ok(())
 }
@@ -31,11 +31,14 @@
 use std::{
     collections::BTreeMap,
     io,
-    io::{Seek, SeekFrom, Write}
+    io::{Read, Seek, SeekFrom, Write}
 };
 
+#[cfg(feature = "multithreading")]
+use crate::core::data::new_section_data;
 use crate::{
     core::{
+        builder::CompressionLevel,
         compression::{
             Checksum,
             Crc32Checksum,
@@ -44,6 +47,7 @@ use crate::{
             XzCompressionMethod,
             ZlibCompressionMethod
         },
+        encryption::encrypt_section,
         error::WriteError,
         header::{
             GetChecksum,
@@ -53,49 +57,106 @@ use crate::{
             FLAG_CHECK_WEAK,
             FLAG_COMPRESS_XZ,
             FLAG_COMPRESS_ZLIB,
+            FLAG_ENCRYPT_AES_GCM,
             SIZE_MAIN_HEADER,
             SIZE_SECTION_HEADER
         },
-        section::SectionEntry,
+        section::{sorted_entries, SectionEntry, SectionEntry1},
+        AutoSectionData,
+        SaveProgress,
         SectionData
     },
-    utils::ReadFill
+    utils::ReadFill,
+    ContainerTag,
+    Handle
 };
 
 const READ_BLOCK_SIZE: usize = 8192;
 
-fn write_sections<T: Write + Seek>(
+fn section_size(section: &SectionEntry) -> u64
+{
+    match (section.data.as_ref(), section.raw.as_ref()) {
+        (Some(data), _) => data.size() as u64,
+        (None, Some(raw)) => raw.size() as u64,
+        (None, None) => 0
+    }
+}
+
+fn in_memory_size(section: &SectionEntry) -> usize
+{
+    match section.data.as_ref() {
+        Some(data) if data.is_in_memory() => data.size(),
+        _ => 0
+    }
+}
+
+/// Moves the data of a written section to a temporary file while the sections held in
+/// memory exceed `budget`, so that memory usage goes down to the budget while saving.
+fn release_section(
+    section: &mut SectionEntry,
+    budget: Option<usize>,
+    in_memory: &mut usize
+) -> io::Result<()>
+{
+    if let (Some(budget), Some(data)) = (budget, section.data.as_mut()) {
+        if *in_memory > budget && data.is_in_memory() {
+            *in_memory -= data.size();
+            data.spill()?;
+        }
+    }
+    Ok(())
+}
+
+fn write_sections<T: Write + Seek, P: FnMut(SaveProgress)>(
     mut backend: T,
     sections: &mut BTreeMap<u32, SectionEntry>,
-    file_start_offset: usize
+    file_start_offset: usize,
+    budget: Option<usize>,
+    tag: ContainerTag,
+    progress: &mut P
 ) -> Result<(u32, usize), WriteError>
 {
     let mut ptr: u64 = file_start_offset as _;
     let mut all_sections_size: usize = 0;
     let mut chksum_sht: u32 = 0;
+    let total = sections.values().map(section_size).sum();
+    let mut bytes = 0;
+    let mut in_memory: usize = sections.values().map(in_memory_size).sum();
 
-    for (idx, (_handle, section)) in sections.iter_mut().enumerate() {
+    for (idx, (handle, section)) in sorted_entries(sections.iter_mut()).into_iter().enumerate() {
         //At this point the handle must be valid otherwise sections_in_order is broken
-        let data = section.data.as_mut().ok_or(WriteError::SectionNotLoaded)?;
-        if data.size() > u32::MAX as usize {
-            return Err(WriteError::Capacity(data.size()));
-        }
-        let last_section_ptr = data.stream_position()?;
-        data.seek(io::SeekFrom::Start(0))?;
-        let flags = section.entry1.get_flags(data.size() as u32);
-        let (csize, chksum) = write_section(flags, data, &mut backend)?;
-        data.seek(io::SeekFrom::Start(last_section_ptr))?;
-        section.header.csize = csize as u32;
-        section.header.size = data.size() as u32;
-        section.header.chksum = chksum;
-        section.header.flags = flags;
+        bytes += section_size(section);
+        let csize = match (section.data.as_mut(), section.raw.as_mut()) {
+            (Some(data), _) => {
+                if data.size() > u32::MAX as usize {
+                    return Err(WriteError::Capacity(data.size()));
+                }
+                let last_section_ptr = data.stream_position()?;
+                data.seek(io::SeekFrom::Start(0))?;
+                let flags = section.entry1.get_flags(data.size() as u32);
+                let (csize, chksum) = write_section(flags, &section.entry1, data, &mut backend)?;
+                data.seek(io::SeekFrom::Start(last_section_ptr))?;
+                section.header.csize = csize as u32;
+                section.header.size = data.size() as u32;
+                section.header.chksum = chksum;
+                section.header.flags = flags;
+                csize
+            },
+            (None, Some(raw)) => write_raw_section(raw, &mut backend)?,
+            (None, None) => return Err(WriteError::SectionNotLoaded)
+        };
+        release_section(section, budget, &mut in_memory)?;
         section.header.pointer = ptr;
         section.index = idx as _;
-        #[cfg(feature = "debug-log")]
-        println!(
-            "Writing section #{}: Size = {}, Size after compression = {}, Handle = {}",
-            idx, section.header.size, section.header.csize, _handle
-        );
+        section.set_saved();
+        progress(SaveProgress {
+            handle: Handle(*handle, tag),
+            index: section.index,
+            size: section.header.size,
+            csize: section.header.csize,
+            bytes,
+            total
+        });
         ptr += csize as u64;
         {
             //Locate section header offset, then directly write section header
@@ -111,10 +172,20 @@ fn write_sections<T: Write + Seek>(
     Ok((chksum_sht, all_sections_size))
 }
 
-pub fn internal_save<T: Write + Seek>(
+fn write_raw_section<T: Write>(raw: &mut AutoSectionData, mut out: T) -> Result<usize, WriteError>
+{
+    raw.seek(io::SeekFrom::Start(0))?;
+    io::copy(raw, &mut out)?;
+    Ok(raw.size())
+}
+
+pub fn internal_save<T: Write + Seek, P: FnMut(SaveProgress)>(
     mut backend: T,
     sections: &mut BTreeMap<u32, SectionEntry>,
-    main_header: &mut MainHeader
+    main_header: &mut MainHeader,
+    budget: Option<usize>,
+    tag: ContainerTag,
+    progress: &mut P
 ) -> Result<(), WriteError>
 {
     let file_start_offset =
@@ -123,7 +194,7 @@ pub fn internal_save<T: Write + Seek>(
     backend.seek(SeekFrom::Start(file_start_offset as _))?;
     //Write all section data and section headers
     let (chksum_sht, all_sections_size) =
-        write_sections(&mut backend, sections, file_start_offset)?;
+        write_sections(&mut backend, sections, file_start_offset, budget, tag, progress)?;
     main_header.file_size = all_sections_size as u64 + file_start_offset as u64;
     main_header.chksum = 0;
     main_header.chksum = chksum_sht + main_header.get_checksum();
@@ -133,26 +204,198 @@ pub fn internal_save<T: Write + Seek>(
     Ok(())
 }
 
+#[cfg(feature = "multithreading")]
+struct CompressedSection
+{
+    flags: u8,
+    size: usize,
+    csize: usize,
+    chksum: u32,
+    data: AutoSectionData
+}
+
+#[cfg(feature = "multithreading")]
+fn compress_section(
+    section: &mut SectionEntry,
+    budget: Option<usize>
+) -> Result<Option<CompressedSection>, WriteError>
+{
+    let data = match section.data.as_mut() {
+        Some(v) => v,
+        //Staged sections are copied as is when writing
+        None if section.raw.is_some() => return Ok(None),
+        None => return Err(WriteError::SectionNotLoaded)
+    };
+    if data.size() > u32::MAX as usize {
+        return Err(WriteError::Capacity(data.size()));
+    }
+    let last_section_ptr = data.stream_position()?;
+    data.seek(io::SeekFrom::Start(0))?;
+    let flags = section.entry1.get_flags(data.size() as u32);
+    let threshold = budget.map_or(section.entry1.memory_threshold, |v| {
+        v.min(section.entry1.memory_threshold)
+    });
+    let mut out = new_section_data(None, threshold)?;
+    let (csize, chksum) = write_section(flags, &section.entry1, data, &mut out)?;
+    data.seek(io::SeekFrom::Start(last_section_ptr))?;
+    out.seek(io::SeekFrom::Start(0))?;
+    Ok(Some(CompressedSection {
+        flags,
+        size: data.size(),
+        csize,
+        chksum,
+        data: out
+    }))
+}
+
+#[cfg(feature = "multithreading")]
+pub fn internal_save_parallel<T: Write + Seek, P: FnMut(SaveProgress)>(
+    mut backend: T,
+    sections: &mut BTreeMap<u32, SectionEntry>,
+    main_header: &mut MainHeader,
+    budget: Option<usize>,
+    tag: ContainerTag,
+    progress: &mut P
+) -> Result<(), WriteError>
+{
+    use rayon::prelude::*;
+    let file_start_offset =
+        SIZE_MAIN_HEADER + (SIZE_SECTION_HEADER * main_header.section_num as usize);
+    let total = sections.values().map(section_size).sum();
+    let mut bytes = 0;
+    let mut in_memory: usize = sections.values().map(in_memory_size).sum();
+    let mut entries = sorted_entries(sections.iter_mut());
+    let mut ptr = file_start_offset as u64;
+    let mut idx = 0;
+    backend.seek(SeekFrom::Start(ptr))?;
+    //Sections are compressed by batches of one section per thread, then written in order so
+    // that only the compressed data of one batch is buffered at a time
+    for batch in entries.chunks_mut(rayon::current_num_threads().max(1)) {
+        let compressed = batch
+            .par_iter_mut()
+            .map(|(_, section)| compress_section(section, budget))
+            .collect::<Result<Vec<Option<CompressedSection>>, WriteError>>()?;
+        for ((handle, section), v) in batch.iter_mut().zip(compressed) {
+            bytes += section_size(section);
+            let csize = match (v, section.raw.as_mut()) {
+                (Some(mut v), _) => {
+                    io::copy(&mut v.data, &mut backend)?;
+                    section.header.csize = v.csize as u32;
+                    section.header.size = v.size as u32;
+                    section.header.chksum = v.chksum;
+                    section.header.flags = v.flags;
+                    v.csize
+                },
+                (None, Some(raw)) => write_raw_section(raw, &mut backend)?,
+                (None, None) => return Err(WriteError::SectionNotLoaded)
+            };
+            release_section(section, budget, &mut in_memory)?;
+            section.header.pointer = ptr;
+            section.index = idx;
+            section.set_saved();
+            progress(SaveProgress {
+                handle: Handle(**handle, tag),
+                index: section.index,
+                size: section.header.size,
+                csize: section.header.csize,
+                bytes,
+                total
+            });
+            ptr += csize as u64;
+            idx += 1;
+        }
+    }
+    let mut chksum_sht: u32 = 0;
+    backend.seek(SeekFrom::Start(SIZE_MAIN_HEADER as _))?;
+    for (_, section) in sorted_entries(sections.iter()) {
+        section.header.write(&mut backend)?;
+        chksum_sht += section.header.get_checksum();
+    }
+    main_header.file_size = ptr;
+    main_header.chksum = 0;
+    main_header.chksum = chksum_sht + main_header.get_checksum();
+    backend.seek(SeekFrom::Start(0))?;
+    main_header.write(&mut backend)?;
+    Ok(())
+}
+
+pub fn internal_save_to<T: Read + Seek, W: Write + Seek>(
+    mut backend: T,
+    mut out: W,
+    sections: &mut BTreeMap<u32, SectionEntry>,
+    main_header: &MainHeader
+) -> Result<(), WriteError>
+{
+    // Unlike internal_save this must not change any state of the container: headers are
+    // computed on copies and sections keep their modified flag.
+    let mut main_header = *main_header;
+    main_header.section_num = sections.len() as _;
+    let file_start_offset =
+        SIZE_MAIN_HEADER + (SIZE_SECTION_HEADER * main_header.section_num as usize);
+    let mut ptr = file_start_offset as u64;
+    let mut chksum_sht: u32 = 0;
+    for (idx, (_, section)) in sorted_entries(sections.iter_mut()).into_iter().enumerate() {
+        let mut header = section.header;
+        out.seek(SeekFrom::Start(ptr))?;
+        if let Some(data) = section.data.as_mut() {
+            if data.size() > u32::MAX as usize {
+                return Err(WriteError::Capacity(data.size()));
+            }
+            let last_section_ptr = data.stream_position()?;
+            data.seek(io::SeekFrom::Start(0))?;
+            let flags = section.entry1.get_flags(data.size() as u32);
+            let (csize, chksum) = write_section(flags, &section.entry1, data, &mut out)?;
+            data.seek(io::SeekFrom::Start(last_section_ptr))?;
+            header.csize = csize as u32;
+            header.size = data.size() as u32;
+            header.chksum = chksum;
+            header.flags = flags;
+        } else {
+            //The section was never loaded: copy it as is from the original backend
+            backend.seek(SeekFrom::Start(header.pointer))?;
+            let count = io::copy(&mut (&mut backend).take(header.csize as u64), &mut out)?;
+            if count != header.csize as u64 {
+                return Err(WriteError::Io(io::Error::from(
+                    io::ErrorKind::UnexpectedEof
+                )));
+            }
+        }
+        header.pointer = ptr;
+        ptr += header.csize as u64;
+        let header_start_offset = SIZE_MAIN_HEADER + (idx * SIZE_SECTION_HEADER);
+        out.seek(SeekFrom::Start(header_start_offset as _))?;
+        header.write(&mut out)?;
+        chksum_sht += header.get_checksum();
+    }
+    main_header.file_size = ptr;
+    main_header.chksum = 0;
+    main_header.chksum = chksum_sht + main_header.get_checksum();
+    out.seek(SeekFrom::Start(0))?;
+    main_header.write(&mut out)?;
+    Ok(())
+}
+
 fn write_last_section<T: Write + Seek>(
     mut backend: T,
     sections: &mut BTreeMap<u32, SectionEntry>,
     last_handle: u32
-) -> Result<(bool, i64), WriteError>
+) -> Result<bool, WriteError>
 {
     let entry = sections.get_mut(&last_handle).unwrap();
     backend.seek(SeekFrom::Start(entry.header.pointer))?;
     let data = entry.data.as_mut().ok_or(WriteError::SectionNotLoaded)?;
     let last_section_ptr = data.stream_position()?;
+    data.seek(io::SeekFrom::Start(0))?;
     let flags = entry.entry1.get_flags(data.size() as u32);
-    let (csize, chksum) = write_section(flags, data, &mut backend)?;
+    let (csize, chksum) = write_section(flags, &entry.entry1, data, &mut backend)?;
     data.seek(io::SeekFrom::Start(last_section_ptr))?;
     let old = entry.header;
     entry.header.csize = csize as u32;
     entry.header.size = data.size() as u32;
     entry.header.chksum = chksum;
     entry.header.flags = flags;
-    let diff = entry.header.csize as i64 - old.csize as i64;
-    Ok((old == entry.header, diff))
+    entry.set_saved();
+    Ok(old != entry.header)
 }
 
 pub fn internal_save_last<T: Write + Seek>(
@@ -163,17 +406,22 @@ pub fn internal_save_last<T: Write + Seek>(
 ) -> Result<(), WriteError>
 {
     // This function saves only the last section.
-    let (update_sht, diff) = write_last_section(&mut backend, sections, last_handle)?;
+    let update_sht = write_last_section(&mut backend, sections, last_handle)?;
     if update_sht {
         let offset_section_header =
             SIZE_MAIN_HEADER + (SIZE_SECTION_HEADER * (main_header.section_num - 1) as usize);
         backend.seek(SeekFrom::Start(offset_section_header as _))?;
         let entry = &sections[&last_handle];
         entry.header.write(&mut backend)?;
-    }
-    if diff != 0 {
+        //The main header checksum covers all section headers so it must be re-computed
+        main_header.file_size = entry.header.pointer + entry.header.csize as u64;
+        main_header.chksum = 0;
+        main_header.chksum = sections
+            .values()
+            .map(|v| v.header.get_checksum())
+            .sum::<u32>()
+            + main_header.get_checksum();
         backend.seek(SeekFrom::Start(0))?;
-        main_header.file_size = main_header.file_size.wrapping_add(diff as u64);
         main_header.write(&mut backend)?;
     }
     Ok(())
@@ -187,7 +435,7 @@ fn write_section_uncompressed<TWrite: Write, TChecksum: Checksum>(
 {
     let mut idata: [u8; READ_BLOCK_SIZE] = [0; READ_BLOCK_SIZE];
     let mut count: usize = 0;
-    while count < section.size() as usize {
+    while count < section.size() {
         let res = section.read_fill(&mut idata)?;
         out.write_all(&idata[0..res])?;
         chksum.push(&idata[0..res]);
@@ -200,47 +448,95 @@ fn write_section_uncompressed<TWrite: Write, TChecksum: Checksum>(
 fn write_section_compressed<TMethod: Deflater, TWrite: Write, TChecksum: Checksum>(
     mut section: &mut dyn SectionData,
     out: &mut TWrite,
+    level: Option<CompressionLevel>,
     chksum: &mut TChecksum
 ) -> Result<usize, WriteError>
 {
     let size = section.size();
-    let csize = TMethod::deflate(&mut section, out, size, chksum)?;
+    let csize = TMethod::deflate(&mut section, out, size, level, chksum)?;
     Ok(csize)
 }
 
-fn write_section_checked<TWrite: Write, TChecksum: Checksum>(
+fn write_section_encoded<TWrite: Write, TChecksum: Checksum>(
     flags: u8,
+    level: Option<CompressionLevel>,
     section: &mut dyn SectionData,
     out: &mut TWrite,
     chksum: &mut TChecksum
 ) -> Result<usize, WriteError>
 {
     if flags & FLAG_COMPRESS_XZ != 0 {
-        write_section_compressed::<XzCompressionMethod, _, _>(section, out, chksum)
+        write_section_compressed::<XzCompressionMethod, _, _>(section, out, level, chksum)
     } else if flags & FLAG_COMPRESS_ZLIB != 0 {
-        write_section_compressed::<ZlibCompressionMethod, _, _>(section, out, chksum)
+        write_section_compressed::<ZlibCompressionMethod, _, _>(section, out, level, chksum)
     } else {
         write_section_uncompressed(section, out, chksum)
     }
 }
 
+/// A writer pushing all data written through it to a checksum.
+struct ChecksumWriter<'a, W, C>
+{
+    inner: &'a mut W,
+    chksum: &'a mut C
+}
+
+impl<'a, W: Write, C: Checksum> Write for ChecksumWriter<'a, W, C>
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize>
+    {
+        let len = self.inner.write(buf)?;
+        self.chksum.push(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+        self.inner.flush()
+    }
+}
+
+fn write_section_checked<TWrite: Write, TChecksum: Checksum>(
+    flags: u8,
+    entry1: &SectionEntry1,
+    section: &mut dyn SectionData,
+    out: &mut TWrite,
+    chksum: &mut TChecksum
+) -> Result<usize, WriteError>
+{
+    if flags & FLAG_ENCRYPT_AES_GCM != 0 {
+        //The checksum of encrypted sections covers the stored encrypted data
+        let mut out = ChecksumWriter { inner: out, chksum };
+        return encrypt_section(entry1.key.as_ref(), &mut out, |mut w| {
+            write_section_encoded(flags, entry1.level, section, &mut w, &mut WeakChecksum::new())
+        });
+    }
+    write_section_encoded(flags, entry1.level, section, out, chksum)
+}
+
 pub fn write_section<TWrite: Write>(
     flags: u8,
+    entry1: &SectionEntry1,
     section: &mut dyn SectionData,
     out: &mut TWrite
 ) -> Result<(usize, u32), WriteError>
 {
+    trace_span!(
+        "bpx::compress",
+        size = section.size(),
+        compression = ?crate::core::CompressionKind::from_flags(flags)
+    );
     if flags & FLAG_CHECK_CRC32 != 0 {
         let mut chksum = Crc32Checksum::new();
-        let size = write_section_checked(flags, section, out, &mut chksum)?;
+        let size = write_section_checked(flags, entry1, section, out, &mut chksum)?;
         Ok((size, chksum.finish()))
     } else if flags & FLAG_CHECK_WEAK != 0 {
         let mut chksum = WeakChecksum::new();
-        let size = write_section_checked(flags, section, out, &mut chksum)?;
+        let size = write_section_checked(flags, entry1, section, out, &mut chksum)?;
         Ok((size, chksum.finish()))
     } else {
         let mut chksum = WeakChecksum::new();
-        let size = write_section_checked(flags, section, out, &mut chksum)?;
+        let size = write_section_checked(flags, entry1, section, out, &mut chksum)?;
         Ok((size, 0))
     }
 }
@@ -0,0 +1,224 @@
+// Copyright (c) 2021, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Optional AES-256-GCM encryption layer applied on top of section compression.
+//!
+//! Encrypted section data starts with a nonce followed by the encoded section split in chunks
+//! of [CHUNK_SIZE] bytes, each chunk being encrypted with the STREAM construction and followed
+//! by its authentication tag. The last chunk may be smaller or even empty.
+
+use std::io::{Cursor, Read, Seek, Write};
+
+use crate::core::{
+    compression::Checksum,
+    error::{ReadError, WriteError},
+    header::SectionHeader
+};
+
+/// The size in bytes of encoded section data encrypted at once.
+#[cfg(feature = "encryption")]
+const CHUNK_SIZE: usize = 65536;
+
+/// The size in bytes of the nonce stored in front of encrypted section data.
+#[cfg(feature = "encryption")]
+const NONCE_SIZE: usize = 7;
+
+/// The size in bytes of the authentication tag stored after each encrypted chunk.
+#[cfg(feature = "encryption")]
+const TAG_SIZE: usize = 16;
+
+/// Reads and decrypts the data of an encrypted section.
+///
+/// The stored (encrypted) bytes are pushed to `chksum`. Returns a backend containing the
+/// decrypted data along with a header describing it as an unencrypted section starting at
+/// offset 0.
+#[cfg(feature = "encryption")]
+pub fn decrypt_section<T: Read + Seek, TChecksum: Checksum>(
+    file: &mut T,
+    header: &SectionHeader,
+    key: Option<&[u8; 32]>,
+    chksum: &mut TChecksum
+) -> Result<(Cursor<Vec<u8>>, SectionHeader), ReadError>
+{
+    use std::io::SeekFrom;
+
+    use aes_gcm::{aead::stream::DecryptorBE32, Aes256Gcm, Key};
+
+    use crate::{core::header::FLAG_ENCRYPT_AES_GCM, utils::ReadFill};
+    let key = key.ok_or(ReadError::Decryption)?;
+    if (header.csize as usize) < NONCE_SIZE + TAG_SIZE {
+        return Err(ReadError::Decryption);
+    }
+    file.seek(SeekFrom::Start(header.pointer))?;
+    let mut nonce = [0; NONCE_SIZE];
+    if file.read_fill(&mut nonce)? != NONCE_SIZE {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    chksum.push(&nonce);
+    let mut decryptor =
+        DecryptorBE32::<Aes256Gcm>::new(Key::<Aes256Gcm>::from_slice(key), &nonce.into());
+    let mut remaining = header.csize as usize - NONCE_SIZE;
+    let mut data = Vec::with_capacity(remaining);
+    let mut chunk = Vec::with_capacity(CHUNK_SIZE + TAG_SIZE);
+    loop {
+        let len = remaining.min(CHUNK_SIZE + TAG_SIZE);
+        chunk.resize(len, 0);
+        if file.read_fill(&mut chunk)? != len {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        chksum.push(&chunk);
+        remaining -= len;
+        if remaining == 0 {
+            decryptor
+                .decrypt_last_in_place(&[], &mut chunk)
+                .map_err(|_| ReadError::Decryption)?;
+            data.extend_from_slice(&chunk);
+            break;
+        }
+        decryptor
+            .decrypt_next_in_place(&[], &mut chunk)
+            .map_err(|_| ReadError::Decryption)?;
+        data.extend_from_slice(&chunk);
+    }
+    let header = SectionHeader {
+        pointer: 0,
+        csize: data.len() as u32,
+        flags: header.flags & !FLAG_ENCRYPT_AES_GCM,
+        ..*header
+    };
+    Ok((Cursor::new(data), header))
+}
+
+/// Reads and decrypts the data of an encrypted section.
+///
+/// *Always fails as this build does not support encryption.*
+#[cfg(not(feature = "encryption"))]
+pub fn decrypt_section<T: Read + Seek, TChecksum: Checksum>(
+    _: &mut T,
+    _: &SectionHeader,
+    _: Option<&[u8; 32]>,
+    _: &mut TChecksum
+) -> Result<(Cursor<Vec<u8>>, SectionHeader), ReadError>
+{
+    Err(ReadError::Decryption)
+}
+
+/// A writer encrypting data in chunks of [CHUNK_SIZE] bytes.
+#[cfg(feature = "encryption")]
+struct EncryptWriter<'a, W>
+{
+    inner: &'a mut W,
+    encryptor: aes_gcm::aead::stream::EncryptorBE32<aes_gcm::Aes256Gcm>,
+    buffer: Vec<u8>,
+    written: usize
+}
+
+#[cfg(feature = "encryption")]
+impl<'a, W: Write> EncryptWriter<'a, W>
+{
+    fn finish(mut self) -> Result<usize, WriteError>
+    {
+        self.encryptor
+            .encrypt_last_in_place(&[], &mut self.buffer)
+            .map_err(|_| WriteError::Encryption)?;
+        self.inner.write_all(&self.buffer)?;
+        Ok(self.written + self.buffer.len())
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl<'a, W: Write> Write for EncryptWriter<'a, W>
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize>
+    {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        //A full chunk is only encrypted once more data arrives as the last chunk is special
+        if self.buffer.len() == CHUNK_SIZE {
+            self.encryptor
+                .encrypt_next_in_place(&[], &mut self.buffer)
+                .map_err(|_| std::io::Error::other("section encryption failed"))?;
+            self.inner.write_all(&self.buffer)?;
+            self.written += self.buffer.len();
+            self.buffer.clear();
+        }
+        let len = buf.len().min(CHUNK_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()>
+    {
+        self.inner.flush()
+    }
+}
+
+/// Encrypts section data produced by `encode` with a new random nonce and writes it to `out`.
+///
+/// Data is encrypted in chunks as `encode` writes it: at most one chunk is held in memory.
+///
+/// Returns the number of bytes written.
+#[cfg(feature = "encryption")]
+pub fn encrypt_section<TWrite: Write>(
+    key: Option<&[u8; 32]>,
+    out: &mut TWrite,
+    encode: impl FnOnce(&mut dyn Write) -> Result<usize, WriteError>
+) -> Result<usize, WriteError>
+{
+    use aes_gcm::{
+        aead::{rand_core::RngCore, stream::EncryptorBE32, OsRng},
+        Aes256Gcm,
+        Key
+    };
+    let key = key.ok_or(WriteError::Encryption)?;
+    let mut nonce = [0; NONCE_SIZE];
+    OsRng.fill_bytes(&mut nonce);
+    out.write_all(&nonce)?;
+    let mut writer = EncryptWriter {
+        inner: out,
+        encryptor: EncryptorBE32::new(Key::<Aes256Gcm>::from_slice(key), &nonce.into()),
+        buffer: Vec::with_capacity(CHUNK_SIZE + TAG_SIZE),
+        written: NONCE_SIZE
+    };
+    encode(&mut writer)?;
+    writer.finish()
+}
+
+/// Encrypts section data produced by `encode` with a new random nonce and writes it to `out`.
+///
+/// *Always fails as this build does not support encryption.*
+#[cfg(not(feature = "encryption"))]
+pub fn encrypt_section<TWrite: Write>(
+    _: Option<&[u8; 32]>,
+    _: &mut TWrite,
+    _: impl FnOnce(&mut dyn Write) -> Result<usize, WriteError>
+) -> Result<usize, WriteError>
+{
+    Err(WriteError::Encryption)
+}
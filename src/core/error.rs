@@ -33,7 +33,7 @@ use std::{
     fmt::{Display, Formatter}
 };
 
-use crate::impl_err_conversion;
+use crate::{impl_err_conversion, Handle};
 
 /// Represents a generic decompression error.
 #[derive(Debug)]
@@ -107,6 +107,48 @@ impl Display for InflateError
     }
 }
 
+/// Describes which check failed when validating the headers of a BPX file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CorruptionContext
+{
+    /// The section header table extends past the end of the file.
+    SectionTable,
+
+    /// The data of a section extends past the end of the file.
+    ///
+    /// # Arguments
+    /// * the index of the section.
+    SectionPastEnd(u32),
+
+    /// The data of a section overlaps the main header or the section header table.
+    ///
+    /// # Arguments
+    /// * the index of the section.
+    SectionOverlapsHeaders(u32),
+
+    /// The file does not match the CRC32 stored in its whole-file integrity trailer.
+    Trailer
+}
+
+impl Display for CorruptionContext
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self {
+            CorruptionContext::SectionTable => {
+                f.write_str("section header table extends past the end of the file")
+            },
+            CorruptionContext::SectionPastEnd(index) => {
+                write!(f, "section {} extends past the end of the file", index)
+            },
+            CorruptionContext::SectionOverlapsHeaders(index) => {
+                write!(f, "section {} overlaps the file headers", index)
+            },
+            CorruptionContext::Trailer => f.write_str("whole-file checksum mismatch")
+        }
+    }
+}
+
 /// Represents a BPX read error.
 #[derive(Debug)]
 pub enum ReadError
@@ -134,7 +176,64 @@ pub enum ReadError
     BadSignature([u8; 3]),
 
     /// Describes a decompression error.
-    Inflate(InflateError)
+    Inflate(InflateError),
+
+    /// Describes a main header declaring more sections than allowed.
+    ///
+    /// # Arguments
+    /// * the number of sections declared in the main header.
+    /// * the maximum number of sections allowed.
+    TooManySections(u32, u32),
+
+    /// Describes an encrypted section which could not be decrypted (missing or wrong key,
+    /// corrupted data or encryption not supported by this build).
+    Decryption,
+
+    /// Describes a file with unexpected data after the end of the last section
+    /// (only reported when [OpenOptions](crate::core::builder::OpenOptions) reject trailing data).
+    ///
+    /// # Arguments
+    /// * the number of trailing bytes.
+    TrailingData(u64),
+
+    /// Describes an error while loading a given section.
+    ///
+    /// # Arguments
+    /// * a handle to the section which failed to load.
+    /// * the error.
+    Section(Handle, Box<ReadError>),
+
+    /// Describes a section which is already locked by another user of a
+    /// [SyncContainer](crate::core::SyncContainer).
+    ///
+    /// # Arguments
+    /// * a handle to the section.
+    SectionInUse(Handle),
+
+    /// Describes headers which are inconsistent with the size of the file (only reported
+    /// unless [OpenOptions](crate::core::builder::OpenOptions) skip validation).
+    ///
+    /// # Arguments
+    /// * the check which failed.
+    Corrupted(CorruptionContext),
+
+    /// Describes a section which is larger than allowed by the memory limits of a container
+    /// (see [OpenOptions](crate::core::builder::OpenOptions)).
+    ///
+    /// # Arguments
+    /// * the size of the section.
+    /// * the maximum size allowed.
+    SectionTooLarge(u32, u32),
+
+    /// Describes a handle which does not match the key of any section of a container, usually
+    /// because the section was removed, or a handle created by another container.
+    ///
+    /// *A handle from another container is only recognized as such in debug builds: in release
+    /// builds it is only reported when its key is out of range for this container.*
+    ///
+    /// # Arguments
+    /// * the invalid handle.
+    InvalidHandle(Handle)
 }
 
 impl_err_conversion!(
@@ -159,7 +258,31 @@ impl Display for ReadError
             ReadError::BadSignature(sig) => {
                 write!(f, "unknown file signature ({}{}{})", sig[0], sig[1], sig[2])
             },
-            ReadError::Inflate(e) => write!(f, "inflate error: {}", e)
+            ReadError::Inflate(e) => write!(f, "inflate error: {}", e),
+            ReadError::TooManySections(got, limit) => {
+                write!(f, "too many sections ({} > {})", got, limit)
+            },
+            ReadError::Decryption => f.write_str("section decryption failed"),
+            ReadError::TrailingData(bytes) => {
+                write!(
+                    f,
+                    "{} bytes of unexpected data after the last section",
+                    bytes
+                )
+            },
+            ReadError::Section(handle, e) => {
+                write!(f, "failed to load section {}: {}", handle.into_raw(), e)
+            },
+            ReadError::SectionInUse(handle) => {
+                write!(f, "section {} is already in use", handle.into_raw())
+            },
+            ReadError::Corrupted(context) => write!(f, "corrupted file: {}", context),
+            ReadError::SectionTooLarge(size, limit) => {
+                write!(f, "section too large ({} > {})", size, limit)
+            },
+            ReadError::InvalidHandle(handle) => {
+                write!(f, "invalid section handle {}", handle.into_raw())
+            }
         }
     }
 }
@@ -182,7 +305,11 @@ pub enum WriteError
     Deflate(DeflateError),
 
     /// A section has not yet been loaded.
-    SectionNotLoaded
+    SectionNotLoaded,
+
+    /// Describes a section which could not be encrypted (missing key or encryption not
+    /// supported by this build).
+    Encryption
 }
 
 impl_err_conversion!(
@@ -202,7 +329,66 @@ impl Display for WriteError
                 write!(f, "maximum section size exceeded ({} > 2^32)", size)
             },
             WriteError::Deflate(e) => write!(f, "deflate error: {}", e),
-            WriteError::SectionNotLoaded => f.write_str("section not loaded")
+            WriteError::SectionNotLoaded => f.write_str("section not loaded"),
+            WriteError::Encryption => f.write_str("section encryption failed")
+        }
+    }
+}
+
+/// Represents a BPX migration error.
+#[derive(Debug)]
+pub enum MigrateError
+{
+    /// Describes an error while reading the old container.
+    Read(ReadError),
+
+    /// Describes an error while writing the new container.
+    Write(WriteError)
+}
+
+impl_err_conversion!(
+    MigrateError {
+        ReadError => Read,
+        WriteError => Write
+    }
+);
+
+/// Represents a BPX repack error.
+#[derive(Debug)]
+pub enum RepackError
+{
+    /// Describes an error while loading the sections to repack.
+    Read(ReadError),
+
+    /// Describes an error while saving the repacked container.
+    Write(WriteError)
+}
+
+impl_err_conversion!(
+    RepackError {
+        ReadError => Read,
+        WriteError => Write
+    }
+);
+
+impl Display for RepackError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self {
+            RepackError::Read(e) => write!(f, "read error: {}", e),
+            RepackError::Write(e) => write!(f, "write error: {}", e)
+        }
+    }
+}
+
+impl Display for MigrateError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self {
+            MigrateError::Read(e) => write!(f, "read error: {}", e),
+            MigrateError::Write(e) => write!(f, "write error: {}", e)
         }
     }
 }
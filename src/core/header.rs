@@ -35,9 +35,11 @@ use byteorder::{ByteOrder, LittleEndian};
 use crate::{
     core::{
         builder::{Checksum, CompressionMethod},
-        error::ReadError
+        error::ReadError,
+        reserved
     },
     garraylen::*,
+    spec::core as bpx,
     utils::ReadFill
 };
 
@@ -146,34 +148,161 @@ where
 }
 
 /// The size in bytes of the BPX Main Header.
-pub const SIZE_MAIN_HEADER: usize = 40;
+pub const SIZE_MAIN_HEADER: usize = bpx::MAIN_HEADER_SIZE;
 
 /// The size in bytes of a BPX Section Header.
-pub const SIZE_SECTION_HEADER: usize = 24;
+pub const SIZE_SECTION_HEADER: usize = bpx::SECTION_HEADER_SIZE;
 
 /// XZ section compression enable flag.
-pub const FLAG_COMPRESS_XZ: u8 = 0x2;
+pub const FLAG_COMPRESS_XZ: u8 = bpx::FLAG_COMPRESS_XZ;
 
 /// Section weak checksum enable flag.
-pub const FLAG_CHECK_WEAK: u8 = 0x8;
+pub const FLAG_CHECK_WEAK: u8 = bpx::FLAG_CHECK_WEAK;
 
 /// ZLIB section compression enable flag.
-pub const FLAG_COMPRESS_ZLIB: u8 = 0x1;
+pub const FLAG_COMPRESS_ZLIB: u8 = bpx::FLAG_COMPRESS_ZLIB;
 
 /// Section CRC32 checksum enable flag.
-pub const FLAG_CHECK_CRC32: u8 = 0x4;
+pub const FLAG_CHECK_CRC32: u8 = bpx::FLAG_CHECK_CRC32;
+
+/// Section AES-256-GCM encryption enable flag.
+///
+/// *Encrypted sections are stored as a 7 bytes random nonce, followed by the (possibly
+/// compressed) data encrypted in chunks of 64 KiB, each chunk being followed by a 16 bytes
+/// authentication tag. The checksum of the section covers these stored bytes. The key is
+/// never stored in the file.*
+pub const FLAG_ENCRYPT_AES_GCM: u8 = bpx::FLAG_ENCRYPT_AES_GCM;
+
+/// The magic bytes starting the optional whole-file integrity trailer.
+pub const TRAILER_MAGIC: [u8; 4] = bpx::TRAILER_MAGIC;
+
+/// The size in bytes of the optional whole-file integrity trailer.
+///
+/// *The trailer is located right after the last section (at the file size recorded in the
+/// main header) and is made of [TRAILER_MAGIC] followed by the little endian CRC32 of all
+/// preceding bytes.*
+pub const SIZE_TRAILER: usize = bpx::TRAILER_SIZE;
 
 /// The standard variant for a BPX Strings section.
-pub const SECTION_TYPE_STRING: u8 = 0xFF;
+pub const SECTION_TYPE_STRING: u8 = bpx::SECTION_TYPE_STRING;
 
 /// The standard variant for a BPX Structured Data section.
-pub const SECTION_TYPE_SD: u8 = 0xFE;
+pub const SECTION_TYPE_SD: u8 = bpx::SECTION_TYPE_SD;
+
+/// The known section types of the BPX specification.
+///
+/// *Variant section types only have a meaning under the main type of their variant: the
+/// same type byte is used by different sections in a BPX Package and in a BPX Shader
+/// Package.*
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SectionType
+{
+    /// The standard strings section ([SECTION_TYPE_STRING]).
+    Strings,
+
+    /// The standard structured data section ([SECTION_TYPE_SD]).
+    Sd,
+
+    /// The data section of a BPX Package.
+    PackageData,
+
+    /// The object table section of a BPX Package.
+    PackageObjectTable,
+
+    /// The object attribute table section of a BPX Package.
+    PackageAttributeTable,
+
+    /// The shader section of a BPX Shader Package.
+    Shader,
+
+    /// The symbol table section of a BPX Shader Package.
+    ShaderSymbolTable,
+
+    /// The extended data section of a BPX Shader Package.
+    ShaderExtendedData,
+
+    /// A section type defined by a custom variant.
+    ///
+    /// # Arguments
+    /// * the type byte.
+    Custom(u8)
+}
+
+impl SectionType
+{
+    /// Identifies the type byte of a section under a given main type.
+    ///
+    /// *Known types are never returned as [Custom](SectionType::Custom).*
+    ///
+    /// # Arguments
+    ///
+    /// * `main_type`: the main type of the BPX variant.
+    /// * `ty`: the type byte of the section.
+    ///
+    /// returns: SectionType
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::header::SectionType;
+    ///
+    /// assert_eq!(SectionType::from_raw(b'P', 0x1), SectionType::PackageData);
+    /// assert_eq!(SectionType::from_raw(b'S', 0x1), SectionType::Shader);
+    /// assert_eq!(SectionType::from_raw(b'X', 0x1), SectionType::Custom(0x1));
+    /// assert_eq!(u8::from(SectionType::Strings), 0xFF);
+    /// ```
+    pub fn from_raw(main_type: u8, ty: u8) -> SectionType
+    {
+        match ty {
+            SECTION_TYPE_STRING => return SectionType::Strings,
+            SECTION_TYPE_SD => return SectionType::Sd,
+            _ => ()
+        }
+        match reserved::lookup(main_type, ty) {
+            Some(&reserved::PACKAGE_DATA) => SectionType::PackageData,
+            Some(&reserved::PACKAGE_OBJECT_TABLE) => SectionType::PackageObjectTable,
+            Some(&reserved::PACKAGE_ATTRIBUTE_TABLE) => SectionType::PackageAttributeTable,
+            Some(&reserved::SHADER_SHADER) => SectionType::Shader,
+            Some(&reserved::SHADER_SYMBOL_TABLE) => SectionType::ShaderSymbolTable,
+            Some(&reserved::SHADER_EXTENDED_DATA) => SectionType::ShaderExtendedData,
+            _ => SectionType::Custom(ty)
+        }
+    }
+}
+
+impl From<SectionType> for u8
+{
+    fn from(ty: SectionType) -> Self
+    {
+        match ty {
+            SectionType::Strings => SECTION_TYPE_STRING,
+            SectionType::Sd => SECTION_TYPE_SD,
+            SectionType::PackageData => reserved::PACKAGE_DATA.section_type,
+            SectionType::PackageObjectTable => reserved::PACKAGE_OBJECT_TABLE.section_type,
+            SectionType::PackageAttributeTable => reserved::PACKAGE_ATTRIBUTE_TABLE.section_type,
+            SectionType::Shader => reserved::SHADER_SHADER.section_type,
+            SectionType::ShaderSymbolTable => reserved::SHADER_SYMBOL_TABLE.section_type,
+            SectionType::ShaderExtendedData => reserved::SHADER_EXTENDED_DATA.section_type,
+            SectionType::Custom(v) => v
+        }
+    }
+}
 
 /// The BPX version this crate supports.
-pub const BPX_CURRENT_VERSION: u32 = 0x2;
+pub const BPX_CURRENT_VERSION: u32 = bpx::VERSION;
 
 /// The values allowed for the version field in BPX main header.
-pub const KNOWN_VERSIONS: &[u32] = &[0x1, 0x2];
+pub const KNOWN_VERSIONS: &[u32] = bpx::KNOWN_VERSIONS;
+
+// Ties the main and section header codecs below to the layout declared in the spec module.
+const _: () = assert!(bpx::MAIN_HEADER_TYPE_EXT_OFFSET + bpx::TYPE_EXT_SIZE == SIZE_MAIN_HEADER);
+const _: () = assert!(bpx::MAIN_HEADER_VERSION_OFFSET + 4 == bpx::MAIN_HEADER_TYPE_EXT_OFFSET);
+const _: () = assert!(
+    bpx::MAIN_HEADER_SIGNATURE_OFFSET + bpx::SIGNATURE.len() == bpx::MAIN_HEADER_TYPE_OFFSET
+);
+const _: () = assert!(bpx::SECTION_HEADER_FLAGS_OFFSET < SIZE_SECTION_HEADER);
+const _: () = assert!(bpx::SECTION_HEADER_CHECKSUM_OFFSET + 4 == bpx::SECTION_HEADER_TYPE_OFFSET);
+const _: () = assert!(bpx::TRAILER_MAGIC.len() + 4 == SIZE_TRAILER);
 
 /// The BPX Main Header.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -223,13 +352,13 @@ impl Struct<SIZE_MAIN_HEADER> for MainHeader
     fn new() -> Self
     {
         MainHeader {
-            signature: *b"BPX",                 //+0
+            signature: bpx::SIGNATURE,          //+0
             ty: b'P',                           //+3
             chksum: 0,                          //+4
             file_size: SIZE_MAIN_HEADER as u64, //+8
             section_num: 0,                     //+16
             version: BPX_CURRENT_VERSION,       //+20
-            type_ext: [0; 16]
+            type_ext: [0; bpx::TYPE_EXT_SIZE]
         }
     }
 
@@ -243,20 +372,22 @@ impl Struct<SIZE_MAIN_HEADER> for MainHeader
         let mut checksum: u32 = 0;
 
         for (i, byte) in buffer.iter().enumerate() {
-            if !(4..8).contains(&i) {
+            if !(bpx::MAIN_HEADER_CHECKSUM_OFFSET..bpx::MAIN_HEADER_CHECKSUM_OFFSET + 4)
+                .contains(&i)
+            {
                 checksum += *byte as u32;
             }
         }
         let head = MainHeader {
-            signature: extract_slice(&buffer, 0),
-            ty: buffer[3],
-            chksum: LittleEndian::read_u32(&buffer[4..8]),
-            file_size: LittleEndian::read_u64(&buffer[8..16]),
-            section_num: LittleEndian::read_u32(&buffer[16..20]),
-            version: LittleEndian::read_u32(&buffer[20..24]),
-            type_ext: extract_slice(&buffer, 24)
+            signature: extract_slice(&buffer, bpx::MAIN_HEADER_SIGNATURE_OFFSET),
+            ty: buffer[bpx::MAIN_HEADER_TYPE_OFFSET],
+            chksum: LittleEndian::read_u32(&buffer[bpx::MAIN_HEADER_CHECKSUM_OFFSET..]),
+            file_size: LittleEndian::read_u64(&buffer[bpx::MAIN_HEADER_FILE_SIZE_OFFSET..]),
+            section_num: LittleEndian::read_u32(&buffer[bpx::MAIN_HEADER_SECTION_NUM_OFFSET..]),
+            version: LittleEndian::read_u32(&buffer[bpx::MAIN_HEADER_VERSION_OFFSET..]),
+            type_ext: extract_slice(&buffer, bpx::MAIN_HEADER_TYPE_EXT_OFFSET)
         };
-        if &head.signature != b"BPX" {
+        if head.signature != bpx::SIGNATURE {
             return Err(ReadError::BadSignature(head.signature));
         }
         if !KNOWN_VERSIONS.contains(&head.version) {
@@ -268,15 +399,20 @@ impl Struct<SIZE_MAIN_HEADER> for MainHeader
     fn to_bytes(&self) -> [u8; SIZE_MAIN_HEADER]
     {
         let mut block: [u8; SIZE_MAIN_HEADER] = [0; SIZE_MAIN_HEADER];
-        block[0] = self.signature[0];
-        block[1] = self.signature[1];
-        block[2] = self.signature[2];
-        block[3] = self.ty;
-        LittleEndian::write_u32(&mut block[4..8], self.chksum);
-        LittleEndian::write_u64(&mut block[8..16], self.file_size);
-        LittleEndian::write_u32(&mut block[16..20], self.section_num);
-        LittleEndian::write_u32(&mut block[20..24], self.version);
-        block[24..40].copy_from_slice(&self.type_ext);
+        block[bpx::MAIN_HEADER_SIGNATURE_OFFSET..bpx::MAIN_HEADER_TYPE_OFFSET]
+            .copy_from_slice(&self.signature);
+        block[bpx::MAIN_HEADER_TYPE_OFFSET] = self.ty;
+        LittleEndian::write_u32(&mut block[bpx::MAIN_HEADER_CHECKSUM_OFFSET..], self.chksum);
+        LittleEndian::write_u64(
+            &mut block[bpx::MAIN_HEADER_FILE_SIZE_OFFSET..],
+            self.file_size
+        );
+        LittleEndian::write_u32(
+            &mut block[bpx::MAIN_HEADER_SECTION_NUM_OFFSET..],
+            self.section_num
+        );
+        LittleEndian::write_u32(&mut block[bpx::MAIN_HEADER_VERSION_OFFSET..], self.version);
+        block[bpx::MAIN_HEADER_TYPE_EXT_OFFSET..].copy_from_slice(&self.type_ext);
         block
     }
 }
@@ -350,12 +486,12 @@ impl Struct<SIZE_SECTION_HEADER> for SectionHeader
         Ok((
             checksum,
             SectionHeader {
-                pointer: LittleEndian::read_u64(&buffer[0..8]),
-                csize: LittleEndian::read_u32(&buffer[8..12]),
-                size: LittleEndian::read_u32(&buffer[12..16]),
-                chksum: LittleEndian::read_u32(&buffer[16..20]),
-                ty: buffer[20],
-                flags: buffer[21]
+                pointer: LittleEndian::read_u64(&buffer[bpx::SECTION_HEADER_POINTER_OFFSET..]),
+                csize: LittleEndian::read_u32(&buffer[bpx::SECTION_HEADER_CSIZE_OFFSET..]),
+                size: LittleEndian::read_u32(&buffer[bpx::SECTION_HEADER_SIZE_OFFSET..]),
+                chksum: LittleEndian::read_u32(&buffer[bpx::SECTION_HEADER_CHECKSUM_OFFSET..]),
+                ty: buffer[bpx::SECTION_HEADER_TYPE_OFFSET],
+                flags: buffer[bpx::SECTION_HEADER_FLAGS_OFFSET]
             }
         ))
     }
@@ -363,12 +499,18 @@ impl Struct<SIZE_SECTION_HEADER> for SectionHeader
     fn to_bytes(&self) -> [u8; SIZE_SECTION_HEADER]
     {
         let mut block: [u8; SIZE_SECTION_HEADER] = [0; SIZE_SECTION_HEADER];
-        LittleEndian::write_u64(&mut block[0..8], self.pointer);
-        LittleEndian::write_u32(&mut block[8..12], self.csize);
-        LittleEndian::write_u32(&mut block[12..16], self.size);
-        LittleEndian::write_u32(&mut block[16..20], self.chksum);
-        block[20] = self.ty;
-        block[21] = self.flags;
+        LittleEndian::write_u64(
+            &mut block[bpx::SECTION_HEADER_POINTER_OFFSET..],
+            self.pointer
+        );
+        LittleEndian::write_u32(&mut block[bpx::SECTION_HEADER_CSIZE_OFFSET..], self.csize);
+        LittleEndian::write_u32(&mut block[bpx::SECTION_HEADER_SIZE_OFFSET..], self.size);
+        LittleEndian::write_u32(
+            &mut block[bpx::SECTION_HEADER_CHECKSUM_OFFSET..],
+            self.chksum
+        );
+        block[bpx::SECTION_HEADER_TYPE_OFFSET] = self.ty;
+        block[bpx::SECTION_HEADER_FLAGS_OFFSET] = self.flags;
         block
     }
 }
@@ -0,0 +1,197 @@
+// Copyright (c) 2021, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Utilities to upgrade BPX files written by older revisions of this library.
+//!
+//! Revision 1 (BPX version 1) files differ from current files in the following ways:
+//!
+//! - the `csize` field of uncompressed sections is not guaranteed to match `size`,
+//! - CRC32 checksums were computed with a faulty table; they are reported as
+//!   [Legacy](ChecksumStatus::Legacy) when they match that table.
+//!
+//! The migration reads each section using its `size` for uncompressed data, ignores checksum
+//! mismatches and re-writes all sections through the current encoder, which recomputes every
+//! CRC32 checksum with the standard table.
+
+use std::io::{Read, Seek, Write};
+
+pub use crate::core::ChecksumStatus;
+use crate::core::{
+    builder::{MainHeaderBuilder, SectionHeaderBuilder},
+    decoder::{
+        backend_size,
+        load_section_unchecked,
+        loaded_checksum_status,
+        read_section_header_table
+    },
+    error::{MigrateError, ReadError, WriteError},
+    header::{MainHeader, Struct, BPX_CURRENT_VERSION},
+    Container,
+    DEFAULT_MAX_SECTIONS,
+    DEFAULT_MEMORY_THRESHOLD
+};
+
+/// Describes the outcome of the migration of a single section.
+#[derive(Copy, Clone, Debug)]
+pub struct SectionReport
+{
+    /// The index of the section in both the old and the new file.
+    pub index: u32,
+
+    /// The type byte of the section.
+    pub ty: u8,
+
+    /// The flags of the section in the old file.
+    pub old_flags: u8,
+
+    /// The flags of the section in the new file.
+    pub new_flags: u8,
+
+    /// The size in bytes of the section data.
+    pub size: u32,
+
+    /// The checksum verification status of the section in the old file.
+    pub checksum: ChecksumStatus
+}
+
+/// A report of a container migration.
+#[derive(Clone, Debug)]
+pub struct MigrateReport
+{
+    /// The version of the old file.
+    pub version: u32,
+
+    /// The outcome of each section in order.
+    pub sections: Vec<SectionReport>
+}
+
+impl MigrateReport
+{
+    /// Returns true if all checksums of the old file could be verified.
+    pub fn is_verified(&self) -> bool
+    {
+        self.sections.iter().all(|v| v.checksum.is_valid())
+    }
+}
+
+/// Upgrades a BPX file to the current version of the format.
+///
+/// Sections are written in the same order, with the same type, compression method and checksum
+/// algorithm.
+///
+/// # Arguments
+///
+/// * `old`: the old BPX file.
+/// * `new`: the backend to write the upgraded BPX to.
+///
+/// returns: Result<MigrateReport, MigrateError>
+///
+/// # Errors
+///
+/// A [MigrateError](crate::core::error::MigrateError) is returned if the old file could not be
+/// read or if the new file could not be written.
+///
+/// # Examples
+///
+/// ```
+/// use bpx::core::builder::MainHeaderBuilder;
+/// use bpx::core::Container;
+/// use bpx::core::migrate::upgrade;
+/// use bpx::utils::new_byte_buf;
+///
+/// let mut old = Container::create(new_byte_buf(0), MainHeaderBuilder::new().version(1));
+/// old.save().unwrap();
+/// let mut old = old.into_inner();
+/// old.set_position(0);
+/// let mut new = new_byte_buf(0);
+/// let report = upgrade(old, &mut new).unwrap();
+/// assert_eq!(report.version, 1);
+/// assert!(report.sections.is_empty());
+/// ```
+pub fn upgrade<R: Read + Seek, W: Write + Seek>(
+    mut old: R,
+    new: W
+) -> Result<MigrateReport, MigrateError>
+{
+    let (checksum, header) = MainHeader::read(&mut old)?;
+    let file_size = backend_size(&mut old).map_err(ReadError::Io)?;
+    let (_, sections) = read_section_header_table(
+        &mut old,
+        &header,
+        checksum,
+        DEFAULT_MAX_SECTIONS,
+        Some(file_size)
+    )?;
+    let mut container = Container::create(
+        new,
+        MainHeaderBuilder::new()
+            .ty(header.ty)
+            .type_ext(header.type_ext)
+            .version(BPX_CURRENT_VERSION)
+    );
+    let mut report = MigrateReport {
+        version: header.version,
+        sections: Vec::with_capacity(sections.len())
+    };
+    for entry in sections.values() {
+        let (mut data, chksum) = load_section_unchecked(
+            &mut old,
+            &entry.header,
+            DEFAULT_MEMORY_THRESHOLD,
+            None,
+            false
+        )?;
+        let mut builder = SectionHeaderBuilder::new();
+        builder.ty(entry.header.ty);
+        if let Some((method, _)) = entry.header.compression() {
+            //Compressed sections stay compressed whatever their size
+            builder.compression(method).threshold(0);
+        }
+        if let Some(chksum) = entry.header.checksum() {
+            builder.checksum(chksum);
+        }
+        let handle = container.create_section(&mut builder);
+        let mut section = container.get_mut(handle);
+        let out = section.open().ok_or(WriteError::SectionNotLoaded)?;
+        std::io::copy(&mut data, out).map_err(WriteError::Io)?;
+        let checksum = loaded_checksum_status(&mut data, &entry.header, chksum)?;
+        report.sections.push(SectionReport {
+            index: entry.index,
+            ty: entry.header.ty,
+            old_flags: entry.header.flags,
+            new_flags: 0,
+            size: entry.header.size,
+            checksum
+        });
+    }
+    container.save()?;
+    for (section, report) in container.iter().zip(report.sections.iter_mut()) {
+        report.new_flags = section.flags;
+    }
+    Ok(report)
+}
@@ -28,17 +28,33 @@
 
 //! The core BPX container implementation.
 
+#[cfg(feature = "async")]
+mod async_container;
 mod container;
+mod cursor;
 
 pub mod builder;
 mod compression;
 mod data;
 mod decoder;
 mod encoder;
+mod encryption;
 pub mod error;
 pub mod header;
+pub mod migrate;
+mod reader;
+pub mod reserved;
 mod section;
+#[cfg(feature = "sync")]
+mod sync_container;
+mod trailer;
 
+#[cfg(feature = "async")]
+pub use async_container::AsyncContainer;
 pub use container::*;
-pub use data::{AutoSectionData, SectionData};
+pub use cursor::SectionCursor;
+pub use data::{compare, AutoSectionData, BorrowedSection, SectionData};
+pub use reader::SectionReader;
 pub use section::{Section, SectionMut};
+#[cfg(feature = "sync")]
+pub use sync_container::{SyncContainer, SyncSection};
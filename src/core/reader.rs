@@ -0,0 +1,200 @@
+// Copyright (c) 2021, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+
+use crate::core::{
+    compression::{Checksum, Crc32Checksum, WeakChecksum, XzDecoder, ZlibDecoder},
+    data::AutoSectionData,
+    decoder::legacy_crc32_section,
+    error::{InflateError, ReadError},
+    header::{
+        SectionHeader,
+        Struct,
+        FLAG_CHECK_CRC32,
+        FLAG_CHECK_WEAK,
+        FLAG_COMPRESS_XZ,
+        FLAG_COMPRESS_ZLIB
+    }
+};
+
+enum Source<'a, T>
+{
+    Loaded(&'a mut AutoSectionData),
+    Raw(&'a mut T, usize),
+    Zlib(&'a mut T, ZlibDecoder),
+    Xz(&'a mut T, XzDecoder)
+}
+
+enum Check
+{
+    Weak(WeakChecksum),
+    Crc32(Crc32Checksum)
+}
+
+impl Check
+{
+    fn push(&mut self, buffer: &[u8])
+    {
+        match self {
+            Check::Weak(v) => v.push(buffer),
+            Check::Crc32(v) => v.push(buffer)
+        }
+    }
+
+    fn finish(self) -> u32
+    {
+        match self {
+            Check::Weak(v) => v.finish(),
+            Check::Crc32(v) => v.finish()
+        }
+    }
+}
+
+/// A forward-only reader over the content of a section.
+///
+/// Data is read from the backend and decompressed on the fly, block by block: the section is
+/// never entirely decoded in memory. The checksum of the section, if any, is verified once the
+/// end of the section is reached.
+pub struct SectionReader<'a, T>
+{
+    source: Source<'a, T>,
+    check: Option<Check>,
+    header: SectionHeader
+}
+
+pub fn new_section_reader<'a, T: Read + Seek>(
+    backend: &'a mut T,
+    header: &SectionHeader
+) -> Result<SectionReader<'a, T>, ReadError>
+{
+    backend.seek(SeekFrom::Start(header.pointer))?;
+    let source = if header.flags & FLAG_COMPRESS_XZ != 0 {
+        Source::Xz(backend, XzDecoder::new(header.csize as usize)?)
+    } else if header.flags & FLAG_COMPRESS_ZLIB != 0 {
+        Source::Zlib(backend, ZlibDecoder::new(header.csize as usize)?)
+    } else {
+        Source::Raw(backend, header.size as usize)
+    };
+    let check = if header.flags & FLAG_CHECK_WEAK != 0 {
+        Some(Check::Weak(WeakChecksum::new()))
+    } else if header.flags & FLAG_CHECK_CRC32 != 0 {
+        Some(Check::Crc32(Crc32Checksum::new()))
+    } else {
+        None
+    };
+    Ok(SectionReader {
+        source,
+        check,
+        header: *header
+    })
+}
+
+pub fn new_loaded_section_reader<T>(
+    data: &mut AutoSectionData
+) -> Result<SectionReader<'_, T>, ReadError>
+{
+    data.seek(SeekFrom::Start(0))?;
+    Ok(SectionReader {
+        source: Source::Loaded(data),
+        check: None,
+        header: SectionHeader::new()
+    })
+}
+
+impl<'a, T: Read + Seek> SectionReader<'a, T>
+{
+    /// Checks a mismatching CRC32 again with the legacy table, reading the section once more.
+    fn is_legacy_crc32(&mut self) -> Result<bool, ReadError>
+    {
+        if self.header.flags & FLAG_CHECK_CRC32 == 0 {
+            return Ok(false);
+        }
+        let backend = match &mut self.source {
+            Source::Loaded(_) => return Ok(false),
+            Source::Raw(backend, _) | Source::Zlib(backend, _) | Source::Xz(backend, _) => backend
+        };
+        Ok(legacy_crc32_section(&mut **backend, &self.header, None)? == self.header.chksum)
+    }
+}
+
+impl<'a, T: Read + Seek> Read for SectionReader<'a, T>
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize>
+    {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let len = match &mut self.source {
+            Source::Loaded(data) => data.read(buf)?,
+            Source::Raw(backend, remaining) => {
+                let size = std::cmp::min(buf.len(), *remaining);
+                let len = backend.read(&mut buf[..size])?;
+                if len == 0 && size > 0 {
+                    return Err(Error::from(ErrorKind::UnexpectedEof));
+                }
+                *remaining -= len;
+                len
+            },
+            Source::Zlib(backend, decoder) => {
+                decoder.decode(&mut **backend, buf).map_err(map_err)?
+            },
+            Source::Xz(backend, decoder) => decoder.decode(&mut **backend, buf).map_err(map_err)?
+        };
+        if len > 0 {
+            if let Some(check) = &mut self.check {
+                check.push(&buf[..len]);
+            }
+        } else if let Some(check) = self.check.take() {
+            let actual = check.finish();
+            if actual != self.header.chksum && !self.is_legacy_crc32().map_err(to_io)? {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    ReadError::Checksum(actual, self.header.chksum).to_string()
+                ));
+            }
+        }
+        Ok(len)
+    }
+}
+
+fn to_io(e: ReadError) -> Error
+{
+    match e {
+        ReadError::Io(e) => e,
+        e => Error::new(ErrorKind::InvalidData, e.to_string())
+    }
+}
+
+fn map_err(e: InflateError) -> Error
+{
+    match e {
+        InflateError::Io(e) => e,
+        e => Error::new(ErrorKind::InvalidData, e.to_string())
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright (c) 2021, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Registry of the section types reserved by official BPX variants.
+//!
+//! Custom variants should avoid reusing these section types to prevent confusion with the
+//! official variants. The standard core section types
+//! ([SECTION_TYPE_STRING](crate::core::header::SECTION_TYPE_STRING) and
+//! [SECTION_TYPE_SD](crate::core::header::SECTION_TYPE_SD)) share the same meaning in every
+//! variant and are therefore not listed here.
+
+use crate::{
+    core::header::{SECTION_TYPE_SD, SECTION_TYPE_STRING},
+    spec::{bpxp, bpxs},
+    Handle
+};
+
+/// The main type of a BPX Package (type P).
+pub const MAIN_TYPE_PACKAGE: u8 = bpxp::TYPE_BYTE;
+
+/// The main type of a BPX Shader Package (type S).
+pub const MAIN_TYPE_SHADER: u8 = bpxs::TYPE_BYTE;
+
+/// A section type reserved by an official BPX variant.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct ReservedSectionType
+{
+    /// The main type of the official variant which reserves this section type.
+    pub main_type: u8,
+
+    /// The reserved section type byte.
+    pub section_type: u8,
+
+    /// A short description of the section.
+    pub name: &'static str
+}
+
+/// The data section of a BPX Package (type P).
+pub const PACKAGE_DATA: ReservedSectionType = ReservedSectionType {
+    main_type: MAIN_TYPE_PACKAGE,
+    section_type: bpxp::DATA_TYPE,
+    name: "package data"
+};
+
+/// The object table section of a BPX Package (type P).
+pub const PACKAGE_OBJECT_TABLE: ReservedSectionType = ReservedSectionType {
+    main_type: MAIN_TYPE_PACKAGE,
+    section_type: bpxp::OBJECT_TABLE_TYPE,
+    name: "package object table"
+};
+
+/// The object attribute table section of a BPX Package (type P).
+pub const PACKAGE_ATTRIBUTE_TABLE: ReservedSectionType = ReservedSectionType {
+    main_type: MAIN_TYPE_PACKAGE,
+    section_type: bpxp::ATTRIBUTE_TABLE_TYPE,
+    name: "package attribute table"
+};
+
+/// The shader section of a BPX Shader Package (type S).
+pub const SHADER_SHADER: ReservedSectionType = ReservedSectionType {
+    main_type: MAIN_TYPE_SHADER,
+    section_type: bpxs::SHADER_TYPE,
+    name: "shader"
+};
+
+/// The symbol table section of a BPX Shader Package (type S).
+pub const SHADER_SYMBOL_TABLE: ReservedSectionType = ReservedSectionType {
+    main_type: MAIN_TYPE_SHADER,
+    section_type: bpxs::SYMBOL_TABLE_TYPE,
+    name: "shader symbol table"
+};
+
+/// The extended data section of a BPX Shader Package (type S).
+pub const SHADER_EXTENDED_DATA: ReservedSectionType = ReservedSectionType {
+    main_type: MAIN_TYPE_SHADER,
+    section_type: bpxs::EXTENDED_DATA_TYPE,
+    name: "shader extended data"
+};
+
+/// All section types reserved by official BPX variants.
+pub const RESERVED_SECTION_TYPES: &[ReservedSectionType] = &[
+    PACKAGE_DATA,
+    PACKAGE_OBJECT_TABLE,
+    PACKAGE_ATTRIBUTE_TABLE,
+    SHADER_SHADER,
+    SHADER_SYMBOL_TABLE,
+    SHADER_EXTENDED_DATA
+];
+
+/// Looks up the reserved meaning of a section type under a given main type.
+///
+/// # Arguments
+///
+/// * `main_type`: the main type of the BPX variant.
+/// * `section_type`: the section type byte.
+///
+/// returns: Option<&ReservedSectionType>
+///
+/// # Examples
+///
+/// ```
+/// use bpx::core::reserved::{lookup, MAIN_TYPE_SHADER, SHADER_SYMBOL_TABLE};
+///
+/// assert_eq!(lookup(MAIN_TYPE_SHADER, 0x2), Some(&SHADER_SYMBOL_TABLE));
+/// assert_eq!(lookup(MAIN_TYPE_SHADER, 0x4), None);
+/// ```
+pub fn lookup(main_type: u8, section_type: u8) -> Option<&'static ReservedSectionType>
+{
+    RESERVED_SECTION_TYPES
+        .iter()
+        .find(|v| v.main_type == main_type && v.section_type == section_type)
+}
+
+/// Checks if a section type is a standard core section type or is reserved by any official
+/// variant.
+///
+/// *Custom variants should not use reserved section types.*
+///
+/// # Arguments
+///
+/// * `section_type`: the section type byte.
+///
+/// returns: bool
+///
+/// # Examples
+///
+/// ```
+/// use bpx::core::reserved::is_reserved;
+///
+/// assert!(is_reserved(0xFF));
+/// assert!(is_reserved(0x1));
+/// assert!(!is_reserved(0x40));
+/// ```
+pub fn is_reserved(section_type: u8) -> bool
+{
+    section_type == SECTION_TYPE_STRING
+        || section_type == SECTION_TYPE_SD
+        || RESERVED_SECTION_TYPES
+            .iter()
+            .any(|v| v.section_type == section_type)
+}
+
+/// A section whose type collides with a section type reserved by an official variant.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Conflict
+{
+    /// The handle of the section.
+    pub handle: Handle,
+
+    /// The reserved section type the section collides with.
+    pub reserved: ReservedSectionType
+}
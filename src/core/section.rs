@@ -28,12 +28,15 @@
 
 use std::{
     io::{Read, Seek},
-    ops::Deref
+    ops::Deref,
+    sync::atomic::{AtomicU64, Ordering}
 };
 
 use crate::{
     core::{
-        data::AutoSectionData,
+        builder::CompressionLevel,
+        cursor::SectionCursor,
+        data::{AutoSectionData, SectionData},
         decoder::load_section1,
         error::ReadError,
         header::{
@@ -41,17 +44,24 @@ use crate::{
             FLAG_CHECK_CRC32,
             FLAG_CHECK_WEAK,
             FLAG_COMPRESS_XZ,
-            FLAG_COMPRESS_ZLIB
+            FLAG_COMPRESS_ZLIB,
+            FLAG_ENCRYPT_AES_GCM
         }
     },
     utils::OptionExtension,
     Handle
 };
 
+#[derive(Clone)]
 pub struct SectionEntry1
 {
     pub threshold: u32,
-    pub flags: u8
+    pub flags: u8,
+    pub memory_threshold: usize,
+    pub level: Option<CompressionLevel>,
+    pub key: Option<[u8; 32]>,
+    pub skip_checksum: bool,
+    pub max_size: Option<u32>
 }
 
 impl SectionEntry1
@@ -69,7 +79,7 @@ impl SectionEntry1
         } else if self.flags & FLAG_COMPRESS_ZLIB != 0 && size > self.threshold {
             flags |= FLAG_COMPRESS_ZLIB;
         }
-        flags
+        flags | (self.flags & FLAG_ENCRYPT_AES_GCM)
     }
 }
 
@@ -78,16 +88,50 @@ pub struct SectionEntry
     pub entry1: SectionEntry1,
     pub header: SectionHeader,
     pub data: Option<AutoSectionData>,
+    /// The stored (compressed and encrypted) bytes of a section which is not loaded, copied as
+    /// is on save.
+    pub raw: Option<AutoSectionData>,
     pub index: u32,
+    /// True if the section must be encoded again on save regardless of its data, for example
+    /// after a change of its flags.
     pub modified: bool
 }
 
+impl SectionEntry
+{
+    /// Returns true if the section must be written on the next save.
+    pub fn is_modified(&self) -> bool
+    {
+        self.modified || self.data.as_ref().map(|v| v.is_written()).unwrap_or(false)
+    }
+
+    /// Marks the section as identical to the data stored in the backend.
+    pub fn set_saved(&mut self)
+    {
+        self.modified = false;
+        if let Some(data) = self.data.as_mut() {
+            data.clear_written();
+        }
+    }
+}
+
+/// Sorts section entries by their index in the file.
+pub fn sorted_entries<'a, E: std::ops::Deref<Target = SectionEntry>>(
+    entries: impl Iterator<Item = (&'a u32, E)>
+) -> Vec<(&'a u32, E)>
+{
+    let mut entries: Vec<(&'a u32, E)> = entries.collect();
+    entries.sort_by_key(|(_, entry)| entry.index);
+    entries
+}
+
 /// A mutable reference to a section.
 pub struct SectionMut<'a, T>
 {
     backend: &'a mut T,
     entry: &'a mut SectionEntry,
-    handle: Handle
+    handle: Handle,
+    available: &'a AtomicU64
 }
 
 impl<'a, T: Read + Seek> SectionMut<'a, T>
@@ -101,11 +145,31 @@ impl<'a, T: Read + Seek> SectionMut<'a, T>
     /// truncated or if some data couldn't be read.
     pub fn load(&mut self) -> Result<&mut AutoSectionData, ReadError>
     {
-        let data = self
-            .entry
-            .data
-            .get_or_insert_with_err(|| load_section1(self.backend, &self.entry.header))?;
-        self.entry.modified = true;
+        let data = self.entry.data.get_or_insert_with_err(|| {
+            trace_span!(
+                "bpx::load",
+                index = self.entry.index,
+                size = self.entry.header.size,
+                csize = self.entry.header.csize,
+                compression = ?crate::core::CompressionKind::from_flags(self.entry.header.flags)
+            );
+            //u64::MAX means no total memory limit
+            let available = self.available.load(Ordering::Relaxed);
+            if available == u64::MAX {
+                return load_section1(self.backend, &self.entry.header, &self.entry.entry1);
+            }
+            let mut entry1 = self.entry.entry1.clone();
+            let limit = available.min(u32::MAX as u64) as u32;
+            entry1.max_size = Some(entry1.max_size.map_or(limit, |v| v.min(limit)));
+            let data = load_section1(self.backend, &self.entry.header, &entry1)?;
+            self.available.store(
+                available.saturating_sub(data.size() as u64),
+                Ordering::Relaxed
+            );
+            Ok(data)
+        })?;
+        //Once loaded the section is encoded again on save
+        self.entry.raw = None;
         Ok(data)
     }
 }
@@ -116,7 +180,6 @@ impl<'a, T> SectionMut<'a, T>
     /// Returns None if the section is not loaded.
     pub fn open(&mut self) -> Option<&mut AutoSectionData>
     {
-        self.entry.modified = true;
         self.entry.data.as_mut()
     }
 
@@ -146,13 +209,15 @@ impl<'a, T> Deref for SectionMut<'a, T>
 pub fn new_section_mut<'a, T>(
     backend: &'a mut T,
     entry: &'a mut SectionEntry,
-    handle: Handle
+    handle: Handle,
+    available: &'a AtomicU64
 ) -> SectionMut<'a, T>
 {
     SectionMut {
         backend,
         entry,
-        handle
+        handle,
+        available
     }
 }
 
@@ -172,6 +237,16 @@ impl<'a> Section<'a>
         self.entry.data.as_ref()
     }
 
+    /// Creates a new read cursor over the inner section data.
+    /// Returns None if the section is not loaded.
+    ///
+    /// *The cursor has its own position: any number of cursors may read this section at the
+    /// same time.*
+    pub fn cursor(&self) -> Option<SectionCursor<'a>>
+    {
+        self.entry.data.as_ref().map(SectionCursor::new)
+    }
+
     /// Gets the handle of this section.
     pub fn handle(&self) -> Handle
     {
@@ -195,7 +270,7 @@ impl<'a> Deref for Section<'a>
     }
 }
 
-pub fn new_section(entry: &SectionEntry, handle: Handle) -> Section
+pub fn new_section(entry: &SectionEntry, handle: Handle) -> Section<'_>
 {
     Section { entry, handle }
 }
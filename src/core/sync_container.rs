@@ -0,0 +1,527 @@
+// Copyright (c) 2021, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    ops::{Deref, DerefMut},
+    sync::{Mutex, MutexGuard, TryLockError}
+};
+
+use crate::{
+    core::{
+        data::AutoSectionData,
+        decoder::{backend_size, load_section1, read_section_header_table},
+        error::ReadError,
+        header::{MainHeader, SectionHeader, Struct},
+        section::SectionEntry1,
+        DEFAULT_MAX_SECTIONS
+    },
+    ContainerTag,
+    Handle
+};
+
+struct SyncEntry
+{
+    entry1: SectionEntry1,
+    header: SectionHeader,
+    index: u32,
+    data: Mutex<Option<AutoSectionData>>
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T>
+{
+    //A panic while holding the lock cannot leave the data in an inconsistent state:
+    //the backend is always re-positioned before reading and sections are only stored
+    //once fully decoded.
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A read-only BPX container which can be shared between threads.
+///
+/// Unlike [Container](crate::core::Container), sections are loaded through a shared
+/// reference: the backend is protected by a [Mutex](std::sync::Mutex) which is only held
+/// while reading raw section data so that decompression, decryption and checksum verification
+/// of different sections run concurrently.
+///
+/// *Each section is locked while a [SyncSection](crate::core::SyncSection) to it is alive;
+/// accessing the same section from another thread meanwhile returns
+/// [SectionInUse](crate::core::error::ReadError::SectionInUse) instead of blocking.*
+///
+/// # Locking
+///
+/// Headers are immutable once opened and are read without any lock. There are two levels
+/// of locks, always acquired in this order:
+///
+/// 1. the lock of a section, held for the lifetime of a [SyncSection](crate::core::SyncSection),
+/// 2. the lock of the backend, held only while [load](SyncContainer::load) reads raw section data.
+///
+/// A thread never holds the backend lock while acquiring a section lock, and section locks are
+/// only ever tried, never waited on, so holding several sections at once cannot deadlock.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{Read, Write};
+///
+/// use bpx::core::{builder::{MainHeaderBuilder, SectionHeaderBuilder}, Container, SyncContainer};
+/// use bpx::utils::new_byte_buf;
+///
+/// let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+/// let handle = file.create_section(SectionHeaderBuilder::new().ty(1));
+/// file.get_mut(handle).open().unwrap().write_all(b"shared").unwrap();
+/// file.save().unwrap();
+/// let mut backend = file.into_inner();
+/// backend.set_position(0);
+///
+/// let file = SyncContainer::open(backend).unwrap();
+/// let handle = file.find_section_by_type(1).unwrap();
+/// std::thread::scope(|s| {
+///     s.spawn(|| {
+///         let mut buf = Vec::new();
+///         file.load(handle).unwrap().read_to_end(&mut buf).unwrap();
+///         assert_eq!(buf, b"shared");
+///     });
+/// });
+/// ```
+pub struct SyncContainer<T>
+{
+    backend: Mutex<T>,
+    main_header: MainHeader,
+    sections: BTreeMap<u32, SyncEntry>,
+    //Identifies the handles created by this container
+    tag: ContainerTag
+}
+
+/// A locked reference to a loaded section of a [SyncContainer](crate::core::SyncContainer).
+///
+/// *The section is unlocked when this is dropped.*
+pub struct SyncSection<'a>
+{
+    data: MutexGuard<'a, Option<AutoSectionData>>,
+    header: &'a SectionHeader,
+    handle: Handle,
+    index: u32
+}
+
+impl<'a> SyncSection<'a>
+{
+    /// Gets the header of this section.
+    pub fn header(&self) -> &SectionHeader
+    {
+        self.header
+    }
+
+    /// Gets the handle of this section.
+    pub fn handle(&self) -> Handle
+    {
+        self.handle
+    }
+
+    /// Gets the index of this section.
+    pub fn index(&self) -> u32
+    {
+        self.index
+    }
+}
+
+impl<'a> Deref for SyncSection<'a>
+{
+    type Target = AutoSectionData;
+
+    fn deref(&self) -> &Self::Target
+    {
+        //A SyncSection is only ever constructed from a loaded section.
+        self.data.as_ref().unwrap()
+    }
+}
+
+impl<'a> DerefMut for SyncSection<'a>
+{
+    fn deref_mut(&mut self) -> &mut Self::Target
+    {
+        self.data.as_mut().unwrap()
+    }
+}
+
+impl<T: io::Read + io::Seek> SyncContainer<T>
+{
+    /// Loads a BPX container from the given `backend`.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend`: A [Read](std::io::Read) + [Seek](std::io::Seek) backend to use for reading the BPX container.
+    ///
+    /// returns: Result<SyncContainer<T>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if some headers
+    /// could not be read or if the header data is corrupted.
+    pub fn open(backend: T) -> Result<SyncContainer<T>, ReadError>
+    {
+        SyncContainer::open_with_max_sections(backend, DEFAULT_MAX_SECTIONS)
+    }
+
+    /// Loads a BPX container from the given `backend`, accepting at most
+    /// `max_sections` sections.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend`: A [Read](std::io::Read) + [Seek](std::io::Seek) backend to use for reading the BPX container.
+    /// * `max_sections`: the maximum number of sections the main header may declare.
+    ///
+    /// returns: Result<SyncContainer<T>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::core::error::ReadError) is returned if some headers
+    /// could not be read, if the header data is corrupted or if the main header declares
+    /// more than `max_sections` sections.
+    pub fn open_with_max_sections(
+        mut backend: T,
+        max_sections: u32
+    ) -> Result<SyncContainer<T>, ReadError>
+    {
+        let (checksum, header) = MainHeader::read(&mut backend)?;
+        let file_size = backend_size(&mut backend)?;
+        let (_, sections) = read_section_header_table(
+            &mut backend,
+            &header,
+            checksum,
+            max_sections,
+            Some(file_size)
+        )?;
+        let sections = sections
+            .into_iter()
+            .map(|(handle, entry)| {
+                (
+                    handle,
+                    SyncEntry {
+                        entry1: entry.entry1,
+                        header: entry.header,
+                        index: entry.index,
+                        data: Mutex::new(None)
+                    }
+                )
+            })
+            .collect();
+        Ok(SyncContainer {
+            backend: Mutex::new(backend),
+            main_header: header,
+            sections,
+            tag: ContainerTag::new()
+        })
+    }
+
+    /// Locks a section, loading it if needed.
+    ///
+    /// The backend is only locked while reading the raw section data; decoding happens
+    /// without holding it so that other threads may load other sections concurrently.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: Result<SyncSection, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// Returns [SectionInUse](crate::core::error::ReadError::SectionInUse) if the section is
+    /// already locked and a [ReadError](crate::core::error::ReadError) if the section is
+    /// corrupted, truncated or if some data couldn't be read.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    pub fn load(&self, handle: Handle) -> Result<SyncSection<'_>, ReadError>
+    {
+        let entry = &self.sections[&handle.0];
+        let mut data = try_lock(entry, handle)?;
+        if data.is_none() {
+            trace_span!(
+                "bpx::load",
+                index = entry.index,
+                size = entry.header.size,
+                csize = entry.header.csize,
+                compression = ?crate::core::CompressionKind::from_flags(entry.header.flags)
+            );
+            let mut raw = vec![0; entry.header.csize as usize];
+            {
+                let mut backend = lock(&self.backend);
+                backend.seek(io::SeekFrom::Start(entry.header.pointer))?;
+                backend.read_exact(&mut raw)?;
+            }
+            let mut header = entry.header;
+            header.pointer = 0;
+            *data = Some(load_section1(
+                &mut io::Cursor::new(raw),
+                &header,
+                &entry.entry1
+            )?);
+        }
+        Ok(SyncSection {
+            data,
+            header: &entry.header,
+            handle,
+            index: entry.index
+        })
+    }
+}
+
+fn try_lock(
+    entry: &SyncEntry,
+    handle: Handle
+) -> Result<MutexGuard<'_, Option<AutoSectionData>>, ReadError>
+{
+    match entry.data.try_lock() {
+        Ok(v) => Ok(v),
+        Err(TryLockError::Poisoned(e)) => Ok(e.into_inner()),
+        Err(TryLockError::WouldBlock) => Err(ReadError::SectionInUse(handle))
+    }
+}
+
+impl<T> SyncContainer<T>
+{
+    /// Locks a section if it is loaded.
+    /// Returns None if the section is not loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: Result<Option<SyncSection>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// Returns [SectionInUse](crate::core::error::ReadError::SectionInUse) if the section is
+    /// already locked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    pub fn get(&self, handle: Handle) -> Result<Option<SyncSection<'_>>, ReadError>
+    {
+        let entry = &self.sections[&handle.0];
+        let data = try_lock(entry, handle)?;
+        if data.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(SyncSection {
+            data,
+            header: &entry.header,
+            handle,
+            index: entry.index
+        }))
+    }
+
+    /// Unloads a section, freeing its data.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: Result<(), ReadError>
+    ///
+    /// # Errors
+    ///
+    /// Returns [SectionInUse](crate::core::error::ReadError::SectionInUse) if the section is
+    /// locked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    pub fn unload(&self, handle: Handle) -> Result<(), ReadError>
+    {
+        *try_lock(&self.sections[&handle.0], handle)? = None;
+        Ok(())
+    }
+
+    /// Returns a read-only reference to the BPX main header.
+    pub fn get_main_header(&self) -> &MainHeader
+    {
+        &self.main_header
+    }
+
+    /// Returns the header of a section.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: &SectionHeader
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given section handle is invalid.
+    pub fn get_section_header(&self, handle: Handle) -> &SectionHeader
+    {
+        &self.sections[&handle.0].header
+    }
+
+    /// Returns the header of a section, without panicking on invalid handles.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: Result<&SectionHeader, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// [InvalidHandle](crate::core::error::ReadError::InvalidHandle) is returned if the handle
+    /// does not match the key of any section of this container or, in debug builds, if it was
+    /// created by another container.
+    pub fn try_header(&self, handle: Handle) -> Result<&SectionHeader, ReadError>
+    {
+        self.sections
+            .get(&handle.key_in(self.tag)?)
+            .map(|v| &v.header)
+            .ok_or(ReadError::InvalidHandle(handle))
+    }
+
+    /// Returns the index of a section in the file, without panicking on invalid handles.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle`: a handle to the section.
+    ///
+    /// returns: Result<u32, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// [InvalidHandle](crate::core::error::ReadError::InvalidHandle) is returned if the handle
+    /// does not match the key of any section of this container or, in debug builds, if it was
+    /// created by another container.
+    pub fn try_index(&self, handle: Handle) -> Result<u32, ReadError>
+    {
+        self.sections
+            .get(&handle.key_in(self.tag)?)
+            .map(|v| v.index)
+            .ok_or(ReadError::InvalidHandle(handle))
+    }
+
+    /// Searches for the first section of a given type.
+    /// Returns None if no section could be found.
+    ///
+    /// # Arguments
+    ///
+    /// * `ty`: section type byte.
+    ///
+    /// returns: Option<Handle>
+    pub fn find_section_by_type(&self, ty: u8) -> Option<Handle>
+    {
+        self.iter()
+            .find(|(_, header)| header.ty == ty)
+            .map(|(handle, _)| handle)
+    }
+
+    /// Locates a section by its index in the file.
+    /// Returns None if the section does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: the section index to search for.
+    ///
+    /// returns: Option<Handle>
+    pub fn find_section_by_index(&self, index: u32) -> Option<Handle>
+    {
+        self.sections
+            .iter()
+            .find(|(_, entry)| entry.index == index)
+            .map(|(handle, _)| Handle(*handle, self.tag))
+    }
+
+    /// Creates an iterator over the handle and header of each section, in file order.
+    pub fn iter(&self) -> impl Iterator<Item = (Handle, &SectionHeader)> + '_
+    {
+        let mut entries: Vec<(&u32, &SyncEntry)> = self.sections.iter().collect();
+        entries.sort_by_key(|(_, entry)| entry.index);
+        entries
+            .into_iter()
+            .map(|(handle, entry)| (Handle(*handle, self.tag), &entry.header))
+    }
+
+    /// Returns the number of sections in this container.
+    pub fn len(&self) -> usize
+    {
+        self.sections.len()
+    }
+
+    /// Returns true if this container has no sections.
+    pub fn is_empty(&self) -> bool
+    {
+        self.sections.is_empty()
+    }
+
+    /// Sets the maximum size in bytes of a section to keep in memory when loading; larger
+    /// sections are decoded to a temporary file instead.
+    ///
+    /// *By default, this is [DEFAULT_MEMORY_THRESHOLD](crate::core::DEFAULT_MEMORY_THRESHOLD).*
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes`: the memory threshold in bytes.
+    pub fn set_memory_threshold(&mut self, bytes: usize)
+    {
+        for entry in self.sections.values_mut() {
+            entry.entry1.memory_threshold = bytes;
+        }
+    }
+
+    /// Enables or disables checksum verification when loading sections.
+    ///
+    /// # Arguments
+    ///
+    /// * `skip`: true to disable checksum verification, false to enable it (the default).
+    pub fn set_skip_checksum(&mut self, skip: bool)
+    {
+        for entry in self.sections.values_mut() {
+            entry.entry1.skip_checksum = skip;
+        }
+    }
+
+    /// Sets the AES-256-GCM key used to decrypt encrypted sections.
+    ///
+    /// # Arguments
+    ///
+    /// * `key`: the 256 bits key.
+    #[cfg(feature = "encryption")]
+    pub fn set_encryption_key(&mut self, key: [u8; 32])
+    {
+        for entry in self.sections.values_mut() {
+            entry.entry1.key = Some(key);
+        }
+    }
+
+    /// Consumes this container and returns the underlying backend.
+    pub fn into_inner(self) -> T
+    {
+        self.backend.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright (c) 2021, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Optional whole-file integrity trailer appended after the last section.
+
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::{
+    core::{
+        compression::{Checksum, Crc32Checksum},
+        error::{CorruptionContext, ReadError, WriteError},
+        header::{SIZE_TRAILER, TRAILER_MAGIC}
+    },
+    utils::ReadFill
+};
+
+const READ_BLOCK_SIZE: usize = 8192;
+
+/// Computes the CRC32 of the first `size` bytes of the backend.
+///
+/// Returns None if the backend is shorter than `size` bytes.
+fn checksum_file<T: Read + Seek>(file: &mut T, size: u64) -> std::io::Result<Option<u32>>
+{
+    file.seek(SeekFrom::Start(0))?;
+    let mut chksum = Crc32Checksum::new();
+    let mut buf = [0; READ_BLOCK_SIZE];
+    let mut remaining = size;
+    while remaining > 0 {
+        let len = std::cmp::min(remaining, READ_BLOCK_SIZE as u64) as usize;
+        let count = file.read_fill(&mut buf[..len])?;
+        if count == 0 {
+            return Ok(None);
+        }
+        chksum.push(&buf[..count]);
+        remaining -= count as u64;
+    }
+    Ok(Some(chksum.finish()))
+}
+
+/// Returns the number of bytes stored in the backend after `data_end`, not counting a
+/// trailer located right at `data_end`.
+pub fn trailing_data<T: Read + Seek>(file: &mut T, data_end: u64) -> Result<u64, ReadError>
+{
+    let len = file.seek(SeekFrom::End(0))?;
+    if len <= data_end {
+        return Ok(0);
+    }
+    if len - data_end >= SIZE_TRAILER as u64 {
+        file.seek(SeekFrom::Start(data_end))?;
+        let mut magic = [0; 4];
+        if file.read_fill(&mut magic)? == magic.len() && magic == TRAILER_MAGIC {
+            return Ok(len - data_end - SIZE_TRAILER as u64);
+        }
+    }
+    Ok(len - data_end)
+}
+
+/// Reads the CRC32 stored in the trailer located at `file_size`.
+///
+/// Returns None if there is no complete trailer.
+fn read_trailer<T: Read + Seek>(file: &mut T, file_size: u64) -> Result<Option<u32>, ReadError>
+{
+    file.seek(SeekFrom::Start(file_size))?;
+    let mut trailer = [0; SIZE_TRAILER];
+    if file.read_fill(&mut trailer)? != SIZE_TRAILER || trailer[..4] != TRAILER_MAGIC {
+        return Ok(None);
+    }
+    Ok(Some(LittleEndian::read_u32(&trailer[4..])))
+}
+
+/// Returns true if a complete trailer is located at `file_size`, without checking its CRC32.
+pub fn has_trailer<T: Read + Seek>(file: &mut T, file_size: u64) -> Result<bool, ReadError>
+{
+    Ok(read_trailer(file, file_size)?.is_some())
+}
+
+/// Overwrites the trailer located at `file_size` with zeros so that it is no longer found.
+pub fn strip_trailer<T: Write + Seek>(file: &mut T, file_size: u64) -> std::io::Result<()>
+{
+    file.seek(SeekFrom::Start(file_size))?;
+    file.write_all(&[0; SIZE_TRAILER])
+}
+
+/// Checks the trailer located at `file_size`.
+///
+/// Returns false if there is no complete trailer or if the CRC32 does not match.
+pub fn check_trailer<T: Read + Seek>(file: &mut T, file_size: u64) -> Result<bool, ReadError>
+{
+    match read_trailer(file, file_size)? {
+        Some(expected) => Ok(checksum_file(file, file_size)? == Some(expected)),
+        None => Ok(false)
+    }
+}
+
+/// Checks the trailer located at `file_size` if there is one.
+///
+/// Fails with [Corrupted](ReadError::Corrupted) if the CRC32 does not match.
+pub fn verify_trailer<T: Read + Seek>(file: &mut T, file_size: u64) -> Result<(), ReadError>
+{
+    if let Some(expected) = read_trailer(file, file_size)? {
+        if checksum_file(file, file_size)? != Some(expected) {
+            return Err(ReadError::Corrupted(CorruptionContext::Trailer));
+        }
+    }
+    Ok(())
+}
+
+/// Computes the CRC32 of the first `file_size` bytes of the backend and writes the trailer
+/// right after.
+pub fn write_trailer<T: Read + Write + Seek>(file: &mut T, file_size: u64)
+    -> Result<(), WriteError>
+{
+    let chksum = checksum_file(file, file_size)?
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+    let mut trailer = [0; SIZE_TRAILER];
+    trailer[..4].copy_from_slice(&TRAILER_MAGIC);
+    LittleEndian::write_u32(&mut trailer[4..], chksum);
+    file.seek(SeekFrom::Start(file_size))?;
+    file.write_all(&trailer)?;
+    Ok(())
+}
@@ -29,10 +29,33 @@
 #![warn(missing_docs)]
 
 //! This library is the official implementation for the [BPX](https://gitlab.com/bp3d/bpx/bpx/-/blob/rev2/BPX_Format.pdf) container format.
+//!
+//! # Tracing
+//!
+//! When the `tracing` feature is enabled, expensive operations are wrapped in
+//! [tracing](https://docs.rs/tracing) spans at the `INFO` level. Span names and fields are
+//! part of the public API and are only changed in major releases:
+//!
+//! | Span name | Fields | Operation |
+//! |-----------|--------|-----------|
+//! | `bpx::load` | `index`, `size`, `csize`, `compression` | Loading a section in memory |
+//! | `bpx::decompress` | `size`, `csize`, `compression` | Decompressing a section |
+//! | `bpx::checksum` | `size`, `algorithm` | Computing the checksum of a section without loading it |
+//! | `bpx::compress` | `size`, `compression` | Encoding a section while saving |
+//! | `bpx::save` | `sections` | Saving a container |
+//! | `bpx::package::pack` | `name` | Packing an object in a package |
+//! | `bpx::package::unpack` | `name` | Unpacking an object from a package |
+//!
+//! `size` and `csize` are the sizes in bytes of a section before and after compression,
+//! `compression` is one of `None`, `Zlib` or `Xz` and `algorithm` is one of `Weak` or `Crc32`.
+
+#[macro_use]
+mod trace;
 
 pub mod core;
 mod garraylen;
 pub mod macros;
+pub mod spec;
 pub mod utils;
 
 #[cfg(feature = "table")]
@@ -53,13 +76,33 @@ pub mod shader;
 /// Represents a pointer to a section.
 ///
 /// *Allows indirect access to a given section instead of sharing mutable references in user code.*
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub struct Handle(u32);
+///
+/// *In debug builds, a handle remembers the container which created it: using it with another
+/// container, including the same file re-opened after saving, is detected by
+/// [get](crate::core::Container::get), [get_mut](crate::core::Container::get_mut),
+/// [remove_section](crate::core::Container::remove_section) and their `try_*` variants. The
+/// raw key can still be carried to another container with [into_raw](Handle::into_raw) and
+/// [from_raw](Handle::from_raw). In release builds a handle is a plain key which resolves to
+/// an unrelated section when used with another container.*
+#[derive(Copy, Clone, Debug)]
+pub struct Handle(u32, ContainerTag);
+
+impl PartialEq for Handle
+{
+    fn eq(&self, other: &Self) -> bool
+    {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Handle {}
 
 impl Handle
 {
     /// Constructs a Handle from a raw u32.
     ///
+    /// *The returned handle is not bound to any container.*
+    ///
     /// # Arguments
     ///
     /// * `raw`: the raw key.
@@ -71,7 +114,7 @@ impl Handle
     /// You must ensure the raw key is a valid key. Failure to do so could panic bpx::core::Container.
     pub unsafe fn from_raw(raw: u32) -> Self
     {
-        Self(raw)
+        Self(raw, ContainerTag::default())
     }
 
     /// Extracts the raw key from this Handle.
@@ -79,4 +122,56 @@ impl Handle
     {
         self.0
     }
+
+    /// Returns the key of this handle, failing if the handle was created by another container
+    /// than the one tagged `container` (only detected in debug builds).
+    fn key_in(self, container: ContainerTag) -> Result<u32, core::error::ReadError>
+    {
+        match self.1.accepts(container) {
+            true => Ok(self.0),
+            false => Err(core::error::ReadError::InvalidHandle(self))
+        }
+    }
+}
+
+/// Identifies the container which created a [Handle].
+///
+/// *Only tracked in debug builds: the tag is zero-sized in release builds.*
+#[derive(Copy, Clone, Debug, Default)]
+struct ContainerTag(#[cfg(debug_assertions)] u32);
+
+impl ContainerTag
+{
+    /// Creates a tag distinct from all tags created before.
+    #[cfg(debug_assertions)]
+    fn new() -> Self
+    {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        //0 is reserved for handles not bound to any container
+        static NEXT: AtomicU32 = AtomicU32::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Creates a tag distinct from all tags created before.
+    #[cfg(not(debug_assertions))]
+    fn new() -> Self
+    {
+        Self()
+    }
+
+    /// Returns true if a handle carrying this tag may be used with the container tagged
+    /// `container`.
+    #[cfg(debug_assertions)]
+    fn accepts(self, container: Self) -> bool
+    {
+        self.0 == 0 || self.0 == container.0
+    }
+
+    /// Returns true if a handle carrying this tag may be used with the container tagged
+    /// `container`.
+    #[cfg(not(debug_assertions))]
+    fn accepts(self, _: Self) -> bool
+    {
+        true
+    }
 }
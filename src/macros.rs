@@ -107,7 +107,7 @@ macro_rules! variant_error {
         pub enum ReadError
         {
             /// Low-level BPX decoder error.
-            Bpx(crate::core::error::ReadError),
+            Bpx($crate::core::error::ReadError),
 
             /// Describes an io error.
             Io(std::io::Error),
@@ -126,7 +126,7 @@ macro_rules! variant_error {
 
         impl_err_conversion!(
             ReadError {
-                crate::core::error::ReadError => Bpx,
+                $crate::core::error::ReadError => Bpx,
                 std::io::Error => Io
             }
         );
@@ -136,7 +136,7 @@ macro_rules! variant_error {
         pub enum WriteError
         {
             /// Low-level BPX encoder error.
-            Bpx(crate::core::error::WriteError),
+            Bpx($crate::core::error::WriteError),
 
             /// Describes an io error.
             Io(std::io::Error),
@@ -149,7 +149,7 @@ macro_rules! variant_error {
 
         impl_err_conversion!(
             WriteError {
-                crate::core::error::WriteError => Bpx,
+                $crate::core::error::WriteError => Bpx,
                 std::io::Error => Io
             }
         );
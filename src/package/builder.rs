@@ -27,7 +27,23 @@
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    package::{Architecture, Platform},
+    core::{
+        builder::SectionOptions,
+        header::{SECTION_TYPE_SD, SECTION_TYPE_STRING}
+    },
+    package::{
+        encoder::{
+            default_data_header,
+            default_metadata_header,
+            default_object_table_header,
+            default_string_header
+        },
+        Architecture,
+        Platform,
+        DEFAULT_MAX_SECTION_SIZE,
+        SECTION_TYPE_DATA,
+        SECTION_TYPE_OBJECT_TABLE
+    },
     sd::Object
 };
 
@@ -47,7 +63,25 @@ pub struct Settings
     pub metadata: Option<Object>,
 
     /// The package type code.
-    pub type_code: [u8; 2]
+    pub type_code: [u8; 2],
+
+    /// The header settings of data sections.
+    pub data_header: SectionOptions,
+
+    /// The header settings of the object table section.
+    pub object_table_header: SectionOptions,
+
+    /// The header settings of the string section.
+    pub string_header: SectionOptions,
+
+    /// The header settings of the metadata section.
+    pub metadata_header: SectionOptions,
+
+    /// The maximum size in bytes of a data section before objects roll over to a new one.
+    pub max_section_size: usize,
+
+    /// The number of objects expected to be packed, used to pre-size the object table.
+    pub expected_objects: usize
 }
 
 /// Utility to simplify generation of [Settings](crate::package::Settings) required when creating a new BPXP.
@@ -74,7 +108,13 @@ impl Builder
                 architecture: Architecture::Any,
                 platform: Platform::Any,
                 metadata: None,
-                type_code: [0x50, 0x48]
+                type_code: crate::spec::bpxp::DEFAULT_TYPE_CODE,
+                data_header: default_data_header(),
+                object_table_header: default_object_table_header(),
+                string_header: default_string_header(),
+                metadata_header: default_metadata_header(),
+                max_section_size: DEFAULT_MAX_SECTION_SIZE,
+                expected_objects: 0
             }
         }
     }
@@ -140,6 +180,134 @@ impl Builder
         self
     }
 
+    /// Defines the header to use when creating data sections.
+    ///
+    /// *By default, data sections use xz compression with a CRC32 checksum.*
+    ///
+    /// *The type byte is always replaced by [SECTION_TYPE_DATA](crate::package::SECTION_TYPE_DATA).*
+    ///
+    /// # Arguments
+    ///
+    /// * `header`: the section header to use.
+    ///
+    /// returns: PackageBuilder
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{CompressionMethod, SectionHeaderBuilder};
+    /// use bpx::package::{Builder, SECTION_TYPE_DATA};
+    ///
+    /// let settings = Builder::new()
+    ///     .data_header(SectionHeaderBuilder::new().ty(42).compression(CompressionMethod::Zlib))
+    ///     .build();
+    /// assert_eq!(settings.data_header.header.ty, SECTION_TYPE_DATA);
+    /// ```
+    pub fn data_header<H: Into<SectionOptions>>(&mut self, header: H) -> &mut Self
+    {
+        self.settings.data_header = header.into();
+        self.settings.data_header.header.ty = SECTION_TYPE_DATA;
+        self
+    }
+
+    /// Defines the header to use when creating the object table section.
+    ///
+    /// *By default, the object table uses zlib compression with a weak checksum.*
+    ///
+    /// *The type byte is always replaced by
+    /// [SECTION_TYPE_OBJECT_TABLE](crate::package::SECTION_TYPE_OBJECT_TABLE).*
+    ///
+    /// # Arguments
+    ///
+    /// * `header`: the section header to use.
+    ///
+    /// returns: PackageBuilder
+    pub fn object_table_header<H: Into<SectionOptions>>(&mut self, header: H) -> &mut Self
+    {
+        self.settings.object_table_header = header.into();
+        self.settings.object_table_header.header.ty = SECTION_TYPE_OBJECT_TABLE;
+        self
+    }
+
+    /// Defines the header to use when creating the string section.
+    ///
+    /// *By default, the string section uses zlib compression with a weak checksum.*
+    ///
+    /// *The type byte is always replaced by
+    /// [SECTION_TYPE_STRING](crate::core::header::SECTION_TYPE_STRING).*
+    ///
+    /// # Arguments
+    ///
+    /// * `header`: the section header to use.
+    ///
+    /// returns: PackageBuilder
+    pub fn string_header<H: Into<SectionOptions>>(&mut self, header: H) -> &mut Self
+    {
+        self.settings.string_header = header.into();
+        self.settings.string_header.header.ty = SECTION_TYPE_STRING;
+        self
+    }
+
+    /// Defines the header to use when creating the metadata section.
+    ///
+    /// *By default, the metadata section uses zlib compression with a weak checksum.*
+    ///
+    /// *The type byte is always replaced by
+    /// [SECTION_TYPE_SD](crate::core::header::SECTION_TYPE_SD).*
+    ///
+    /// # Arguments
+    ///
+    /// * `header`: the section header to use.
+    ///
+    /// returns: PackageBuilder
+    pub fn metadata_header<H: Into<SectionOptions>>(&mut self, header: H) -> &mut Self
+    {
+        self.settings.metadata_header = header.into();
+        self.settings.metadata_header.header.ty = SECTION_TYPE_SD;
+        self
+    }
+
+    /// Defines the maximum size of a data section.
+    ///
+    /// *By default, data sections are limited to
+    /// [DEFAULT_MAX_SECTION_SIZE](crate::package::DEFAULT_MAX_SECTION_SIZE) bytes.*
+    ///
+    /// Objects which do not fit in the remaining space of the current data section are
+    /// split across as many new data sections as needed. Values above [u32::MAX] are clamped
+    /// as section sizes are stored as u32.
+    ///
+    /// # Arguments
+    ///
+    /// * `size`: the maximum size in bytes of a data section.
+    ///
+    /// returns: PackageBuilder
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn max_section_size(&mut self, size: usize) -> &mut Self
+    {
+        assert!(size > 0, "the maximum size of a data section must not be 0");
+        self.settings.max_section_size = size.min(u32::MAX as usize);
+        self
+    }
+
+    /// Defines the number of objects expected to be packed.
+    ///
+    /// *This is only a hint used to pre-size the object table and has no effect on the
+    /// resulting package. By default, no capacity is reserved.*
+    ///
+    /// # Arguments
+    ///
+    /// * `count`: the expected number of objects.
+    ///
+    /// returns: PackageBuilder
+    pub fn expected_objects(&mut self, count: usize) -> &mut Self
+    {
+        self.settings.expected_objects = count;
+        self
+    }
+
     /// Returns the built settings.
     pub fn build(&self) -> Settings
     {
@@ -27,44 +27,84 @@
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::Hasher,
+    io,
     io::{Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    path::Path,
+    rc::Rc,
     slice::Iter
 };
 
 use crate::{
     core::{
-        builder::{Checksum, CompressionMethod, MainHeaderBuilder, SectionHeaderBuilder},
-        header::{Struct, SECTION_TYPE_SD, SECTION_TYPE_STRING},
+        builder::{MainHeaderBuilder, OpenOptions, SectionOptions},
+        header::{
+            Struct,
+            FLAG_COMPRESS_XZ,
+            FLAG_COMPRESS_ZLIB,
+            SECTION_TYPE_SD,
+            SECTION_TYPE_STRING
+        },
+        AutoSectionData,
         Container,
-        SectionData
+        SectionData,
+        SectionReader
     },
     package::{
-        decoder::{get_arch_platform_from_code, read_object_table, unpack_object},
-        encoder::{create_data_section_header, get_type_ext},
-        error::{ReadError, Section, WriteError},
-        object::ObjectHeader,
+        decoder::{
+            get_arch_platform_from_code,
+            read_attribute_table,
+            read_object_table,
+            read_object_table_lenient,
+            stream_object,
+            stream_object_table,
+            unpack_object
+        },
+        encoder::{
+            default_attribute_table_header,
+            default_data_header,
+            default_metadata_header,
+            default_object_table_header,
+            default_string_header,
+            get_code_from_arch_platform,
+            get_type_ext
+        },
+        error::{EosContext, ReadError, Section, WriteError},
+        object::{ObjectAttributes, ObjectHeader, SIZE_OBJECT_HEADER},
+        utils::normalize_name,
         Architecture,
         Platform,
         Settings,
+        DEFAULT_MAX_SECTION_SIZE,
+        DIRECTORY_MARKER,
+        SECTION_TYPE_ATTRIBUTE_TABLE,
+        SECTION_TYPE_DATA,
         SECTION_TYPE_OBJECT_TABLE,
         SUPPORTED_VERSION
     },
+    spec::bpxp,
     strings::{load_string_section, StringSection},
     table::ItemTable,
-    utils::{OptionExtension, ReadFill},
+    utils::ReadFill,
     Handle
 };
 
 const DATA_WRITE_BUFFER_SIZE: usize = 8192;
-const MIN_DATA_REMAINING_SIZE: usize = DATA_WRITE_BUFFER_SIZE;
-const MAX_DATA_SECTION_SIZE: usize = 200000000 - MIN_DATA_REMAINING_SIZE; //200MB
+
+/// The start section index, offset and size of the data of an object.
+type DataRange = (u32, u32, u64);
 
 /// Represents an object reference.
 pub struct Object<'a, T>
 {
     container: &'a mut Container<T>,
     strings: &'a mut StringSection,
-    header: &'a ObjectHeader
+    header: &'a ObjectHeader,
+    attributes: Option<&'a ObjectAttributes>
 }
 
 impl<'a, T: Read + Seek> Object<'a, T>
@@ -104,6 +144,515 @@ impl<'a, T: Read + Seek> Object<'a, T>
     {
         self.header.size
     }
+
+    /// Returns the Unix permissions of this object if stored in the package.
+    pub fn mode(&self) -> Option<u32>
+    {
+        self.attributes.and_then(|v| v.mode)
+    }
+
+    /// Returns the modification time of this object, in seconds since the Unix epoch, if
+    /// stored in the package.
+    pub fn mtime(&self) -> Option<u64>
+    {
+        self.attributes.and_then(|v| v.mtime)
+    }
+
+    /// Returns true if this object is a symbolic link, in which case the object data is the
+    /// link target.
+    pub fn is_symlink(&self) -> bool
+    {
+        self.attributes.map(|v| v.symlink).unwrap_or(false)
+    }
+}
+
+struct Span
+{
+    handle: Handle,
+    offset: u32,
+    start: u64,
+    len: u32
+}
+
+fn get_spans<T>(container: &Container<T>, header: &ObjectHeader) -> Result<Vec<Span>, ReadError>
+{
+    let mut spans = Vec::new();
+    let mut section_id = header.start;
+    let mut offset = header.offset;
+    let mut pos = 0;
+    while pos < header.size {
+        let handle = container
+            .find_section_by_index(section_id)
+            .ok_or(ReadError::Eos(EosContext::Object))?;
+        let section = container.get(handle);
+        let size = section
+            .open()
+            .map(|v| v.size() as u32)
+            .unwrap_or(section.size);
+        let len = std::cmp::min(size.saturating_sub(offset) as u64, header.size - pos) as u32;
+        if len > 0 {
+            spans.push(Span {
+                handle,
+                offset,
+                start: pos,
+                len
+            });
+        }
+        pos += len as u64;
+        offset = 0;
+        section_id += 1;
+    }
+    Ok(spans)
+}
+
+/// The kind of an object in a package.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ObjectKind
+{
+    /// A regular file.
+    File,
+
+    /// An empty directory: the object name ends with
+    /// [DIRECTORY_MARKER](crate::package::DIRECTORY_MARKER).
+    Directory,
+
+    /// A symbolic link: the object data is the link target.
+    Symlink
+}
+
+/// Information about an object in a package.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct ObjectStat
+{
+    /// The size in bytes of the object.
+    pub size: u64,
+
+    /// The kind of the object.
+    pub kind: ObjectKind,
+
+    /// The Unix permissions of the object if stored in the package.
+    pub mode: Option<u32>,
+
+    /// The modification time of the object, in seconds since the Unix epoch, if stored in the package.
+    pub mtime: Option<u64>,
+
+    /// The number of data sections the content of the object spans.
+    pub sections: u32
+}
+
+/// An entry of the table of contents of a package, as returned by
+/// [list](crate::package::Package::list).
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ObjectEntry
+{
+    /// The name of the object.
+    pub name: String,
+
+    /// The size in bytes of the object.
+    pub size: u64,
+
+    /// The kind of the object.
+    pub kind: ObjectKind
+}
+
+/// A report of the integrity of a package, as returned by
+/// [verify](crate::package::Package::verify).
+#[derive(Clone, Debug)]
+pub struct VerifyReport
+{
+    /// The checksum report of the underlying BPX container.
+    pub container: crate::core::VerifyReport,
+
+    /// The names of the objects whose size does not fit in their data sections.
+    pub inconsistent_objects: Vec<String>
+}
+
+impl VerifyReport
+{
+    /// Returns true if all checksums are valid and all objects fit in their data sections.
+    pub fn is_valid(&self) -> bool
+    {
+        self.container.is_valid() && self.inconsistent_objects.is_empty()
+    }
+}
+
+/// The compression to apply to the data of an object, as set in
+/// [ObjectOptions](crate::package::ObjectOptions).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum ObjectCompression
+{
+    /// Use the compression of the [data_header](crate::package::Builder::data_header) of the
+    /// package.
+    #[default]
+    Default,
+
+    /// Do not compress the data of the object.
+    None,
+
+    /// Compress the data of the object with zlib.
+    Zlib,
+
+    /// Compress the data of the object with xz.
+    Xz
+}
+
+/// File extensions of formats which are already compressed.
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "7z", "aac", "apk", "avi", "br", "bz2", "flac", "gif", "gz", "jar", "jpeg", "jpg", "lz4",
+    "m4a", "mkv", "mov", "mp3", "mp4", "ogg", "opus", "png", "rar", "tgz", "webm", "webp", "xz",
+    "zip", "zst"
+];
+
+/// Options for packing a single object, as accepted by
+/// [pack_with_options](crate::package::Package::pack_with_options).
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct ObjectOptions
+{
+    /// The compression to apply to the data of the object.
+    pub compression: ObjectCompression,
+
+    /// The modification time of the object, in seconds since the Unix epoch.
+    pub mtime: Option<u64>,
+
+    /// The Unix permissions of the object.
+    pub mode: Option<u32>
+}
+
+impl ObjectOptions
+{
+    /// Returns options disabling compression for files whose extension is known to be an
+    /// already compressed format (images, audio, video and archives).
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the file to pack.
+    ///
+    /// returns: ObjectOptions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use bpx::package::{ObjectCompression, ObjectOptions};
+    ///
+    /// let options = ObjectOptions::from_extension(Path::new("textures/stone.PNG"));
+    /// assert_eq!(options.compression, ObjectCompression::None);
+    /// let options = ObjectOptions::from_extension(Path::new("shaders/main.glsl"));
+    /// assert_eq!(options.compression, ObjectCompression::Default);
+    /// ```
+    pub fn from_extension(path: &Path) -> ObjectOptions
+    {
+        let compressed = path
+            .extension()
+            .and_then(|v| v.to_str())
+            .map(|v| COMPRESSED_EXTENSIONS.contains(&&*v.to_ascii_lowercase()))
+            .unwrap_or(false);
+        ObjectOptions {
+            compression: match compressed {
+                true => ObjectCompression::None,
+                false => ObjectCompression::Default
+            },
+            ..Default::default()
+        }
+    }
+}
+
+fn object_kind(name: &str, attributes: Option<&ObjectAttributes>) -> ObjectKind
+{
+    if name.ends_with(DIRECTORY_MARKER) {
+        ObjectKind::Directory
+    } else if attributes.map(|v| v.symlink).unwrap_or(false) {
+        ObjectKind::Symlink
+    } else {
+        ObjectKind::File
+    }
+}
+
+fn normalize(enabled: bool, name: &str) -> Cow<'_, str>
+{
+    match enabled {
+        true => Cow::Owned(normalize_name(name)),
+        false => Cow::Borrowed(name)
+    }
+}
+
+/// The policy to apply when several objects of a package share the same name.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum DuplicatePolicy
+{
+    /// Looking up or unpacking objects fails with
+    /// [DuplicateObject](crate::package::error::ReadError::DuplicateObject).
+    Error,
+
+    /// Lookups resolve to the first object with a given name; other objects with the same
+    /// name are not unpacked.
+    FirstWins,
+
+    /// Lookups resolve to the last object with a given name; when unpacking, each object
+    /// overwrites the previous one with the same name.
+    #[default]
+    LastWins,
+
+    /// Lookups resolve to the first object with a given name; when unpacking, other objects
+    /// with the same name are written with a numeric suffix (`name.1`, `name.2`, ...).
+    KeepBoth
+}
+
+/// A [Read](std::io::Read) + [Seek](std::io::Seek) over the content of an object.
+///
+/// *Seeking past the end of the object is allowed: further reads return 0 bytes.*
+pub struct ObjectReader<'a, T>
+{
+    container: &'a mut Container<T>,
+    spans: Vec<Span>,
+    size: u64,
+    pos: u64
+}
+
+impl<'a, T: Read + Seek> ObjectReader<'a, T>
+{
+    fn new(container: &'a mut Container<T>, header: &ObjectHeader) -> Result<Self, ReadError>
+    {
+        let spans = get_spans(container, header)?;
+        Ok(ObjectReader {
+            container,
+            spans,
+            size: header.size,
+            pos: 0
+        })
+    }
+
+    /// Returns the size in bytes of this object.
+    pub fn size(&self) -> u64
+    {
+        self.size
+    }
+}
+
+impl<'a, T: Read + Seek> Read for ObjectReader<'a, T>
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize>
+    {
+        if self.pos >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+        let span = match self
+            .spans
+            .iter()
+            .find(|v| self.pos < v.start + v.len as u64)
+        {
+            Some(v) => v,
+            None => return Ok(0)
+        };
+        let pos = self.pos - span.start;
+        let len = std::cmp::min(buf.len() as u64, span.len as u64 - pos) as usize;
+        let mut section = self.container.get_mut(span.handle);
+        let data = section.load().map_err(|e| match e {
+            crate::core::error::ReadError::Io(e) => e,
+            e => std::io::Error::other(e.to_string())
+        })?;
+        data.seek(SeekFrom::Start(span.offset as u64 + pos))?;
+        let len = data.read(&mut buf[..len])?;
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl<'a, T> Seek for ObjectReader<'a, T>
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64>
+    {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset)
+        };
+        self.pos = pos.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position"
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
+
+fn to_io_error(e: ReadError) -> io::Error
+{
+    match e {
+        ReadError::Io(e) => e,
+        ReadError::Bpx(crate::core::error::ReadError::Io(e)) => e,
+        e => io::Error::other(e.to_string())
+    }
+}
+
+/// Reports the number of bytes read from an object source after each read.
+struct ProgressReader<R, P>
+{
+    inner: R,
+    bytes: u64,
+    total: Option<u64>,
+    progress: P
+}
+
+impl<R: Read, P: FnMut(u64, Option<u64>)> Read for ProgressReader<R, P>
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+        let res = self.inner.read(buf)?;
+        if res > 0 {
+            self.bytes += res as u64;
+            (self.progress)(self.bytes, self.total);
+        }
+        Ok(res)
+    }
+}
+
+struct Stream<'a, T>
+{
+    container: *mut Container<T>,
+    reader: Option<SectionReader<'a, T>>,
+    section: u32,
+    size: u64,
+    pos: u64,
+    owner: usize,
+    marker: PhantomData<&'a mut Container<T>>
+}
+
+impl<'a, T: Read + Seek> Stream<'a, T>
+{
+    fn seek_to(&mut self, section: u32, offset: u64) -> Result<(), ReadError>
+    {
+        if self.reader.is_none() || self.section != section || self.pos > offset {
+            if let Some(mut reader) = self.reader.take() {
+                // Read the remaining data so that the section checksum gets verified.
+                io::copy(&mut reader, &mut io::sink())?;
+            }
+            // The previous reader is dropped so nothing else borrows the container.
+            let container = unsafe { &mut *self.container };
+            let handle = container
+                .find_section_by_index(section)
+                .ok_or(ReadError::Eos(EosContext::Object))?;
+            let header = container.get(handle);
+            self.size = header
+                .open()
+                .map(|v| v.size() as u64)
+                .unwrap_or(header.size as u64);
+            self.reader = Some(container.section_reader(handle)?);
+            self.section = section;
+            self.pos = 0;
+        }
+        if let Some(reader) = &mut self.reader {
+            self.pos += io::copy(&mut reader.take(offset - self.pos), &mut io::sink())?;
+        }
+        Ok(())
+    }
+}
+
+/// An object yielded by [StreamingIter](crate::package::StreamingIter).
+///
+/// The content of the object is read through [Read](std::io::Read) directly from the backend.
+///
+/// *Only the last object returned by the iterator can be read: reading a previous object
+/// returns an error.*
+pub struct StreamingObject<'a, T>
+{
+    stream: Rc<RefCell<Stream<'a, T>>>,
+    id: usize,
+    name: String,
+    size: u64,
+    section: u32,
+    offset: u64,
+    remaining: u64
+}
+
+impl<'a, T> StreamingObject<'a, T>
+{
+    /// Returns the name of this object.
+    pub fn name(&self) -> &str
+    {
+        &self.name
+    }
+
+    /// Returns the size in bytes of this object.
+    pub fn size(&self) -> u64
+    {
+        self.size
+    }
+}
+
+impl<'a, T: Read + Seek> Read for StreamingObject<'a, T>
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+        if self.remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        let mut stream = self.stream.borrow_mut();
+        if stream.owner != self.id {
+            return Err(io::Error::other(
+                "streaming object is no longer readable: the iterator has moved on"
+            ));
+        }
+        loop {
+            stream
+                .seek_to(self.section, self.offset)
+                .map_err(to_io_error)?;
+            if self.offset < stream.size {
+                break;
+            }
+            self.section += 1;
+            self.offset = 0;
+        }
+        let len = std::cmp::min(
+            buf.len() as u64,
+            std::cmp::min(self.remaining, stream.size - self.offset)
+        ) as usize;
+        let len = match &mut stream.reader {
+            Some(reader) => reader.read(&mut buf[..len])?,
+            None => 0
+        };
+        if len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "reached end of section before end of object"
+            ));
+        }
+        stream.pos += len as u64;
+        self.offset += len as u64;
+        self.remaining -= len as u64;
+        Ok(len)
+    }
+}
+
+/// A streaming iterator over [StreamingObject](crate::package::StreamingObject).
+pub struct StreamingIter<'a, T>
+{
+    stream: Rc<RefCell<Stream<'a, T>>>,
+    objects: std::vec::IntoIter<(String, ObjectHeader)>,
+    next_id: usize
+}
+
+impl<'a, T> Iterator for StreamingIter<'a, T>
+{
+    type Item = StreamingObject<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        let (name, header) = self.objects.next()?;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.stream.borrow_mut().owner = id;
+        Some(StreamingObject {
+            stream: self.stream.clone(),
+            id,
+            name,
+            size: header.size,
+            section: header.start,
+            offset: header.offset as u64,
+            remaining: header.size
+        })
+    }
 }
 
 /// An iterator over [Object](crate::package::Object).
@@ -111,6 +660,7 @@ pub struct ObjectIter<'a, T>
 {
     container: &'a mut Container<T>,
     strings: &'a mut StringSection,
+    attributes: &'a HashMap<u32, ObjectAttributes>,
     iter: Iter<'a, ObjectHeader>
 }
 
@@ -127,12 +677,41 @@ impl<'a, T> Iterator for ObjectIter<'a, T>
             Some(Object {
                 header,
                 strings: &mut *ptr1,
-                container: &mut *ptr
+                container: &mut *ptr,
+                attributes: self.attributes.get(&header.name)
             })
         }
     }
 }
 
+/// An iterator over the name and content of all objects in a BPXP.
+///
+/// *Created by [unpack_all_to_vec](Package::unpack_all_to_vec).*
+pub struct UnpackIter<'a, T>
+{
+    container: &'a mut Container<T>,
+    strings: &'a mut StringSection,
+    iter: Iter<'a, ObjectHeader>
+}
+
+impl<'a, T: Read + Seek> Iterator for UnpackIter<'a, T>
+{
+    type Item = Result<(String, Vec<u8>), ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        let header = self.iter.next()?;
+        let mut unpack = || {
+            load_string_section(self.container, self.strings)?;
+            let name = String::from(self.strings.get(self.container, header.name)?);
+            let mut data = Vec::with_capacity(header.size as usize);
+            stream_object(self.container, header, &mut data)?;
+            Ok((name, data))
+        };
+        Some(unpack())
+    }
+}
+
 /// A BPXP (Package).
 ///
 /// # Examples
@@ -174,8 +753,18 @@ pub struct Package<T>
     object_table: Handle,
     strings: StringSection,
     objects: Vec<ObjectHeader>,
+    objects_loaded: bool,
     table: Option<ItemTable<ObjectHeader>>,
-    last_data_section: Option<Handle>
+    last_data_section: Option<Handle>,
+    data_sections: HashMap<ObjectCompression, Handle>,
+    duplicate_policy: DuplicatePolicy,
+    normalize_names: bool,
+    attributes: HashMap<u32, ObjectAttributes>,
+    attributes_loaded: bool,
+    preserve_permissions: bool,
+    preserve_timestamps: bool,
+    deduplicate: bool,
+    content_hashes: HashMap<(u64, u64), Vec<DataRange>>
 }
 
 impl<T> Package<T>
@@ -198,341 +787,1897 @@ impl<T> Package<T>
         self.settings.platform
     }
 
-    /// Consumes this Package and returns the inner BPX container.
-    pub fn into_inner(self) -> Container<T>
+    /// Returns the [Settings](crate::package::Settings) of this BPXP.
+    pub fn settings(&self) -> &Settings
     {
-        self.container
+        &self.settings
     }
-}
 
-impl<T: Write + Seek> Package<T>
-{
-    /// Creates a new BPX type P.
+    fn update_arch_platform(&mut self)
+    {
+        let (acode, pcode) =
+            get_code_from_arch_platform(self.settings.architecture, self.settings.platform);
+        let mut header = *self.container.get_main_header();
+        header.type_ext[bpxp::TYPE_EXT_ARCHITECTURE_OFFSET] = acode;
+        header.type_ext[bpxp::TYPE_EXT_PLATFORM_OFFSET] = pcode;
+        self.container.set_main_header(header);
+    }
+
+    /// Sets the [DuplicatePolicy](crate::package::DuplicatePolicy) to apply when several objects
+    /// share the same name.
+    ///
+    /// *By default, the last object with a given name wins.*
+    ///
+    /// *Iterating over [objects](Package::objects) always yields all objects regardless of the
+    /// policy.*
     ///
     /// # Arguments
     ///
-    /// * `backend`: A [Write](std::io::Write) + [Seek](std::io::Seek) to use as backend.
-    /// * `settings`: The package creation settings.
+    /// * `policy`: the policy to apply.
+    pub fn set_duplicate_policy(&mut self, policy: DuplicatePolicy)
+    {
+        self.duplicate_policy = policy;
+    }
+
+    /// Gets the [DuplicatePolicy](crate::package::DuplicatePolicy) of this BPXP.
+    pub fn get_duplicate_policy(&self) -> DuplicatePolicy
+    {
+        self.duplicate_policy
+    }
+
+    /// Enables or disables normalization of object names.
     ///
-    /// returns: Result<Package<T>, WriteError>
+    /// When enabled, names are normalized with [normalize_name](crate::package::utils::normalize_name)
+    /// both when packing objects and when looking objects up, so that an object packed as
+    /// `./foo/bar` is found as `foo/bar`.
+    ///
+    /// *By default, names are stored and looked up exactly as given.*
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled`: true to normalize object names.
     ///
     /// # Examples
     ///
     /// ```
-    /// use bpx::package::Builder;
-    /// use bpx::package::Package;
+    /// use bpx::package::{Builder, Package};
     /// use bpx::utils::new_byte_buf;
     ///
     /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
-    /// bpxp.save();
-    /// assert!(!bpxp.into_inner().into_inner().into_inner().is_empty());
+    /// bpxp.set_name_normalization(true);
+    /// bpxp.pack("./foo\\bar", "Hello".as_bytes()).unwrap();
+    /// assert_eq!(bpxp.stat("foo/bar").unwrap().size, 5);
     /// ```
-    pub fn create<S: Into<Settings>>(backend: T, settings: S) -> Result<Package<T>, WriteError>
+    pub fn set_name_normalization(&mut self, enabled: bool)
     {
-        let settings = settings.into();
-        let mut container = Container::create(
-            backend,
-            MainHeaderBuilder::new()
-                .ty(b'P')
-                .type_ext(get_type_ext(&settings))
-                .version(SUPPORTED_VERSION)
-        );
-        let object_table = container.create_section(
-            SectionHeaderBuilder::new()
-                .checksum(Checksum::Weak)
-                .compression(CompressionMethod::Zlib)
-                .ty(SECTION_TYPE_OBJECT_TABLE)
-        );
-        let string_section = container.create_section(
-            SectionHeaderBuilder::new()
-                .checksum(Checksum::Weak)
-                .compression(CompressionMethod::Zlib)
-                .ty(SECTION_TYPE_STRING)
-        );
-        let strings = StringSection::new(string_section);
-        if let Some(metadata) = &settings.metadata {
-            let metadata_section = container.create_section(
-                SectionHeaderBuilder::new()
-                    .checksum(Checksum::Weak)
-                    .compression(CompressionMethod::Zlib)
-                    .ty(SECTION_TYPE_SD)
-            );
-            let mut section = container.get_mut(metadata_section);
-            metadata.write(section.open().ok_or(WriteError::SectionNotLoaded)?)?;
-        }
-        Ok(Package {
-            settings,
-            strings,
-            container,
-            object_table,
-            objects: Vec::new(),
-            table: None,
-            last_data_section: None
-        })
+        self.normalize_names = enabled;
+        self.table = None;
     }
 
-    fn write_object<TRead: Read>(
-        &mut self,
+    /// Enables or disables recording of Unix permissions when packing files with
+    /// [pack_file](crate::package::utils::pack_file) and similar functions.
+    ///
+    /// *By default, permissions are not recorded so that packing the same files on different
+    /// machines produces the same package. Permissions are never recorded on non-Unix
+    /// platforms.*
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled`: true to record Unix permissions.
+    pub fn set_preserve_permissions(&mut self, enabled: bool)
+    {
+        self.preserve_permissions = enabled;
+    }
+
+    /// Returns true if Unix permissions are recorded when packing files.
+    pub fn get_preserve_permissions(&self) -> bool
+    {
+        self.preserve_permissions
+    }
+
+    /// Enables or disables recording of file modification times when packing files with
+    /// [pack_file](crate::package::utils::pack_file) and similar functions.
+    ///
+    /// *By default, modification times are not recorded so that packages are reproducible.*
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled`: true to record file modification times.
+    pub fn set_preserve_timestamps(&mut self, enabled: bool)
+    {
+        self.preserve_timestamps = enabled;
+    }
+
+    /// Returns true if file modification times are recorded when packing files.
+    pub fn get_preserve_timestamps(&self) -> bool
+    {
+        self.preserve_timestamps
+    }
+
+    /// Enables or disables deduplication of object contents.
+    ///
+    /// When enabled, the content of each packed object is staged and hashed; if an object
+    /// with the same content was already packed since this package was created or opened,
+    /// the new object points to the existing data instead of storing it again.
+    ///
+    /// *By default, deduplication is disabled as staging the content of each object has a
+    /// cost.*
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled`: true to deduplicate object contents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.set_deduplicate(true);
+    /// bpxp.pack("LICENSE", "MIT".as_bytes()).unwrap();
+    /// bpxp.pack("assets/LICENSE", "MIT".as_bytes()).unwrap();
+    /// assert_eq!(bpxp.stat("assets/LICENSE").unwrap().size, 3);
+    /// ```
+    pub fn set_deduplicate(&mut self, enabled: bool)
+    {
+        self.deduplicate = enabled;
+    }
+
+    /// Returns true if object contents are deduplicated when packing.
+    pub fn get_deduplicate(&self) -> bool
+    {
+        self.deduplicate
+    }
+
+    /// Consumes this Package and returns the inner BPX container.
+    pub fn into_inner(self) -> Container<T>
+    {
+        self.container
+    }
+}
+
+impl<T: Write + Seek> Package<T>
+{
+    /// Creates a new BPX type P.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend`: A [Write](std::io::Write) + [Seek](std::io::Seek) to use as backend.
+    /// * `settings`: The package creation settings.
+    ///
+    /// returns: Result<Package<T>, WriteError>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::Builder;
+    /// use bpx::package::Package;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.save();
+    /// assert!(!bpxp.into_inner().into_inner().into_inner().is_empty());
+    /// ```
+    pub fn create<S: Into<Settings>>(backend: T, settings: S) -> Result<Package<T>, WriteError>
+    {
+        let settings = settings.into();
+        let mut container = Container::create(
+            backend,
+            MainHeaderBuilder::new()
+                .ty(bpxp::TYPE_BYTE)
+                .type_ext(get_type_ext(&settings))
+                .version(SUPPORTED_VERSION)
+        );
+        let object_table = container.create_section(settings.object_table_header);
+        if let Some(data) = container.get_mut(object_table).open() {
+            data.reserve(settings.expected_objects.saturating_mul(SIZE_OBJECT_HEADER));
+        }
+        let string_section = container.create_section(settings.string_header);
+        let strings = StringSection::new(string_section);
+        if let Some(metadata) = &settings.metadata {
+            let metadata_section = container.create_section(settings.metadata_header);
+            let mut section = container.get_mut(metadata_section);
+            metadata.write(section.open().ok_or(WriteError::SectionNotLoaded)?)?;
+        }
+        Ok(Package {
+            objects: Vec::with_capacity(settings.expected_objects),
+            settings,
+            strings,
+            container,
+            object_table,
+            objects_loaded: true,
+            table: None,
+            last_data_section: None,
+            data_sections: HashMap::new(),
+            duplicate_policy: DuplicatePolicy::default(),
+            normalize_names: false,
+            attributes: HashMap::new(),
+            attributes_loaded: true,
+            preserve_permissions: false,
+            preserve_timestamps: false,
+            deduplicate: false,
+            content_hashes: HashMap::new()
+        })
+    }
+
+    fn write_object<TRead: Read>(
+        &mut self,
         source: &mut TRead,
         data_id: Handle
     ) -> Result<(usize, bool), WriteError>
     {
+        let max_size = self.settings.max_section_size;
         let mut section = self.container.get_mut(data_id);
         let data = section.open().ok_or(WriteError::SectionNotLoaded)?;
+        data.seek(SeekFrom::End(0))?;
         let mut buf: [u8; DATA_WRITE_BUFFER_SIZE] = [0; DATA_WRITE_BUFFER_SIZE];
-        let mut res = source.read_fill(&mut buf)?;
-        let mut count = res;
+        let mut count = 0;
 
-        while res > 0 {
-            data.write_all(&buf[0..res])?;
-            if data.size() >= MAX_DATA_SECTION_SIZE
+        loop {
             //Split sections (this is to avoid reaching the 4Gb max)
-            {
+            let remaining = max_size.saturating_sub(data.size());
+            if remaining == 0 {
                 return Ok((count, true));
             }
-            res = source.read_fill(&mut buf)?;
+            let res = source.read_fill(&mut buf[..remaining.min(DATA_WRITE_BUFFER_SIZE)])?;
+            if res == 0 {
+                return Ok((count, false));
+            }
+            data.write_all(&buf[0..res])?;
             count += res;
         }
-        Ok((count, false))
     }
 
-    /// Creates a new object in this package.
+    /// Creates a new object in this package.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The name of the object.
+    /// * `source`: A [Read](std::io::Read) to read object data from.
+    ///
+    /// returns: Result<(), WriteError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [WriteError](crate::package::error::WriteError) if the object couldn't be saved
+    /// in this package.
+    pub fn pack<R: Read>(&mut self, name: &str, source: R) -> Result<(), WriteError>
+    {
+        self.pack_with_options(name, source, ObjectOptions::default())
+    }
+
+    /// Creates a new object in this package and reports progress while reading its data.
+    ///
+    /// The progress function is called with the number of bytes read so far and the given
+    /// size hint after each read of at most 8192 bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The name of the object.
+    /// * `source`: A [Read](std::io::Read) to read object data from.
+    /// * `size_hint`: The expected size of the object, if known.
+    /// * `progress`: The function to call after each read.
+    ///
+    /// returns: Result<(), WriteError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [WriteError](crate::package::error::WriteError) if the object couldn't be saved
+    /// in this package.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let data = vec![0; 20000];
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// let mut reports = Vec::new();
+    /// bpxp.pack_with_progress("zeros", &data[..], Some(data.len() as u64), |bytes, _| {
+    ///     reports.push(bytes)
+    /// })
+    /// .unwrap();
+    /// assert_eq!(reports, vec![8192, 16384, 20000]);
+    /// ```
+    pub fn pack_with_progress<R: Read, P: FnMut(u64, Option<u64>)>(
+        &mut self,
+        name: &str,
+        source: R,
+        size_hint: Option<u64>,
+        progress: P
+    ) -> Result<(), WriteError>
+    {
+        let source = ProgressReader {
+            inner: source,
+            bytes: 0,
+            total: size_hint,
+            progress
+        };
+        self.pack(name, source)
+    }
+
+    /// Creates a new object in this package with the given options.
+    ///
+    /// *Objects whose compression differs from the package
+    /// [data_header](crate::package::Builder::data_header) are written to separate data
+    /// sections. The modification time and permissions are stored in the optional attribute
+    /// table section of the package, as with
+    /// [pack_with_attributes](crate::package::Package::pack_with_attributes).*
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The name of the object.
+    /// * `source`: A [Read](std::io::Read) to read object data from.
+    /// * `options`: The [ObjectOptions](crate::package::ObjectOptions) of the object.
+    ///
+    /// returns: Result<(), WriteError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [WriteError](crate::package::error::WriteError) if the object couldn't be saved
+    /// in this package.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, ObjectCompression, ObjectOptions, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// let options = ObjectOptions {
+    ///     compression: ObjectCompression::None,
+    ///     mtime: Some(1600000000),
+    ///     ..Default::default()
+    /// };
+    /// bpxp.pack_with_options("image.png", &[0x89, 0x50, 0x4E, 0x47][..], options)
+    ///     .unwrap();
+    /// assert_eq!(bpxp.unpack_to_vec("image.png").unwrap(), [0x89, 0x50, 0x4E, 0x47]);
+    /// assert_eq!(bpxp.stat("image.png").unwrap().mtime, Some(1600000000));
+    /// ```
+    pub fn pack_with_options<R: Read>(
+        &mut self,
+        name: &str,
+        source: R,
+        options: ObjectOptions
+    ) -> Result<(), WriteError>
+    {
+        trace_span!("bpx::package::pack", name);
+        let name = normalize(self.normalize_names, name).into_owned();
+        let (start, offset, size) = match self.deduplicate {
+            true => self.append_object_deduplicated(source, options.compression)?,
+            false => self.append_object(source, options.compression)?
+        };
+        // Fill and write the object header
+        let buf = ObjectHeader {
+            size,
+            name: self.strings.put(&mut self.container, &name)?,
+            start,
+            offset
+        };
+        let attributes = ObjectAttributes {
+            name: buf.name,
+            mode: options.mode,
+            mtime: options.mtime,
+            symlink: false
+        };
+        if !attributes.is_empty() {
+            self.attributes.insert(buf.name, attributes);
+        }
+        self.objects.push(buf);
+        self.table = None;
+        Ok(())
+    }
+
+    /// Creates a new object in this package and records its Unix permissions.
+    ///
+    /// *The permissions are stored in the optional attribute table section of the package and
+    /// are ignored when unpacking on non-Unix platforms.*
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The name of the object.
+    /// * `source`: A [Read](std::io::Read) to read object data from.
+    /// * `mode`: The Unix permissions of the object.
+    ///
+    /// returns: Result<(), WriteError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [WriteError](crate::package::error::WriteError) if the object couldn't be saved
+    /// in this package.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack_with_mode("run.sh", "#!/bin/sh".as_bytes(), 0o755).unwrap();
+    /// assert_eq!(bpxp.stat("run.sh").unwrap().mode, Some(0o755));
+    /// ```
+    pub fn pack_with_mode<R: Read>(
+        &mut self,
+        name: &str,
+        source: R,
+        mode: u32
+    ) -> Result<(), WriteError>
+    {
+        let attributes = ObjectAttributes {
+            mode: Some(mode),
+            ..Default::default()
+        };
+        self.pack_with_attributes(name, source, attributes)
+    }
+
+    /// Creates a new object in this package and records its attributes.
+    ///
+    /// *The attributes are stored in the optional attribute table section of the package. The
+    /// `name` field of `attributes` is ignored and no attribute table record is created if no
+    /// attribute is set.*
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The name of the object.
+    /// * `source`: A [Read](std::io::Read) to read object data from.
+    /// * `attributes`: The [ObjectAttributes](crate::package::object::ObjectAttributes) to record.
+    ///
+    /// returns: Result<(), WriteError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [WriteError](crate::package::error::WriteError) if the object couldn't be saved
+    /// in this package.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{object::ObjectAttributes, Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// let attributes = ObjectAttributes {
+    ///     mode: Some(0o644),
+    ///     mtime: Some(1600000000),
+    ///     ..Default::default()
+    /// };
+    /// bpxp.pack_with_attributes("file.txt", "Hello".as_bytes(), attributes).unwrap();
+    /// let stat = bpxp.stat("file.txt").unwrap();
+    /// assert_eq!(stat.mode, Some(0o644));
+    /// assert_eq!(stat.mtime, Some(1600000000));
+    /// ```
+    pub fn pack_with_attributes<R: Read>(
+        &mut self,
+        name: &str,
+        source: R,
+        attributes: ObjectAttributes
+    ) -> Result<(), WriteError>
+    {
+        self.pack(name, source)?;
+        if let Some(header) = self.objects.last() {
+            if !attributes.is_empty() {
+                let attributes = ObjectAttributes {
+                    name: header.name,
+                    ..attributes
+                };
+                self.attributes.insert(header.name, attributes);
+            }
+        }
+        Ok(())
+    }
+
+    fn data_section_header(&self, compression: ObjectCompression) -> SectionOptions
+    {
+        let mut options = self.settings.data_header;
+        let flag = match compression {
+            ObjectCompression::Default => return options,
+            ObjectCompression::None => 0,
+            ObjectCompression::Zlib => FLAG_COMPRESS_ZLIB,
+            ObjectCompression::Xz => FLAG_COMPRESS_XZ
+        };
+        let header = &mut options.header;
+        if header.flags & (FLAG_COMPRESS_ZLIB | FLAG_COMPRESS_XZ) == 0 {
+            //The threshold is only meaningful when compression was already enabled
+            options.threshold = None;
+        }
+        header.flags = header.flags & !(FLAG_COMPRESS_ZLIB | FLAG_COMPRESS_XZ) | flag;
+        options
+    }
+
+    fn append_object<R: Read>(
+        &mut self,
+        mut source: R,
+        compression: ObjectCompression
+    ) -> Result<DataRange, WriteError>
+    {
+        let header = self.data_section_header(compression);
+        let last = match compression {
+            ObjectCompression::Default => self.last_data_section,
+            _ => self.data_sections.get(&compression).copied()
+        };
+        let mut object_size = 0;
+        let mut data_section = match last {
+            Some(v) => v,
+            None => self.container.create_section(header)
+        };
+        if self.is_data_section_full(data_section)? {
+            data_section = self.container.create_section(header);
+        }
+        let start = self.container.get(data_section).index();
+        let offset = {
+            let section = self.container.get(data_section);
+            section.open().ok_or(WriteError::SectionNotLoaded)?.size()
+        } as u32;
+
+        loop {
+            let (count, need_section) = self.write_object(&mut source, data_section)?;
+            object_size += count;
+            if need_section {
+                data_section = self.container.create_section(header);
+            } else {
+                break;
+            }
+        }
+        let last = match self.is_data_section_full(data_section)? {
+            true => None,
+            false => Some(data_section)
+        };
+        match (compression, last) {
+            (ObjectCompression::Default, _) => self.last_data_section = last,
+            (_, Some(v)) => {
+                self.data_sections.insert(compression, v);
+            },
+            (_, None) => {
+                self.data_sections.remove(&compression);
+            }
+        }
+        Ok((start, offset, object_size as u64))
+    }
+
+    fn append_object_deduplicated<R: Read>(
+        &mut self,
+        mut source: R,
+        compression: ObjectCompression
+    ) -> Result<DataRange, WriteError>
+    {
+        let mut staged = AutoSectionData::new();
+        let mut hasher = DefaultHasher::new();
+        let mut buf: [u8; DATA_WRITE_BUFFER_SIZE] = [0; DATA_WRITE_BUFFER_SIZE];
+        loop {
+            let res = source.read_fill(&mut buf)?;
+            if res == 0 {
+                break;
+            }
+            hasher.write(&buf[..res]);
+            staged.write_all(&buf[..res])?;
+        }
+        let key = (staged.size() as u64, hasher.finish());
+        if key.0 > 0 {
+            let candidates = self.content_hashes.get(&key).cloned().unwrap_or_default();
+            for (start, offset, size) in candidates {
+                //Hashes only select candidates: contents are always compared
+                if self.data_equals(start, offset, size, &mut staged)? {
+                    return Ok((start, offset, size));
+                }
+            }
+        }
+        staged.seek(SeekFrom::Start(0))?;
+        let range = self.append_object(staged, compression)?;
+        if key.0 > 0 {
+            self.content_hashes.entry(key).or_default().push(range);
+        }
+        Ok(range)
+    }
+
+    fn data_equals(
+        &mut self,
+        start: u32,
+        offset: u32,
+        size: u64,
+        staged: &mut AutoSectionData
+    ) -> Result<bool, WriteError>
+    {
+        let header = ObjectHeader {
+            size,
+            name: 0,
+            start,
+            offset
+        };
+        let spans = match get_spans(&self.container, &header) {
+            Ok(v) => v,
+            Err(_) => return Ok(false)
+        };
+        if spans.iter().map(|v| v.len as u64).sum::<u64>() != size {
+            return Ok(false);
+        }
+        staged.seek(SeekFrom::Start(0))?;
+        let mut expected: [u8; DATA_WRITE_BUFFER_SIZE] = [0; DATA_WRITE_BUFFER_SIZE];
+        let mut actual: [u8; DATA_WRITE_BUFFER_SIZE] = [0; DATA_WRITE_BUFFER_SIZE];
+        for span in spans {
+            let mut section = self.container.get_mut(span.handle);
+            let data = match section.open() {
+                Some(v) => v,
+                None => return Ok(false)
+            };
+            data.seek(SeekFrom::Start(span.offset as u64))?;
+            let mut remaining = span.len as usize;
+            while remaining > 0 {
+                let len = remaining.min(DATA_WRITE_BUFFER_SIZE);
+                data.read_exact(&mut expected[..len])?;
+                staged.read_exact(&mut actual[..len])?;
+                if expected[..len] != actual[..len] {
+                    return Ok(false);
+                }
+                remaining -= len;
+            }
+        }
+        Ok(true)
+    }
+
+    fn is_data_section_full(&self, data_section: Handle) -> Result<bool, WriteError>
+    {
+        let section = self.container.get(data_section);
+        let size = section.open().ok_or(WriteError::SectionNotLoaded)?.size();
+        Ok(size >= self.settings.max_section_size)
+    }
+
+    /// Saves this package.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [WriteError](crate::package::error::WriteError) if some parts of this package
+    /// couldn't be saved.
+    pub fn save(&mut self) -> Result<(), WriteError>
+    {
+        self.write_object_table()?;
+        self.container.save()?;
+        Ok(())
+    }
+
+    /// Saves this package, compressing sections in parallel on the current
+    /// [rayon](https://docs.rs/rayon) thread pool.
+    ///
+    /// *The package is identical to the one written by [save](Package::save). Data sections
+    /// are compressed independently so lowering the maximum size of data sections with
+    /// [max_section_size](crate::package::Builder::max_section_size) increases parallelism.*
+    ///
+    /// # Errors
+    ///
+    /// Returns a [WriteError](crate::package::error::WriteError) if some parts of this package
+    /// couldn't be saved.
+    #[cfg(feature = "multithreading")]
+    pub fn save_parallel(&mut self) -> Result<(), WriteError>
+    {
+        self.write_object_table()?;
+        self.container.save_parallel()?;
+        Ok(())
+    }
+
+    fn write_object_table(&mut self) -> Result<(), WriteError>
+    {
+        {
+            let mut section = self.container.get_mut(self.object_table);
+            if self.objects_loaded {
+                let data = section.open().ok_or(WriteError::SectionNotLoaded)?;
+                data.clear();
+                for v in &self.objects {
+                    v.write(data)?;
+                }
+            } else if !self.objects.is_empty() {
+                //The object table was never read: only append new objects
+                let data = section.open().ok_or(WriteError::SectionNotLoaded)?;
+                data.seek(SeekFrom::End(0))?;
+                for v in &self.objects {
+                    v.write(data)?;
+                }
+            }
+        }
+        self.write_attribute_table()
+    }
+
+    fn write_attribute_table(&mut self) -> Result<(), WriteError>
+    {
+        let handle = self
+            .container
+            .find_section_by_type(SECTION_TYPE_ATTRIBUTE_TABLE);
+        if self.attributes.is_empty() && (handle.is_none() || !self.attributes_loaded) {
+            return Ok(());
+        }
+        let handle = handle.unwrap_or_else(|| {
+            self.container
+                .create_section(default_attribute_table_header())
+        });
+        let mut section = self.container.get_mut(handle);
+        let data = section.open().ok_or(WriteError::SectionNotLoaded)?;
+        if self.attributes_loaded {
+            data.clear();
+            if self.objects_loaded {
+                //Only keep the attributes of objects which still exist, in table order
+                for v in self
+                    .objects
+                    .iter()
+                    .filter_map(|v| self.attributes.get(&v.name))
+                {
+                    v.write(data)?;
+                }
+                return Ok(());
+            }
+        } else {
+            //The attribute table was never read: only append new attributes
+            data.seek(SeekFrom::End(0))?;
+        }
+        let mut attributes: Vec<&ObjectAttributes> = self.attributes.values().collect();
+        attributes.sort_by_key(|v| v.name);
+        for v in attributes {
+            v.write(data)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Read + Seek> Package<T>
+{
+    /// Opens a BPX type P.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend`: A [Read](std::io::Read) + [Seek](std::io::Seek) to use as backend.
+    ///
+    /// returns: Result<PackageDecoder<TBackend>, Error>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::package::error::ReadError) is returned if some
+    /// sections/headers could not be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::Builder;
+    /// use bpx::package::Package;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.save();
+    /// let mut buf = bpxp.into_inner().into_inner();
+    /// buf.set_position(0);
+    /// let mut bpxp = Package::open(buf).unwrap();
+    /// assert_eq!(bpxp.objects().unwrap().count(), 0);
+    /// ```
+    pub fn open(backend: T) -> Result<Package<T>, ReadError>
+    {
+        Package::open_with_options(backend, &OpenOptions::new())
+    }
+
+    /// Loads a BPXP from the given `backend` with the given
+    /// [OpenOptions](crate::core::builder::OpenOptions) for the underlying container.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend`: A [Read](std::io::Read) + [Seek](std::io::Seek) backend to use for reading the BPXP.
+    /// * `options`: the [OpenOptions](crate::core::builder::OpenOptions) to use.
+    ///
+    /// returns: Result<Package<T>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::package::error::ReadError) is returned if some
+    /// sections/headers could not be loaded or if the file does not satisfy the given options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::OpenOptions;
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.save().unwrap();
+    /// let mut buf = bpxp.into_inner().into_inner();
+    /// buf.set_position(0);
+    /// let mut bpxp = Package::open_with_options(buf, OpenOptions::new().skip_validation(true)).unwrap();
+    /// assert_eq!(bpxp.objects().unwrap().count(), 0);
+    /// ```
+    pub fn open_with_options(backend: T, options: &OpenOptions) -> Result<Package<T>, ReadError>
+    {
+        let container = Container::open_with_options(backend, options)?;
+        if container.get_main_header().ty != bpxp::TYPE_BYTE {
+            return Err(ReadError::BadType(container.get_main_header().ty));
+        }
+        if container.get_main_header().version != SUPPORTED_VERSION {
+            return Err(ReadError::BadVersion(container.get_main_header().version));
+        }
+        let (a, p) = get_arch_platform_from_code(
+            container.get_main_header().type_ext[bpxp::TYPE_EXT_ARCHITECTURE_OFFSET],
+            container.get_main_header().type_ext[bpxp::TYPE_EXT_PLATFORM_OFFSET]
+        )?;
+        let strings =
+            StringSection::new(match container.find_section_by_type(SECTION_TYPE_STRING) {
+                Some(v) => v,
+                None => return Err(ReadError::MissingSection(Section::Strings))
+            });
+        let object_table = match container.find_section_by_type(SECTION_TYPE_OBJECT_TABLE) {
+            Some(v) => v,
+            None => return Err(ReadError::MissingSection(Section::ObjectTable))
+        };
+        Ok(Self {
+            settings: Settings {
+                metadata: None,
+                architecture: a,
+                platform: p,
+                type_code: [
+                    container.get_main_header().type_ext[bpxp::TYPE_EXT_TYPE_CODE_OFFSET],
+                    container.get_main_header().type_ext[bpxp::TYPE_EXT_TYPE_CODE_OFFSET + 1]
+                ],
+                data_header: default_data_header(),
+                object_table_header: default_object_table_header(),
+                string_header: default_string_header(),
+                metadata_header: default_metadata_header(),
+                max_section_size: DEFAULT_MAX_SECTION_SIZE,
+                expected_objects: 0
+            },
+            strings,
+            object_table,
+            container,
+            objects: Vec::new(),
+            objects_loaded: false,
+            table: None,
+            last_data_section: None,
+            data_sections: HashMap::new(),
+            duplicate_policy: DuplicatePolicy::default(),
+            normalize_names: false,
+            attributes: HashMap::new(),
+            attributes_loaded: false,
+            preserve_permissions: false,
+            preserve_timestamps: false,
+            deduplicate: false,
+            content_hashes: HashMap::new()
+        })
+    }
+
+    /// Gets an iterator over all [Object](crate::package::Object) in this package.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [ReadError](crate::package::error::ReadError) if the section couldn't be loaded
+    /// or if the object table is truncated.
+    pub fn objects(&mut self) -> Result<ObjectIter<T>, ReadError>
+    {
+        self.load_objects()?;
+        self.load_attributes()?;
+        let table = self
+            .table
+            .get_or_insert_with(|| ItemTable::new(self.objects.clone()));
+        let iter = table.iter();
+        Ok(ObjectIter {
+            container: &mut self.container,
+            strings: &mut self.strings,
+            attributes: &self.attributes,
+            iter
+        })
+    }
+
+    /// Gets a streaming iterator over all objects in this package.
+    ///
+    /// Unlike [objects](Package::objects), neither the object table nor data sections are
+    /// loaded in the container: the content of each object is decoded on demand while reading
+    /// the [StreamingObject](crate::package::StreamingObject). This is the most memory
+    /// efficient way to process all objects of a large package.
+    ///
+    /// *Only the names and headers of objects are kept in memory. Objects are best read in
+    /// table order as data sections are decoded sequentially.*
+    ///
+    /// # Errors
+    ///
+    /// Returns a [ReadError](crate::package::error::ReadError) if the object table or the
+    /// strings couldn't be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Read;
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("TestObject", "Hello world".as_bytes()).unwrap();
+    /// bpxp.save().unwrap();
+    /// let mut buf = bpxp.into_inner().into_inner();
+    /// buf.set_position(0);
+    /// let mut bpxp = Package::open(buf).unwrap();
+    /// for mut object in bpxp.iter_streaming().unwrap() {
+    ///     let mut s = String::new();
+    ///     object.read_to_string(&mut s).unwrap();
+    ///     assert_eq!(object.name(), "TestObject");
+    ///     assert_eq!(s, "Hello world");
+    /// }
+    /// ```
+    pub fn iter_streaming(&mut self) -> Result<StreamingIter<'_, T>, ReadError>
+    {
+        let headers = if self.objects_loaded {
+            self.objects.clone()
+        } else {
+            let mut headers = stream_object_table(&mut self.container, self.object_table)?;
+            headers.extend_from_slice(&self.objects);
+            headers
+        };
+        load_string_section(&mut self.container, &self.strings)?;
+        let mut objects = Vec::with_capacity(headers.len());
+        for header in headers {
+            let name = self.strings.get(&mut self.container, header.name)?;
+            objects.push((name.into(), header));
+        }
+        Ok(StreamingIter {
+            stream: Rc::new(RefCell::new(Stream {
+                container: &mut self.container,
+                reader: None,
+                section: 0,
+                size: 0,
+                pos: 0,
+                owner: 0,
+                marker: PhantomData
+            })),
+            objects: objects.into_iter(),
+            next_id: 0
+        })
+    }
+
+    /// Returns the names of all objects in this package in table order, including objects
+    /// which are not yet saved.
+    ///
+    /// *Objects sharing the same name are all listed.*
+    ///
+    /// returns: Result<Vec<String>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [ReadError](crate::package::error::ReadError) if the object table or the
+    /// strings couldn't be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("a", "Hello".as_bytes()).unwrap();
+    /// bpxp.pack("b", "world".as_bytes()).unwrap();
+    /// assert_eq!(bpxp.object_names().unwrap(), vec!["a", "b"]);
+    /// ```
+    pub fn object_names(&mut self) -> Result<Vec<String>, ReadError>
+    {
+        self.load_objects()?;
+        load_string_section(&mut self.container, &self.strings)?;
+        self.strings.preload(&mut self.container)?;
+        let mut names = Vec::with_capacity(self.objects.len());
+        for v in &self.objects {
+            names.push(self.strings.get(&mut self.container, v.name)?.into());
+        }
+        Ok(names)
+    }
+
+    /// Verifies the checksums of all sections and checks that the size of each object is
+    /// consistent with its data sections.
+    ///
+    /// Section data is streamed from the backend and never stored, see
+    /// [Container::verify](crate::core::Container::verify). Only data already written to the
+    /// backend is verified.
+    ///
+    /// returns: Result<VerifyReport, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [ReadError](crate::package::error::ReadError) if the object table or the
+    /// strings couldn't be loaded or if some section data could not be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("a", "Hello".as_bytes()).unwrap();
+    /// bpxp.save().unwrap();
+    /// assert!(bpxp.verify().unwrap().is_valid());
+    /// ```
+    pub fn verify(&mut self) -> Result<VerifyReport, ReadError>
+    {
+        let container = self.container.verify()?;
+        self.load_objects()?;
+        load_string_section(&mut self.container, &self.strings)?;
+        let mut inconsistent_objects = Vec::new();
+        for v in &self.objects {
+            if !self.fits_data_sections(v) {
+                inconsistent_objects.push(self.strings.get(&mut self.container, v.name)?.into());
+            }
+        }
+        Ok(VerifyReport {
+            container,
+            inconsistent_objects
+        })
+    }
+
+    fn fits_data_sections(&self, object: &ObjectHeader) -> bool
+    {
+        let mut index = object.start;
+        let mut offset = object.offset as u64;
+        let mut len = object.size;
+        loop {
+            let section = match self.container.find_section_by_index(index) {
+                Some(v) => self.container.get(v),
+                None => return false
+            };
+            if section.ty != SECTION_TYPE_DATA {
+                return false;
+            }
+            let size = section
+                .open()
+                .map(|v| v.size() as u64)
+                .unwrap_or(section.size as u64);
+            if offset > size {
+                return false;
+            }
+            if len <= size - offset {
+                return true;
+            }
+            len -= size - offset;
+            offset = 0;
+            index += 1;
+        }
+    }
+
+    /// Lists the objects of this package in table order without loading any data section.
+    ///
+    /// Only the object table, the strings and the attribute table are read which makes this
+    /// suitable to display the content of a package when it is opened.
+    ///
+    /// returns: Result<Vec<ObjectEntry>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [ReadError](crate::package::error::ReadError) if the object table, the
+    /// strings or the attribute table couldn't be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, ObjectKind, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("a", "Hello".as_bytes()).unwrap();
+    /// bpxp.pack("empty/", std::io::empty()).unwrap();
+    /// let list = bpxp.list().unwrap();
+    /// assert_eq!(list[0].name, "a");
+    /// assert_eq!(list[0].size, 5);
+    /// assert_eq!(list[1].kind, ObjectKind::Directory);
+    /// ```
+    pub fn list(&mut self) -> Result<Vec<ObjectEntry>, ReadError>
+    {
+        let names = self.object_names()?;
+        self.load_attributes()?;
+        let entries = names
+            .into_iter()
+            .zip(&self.objects)
+            .map(|(name, header)| ObjectEntry {
+                kind: object_kind(&name, self.attributes.get(&header.name)),
+                name,
+                size: header.size
+            })
+            .collect();
+        Ok(entries)
+    }
+
+    /// Checks if an object exists in this package.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the object.
+    ///
+    /// returns: Result<bool, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [ReadError](crate::package::error::ReadError) if the object table or the
+    /// strings couldn't be loaded or if the name is shared by several objects under
+    /// [DuplicatePolicy::Error](crate::package::DuplicatePolicy::Error).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("TestObject", "Hello world".as_bytes()).unwrap();
+    /// assert!(bpxp.contains("TestObject").unwrap());
+    /// assert!(!bpxp.contains("Other").unwrap());
+    /// ```
+    pub fn contains(&mut self, name: &str) -> Result<bool, ReadError>
+    {
+        Ok(self.lookup_object(name)?.is_some())
+    }
+
+    fn load_attributes(&mut self) -> Result<(), ReadError>
+    {
+        if !self.attributes_loaded {
+            if let Some(handle) = self
+                .container
+                .find_section_by_type(SECTION_TYPE_ATTRIBUTE_TABLE)
+            {
+                //Attributes of objects packed before the attribute table was read take precedence
+                for v in read_attribute_table(&mut self.container, handle)? {
+                    self.attributes.entry(v.name).or_insert(v);
+                }
+            }
+            self.attributes_loaded = true;
+        }
+        Ok(())
+    }
+
+    fn load_objects(&mut self) -> Result<(), ReadError>
+    {
+        if !self.objects_loaded {
+            let mut objects = read_object_table(&mut self.container, self.object_table)?;
+            //Objects packed before the object table was read are appended after existing ones
+            objects.append(&mut self.objects);
+            self.objects = objects;
+            self.objects_loaded = true;
+            self.table = None;
+        }
+        Ok(())
+    }
+
+    /// Loads the object table in lenient mode: reading stops at the last complete object
+    /// instead of failing when the object table is truncated or corrupted.
+    ///
+    /// Returns the number of objects available after recovery.
+    ///
+    /// *Has no effect if the object table was already loaded.*
+    ///
+    /// returns: Result<usize, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [ReadError](crate::package::error::ReadError) if the object table could not
+    /// be read at all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("TestObject", "Hello world".as_bytes()).unwrap();
+    /// bpxp.save().unwrap();
+    /// let mut buf = bpxp.into_inner().into_inner();
+    /// buf.set_position(0);
+    /// let mut bpxp = Package::open(buf).unwrap();
+    /// assert_eq!(bpxp.recover_objects().unwrap(), 1);
+    /// ```
+    pub fn recover_objects(&mut self) -> Result<usize, ReadError>
+    {
+        if !self.objects_loaded {
+            let mut objects = read_object_table_lenient(&mut self.container, self.object_table)?;
+            objects.append(&mut self.objects);
+            self.objects = objects;
+            self.objects_loaded = true;
+            self.table = None;
+        }
+        Ok(self.objects.len())
+    }
+
+    /// Removes an object from this package.
+    ///
+    /// Returns true if the object exists and was removed, false otherwise.
+    ///
+    /// *The data of the object is not removed from data sections.*
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the object to remove.
+    ///
+    /// returns: Result<bool, WriteError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [WriteError](crate::package::error::WriteError) if the object table or some
+    /// strings couldn't be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("TestObject", "Hello world".as_bytes()).unwrap();
+    /// assert!(bpxp.remove("TestObject").unwrap());
+    /// assert!(!bpxp.remove("TestObject").unwrap());
+    /// assert_eq!(bpxp.objects().unwrap().count(), 0);
+    /// ```
+    pub fn remove(&mut self, name: &str) -> Result<bool, WriteError>
+    {
+        self.load_objects()?;
+        if let Some(i) = self.find_object(name)? {
+            //Loading attributes allows dropping the attributes of the removed object on save
+            self.load_attributes()?;
+            self.objects.remove(i);
+            self.table = None;
+            self.load_all()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn find_object(&mut self, name: &str) -> Result<Option<usize>, ReadError>
+    {
+        load_string_section(&mut self.container, &self.strings)?;
+        let name = normalize(self.normalize_names, name).into_owned();
+        for (i, v) in self.objects.iter().enumerate() {
+            if normalize(
+                self.normalize_names,
+                self.strings.get(&mut self.container, v.name)?
+            ) == name
+            {
+                return Ok(Some(i));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the names which are shared by more than one object in this package.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [ReadError](crate::package::error::ReadError) if the object table or the
+    /// strings couldn't be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("TestObject", "Hello".as_bytes()).unwrap();
+    /// assert!(bpxp.duplicates().unwrap().is_empty());
+    /// bpxp.pack("TestObject", "world".as_bytes()).unwrap();
+    /// assert_eq!(bpxp.duplicates().unwrap(), vec!["TestObject"]);
+    /// ```
+    pub fn duplicates(&mut self) -> Result<Vec<String>, ReadError>
+    {
+        self.load_objects()?;
+        let table = self
+            .table
+            .get_or_insert_with(|| ItemTable::new(self.objects.clone()));
+        load_string_section(&mut self.container, &self.strings)?;
+        let keep_first = self.duplicate_policy != DuplicatePolicy::LastWins;
+        let normalize_names = self.normalize_names;
+        let duplicates = table.build_lookup_table_checked(
+            &mut self.container,
+            &mut self.strings,
+            keep_first,
+            |v| normalize(normalize_names, v).into_owned()
+        )?;
+        Ok(duplicates)
+    }
+
+    fn lookup_object(&mut self, name: &str) -> Result<Option<ObjectHeader>, ReadError>
+    {
+        let duplicates = self.duplicates()?;
+        if self.duplicate_policy == DuplicatePolicy::Error {
+            if let Some(name) = duplicates.into_iter().next() {
+                return Err(ReadError::DuplicateObject(name));
+            }
+        }
+        //The table is always built by duplicates
+        let name = normalize(self.normalize_names, name);
+        Ok(self
+            .table
+            .as_ref()
+            .and_then(|v| v.try_lookup(&name).unwrap_or(None))
+            .copied())
+    }
+
+    fn load_all(&mut self) -> Result<(), ReadError>
+    {
+        //Re-writing the object table re-writes the whole container which requires all
+        // sections to be loaded
+        for mut section in self.container.iter_mut() {
+            section.load()?;
+        }
+        Ok(())
+    }
+
+    /// Opens an object for reading without unpacking it.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the object to open.
+    ///
+    /// returns: Result<ObjectReader<T>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [ReadError](crate::package::error::ReadError) if the object does not exist, if
+    /// the object table or the strings couldn't be loaded or if the object is truncated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Read, Seek, SeekFrom};
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("TestObject", "Hello world".as_bytes()).unwrap();
+    /// bpxp.save().unwrap();
+    /// let mut buf = bpxp.into_inner().into_inner();
+    /// buf.set_position(0);
+    /// let mut bpxp = Package::open(buf).unwrap();
+    /// let mut reader = bpxp.open_object("TestObject").unwrap();
+    /// reader.seek(SeekFrom::Start(6)).unwrap();
+    /// let mut s = String::new();
+    /// reader.read_to_string(&mut s).unwrap();
+    /// assert_eq!(s, "world");
+    /// ```
+    pub fn open_object(&mut self, name: &str) -> Result<ObjectReader<'_, T>, ReadError>
+    {
+        match self.lookup_object(name)? {
+            Some(header) => ObjectReader::new(&mut self.container, &header),
+            None => Err(ReadError::MissingObject(name.into()))
+        }
+    }
+
+    /// Returns information about an object without unpacking it.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the object.
+    ///
+    /// returns: Result<ObjectStat, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [ReadError](crate::package::error::ReadError) if the object does not exist, if
+    /// the object table or the strings couldn't be loaded or if the object is truncated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, ObjectKind, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("TestObject", "Hello world".as_bytes()).unwrap();
+    /// let stat = bpxp.stat("TestObject").unwrap();
+    /// assert_eq!(stat.size, 11);
+    /// assert_eq!(stat.kind, ObjectKind::File);
+    /// assert_eq!(stat.sections, 1);
+    /// ```
+    pub fn stat(&mut self, name: &str) -> Result<ObjectStat, ReadError>
+    {
+        let header = self
+            .lookup_object(name)?
+            .ok_or_else(|| ReadError::MissingObject(name.into()))?;
+        let spans = get_spans(&self.container, &header)?;
+        self.load_attributes()?;
+        let attributes = self.attributes.get(&header.name);
+        Ok(ObjectStat {
+            size: header.size,
+            kind: object_kind(name, attributes),
+            mode: attributes.and_then(|v| v.mode),
+            mtime: attributes.and_then(|v| v.mtime),
+            sections: spans.len() as u32
+        })
+    }
+
+    /// Reads the metadata section of this BPXP if any.
+    /// Returns None if there is no metadata in this BPXP.
+    ///
+    /// *The metadata section is decoded on first use and cached, which is why this function
+    /// requires mutable access to the package.*
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::package::error::ReadError) is returned in case of corruption or system error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::sd::Object;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut obj = Object::new();
+    /// obj.set("Version", 1u32.into());
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new().metadata(obj)).unwrap();
+    /// bpxp.save().unwrap();
+    /// let mut buf = bpxp.into_inner().into_inner();
+    /// buf.set_position(0);
+    /// let mut bpxp = Package::open(buf).unwrap();
+    /// let metadata = bpxp.metadata().unwrap().unwrap();
+    /// assert_eq!(metadata.get("Version").unwrap().as_u64(), Some(1));
+    /// ```
+    pub fn metadata(&mut self) -> Result<Option<crate::sd::Object>, ReadError>
+    {
+        if let Some(obj) = &self.settings.metadata {
+            return Ok(Some(obj.clone()));
+        }
+        if let Some(handle) = self.container.find_section_by_type(SECTION_TYPE_SD) {
+            let mut section = self.container.get_mut(handle);
+            let obj = crate::sd::Object::read(section.load()?)?;
+            self.settings.metadata = Some(obj.clone());
+            return Ok(Some(obj));
+        }
+        Ok(None)
+    }
+
+    /// Reads the metadata section of this BPXP if any.
+    /// Returns None if there is no metadata in this BPXP.
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::package::error::ReadError) is returned in case of corruption or system error.
+    #[deprecated(since = "4.0.0", note = "use metadata")]
+    pub fn read_metadata(&mut self) -> Result<Option<crate::sd::Object>, ReadError>
+    {
+        self.metadata()
+    }
+
+    /// Unpacks an object and returns the size of the unpacked object or None if the object does not exist.
+    ///
+    /// *Data sections which are not loaded are streamed from the backend and are never
+    /// entirely decoded in memory.*
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the object to unpack.
+    /// * `out`: the output Write.
+    ///
+    /// returns: Result<Option<u64>, ReadError>
+    pub fn unpack<W: Write>(&mut self, name: &str, out: W) -> Result<Option<u64>, ReadError>
+    {
+        trace_span!("bpx::package::unpack", name);
+        if let Some(header) = self.lookup_object(name)? {
+            let size = stream_object(&mut self.container, &header, out)?;
+            Ok(Some(size))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Unpacks a single object and returns the size of the unpacked object.
+    ///
+    /// Unlike [unpack](Package::unpack), a missing object is reported as an error so that it
+    /// cannot be confused with an empty object. The name lookup table is built on first use.
+    ///
+    /// *Data sections which are not loaded are streamed from the backend and are never
+    /// entirely decoded in memory.*
     ///
     /// # Arguments
     ///
-    /// * `name`: The name of the object.
-    /// * `source`: A [Read](std::io::Read) to read object data from.
+    /// * `name`: the name of the object to unpack.
+    /// * `out`: the output Write.
     ///
-    /// returns: Result<(), WriteError>
+    /// returns: Result<u64, ReadError>
     ///
     /// # Errors
     ///
-    /// Returns a [WriteError](crate::package::error::WriteError) if the object couldn't be saved
-    /// in this package.
-    pub fn pack<R: Read>(&mut self, name: &str, mut source: R) -> Result<(), WriteError>
+    /// A [MissingObject](crate::package::error::ReadError::MissingObject) error is returned if
+    /// the object does not exist. Other [ReadError](crate::package::error::ReadError) are
+    /// returned in case of corruption or system error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{error::ReadError, Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("empty", std::io::empty()).unwrap();
+    /// let mut data = Vec::new();
+    /// assert_eq!(bpxp.unpack_one("empty", &mut data).unwrap(), 0);
+    /// assert!(matches!(bpxp.unpack_one("missing", &mut data), Err(ReadError::MissingObject(_))));
+    /// ```
+    pub fn unpack_one<W: Write>(&mut self, name: &str, out: W) -> Result<u64, ReadError>
     {
-        let mut object_size = 0;
-        let mut data_section = *self
-            .last_data_section
-            .get_or_insert_with(|| self.container.create_section(create_data_section_header()));
-        let start = self.container.get(data_section).index();
-        let offset = {
-            let section = self.container.get(data_section);
-            section.open().ok_or(WriteError::SectionNotLoaded)?.size()
-        } as u32;
+        self.unpack(name, out)?
+            .ok_or_else(|| ReadError::MissingObject(name.into()))
+    }
 
-        loop {
-            let (count, need_section) = self.write_object(&mut source, data_section)?;
-            object_size += count;
-            if need_section {
-                data_section = self.container.create_section(create_data_section_header());
-            } else {
-                break;
-            }
-        }
-        {
-            // Fill and write the object header
-            let buf = ObjectHeader {
-                size: object_size as u64,
-                name: self.strings.put(&mut self.container, name)?,
-                start,
-                offset
-            };
-            self.objects.push(buf);
-        }
-        {
-            let section = self.container.get(data_section);
-            if section.open().ok_or(WriteError::SectionNotLoaded)?.size() > MAX_DATA_SECTION_SIZE {
-                self.last_data_section = None;
-            } else {
-                self.last_data_section = Some(data_section);
-            }
-        }
-        Ok(())
+    /// Unpacks a single object in memory.
+    ///
+    /// *The returned bytes are the same as the ones written by [unpack](Package::unpack).*
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the object to unpack.
+    ///
+    /// returns: Result<Vec<u8>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// A [MissingObject](crate::package::error::ReadError::MissingObject) error is returned if
+    /// the object does not exist. Other [ReadError](crate::package::error::ReadError) are
+    /// returned in case of corruption or system error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("TestObject", "Hello".as_bytes()).unwrap();
+    /// assert_eq!(bpxp.unpack_to_vec("TestObject").unwrap(), b"Hello");
+    /// assert!(bpxp.unpack_to_vec("Missing").is_err());
+    /// ```
+    pub fn unpack_to_vec(&mut self, name: &str) -> Result<Vec<u8>, ReadError>
+    {
+        let header = self
+            .lookup_object(name)?
+            .ok_or_else(|| ReadError::MissingObject(name.into()))?;
+        let mut data = Vec::with_capacity(header.size as usize);
+        stream_object(&mut self.container, &header, &mut data)?;
+        Ok(data)
     }
 
-    /// Saves this package.
+    /// Gets an iterator unpacking all objects of this package in memory.
+    ///
+    /// Objects are yielded in table order with their name. No object is skipped regardless of
+    /// the [DuplicatePolicy](crate::package::DuplicatePolicy) of the package and empty
+    /// directories are yielded as empty objects.
     ///
     /// # Errors
     ///
-    /// Returns a [WriteError](crate::package::error::WriteError) if some parts of this package
-    /// couldn't be saved.
-    pub fn save(&mut self) -> Result<(), WriteError>
+    /// A [ReadError](crate::package::error::ReadError) is returned if the object table
+    /// couldn't be loaded. Each item is also a result as objects are unpacked on demand.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("a", "Hello".as_bytes()).unwrap();
+    /// bpxp.pack("b", std::io::empty()).unwrap();
+    /// let objects: Vec<(String, Vec<u8>)> = bpxp
+    ///     .unpack_all_to_vec()
+    ///     .unwrap()
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(objects, vec![("a".into(), b"Hello".to_vec()), ("b".into(), Vec::new())]);
+    /// ```
+    pub fn unpack_all_to_vec(&mut self) -> Result<UnpackIter<'_, T>, ReadError>
     {
-        {
-            let mut section = self.container.get_mut(self.object_table);
-            let data = section.open().ok_or(WriteError::SectionNotLoaded)?;
-            data.seek(SeekFrom::Start(0))?;
-            for v in &self.objects {
-                v.write(data)?;
-            }
-        }
-        self.container.save()?;
-        Ok(())
+        self.load_objects()?;
+        Ok(UnpackIter {
+            container: &mut self.container,
+            strings: &mut self.strings,
+            iter: self.objects.iter()
+        })
     }
 }
 
-impl<T: Read + Seek> Package<T>
+impl<T: Read + Write + Seek> Package<T>
 {
-    /// Opens a BPX type P.
+    /// Opens a package for appending new objects.
+    ///
+    /// Only the string section, the object table and the attribute table are loaded. New
+    /// objects are written to new data sections and the stored bytes of every other section are
+    /// staged with [stage_unloaded](crate::core::Container::stage_unloaded), so that existing
+    /// object data is neither decoded nor compressed again.
+    ///
+    /// *Adding sections still re-writes the whole file as section data follows the section
+    /// header table; existing sections are copied verbatim.*
     ///
     /// # Arguments
     ///
-    /// * `backend`: A [Read](std::io::Read) + [Seek](std::io::Seek) to use as backend.
+    /// * `backend`: A [Read](std::io::Read) + [Write](std::io::Write) + [Seek](std::io::Seek)
+    ///   backend holding the package.
     ///
-    /// returns: Result<PackageDecoder<TBackend>, Error>
+    /// returns: Result<Package<T>, ReadError>
     ///
     /// # Errors
     ///
-    /// A [ReadError](crate::package::error::ReadError) is returned if some
-    /// sections/headers could not be loaded.
+    /// Returns a [ReadError](crate::package::error::ReadError) if the package could not be
+    /// opened or if some sections could not be read.
     ///
     /// # Examples
     ///
     /// ```
-    /// use bpx::package::Builder;
-    /// use bpx::package::Package;
+    /// use bpx::package::{Builder, Package};
     /// use bpx::utils::new_byte_buf;
     ///
     /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
-    /// bpxp.save();
+    /// bpxp.pack("first", "Hello".as_bytes()).unwrap();
+    /// bpxp.save().unwrap();
     /// let mut buf = bpxp.into_inner().into_inner();
     /// buf.set_position(0);
-    /// let mut bpxp = Package::open(buf).unwrap();
-    /// assert_eq!(bpxp.objects().unwrap().count(), 0);
+    /// let mut bpxp = Package::open_append(buf).unwrap();
+    /// bpxp.pack("second", "World".as_bytes()).unwrap();
+    /// bpxp.save().unwrap();
+    /// assert_eq!(bpxp.unpack_to_vec("first").unwrap(), b"Hello");
+    /// assert_eq!(bpxp.unpack_to_vec("second").unwrap(), b"World");
     /// ```
-    pub fn open(backend: T) -> Result<Package<T>, ReadError>
+    pub fn open_append(backend: T) -> Result<Package<T>, ReadError>
     {
-        let container = Container::open(backend)?;
-        if container.get_main_header().ty != b'P' {
-            return Err(ReadError::BadType(container.get_main_header().ty));
-        }
-        if container.get_main_header().version != SUPPORTED_VERSION {
-            return Err(ReadError::BadVersion(container.get_main_header().version));
+        let mut package = Package::open(backend)?;
+        load_string_section(&mut package.container, &package.strings)?;
+        package.load_objects()?;
+        package.load_attributes()?;
+        package.container.stage_unloaded()?;
+        Ok(package)
+    }
+
+    /// Replaces the content of an existing object in this package.
+    ///
+    /// The new content is written over the previous one when it is not larger, otherwise it is
+    /// appended to the last data section. The name of the object is kept as is.
+    ///
+    /// *The space previously used by a replaced larger object is not reclaimed.*
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the object to replace.
+    /// * `source`: A [Read](std::io::Read) to read the new object data from.
+    ///
+    /// returns: Result<(), WriteError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [WriteError](crate::package::error::WriteError) if the object does not exist or
+    /// if the object couldn't be written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.pack("TestObject", "Hello world".as_bytes()).unwrap();
+    /// bpxp.replace("TestObject", "Hello".as_bytes()).unwrap();
+    /// let mut data = Vec::new();
+    /// bpxp.unpack("TestObject", &mut data).unwrap();
+    /// assert_eq!(data, b"Hello");
+    /// ```
+    pub fn replace<R: Read>(&mut self, name: &str, mut source: R) -> Result<(), WriteError>
+    {
+        self.load_objects()?;
+        let idx = self
+            .find_object(name)?
+            .ok_or_else(|| ReadError::MissingObject(name.into()))?;
+        self.load_all()?;
+        //Stage the new content as its size is needed to know where to write it
+        let mut staged = AutoSectionData::new();
+        std::io::copy(&mut source, &mut staged)?;
+        staged.seek(SeekFrom::Start(0))?;
+        let header = self.objects[idx];
+        //Data shared with a deduplicated object must not be overwritten
+        let shared = self.objects.iter().enumerate().any(|(i, v)| {
+            i != idx && v.size > 0 && v.start == header.start && v.offset == header.offset
+        });
+        if !shared && staged.size() as u64 <= header.size {
+            for span in get_spans(&self.container, &header)? {
+                let mut section = self.container.get_mut(span.handle);
+                let data = section.open().ok_or(WriteError::SectionNotLoaded)?;
+                data.seek(SeekFrom::Start(span.offset as u64))?;
+                std::io::copy(&mut (&mut staged).take(span.len as u64), data)?;
+            }
+            self.objects[idx].size = staged.size() as u64;
+        } else {
+            if self.last_data_section.is_none() {
+                let max_size = self.settings.max_section_size;
+                let last = self
+                    .container
+                    .iter()
+                    .filter(|v| v.ty == SECTION_TYPE_DATA)
+                    .last();
+                self.last_data_section = last
+                    .filter(|v| v.open().map(|v| v.size() < max_size) == Some(true))
+                    .map(|v| v.handle());
+            }
+            let (start, offset, size) = self.append_object(staged, ObjectCompression::Default)?;
+            let object = &mut self.objects[idx];
+            object.start = start;
+            object.offset = offset;
+            object.size = size;
         }
-        let (a, p) = get_arch_platform_from_code(
-            container.get_main_header().type_ext[0],
-            container.get_main_header().type_ext[1]
-        )?;
-        let strings =
-            StringSection::new(match container.find_section_by_type(SECTION_TYPE_STRING) {
-                Some(v) => v,
-                None => return Err(ReadError::MissingSection(Section::Strings))
-            });
-        let object_table = match container.find_section_by_type(SECTION_TYPE_OBJECT_TABLE) {
-            Some(v) => v,
-            None => return Err(ReadError::MissingSection(Section::ObjectTable))
-        };
-        Ok(Self {
-            settings: Settings {
-                metadata: None,
-                architecture: a,
-                platform: p,
-                type_code: [
-                    container.get_main_header().type_ext[2],
-                    container.get_main_header().type_ext[3]
-                ]
-            },
-            strings,
-            object_table,
-            container,
-            objects: Vec::new(),
-            table: None,
-            last_data_section: None
-        })
+        self.table = None;
+        Ok(())
     }
 
-    /// Gets an iterator over all [Object](crate::package::Object) in this package.
+    /// Sets the target CPU [Architecture](crate::package::Architecture) for this BPXP.
+    ///
+    /// *The main header is written on the next save. As this re-writes the whole container,
+    /// all sections are loaded.*
+    ///
+    /// # Arguments
+    ///
+    /// * `arch`: the new target CPU architecture.
+    ///
+    /// returns: Result<(), ReadError>
     ///
     /// # Errors
     ///
-    /// Returns a [ReadError](crate::package::error::ReadError) if the section couldn't be loaded
-    /// or if the object table is truncated.
-    pub fn objects(&mut self) -> Result<ObjectIter<T>, ReadError>
+    /// A [ReadError](crate::package::error::ReadError) is returned if some sections couldn't
+    /// be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Architecture, Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.set_architecture(Architecture::X86_64).unwrap();
+    /// assert_eq!(bpxp.get_architecture(), Architecture::X86_64);
+    /// ```
+    pub fn set_architecture(&mut self, arch: Architecture) -> Result<(), ReadError>
     {
-        let table = self.table.get_or_insert_with_err(|| {
-            read_object_table(&mut self.container, &mut self.objects, self.object_table)
-        })?;
-        let iter = table.iter();
-        Ok(ObjectIter {
-            container: &mut self.container,
-            strings: &mut self.strings,
-            iter
-        })
+        self.load_all()?;
+        self.settings.architecture = arch;
+        self.update_arch_platform();
+        Ok(())
     }
 
-    /// Removes an object from this package.
+    /// Sets the target [Platform](crate::package::Platform) for this BPXP.
     ///
-    /// Returns true if the object exists and was removed, false otherwise.
+    /// *The main header is written on the next save. As this re-writes the whole container,
+    /// all sections are loaded.*
     ///
     /// # Arguments
     ///
-    /// * `name`: the name of the object to remove.
+    /// * `platform`: the new target platform.
     ///
-    /// returns: Result<bool, ReadError>
+    /// returns: Result<(), ReadError>
     ///
     /// # Errors
     ///
-    /// Returns a [ReadError](crate::package::error::ReadError) if some strings couldn't be loaded
-    /// from the string section.
-    pub fn remove(&mut self, name: &str) -> Result<bool, ReadError>
+    /// A [ReadError](crate::package::error::ReadError) is returned if some sections couldn't
+    /// be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package, Platform};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// bpxp.set_platform(Platform::Linux).unwrap();
+    /// assert_eq!(bpxp.get_platform(), Platform::Linux);
+    /// ```
+    pub fn set_platform(&mut self, platform: Platform) -> Result<(), ReadError>
     {
-        let mut idx = None;
-        for (i, v) in self.objects.iter().enumerate() {
-            let name1 = self.strings.get(&mut self.container, v.name)?;
-            if name1 == name {
-                idx = Some(i);
-                break;
-            }
-        }
-        if let Some(i) = idx {
-            self.objects.remove(i);
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+        self.load_all()?;
+        self.settings.platform = platform;
+        self.update_arch_platform();
+        Ok(())
     }
 
-    /// Reads the metadata section of this BPXP if any.
-    /// Returns None if there is no metadata in this BPXP.
+    /// Removes data sections which do not contain data of any object, such as sections left
+    /// over after [remove](Package::remove), and returns the number of removed sections.
+    ///
+    /// The start section index of objects is updated to account for the removed sections.
+    ///
+    /// *As this re-writes the whole container on the next save, all sections are loaded.*
+    ///
+    /// returns: Result<usize, ReadError>
     ///
     /// # Errors
     ///
-    /// A [ReadError](crate::package::error::ReadError) is returned in case of corruption or system error.
-    pub fn read_metadata(&mut self) -> Result<Option<crate::sd::Object>, ReadError>
+    /// A [ReadError](crate::package::error::ReadError) is returned if some sections or the
+    /// object table couldn't be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new().max_section_size(4)).unwrap();
+    /// bpxp.pack("a", "1234".as_bytes()).unwrap();
+    /// bpxp.pack("b", "5678".as_bytes()).unwrap();
+    /// bpxp.pack("c", "90".as_bytes()).unwrap();
+    /// bpxp.remove("b").unwrap();
+    /// assert_eq!(bpxp.compact_data_sections().unwrap(), 1);
+    /// let mut data = Vec::new();
+    /// bpxp.unpack("c", &mut data).unwrap();
+    /// assert_eq!(data, b"90");
+    /// ```
+    pub fn compact_data_sections(&mut self) -> Result<usize, ReadError>
     {
-        if let Some(obj) = &self.settings.metadata {
-            return Ok(Some(obj.clone()));
+        self.load_objects()?;
+        self.load_all()?;
+        //Section indices of deduplication candidates are about to change
+        self.content_hashes.clear();
+        let mut used = Vec::new();
+        for header in &self.objects {
+            used.extend(get_spans(&self.container, header)?.iter().map(|v| v.handle));
         }
-        if let Some(handle) = self.container.find_section_by_type(SECTION_TYPE_SD) {
-            let mut section = self.container.get_mut(handle);
-            let obj = crate::sd::Object::read(section.load()?)?;
-            self.settings.metadata = Some(obj.clone());
-            return Ok(Some(obj));
+        let mut unused: Vec<(u32, Handle)> = self
+            .container
+            .iter()
+            .filter(|v| v.ty == SECTION_TYPE_DATA && !used.contains(&v.handle()))
+            .map(|v| (v.index(), v.handle()))
+            .collect();
+        //Remove from the end so that indices of sections left to remove stay valid
+        unused.sort_by_key(|v| std::cmp::Reverse(v.0));
+        for (_, handle) in &unused {
+            let removed = self.container.remove_section(*handle);
+            for header in &mut self.objects {
+                if header.start > removed.old_index {
+                    header.start -= 1;
+                } else if header.start == removed.old_index {
+                    //The object has no data in its start section: its data starts at the
+                    // beginning of the next section
+                    header.offset = 0;
+                }
+            }
+            if self.last_data_section == Some(*handle) {
+                self.last_data_section = None;
+            }
+            self.data_sections.retain(|_, v| v != handle);
         }
-        Ok(None)
+        if !unused.is_empty() {
+            self.table = None;
+        }
+        Ok(unused.len())
     }
 
-    /// Unpacks an object and returns the size of the unpacked object or None if the object does not exist.
+    /// Replaces the metadata of this BPXP.
+    ///
+    /// The metadata section is created with the
+    /// [metadata_header](crate::package::Builder::metadata_header) of the package if it does
+    /// not yet exist. The new metadata is written when the package is saved.
     ///
     /// # Arguments
     ///
-    /// * `name`: the name of the object to unpack.
-    /// * `out`: the output Write.
+    /// * `obj`: the new BPXSD metadata object.
     ///
-    /// returns: Result<Option<u64>, ReadError>
-    pub fn unpack<W: Write>(&mut self, name: &str, out: W) -> Result<Option<u64>, ReadError>
+    /// returns: Result<(), WriteError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [WriteError](crate::package::error::WriteError) if the existing metadata
+    /// section couldn't be loaded or if the object couldn't be written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::sd::Object;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxp = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// assert!(bpxp.metadata().unwrap().is_none());
+    /// let mut obj = Object::new();
+    /// obj.set("Author", "me".into());
+    /// bpxp.set_metadata(&obj).unwrap();
+    /// let metadata = bpxp.metadata().unwrap().unwrap();
+    /// assert_eq!(metadata.get("Author").unwrap().as_str(), Some("me"));
+    /// ```
+    pub fn set_metadata(&mut self, obj: &crate::sd::Object) -> Result<(), WriteError>
     {
-        let table = self.table.get_or_insert_with_err(|| {
-            read_object_table(&mut self.container, &mut self.objects, self.object_table)
-        })?;
-        load_string_section(&mut self.container, &self.strings)?;
-        table.build_lookup_table(&mut self.container, &mut self.strings)?;
-        if let Some(header) = table.lookup(name) {
-            let size = unpack_object(&mut self.container, header, out)?;
-            Ok(Some(size))
-        } else {
-            Ok(None)
-        }
+        let handle = match self.container.find_section_by_type(SECTION_TYPE_SD) {
+            Some(handle) => handle,
+            None => self.container.create_section(self.settings.metadata_header)
+        };
+        let mut section = self.container.get_mut(handle);
+        let data = section.load().map_err(ReadError::from)?;
+        data.clear();
+        obj.write(data)?;
+        self.settings.metadata = Some(obj.clone());
+        Ok(())
     }
 }
@@ -26,17 +26,20 @@
 // NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::{
+    io,
+    io::{Read, Seek, SeekFrom, Write}
+};
 
 use crate::{
-    core::{header::Struct, Container},
+    core::{header::Struct, Container, SectionCursor, SectionData},
     package::{
-        error::{InvalidCodeContext, ReadError},
-        object::ObjectHeader,
+        error::{EosContext, InvalidCodeContext, ReadError},
+        object::{ObjectAttributes, ObjectHeader, SIZE_OBJECT_ATTRIBUTES, SIZE_OBJECT_HEADER},
         Architecture,
         Platform
     },
-    table::ItemTable,
+    utils::ReadFill,
     Handle
 };
 
@@ -53,13 +56,14 @@ fn load_from_section<T: Read + Seek, W: Write>(
     let mut len = 0;
     let mut buf: [u8; DATA_READ_BUFFER_SIZE] = [0; DATA_READ_BUFFER_SIZE];
     let mut section = container.get_mut(handle);
-    let data = section.load()?;
+    //Read through a cursor so that the position of the section itself is left unchanged
+    let mut cursor = SectionCursor::new(section.load()?);
 
-    data.seek(SeekFrom::Start(offset as u64))?;
+    cursor.seek(SeekFrom::Start(offset as u64))?;
     while len < size {
         let s = std::cmp::min(size - len, DATA_READ_BUFFER_SIZE as u32);
         // Read is enough as Sections are guaranteed to fill the buffer as much as possible
-        let val = data.read(&mut buf[0..s as usize])?;
+        let val = cursor.read(&mut buf[0..s as usize])?;
         len += val as u32;
         out.write_all(&buf[0..val])?;
     }
@@ -97,24 +101,140 @@ pub fn unpack_object<T: Read + Seek, W: Write>(
     Ok(obj.size)
 }
 
+/// Unpacks an object by streaming its data sections instead of loading them; already
+/// loaded sections are read from memory.
+pub fn stream_object<T: Read + Seek, W: Write>(
+    container: &mut Container<T>,
+    obj: &ObjectHeader,
+    mut out: W
+) -> Result<u64, ReadError>
+{
+    let mut section_id = obj.start;
+    let mut offset = obj.offset as u64;
+    let mut len = obj.size;
+
+    while len > 0 {
+        let handle = match container.find_section_by_index(section_id) {
+            Some(i) => i,
+            None => break
+        };
+        let mut reader = container.section_reader(handle)?;
+        io::copy(&mut (&mut reader).take(offset), &mut io::sink())?;
+        len -= io::copy(&mut (&mut reader).take(len), &mut out)?;
+        // Read the remaining data so that the section checksum gets verified.
+        io::copy(&mut reader, &mut io::sink())?;
+        offset = 0;
+        section_id += 1;
+    }
+    Ok(obj.size)
+}
+
+fn read_records<R: Read, S: Struct<N, Output = S, Error = ReadError>, const N: usize>(
+    mut reader: R,
+    count: u32
+) -> Result<Vec<S>, ReadError>
+{
+    let mut v = Vec::with_capacity(count as _);
+    for _ in 0..count {
+        v.push(S::read(&mut reader)?);
+    }
+    Ok(v)
+}
+
 pub fn read_object_table<T: Read + Seek>(
     container: &mut Container<T>,
-    objects: &mut Vec<ObjectHeader>,
     object_table: Handle
-) -> Result<ItemTable<ObjectHeader>, ReadError>
+) -> Result<Vec<ObjectHeader>, ReadError>
 {
     let mut section = container.get_mut(object_table);
-    let count = section.size / 20;
+    let count = section.size / SIZE_OBJECT_HEADER as u32;
+    let data = section.load()?;
+    match data.as_slice() {
+        Some(mut slice) => read_records::<_, ObjectHeader, SIZE_OBJECT_HEADER>(&mut slice, count),
+        None => read_records::<_, ObjectHeader, SIZE_OBJECT_HEADER>(data, count)
+    }
+}
+
+pub fn read_attribute_table<T: Read + Seek>(
+    container: &mut Container<T>,
+    attribute_table: Handle
+) -> Result<Vec<ObjectAttributes>, ReadError>
+{
+    let mut section = container.get_mut(attribute_table);
+    let count = section.size / SIZE_OBJECT_ATTRIBUTES as u32;
+    let data = section.load()?;
+    match data.as_slice() {
+        Some(mut slice) => {
+            read_records::<_, ObjectAttributes, SIZE_OBJECT_ATTRIBUTES>(&mut slice, count)
+        },
+        None => read_records::<_, ObjectAttributes, SIZE_OBJECT_ATTRIBUTES>(data, count)
+    }
+}
+
+/// Reads the object table by streaming its section instead of loading it.
+pub fn stream_object_table<T: Read + Seek>(
+    container: &mut Container<T>,
+    object_table: Handle
+) -> Result<Vec<ObjectHeader>, ReadError>
+{
+    let count = container.get(object_table).size / SIZE_OBJECT_HEADER as u32;
+    let mut reader = container.section_reader(object_table)?;
+    let mut buf = [0; SIZE_OBJECT_HEADER];
     let mut v = Vec::with_capacity(count as _);
 
     for _ in 0..count {
-        let header = ObjectHeader::read(section.load()?)?;
-        v.push(header);
+        if reader.read_fill(&mut buf)? != SIZE_OBJECT_HEADER {
+            return Err(ReadError::Eos(EosContext::ObjectTable));
+        }
+        v.push(ObjectHeader::from_bytes(buf)?);
+    }
+    // Read the remaining data so that the section checksum gets verified.
+    io::copy(&mut reader, &mut io::sink())?;
+    Ok(v)
+}
+
+/// Reads the object table up to the last complete record, ignoring truncation and corruption
+/// past that point.
+pub fn read_object_table_lenient<T: Read + Seek>(
+    container: &mut Container<T>,
+    object_table: Handle
+) -> Result<Vec<ObjectHeader>, ReadError>
+{
+    let mut reader = container.section_reader(object_table)?;
+    let mut buf = [0; SIZE_OBJECT_HEADER];
+    let mut v = Vec::new();
+
+    while let Ok(SIZE_OBJECT_HEADER) = reader.read_fill(&mut buf) {
+        v.push(ObjectHeader::from_bytes(buf)?);
     }
-    *objects = v.clone();
-    Ok(ItemTable::new(v))
+    Ok(v)
 }
 
+/// Decodes the target [Architecture](crate::package::Architecture) and
+/// [Platform](crate::package::Platform) from the code bytes stored in the type-ext of a BPXP.
+///
+/// # Arguments
+///
+/// * `acode`: the architecture code byte.
+/// * `pcode`: the platform code byte.
+///
+/// returns: Result<(Architecture, Platform), ReadError>
+///
+/// # Errors
+///
+/// A [ReadError](crate::package::error::ReadError) is returned if one of the code bytes is
+/// unknown.
+///
+/// # Examples
+///
+/// ```
+/// use bpx::package::{get_arch_platform_from_code, Architecture, Platform};
+///
+/// let (arch, platform) = get_arch_platform_from_code(0x1, 0x2).unwrap();
+/// assert_eq!(arch, Architecture::Aarch64);
+/// assert_eq!(platform, Platform::Windows);
+/// assert!(get_arch_platform_from_code(0x5, 0x0).is_err());
+/// ```
 pub fn get_arch_platform_from_code(
     acode: u8,
     pcode: u8
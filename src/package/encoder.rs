@@ -28,39 +28,111 @@
 
 use crate::{
     core::{
-        builder::{Checksum, CompressionMethod, SectionHeaderBuilder},
-        header::SectionHeader
+        builder::{Checksum, CompressionMethod, SectionHeaderBuilder, SectionOptions},
+        header::{SECTION_TYPE_SD, SECTION_TYPE_STRING}
     },
-    package::{Architecture, Platform, Settings, SECTION_TYPE_DATA}
+    package::{
+        Architecture,
+        Platform,
+        Settings,
+        SECTION_TYPE_ATTRIBUTE_TABLE,
+        SECTION_TYPE_DATA,
+        SECTION_TYPE_OBJECT_TABLE
+    },
+    spec::{bpxp, core as bpx}
 };
 
-pub fn create_data_section_header() -> SectionHeader
+pub fn default_data_header() -> SectionOptions
 {
     SectionHeaderBuilder::new()
         .ty(SECTION_TYPE_DATA)
         .compression(CompressionMethod::Xz)
         .checksum(Checksum::Crc32)
-        .build()
+        .options()
+}
+
+pub fn default_object_table_header() -> SectionOptions
+{
+    SectionHeaderBuilder::new()
+        .checksum(Checksum::Weak)
+        .compression(CompressionMethod::Zlib)
+        .ty(SECTION_TYPE_OBJECT_TABLE)
+        .options()
+}
+
+pub fn default_attribute_table_header() -> SectionOptions
+{
+    SectionHeaderBuilder::new()
+        .checksum(Checksum::Weak)
+        .compression(CompressionMethod::Zlib)
+        .ty(SECTION_TYPE_ATTRIBUTE_TABLE)
+        .options()
+}
+
+pub fn default_string_header() -> SectionOptions
+{
+    SectionHeaderBuilder::new()
+        .checksum(Checksum::Weak)
+        .compression(CompressionMethod::Zlib)
+        .ty(SECTION_TYPE_STRING)
+        .options()
+}
+
+pub fn default_metadata_header() -> SectionOptions
+{
+    SectionHeaderBuilder::new()
+        .checksum(Checksum::Weak)
+        .compression(CompressionMethod::Zlib)
+        .ty(SECTION_TYPE_SD)
+        .options()
+}
+
+/// Encodes the target [Architecture](crate::package::Architecture) and
+/// [Platform](crate::package::Platform) into the code bytes stored in the type-ext of a BPXP.
+///
+/// This is the reverse of [get_arch_platform_from_code](crate::package::get_arch_platform_from_code).
+///
+/// # Arguments
+///
+/// * `arch`: the target CPU architecture.
+/// * `platform`: the target platform.
+///
+/// returns: (u8, u8)
+///
+/// # Examples
+///
+/// ```
+/// use bpx::package::{get_code_from_arch_platform, Architecture, Platform};
+///
+/// assert_eq!(get_code_from_arch_platform(Architecture::Aarch64, Platform::Windows), (0x1, 0x2));
+/// assert_eq!(get_code_from_arch_platform(Architecture::Any, Platform::Any), (0x4, 0x4));
+/// ```
+pub fn get_code_from_arch_platform(arch: Architecture, platform: Platform) -> (u8, u8)
+{
+    let acode = match arch {
+        Architecture::X86_64 => 0x0,
+        Architecture::Aarch64 => 0x1,
+        Architecture::X86 => 0x2,
+        Architecture::Armv7hl => 0x3,
+        Architecture::Any => 0x4
+    };
+    let pcode = match platform {
+        Platform::Linux => 0x0,
+        Platform::Mac => 0x1,
+        Platform::Windows => 0x2,
+        Platform::Android => 0x3,
+        Platform::Any => 0x4
+    };
+    (acode, pcode)
 }
 
 pub fn get_type_ext(settings: &Settings) -> [u8; 16]
 {
-    let mut type_ext: [u8; 16] = [0; 16];
-    match settings.architecture {
-        Architecture::X86_64 => type_ext[0] = 0x0,
-        Architecture::Aarch64 => type_ext[0] = 0x1,
-        Architecture::X86 => type_ext[0] = 0x2,
-        Architecture::Armv7hl => type_ext[0] = 0x3,
-        Architecture::Any => type_ext[0] = 0x4
-    }
-    match settings.platform {
-        Platform::Linux => type_ext[1] = 0x0,
-        Platform::Mac => type_ext[1] = 0x1,
-        Platform::Windows => type_ext[1] = 0x2,
-        Platform::Android => type_ext[1] = 0x3,
-        Platform::Any => type_ext[1] = 0x4
-    }
-    type_ext[2] = settings.type_code[0];
-    type_ext[3] = settings.type_code[1];
+    let mut type_ext: [u8; 16] = [0; bpx::TYPE_EXT_SIZE];
+    let (acode, pcode) = get_code_from_arch_platform(settings.architecture, settings.platform);
+    type_ext[bpxp::TYPE_EXT_ARCHITECTURE_OFFSET] = acode;
+    type_ext[bpxp::TYPE_EXT_PLATFORM_OFFSET] = pcode;
+    type_ext[bpxp::TYPE_EXT_TYPE_CODE_OFFSET..bpxp::TYPE_EXT_TYPE_CODE_OFFSET + 2]
+        .copy_from_slice(&settings.type_code);
     type_ext
 }
@@ -49,7 +49,10 @@ variant_error!(
         Object : "object",
 
         /// Reached EOS while reading the object table.
-        ObjectTable : "object table"
+        ObjectTable : "object table",
+
+        /// Reached EOS while reading the object attribute table.
+        AttributeTable : "attribute table"
     }
 
     S {
@@ -72,17 +75,39 @@ variant_error!(
         /// Describes a missing required section.
         MissingSection(Section),
 
+        /// Describes a missing object.
+        ///
+        /// # Arguments
+        /// * the name of the object.
+        MissingObject(String),
+
+        /// Describes an object name shared by several objects.
+        ///
+        /// # Arguments
+        /// * the name of the object.
+        DuplicateObject(String),
+
         /// Describes an EOS (End Of Section) error while reading some item.
         Eos(EosContext),
 
         /// Indicates a blank string was obtained when attempting to unpack a BPXP to the file system.
         BlankString,
 
+        /// Describes an object name which would be unpacked outside of the target directory.
+        ///
+        /// # Arguments
+        /// * the name of the object.
+        UnsafePath(String),
+
         /// Describes a structured data error.
         Sd(crate::sd::error::ReadError),
 
         /// A strings error.
-        Strings(crate::strings::ReadError)
+        Strings(crate::strings::ReadError),
+
+        /// Describes an error while writing a zip archive.
+        #[cfg(feature = "zip")]
+        Zip(zip::result::ZipError)
     }
 
     /// Represents a BPXP write error.
@@ -97,7 +122,14 @@ variant_error!(
         InvalidPath(crate::strings::PathError),
 
         /// Indicates a section wasn't loaded.
-        SectionNotLoaded
+        SectionNotLoaded,
+
+        /// Describes an error while reading existing data.
+        Read(ReadError),
+
+        /// Describes an error while reading a zip archive.
+        #[cfg(feature = "zip")]
+        Zip(zip::result::ZipError)
     }
 );
 
@@ -112,10 +144,17 @@ impl_err_conversion!(
     WriteError {
         crate::strings::WriteError => Strings,
         crate::sd::error::WriteError => Sd,
-        crate::strings::PathError => InvalidPath
+        crate::strings::PathError => InvalidPath,
+        ReadError => Read
     }
 );
 
+#[cfg(feature = "zip")]
+impl_err_conversion!(ReadError { zip::result::ZipError => Zip });
+
+#[cfg(feature = "zip")]
+impl_err_conversion!(WriteError { zip::result::ZipError => Zip });
+
 impl Display for ReadError
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
@@ -129,12 +168,17 @@ impl Display for ReadError
                 write!(f, "invalid {} code ({})", ctx.name(), code)
             },
             ReadError::MissingSection(s) => write!(f, "missing {} section", s.name()),
+            ReadError::MissingObject(name) => write!(f, "missing object ({})", name),
+            ReadError::DuplicateObject(name) => write!(f, "duplicate object ({})", name),
             ReadError::Eos(ctx) => write!(f, "got EOS while reading {}", ctx.name()),
             ReadError::BlankString => {
                 f.write_str("blank strings are not supported when unpacking to file system")
             },
+            ReadError::UnsafePath(name) => write!(f, "unsafe object path ({})", name),
             ReadError::Sd(e) => write!(f, "BPXSD error: {}", e),
-            ReadError::Strings(e) => write!(f, "strings error: {}", e)
+            ReadError::Strings(e) => write!(f, "strings error: {}", e),
+            #[cfg(feature = "zip")]
+            ReadError::Zip(e) => write!(f, "zip error: {}", e)
         }
     }
 }
@@ -149,7 +193,10 @@ impl Display for WriteError
             WriteError::Strings(e) => write!(f, "strings error: {}", e),
             WriteError::Sd(e) => write!(f, "BPXSD error: {}", e),
             WriteError::InvalidPath(e) => write!(f, "path error: {}", e),
-            WriteError::SectionNotLoaded => f.write_str("section not loaded")
+            WriteError::SectionNotLoaded => f.write_str("section not loaded"),
+            WriteError::Read(e) => write!(f, "read error: {}", e),
+            #[cfg(feature = "zip")]
+            WriteError::Zip(e) => write!(f, "zip error: {}", e)
         }
     }
 }
@@ -38,17 +38,31 @@ mod encoder;
 pub mod utils;
 
 pub use builder::*;
+pub use decoder::get_arch_platform_from_code;
+pub use encoder::get_code_from_arch_platform;
 
 pub use self::core::*;
 
 /// The standard type for a data section in a BPX Package (type P).
-pub const SECTION_TYPE_DATA: u8 = 0x1;
+pub const SECTION_TYPE_DATA: u8 = crate::core::reserved::PACKAGE_DATA.section_type;
 
 /// The standard type for the object table section in a BPX Package (type P).
-pub const SECTION_TYPE_OBJECT_TABLE: u8 = 0x2;
+pub const SECTION_TYPE_OBJECT_TABLE: u8 = crate::core::reserved::PACKAGE_OBJECT_TABLE.section_type;
+
+/// The standard type for the optional object attribute table section in a BPX Package (type P).
+pub const SECTION_TYPE_ATTRIBUTE_TABLE: u8 =
+    crate::core::reserved::PACKAGE_ATTRIBUTE_TABLE.section_type;
+
+/// The suffix of the virtual name of objects representing an empty directory.
+pub const DIRECTORY_MARKER: char = '/';
+
+/// The default maximum size in bytes of a data section (about 200MB).
+///
+/// *Section sizes are stored as u32 so this can never exceed [u32::MAX].*
+pub const DEFAULT_MAX_SECTION_SIZE: usize = 200000000 - 8192;
 
 /// The supported BPX version for this package variant decoder/encoder.
-pub const SUPPORTED_VERSION: u32 = 0x2;
+pub const SUPPORTED_VERSION: u32 = crate::spec::bpxp::VERSION;
 
 /// Enum of all supported processor architectures by BPXP.
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
@@ -33,14 +33,21 @@ use byteorder::{ByteOrder, LittleEndian};
 use crate::{
     core::header::Struct,
     package::error::{EosContext, ReadError},
-    table::Item
+    spec::bpxp,
+    table::{Item, ItemMut}
 };
 
 /// Size in bytes of an object header.
-pub const SIZE_OBJECT_HEADER: usize = 20;
+pub const SIZE_OBJECT_HEADER: usize = bpxp::OBJECT_HEADER_SIZE;
+
+const _: () = assert!(bpxp::OBJECT_HEADER_NAME_OFFSET == bpxp::OBJECT_HEADER_SIZE_OFFSET + 8);
+const _: () = assert!(bpxp::OBJECT_HEADER_START_OFFSET == bpxp::OBJECT_HEADER_NAME_OFFSET + 4);
+const _: () = assert!(bpxp::OBJECT_HEADER_DATA_OFFSET == bpxp::OBJECT_HEADER_START_OFFSET + 4);
+const _: () = assert!(bpxp::OBJECT_HEADER_DATA_OFFSET + 4 == SIZE_OBJECT_HEADER);
 
 /// Represents an object header as read from the package.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectHeader
 {
     /// The size of the object.
@@ -78,10 +85,10 @@ impl Struct<SIZE_OBJECT_HEADER> for ObjectHeader
 
     fn from_bytes(buffer: [u8; SIZE_OBJECT_HEADER]) -> Result<Self::Output, Self::Error>
     {
-        let size = LittleEndian::read_u64(&buffer[0..8]);
-        let name_ptr = LittleEndian::read_u32(&buffer[8..12]);
-        let start = LittleEndian::read_u32(&buffer[12..16]);
-        let offset = LittleEndian::read_u32(&buffer[16..20]);
+        let size = LittleEndian::read_u64(&buffer[bpxp::OBJECT_HEADER_SIZE_OFFSET..]);
+        let name_ptr = LittleEndian::read_u32(&buffer[bpxp::OBJECT_HEADER_NAME_OFFSET..]);
+        let start = LittleEndian::read_u32(&buffer[bpxp::OBJECT_HEADER_START_OFFSET..]);
+        let offset = LittleEndian::read_u32(&buffer[bpxp::OBJECT_HEADER_DATA_OFFSET..]);
         Ok(ObjectHeader {
             size,
             name: name_ptr,
@@ -93,10 +100,92 @@ impl Struct<SIZE_OBJECT_HEADER> for ObjectHeader
     fn to_bytes(&self) -> [u8; SIZE_OBJECT_HEADER]
     {
         let mut buf: [u8; SIZE_OBJECT_HEADER] = [0; SIZE_OBJECT_HEADER];
-        LittleEndian::write_u64(&mut buf[0..8], self.size as u64);
-        LittleEndian::write_u32(&mut buf[8..12], self.name);
-        LittleEndian::write_u32(&mut buf[12..16], self.start);
-        LittleEndian::write_u32(&mut buf[16..20], self.offset);
+        LittleEndian::write_u64(&mut buf[bpxp::OBJECT_HEADER_SIZE_OFFSET..], self.size);
+        LittleEndian::write_u32(&mut buf[bpxp::OBJECT_HEADER_NAME_OFFSET..], self.name);
+        LittleEndian::write_u32(&mut buf[bpxp::OBJECT_HEADER_START_OFFSET..], self.start);
+        LittleEndian::write_u32(&mut buf[bpxp::OBJECT_HEADER_DATA_OFFSET..], self.offset);
+        buf
+    }
+}
+
+/// Size in bytes of an object attribute record.
+pub const SIZE_OBJECT_ATTRIBUTES: usize = bpxp::OBJECT_ATTRIBUTES_SIZE;
+
+/// Represents the optional attributes of an object as read from the attribute table.
+///
+/// *Records are matched to objects by name pointer.*
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObjectAttributes
+{
+    /// The pointer to the name of the object.
+    pub name: u32,
+
+    /// The Unix permissions of the object.
+    pub mode: Option<u32>,
+
+    /// The modification time of the object, in seconds since the Unix epoch.
+    pub mtime: Option<u64>,
+
+    /// True if the object is a symbolic link whose data is the link target.
+    pub symlink: bool
+}
+
+impl ObjectAttributes
+{
+    /// Returns true if no attribute is set.
+    pub fn is_empty(&self) -> bool
+    {
+        self.mode.is_none() && self.mtime.is_none() && !self.symlink
+    }
+}
+
+impl Struct<SIZE_OBJECT_ATTRIBUTES> for ObjectAttributes
+{
+    type Output = ObjectAttributes;
+    type Error = ReadError;
+
+    fn new() -> Self
+    {
+        ObjectAttributes::default()
+    }
+
+    fn error_buffer_size() -> Option<Self::Error>
+    {
+        Some(ReadError::Eos(EosContext::AttributeTable))
+    }
+
+    fn from_bytes(buffer: [u8; SIZE_OBJECT_ATTRIBUTES]) -> Result<Self::Output, Self::Error>
+    {
+        let name = LittleEndian::read_u32(&buffer[bpxp::OBJECT_ATTRIBUTES_NAME_OFFSET..]);
+        let flags = LittleEndian::read_u32(&buffer[bpxp::OBJECT_ATTRIBUTES_FLAGS_OFFSET..]);
+        let mode = LittleEndian::read_u32(&buffer[bpxp::OBJECT_ATTRIBUTES_MODE_OFFSET..]);
+        let mtime = LittleEndian::read_u64(&buffer[bpxp::OBJECT_ATTRIBUTES_MTIME_OFFSET..]);
+        Ok(ObjectAttributes {
+            name,
+            mode: (flags & bpxp::ATTRIBUTE_FLAG_MODE != 0).then_some(mode),
+            mtime: (flags & bpxp::ATTRIBUTE_FLAG_MTIME != 0).then_some(mtime),
+            symlink: flags & bpxp::ATTRIBUTE_FLAG_SYMLINK != 0
+        })
+    }
+
+    fn to_bytes(&self) -> [u8; SIZE_OBJECT_ATTRIBUTES]
+    {
+        let mut buf: [u8; SIZE_OBJECT_ATTRIBUTES] = [0; SIZE_OBJECT_ATTRIBUTES];
+        let mut flags = 0;
+        if let Some(mode) = self.mode {
+            flags |= bpxp::ATTRIBUTE_FLAG_MODE;
+            LittleEndian::write_u32(&mut buf[bpxp::OBJECT_ATTRIBUTES_MODE_OFFSET..], mode);
+        }
+        if let Some(mtime) = self.mtime {
+            flags |= bpxp::ATTRIBUTE_FLAG_MTIME;
+            LittleEndian::write_u64(&mut buf[bpxp::OBJECT_ATTRIBUTES_MTIME_OFFSET..], mtime);
+        }
+        if self.symlink {
+            flags |= bpxp::ATTRIBUTE_FLAG_SYMLINK;
+        }
+        LittleEndian::write_u32(&mut buf[bpxp::OBJECT_ATTRIBUTES_NAME_OFFSET..], self.name);
+        LittleEndian::write_u32(&mut buf[bpxp::OBJECT_ATTRIBUTES_FLAGS_OFFSET..], flags);
         buf
     }
 }
@@ -108,3 +197,11 @@ impl Item for ObjectHeader
         self.name
     }
 }
+
+impl ItemMut for ObjectHeader
+{
+    fn set_name_address(&mut self, address: u32)
+    {
+        self.name = address;
+    }
+}
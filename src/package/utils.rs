@@ -29,21 +29,221 @@
 //! BPXP utility functions.
 
 use std::{
-    fs::{metadata, read_dir, File},
+    collections::HashMap,
+    fs::{read_dir, symlink_metadata, File, Metadata},
     io::{Read, Seek, Write},
-    path::{Path, PathBuf}
+    path::{Component, Path, PathBuf},
+    time::{Duration, UNIX_EPOCH}
 };
 
 use crate::{
+    core::{
+        builder::{MainHeaderBuilder, SectionOptions},
+        CompressionKind,
+        Container
+    },
     package::{
         error::{EosContext, ReadError, WriteError},
-        Package
+        object::{ObjectAttributes, SIZE_OBJECT_HEADER},
+        DuplicatePolicy,
+        Package,
+        DIRECTORY_MARKER
     },
-    strings::{get_name_from_dir_entry, get_name_from_path}
+    strings::{get_name_from_dir_entry, get_name_from_path},
+    utils::new_byte_buf
 };
 
+/// Normalizes the virtual name of an object.
+///
+/// Backslashes are replaced by forward slashes, leading `/` and `./` are removed as well as
+/// empty and `.` path components. A trailing [DIRECTORY_MARKER](crate::package::DIRECTORY_MARKER)
+/// is preserved.
+///
+/// # Arguments
+///
+/// * `name`: the virtual name to normalize.
+///
+/// returns: String
+///
+/// # Examples
+///
+/// ```
+/// use bpx::package::utils::normalize_name;
+///
+/// assert_eq!(normalize_name("./foo/bar"), "foo/bar");
+/// assert_eq!(normalize_name("/foo\\.\\bar"), "foo/bar");
+/// assert_eq!(normalize_name("foo//bar/"), "foo/bar/");
+/// ```
+pub fn normalize_name(name: &str) -> String
+{
+    let mut normalized = String::with_capacity(name.len());
+    let components = name
+        .split(['/', '\\'])
+        .filter(|v| !v.is_empty() && *v != ".");
+    for component in components {
+        if !normalized.is_empty() {
+            normalized.push('/');
+        }
+        normalized.push_str(component);
+    }
+    if !normalized.is_empty() && name.ends_with([DIRECTORY_MARKER, '\\']) {
+        normalized.push(DIRECTORY_MARKER);
+    }
+    normalized
+}
+
+/// A progress report sent after an object is packed or unpacked.
+#[derive(Copy, Clone, Debug)]
+pub struct ProgressEvent<'a>
+{
+    /// The virtual name of the object.
+    pub name: &'a str,
+
+    /// The size in bytes of the object.
+    pub size: u64,
+
+    /// The number of bytes processed so far, including this object.
+    pub bytes: u64,
+
+    /// The total number of bytes to process.
+    pub total: u64
+}
+
+#[cfg(unix)]
+fn file_mode(md: &Metadata) -> Option<u32>
+{
+    use std::os::unix::fs::PermissionsExt;
+    Some(md.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn file_mode(_: &Metadata) -> Option<u32>
+{
+    None
+}
+
+#[cfg(unix)]
+fn apply_mode(path: &Path, mode: u32) -> std::io::Result<()>
+{
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn apply_mode(_: &Path, _: u32) -> std::io::Result<()>
+{
+    Ok(())
+}
+
+fn file_mtime(md: &Metadata) -> Option<u64>
+{
+    md.modified()
+        .ok()
+        .and_then(|v| v.duration_since(UNIX_EPOCH).ok())
+        .map(|v| v.as_secs())
+}
+
+fn apply_mtime(file: &File, mtime: u64) -> std::io::Result<()>
+{
+    file.set_modified(UNIX_EPOCH + Duration::from_secs(mtime))
+}
+
+/// Reads the metadata of a file to pack; symbolic links are not followed so that they can be
+/// packed as links, which also prevents cyclic links from causing infinite recursion.
+#[cfg(unix)]
+fn source_metadata(path: &Path) -> std::io::Result<Metadata>
+{
+    symlink_metadata(path)
+}
+
+/// Reads the metadata of a file to pack; symbolic links to files are followed as they cannot
+/// be recreated on this platform while symbolic links to directories are rejected as they may
+/// be cyclic.
+#[cfg(not(unix))]
+fn source_metadata(path: &Path) -> std::io::Result<Metadata>
+{
+    let md = symlink_metadata(path)?;
+    if md.file_type().is_symlink() {
+        let md = std::fs::metadata(path)?;
+        if md.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!(
+                    "cannot pack symbolic link to directory '{}' on this platform",
+                    path.display()
+                )
+            ));
+        }
+        return Ok(md);
+    }
+    Ok(md)
+}
+
+#[cfg(unix)]
+fn read_link(path: &Path) -> std::io::Result<Vec<u8>>
+{
+    use std::os::unix::ffi::OsStringExt;
+    Ok(std::fs::read_link(path)?.into_os_string().into_vec())
+}
+
+#[cfg(not(unix))]
+fn read_link(path: &Path) -> std::io::Result<Vec<u8>>
+{
+    std::fs::read(path)
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &[u8], path: &Path) -> std::io::Result<()>
+{
+    use std::os::unix::ffi::OsStrExt;
+    if symlink_metadata(path).is_ok() {
+        std::fs::remove_file(path)?;
+    }
+    std::os::unix::fs::symlink(std::ffi::OsStr::from_bytes(target), path)
+}
+
+#[cfg(not(unix))]
+fn create_symlink(_: &[u8], path: &Path) -> std::io::Result<()>
+{
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!(
+            "cannot create symbolic link '{}' on this platform",
+            path.display()
+        )
+    ))
+}
+
+fn pack_with_metadata<T: Write + Seek, R: Read>(
+    package: &mut Package<T>,
+    vname: &str,
+    source: R,
+    md: &Metadata
+) -> Result<(), WriteError>
+{
+    //Permissions and timestamps of a link cannot be restored without following the link
+    let symlink = md.file_type().is_symlink();
+    let attributes = ObjectAttributes {
+        mode: file_mode(md).filter(|_| !symlink && package.get_preserve_permissions()),
+        mtime: file_mtime(md).filter(|_| !symlink && package.get_preserve_timestamps()),
+        symlink,
+        ..Default::default()
+    };
+    package.pack_with_attributes(vname, source, attributes)
+}
+
+fn log_progress(_verb: &str, _name: &str, _size: u64)
+{
+    #[cfg(feature = "debug-log")]
+    println!("{} {} with {} byte(s)", _verb, _name, _size);
+}
+
 /// Packs a file or folder in a BPXP with the given virtual name.
 ///
+/// Empty directories are recorded as empty objects whose virtual name ends with a '/'. The
+/// Unix permissions of files are recorded when
+/// [set_preserve_permissions](crate::package::Package::set_preserve_permissions) is enabled.
+///
 /// **This function prints some information to standard output as a way
 /// to debug data compression issues unless the `debug-log` feature
 /// is disabled.**
@@ -65,21 +265,446 @@ pub fn pack_file_vname<T: Write + Seek>(
     source: &Path
 ) -> Result<(), WriteError>
 {
-    let md = metadata(source)?;
+    pack_file_vname_filtered(package, vname, source, |_| true)
+}
+
+/// Packs a file or folder in a BPXP with the given virtual name, skipping entries rejected
+/// by a matcher.
+///
+/// The matcher is called with the path of each file or directory found under `source`.
+/// Directories rejected by the matcher are skipped without descending into them. A
+/// directory whose entries are all rejected is recorded as an empty directory.
+///
+/// **This function prints some information to standard output as a way
+/// to debug data compression issues unless the `debug-log` feature
+/// is disabled.**
+///
+/// # Arguments
+///
+/// * `package`: the [Package](crate::package::Package) to use.
+/// * `vname`: the virtual name for the root source path.
+/// * `source`: the source [Path](std::path::Path) to pack.
+/// * `matcher`: a function returning true if the given entry should be packed.
+///
+/// returns: Result<(), Error>
+///
+/// # Errors
+///
+/// A [WriteError](crate::package::error::WriteError) is returned if some objects could not be packed.
+///
+/// # Examples
+///
+/// ```
+/// use bpx::package::{utils::pack_file_vname_filtered, Builder, Package};
+/// use bpx::utils::new_byte_buf;
+///
+/// let root = std::env::temp_dir().join(format!("bpx_doc_filtered_{}", std::process::id()));
+/// std::fs::create_dir_all(root.join(".git")).unwrap();
+/// std::fs::write(root.join(".git/HEAD"), b"ref").unwrap();
+/// std::fs::write(root.join("main.txt"), b"content").unwrap();
+/// let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+/// pack_file_vname_filtered(&mut package, "root", &root, |path| {
+///     path.file_name().map(|v| v != ".git").unwrap_or(true)
+/// })
+/// .unwrap();
+/// assert_eq!(package.objects().unwrap().count(), 1);
+/// std::fs::remove_dir_all(&root).unwrap();
+/// ```
+pub fn pack_file_vname_filtered<T: Write + Seek, F: Fn(&Path) -> bool>(
+    package: &mut Package<T>,
+    vname: &str,
+    source: &Path,
+    matcher: F
+) -> Result<(), WriteError>
+{
+    pack_file_count(package, vname, source, &matcher, &mut |name, size| {
+        log_progress("Writing", name, size)
+    })?;
+    Ok(())
+}
+
+fn pack_file_count<T: Write + Seek, F: Fn(&Path) -> bool, P: FnMut(&str, u64)>(
+    package: &mut Package<T>,
+    vname: &str,
+    source: &Path,
+    matcher: &F,
+    progress: &mut P
+) -> Result<usize, WriteError>
+{
+    let md = source_metadata(source)?;
+    if md.file_type().is_symlink() {
+        let target = read_link(source)?;
+        pack_with_metadata(package, vname, &target[..], &md)?;
+        progress(vname, target.len() as u64);
+        return Ok(1);
+    }
     if md.is_file() {
-        #[cfg(feature = "debug-log")]
-        println!("Writing file {} with {} byte(s)", vname, md.len());
         let mut fle = File::open(source)?;
-        package.pack(vname, &mut fle)?;
-    } else {
-        let entries = read_dir(source)?;
-        for rentry in entries {
-            let entry = rentry?;
-            let mut s = String::from(vname);
-            s.push('/');
-            s.push_str(&get_name_from_dir_entry(&entry)?);
-            pack_file_vname(package, &s, &entry.path())?;
+        pack_with_metadata(package, vname, &mut fle, &md)?;
+        progress(vname, md.len());
+        return Ok(1);
+    }
+    let mut count = 0;
+    let entries = read_dir(source)?;
+    for rentry in entries {
+        let entry = rentry?;
+        let path = entry.path();
+        if !matcher(&path) {
+            continue;
+        }
+        let mut s = String::from(vname);
+        s.push('/');
+        s.push_str(&get_name_from_dir_entry(&entry)?);
+        count += pack_file_count(package, &s, &path, matcher, progress)?;
+    }
+    if count == 0 {
+        //Record the empty directory so that unpack can re-create it
+        let name = format!("{}{}", vname, DIRECTORY_MARKER);
+        package.pack(&name, std::io::empty())?;
+        progress(&name, 0);
+        count = 1;
+    }
+    Ok(count)
+}
+
+fn total_file_size(source: &Path) -> Result<u64, WriteError>
+{
+    let md = source_metadata(source)?;
+    if md.file_type().is_symlink() {
+        return Ok(read_link(source)?.len() as u64);
+    }
+    if md.is_file() {
+        return Ok(md.len());
+    }
+    let mut total = 0;
+    for rentry in read_dir(source)? {
+        total += total_file_size(&rentry?.path())?;
+    }
+    Ok(total)
+}
+
+/// Packs a file or folder in a BPXP, automatically computing the virtual name from the
+/// source path file name, and reports progress after each packed object.
+///
+/// The total number of bytes is computed by walking the source directory before packing.
+/// Empty directories are reported as objects of 0 bytes.
+///
+/// # Arguments
+///
+/// * `package`: the [Package](crate::package::Package) to use.
+/// * `source`: the source [Path](std::path::Path) to pack.
+/// * `progress`: the function to call after each object is packed.
+///
+/// returns: Result<(), Error>
+///
+/// # Errors
+///
+/// A [WriteError](crate::package::error::WriteError) is returned if some objects could not be packed.
+///
+/// # Examples
+///
+/// ```
+/// use bpx::package::{utils::pack_file_with_progress, Builder, Package};
+/// use bpx::utils::new_byte_buf;
+///
+/// let root = std::env::temp_dir().join(format!("bpx_doc_progress_{}", std::process::id()));
+/// std::fs::create_dir_all(&root).unwrap();
+/// std::fs::write(root.join("file.txt"), b"content").unwrap();
+/// let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+/// let mut events = Vec::new();
+/// pack_file_with_progress(&mut package, &root, |event| {
+///     events.push((event.name.to_string(), event.bytes, event.total))
+/// })
+/// .unwrap();
+/// assert_eq!(events.len(), 1);
+/// assert_eq!(events[0].1, 7);
+/// assert_eq!(events[0].2, 7);
+/// std::fs::remove_dir_all(&root).unwrap();
+/// ```
+pub fn pack_file_with_progress<T: Write + Seek, P: FnMut(ProgressEvent)>(
+    package: &mut Package<T>,
+    source: &Path,
+    mut progress: P
+) -> Result<(), WriteError>
+{
+    let vname = get_name_from_path(source)?;
+    let total = total_file_size(source)?;
+    let mut bytes = 0;
+    pack_file_count(package, vname, source, &|_| true, &mut |name, size| {
+        bytes += size;
+        progress(ProgressEvent {
+            name,
+            size,
+            bytes,
+            total
+        })
+    })?;
+    Ok(())
+}
+
+/// The number of bytes of each file compressed by
+/// [estimate_size](crate::package::Package::estimate_size) to estimate its compression ratio.
+const ESTIMATE_SAMPLE_SIZE: u64 = 64 * 1024;
+
+/// The result of a dry-run pack, as returned by
+/// [estimate_size](crate::package::Package::estimate_size).
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct PackEstimate
+{
+    /// The number of objects which would be packed.
+    pub objects: usize,
+
+    /// The total size in bytes of all objects before compression.
+    pub size: u64,
+
+    /// A rough estimate of the total size in bytes of all objects after compression.
+    pub compressed_size: u64,
+
+    /// The number of bytes the object names would use in the string section.
+    pub name_bytes: u64
+}
+
+impl PackEstimate
+{
+    /// Returns a rough estimate of the number of bytes added to the package by data
+    /// sections, the object table and object names.
+    pub fn total(&self) -> u64
+    {
+        self.compressed_size + self.name_bytes + (self.objects * SIZE_OBJECT_HEADER) as u64
+    }
+
+    fn add(&mut self, vname: &str, size: u64, compressed_size: u64)
+    {
+        self.objects += 1;
+        self.size += size;
+        self.compressed_size += compressed_size;
+        //Names are stored null-terminated
+        self.name_bytes += vname.len() as u64 + 1;
+    }
+}
+
+fn estimate_compressed_size<R: Read>(
+    mut options: SectionOptions,
+    source: R,
+    size: u64
+) -> Result<u64, WriteError>
+{
+    if CompressionKind::from_flags(options.header.flags) == CompressionKind::None || size == 0 {
+        return Ok(size);
+    }
+    let mut sample = Vec::new();
+    source.take(ESTIMATE_SAMPLE_SIZE).read_to_end(&mut sample)?;
+    if sample.is_empty() {
+        return Ok(size);
+    }
+    //The sample is smaller than the section so it must always be compressed
+    options.threshold = Some(0);
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = container.create_section(options);
+    container
+        .get_mut(handle)
+        .open()
+        .ok_or(WriteError::SectionNotLoaded)?
+        .write_all(&sample)?;
+    container.save()?;
+    let csize = container.get(handle).csize as u64;
+    Ok(size.saturating_mul(csize) / sample.len() as u64)
+}
+
+fn estimate_file<F: Fn(&Path) -> bool>(
+    header: SectionOptions,
+    vname: &str,
+    source: &Path,
+    matcher: &F,
+    estimate: &mut PackEstimate
+) -> Result<usize, WriteError>
+{
+    let md = source_metadata(source)?;
+    if md.file_type().is_symlink() {
+        let size = read_link(source)?.len() as u64;
+        estimate.add(vname, size, size);
+        return Ok(1);
+    }
+    if md.is_file() {
+        let compressed_size = estimate_compressed_size(header, File::open(source)?, md.len())?;
+        estimate.add(vname, md.len(), compressed_size);
+        return Ok(1);
+    }
+    let mut count = 0;
+    for rentry in read_dir(source)? {
+        let entry = rentry?;
+        let path = entry.path();
+        if !matcher(&path) {
+            continue;
+        }
+        let mut s = String::from(vname);
+        s.push('/');
+        s.push_str(&get_name_from_dir_entry(&entry)?);
+        count += estimate_file(header, &s, &path, matcher, estimate)?;
+    }
+    if count == 0 {
+        estimate.add(&format!("{}{}", vname, DIRECTORY_MARKER), 0, 0);
+        count = 1;
+    }
+    Ok(count)
+}
+
+impl<T> Package<T>
+{
+    /// Estimates the size of a file or folder once packed, without writing anything.
+    ///
+    /// The source is walked like [pack_file](crate::package::utils::pack_file) would. The
+    /// compressed size of each file is extrapolated from the compressed size of its first
+    /// 64KB, using the [data_header](crate::package::Builder::data_header) of this package.
+    ///
+    /// # Arguments
+    ///
+    /// * `source`: the source [Path](std::path::Path) to estimate.
+    ///
+    /// returns: Result<PackEstimate, WriteError>
+    ///
+    /// # Errors
+    ///
+    /// A [WriteError](crate::package::error::WriteError) is returned if the source could not
+    /// be read or if a sample failed to compress.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::package::{Builder, Package};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let root = std::env::temp_dir().join(format!("bpx_doc_estimate_{}", std::process::id()));
+    /// std::fs::create_dir_all(&root).unwrap();
+    /// std::fs::write(root.join("file.txt"), b"content").unwrap();
+    /// let package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    /// let estimate = package.estimate_size(&root).unwrap();
+    /// assert_eq!(estimate.objects, 1);
+    /// assert_eq!(estimate.size, 7);
+    /// std::fs::remove_dir_all(&root).unwrap();
+    /// ```
+    pub fn estimate_size(&self, source: &Path) -> Result<PackEstimate, WriteError>
+    {
+        let vname = get_name_from_path(source)?;
+        let mut estimate = PackEstimate::default();
+        estimate_file(
+            self.settings().data_header,
+            vname,
+            source,
+            &|_| true,
+            &mut estimate
+        )?;
+        Ok(estimate)
+    }
+}
+
+/// The maximum amount of file data read ahead by
+/// [pack_file_parallel](crate::package::utils::pack_file_parallel) before packing.
+#[cfg(feature = "multithreading")]
+const PARALLEL_READ_SIZE: u64 = 64 * 1024 * 1024;
+
+#[cfg(feature = "multithreading")]
+struct Entry
+{
+    vname: String,
+    path: Option<PathBuf>,
+    size: u64,
+    metadata: Option<Metadata>
+}
+
+#[cfg(feature = "multithreading")]
+fn collect_entries(vname: &str, source: &Path, entries: &mut Vec<Entry>) -> Result<(), WriteError>
+{
+    let md = source_metadata(source)?;
+    if md.is_file() || md.file_type().is_symlink() {
+        entries.push(Entry {
+            vname: vname.into(),
+            path: Some(source.into()),
+            size: md.len(),
+            metadata: Some(md)
+        });
+        return Ok(());
+    }
+    let mut children = Vec::new();
+    for rentry in read_dir(source)? {
+        let entry = rentry?;
+        children.push((get_name_from_dir_entry(&entry)?.to_string(), entry.path()));
+    }
+    //The order of read_dir is platform dependent
+    children.sort();
+    let len = entries.len();
+    for (name, path) in children {
+        collect_entries(&format!("{}/{}", vname, name), &path, entries)?;
+    }
+    if entries.len() == len {
+        //Record the empty directory so that unpack can re-create it
+        entries.push(Entry {
+            vname: format!("{}{}", vname, DIRECTORY_MARKER),
+            path: None,
+            size: 0,
+            metadata: None
+        });
+    }
+    Ok(())
+}
+
+/// Packs a file or folder in a BPXP with the given virtual name, reading files in parallel.
+///
+/// The directory tree is walked first, then file contents are read ahead in parallel on the
+/// current [rayon](https://docs.rs/rayon) thread pool and packed in the order of the walk.
+/// Directory entries are sorted by name so that the order of objects does not depend on
+/// the platform or on thread scheduling.
+///
+/// *Data sections are compressed when saving: use
+/// [save_parallel](crate::package::Package::save_parallel) to also compress them in
+/// parallel.*
+///
+/// # Arguments
+///
+/// * `package`: the [Package](crate::package::Package) to use.
+/// * `vname`: the virtual name for the root source path.
+/// * `source`: the source [Path](std::path::Path) to pack.
+///
+/// returns: Result<(), Error>
+///
+/// # Errors
+///
+/// A [WriteError](crate::package::error::WriteError) is returned if some objects could not be packed.
+#[cfg(feature = "multithreading")]
+pub fn pack_file_parallel<T: Write + Seek>(
+    package: &mut Package<T>,
+    vname: &str,
+    source: &Path
+) -> Result<(), WriteError>
+{
+    use rayon::prelude::*;
+    let mut entries = Vec::new();
+    collect_entries(vname, source, &mut entries)?;
+    let mut start = 0;
+    while start < entries.len() {
+        let mut end = start + 1;
+        let mut size = entries[start].size;
+        while end < entries.len() && size + entries[end].size <= PARALLEL_READ_SIZE {
+            size += entries[end].size;
+            end += 1;
         }
+        let contents: Vec<std::io::Result<Vec<u8>>> = entries[start..end]
+            .par_iter()
+            .map(|v| match (&v.path, &v.metadata) {
+                (Some(path), Some(md)) if md.file_type().is_symlink() => read_link(path),
+                (Some(path), _) => std::fs::read(path),
+                (None, _) => Ok(Vec::new())
+            })
+            .collect();
+        for (entry, data) in entries[start..end].iter().zip(contents) {
+            let data = data?;
+            match &entry.metadata {
+                Some(md) => pack_with_metadata(package, &entry.vname, &data[..], md)?,
+                None => package.pack(&entry.vname, &data[..])?
+            }
+            log_progress("Writing", &entry.vname, data.len() as u64);
+        }
+        start = end;
     }
     Ok(())
 }
@@ -108,8 +733,78 @@ pub fn pack_file<T: Write + Seek>(package: &mut Package<T>, source: &Path)
     pack_file_vname(package, str, source)
 }
 
+/// Converts the name of an object to a path relative to the unpack target.
+///
+/// Backslashes are treated as separators, leading separators and drive prefixes are stripped
+/// and `.` components are dropped.
+fn safe_relative_path(name: &str) -> Result<PathBuf, ReadError>
+{
+    let normalized = name.replace('\\', "/");
+    let mut rest = normalized.as_str();
+    let bytes = rest.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        rest = &rest[2..];
+    }
+    let mut path = PathBuf::new();
+    for component in rest.split('/') {
+        match component {
+            "" | "." => (),
+            ".." => return Err(ReadError::UnsafePath(name.into())),
+            v => {
+                //Reject anything the platform would not treat as a plain file name
+                let mut components = Path::new(v).components();
+                match (components.next(), components.next()) {
+                    (Some(Component::Normal(_)), None) => path.push(v),
+                    _ => return Err(ReadError::UnsafePath(name.into()))
+                }
+            }
+        }
+    }
+    if path.as_os_str().is_empty() {
+        return Err(ReadError::UnsafePath(name.into()));
+    }
+    Ok(path)
+}
+
+/// Creates the parent directories of an object to unpack, refusing to go through symbolic
+/// links so that a previously unpacked link cannot redirect objects outside of the target.
+fn prepare_destination(target: &Path, relative: &Path, name: &str) -> Result<PathBuf, ReadError>
+{
+    std::fs::create_dir_all(target)?;
+    let mut dest = target.to_path_buf();
+    let mut components = relative.components().peekable();
+    while let Some(component) = components.next() {
+        dest.push(component);
+        if components.peek().is_none() {
+            break;
+        }
+        match symlink_metadata(&dest) {
+            Ok(md) if md.file_type().is_symlink() => {
+                return Err(ReadError::UnsafePath(name.into()))
+            },
+            Ok(_) => (),
+            Err(_) => std::fs::create_dir_all(&dest)?
+        }
+    }
+    //Replace an existing link instead of writing through it
+    if let Ok(md) = symlink_metadata(&dest) {
+        if md.file_type().is_symlink() {
+            std::fs::remove_file(&dest)?;
+        }
+    }
+    Ok(dest)
+}
+
 /// Unpacks a BPXP.
 ///
+/// Objects sharing the same name are handled according to the
+/// [DuplicatePolicy](crate::package::DuplicatePolicy) of the package. Unix permissions
+/// recorded in the package are restored on Unix platforms.
+///
+/// Object names are sanitized: `\\` is treated as a separator and leading separators and drive
+/// prefixes are stripped. Objects whose name contains a `..` component or whose path goes
+/// through a previously unpacked symbolic link are rejected.
+///
 /// **This function prints some information to standard output as a way
 /// to debug a broken or incorrectly packed BPXP unless the `debug-log`
 /// feature is disabled.**
@@ -126,25 +821,329 @@ pub fn pack_file<T: Write + Seek>(package: &mut Package<T>, source: &Path)
 /// An [ReadError](crate::package::error::ReadError) is returned if some objects could not be unpacked.
 pub fn unpack<T: Read + Seek>(package: &mut Package<T>, target: &Path) -> Result<(), ReadError>
 {
+    unpack_with_progress(package, target, |event| {
+        log_progress("Reading", event.name, event.size)
+    })
+}
+
+/// The result of a selective unpack, as returned by [unpack_filtered](self::unpack_filtered).
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct UnpackSummary
+{
+    /// The number of objects written to the target directory.
+    pub extracted: usize,
+
+    /// The number of objects which were not written, either because they were rejected by
+    /// the filter or because of the [DuplicatePolicy](crate::package::DuplicatePolicy) of
+    /// the package.
+    pub skipped: usize
+}
+
+/// Unpacks a BPXP and reports progress after each object.
+///
+/// The total number of bytes is the sum of the sizes of all objects in the package. Objects
+/// skipped because of the [DuplicatePolicy](crate::package::DuplicatePolicy) of the package
+/// are still reported so that the last event always reaches the total.
+///
+/// # Arguments
+///
+/// * `package`: the [Package](crate::package::Package) to use.
+/// * `target`: the target [Path](std::path::Path) to extract the content to.
+/// * `progress`: the function to call after each object is unpacked.
+///
+/// returns: Result<(), Error>
+///
+/// # Errors
+///
+/// An [ReadError](crate::package::error::ReadError) is returned if some objects could not be unpacked.
+pub fn unpack_with_progress<T: Read + Seek, P: FnMut(ProgressEvent)>(
+    package: &mut Package<T>,
+    target: &Path,
+    progress: P
+) -> Result<(), ReadError>
+{
+    unpack_objects(package, target, |_| true, progress)?;
+    Ok(())
+}
+
+/// Unpacks the objects of a BPXP whose name is accepted by a filter.
+///
+/// Objects are otherwise handled exactly like [unpack](self::unpack) does.
+///
+/// **This function prints some information to standard output as a way
+/// to debug a broken or incorrectly packed BPXP unless the `debug-log`
+/// feature is disabled. Only extracted objects are printed.**
+///
+/// # Arguments
+///
+/// * `package`: the [Package](crate::package::Package) to use.
+/// * `target`: the target [Path](std::path::Path) to extract the content to.
+/// * `filter`: a function returning true if the object with the given name should be extracted.
+///
+/// returns: Result<UnpackSummary, Error>
+///
+/// # Errors
+///
+/// An [ReadError](crate::package::error::ReadError) is returned if some objects could not be unpacked.
+pub fn unpack_filtered<T: Read + Seek, F: FnMut(&str) -> bool>(
+    package: &mut Package<T>,
+    target: &Path,
+    filter: F
+) -> Result<UnpackSummary, ReadError>
+{
+    unpack_objects(package, target, filter, |event| {
+        log_progress("Reading", event.name, event.size)
+    })
+}
+
+/// Unpacks the objects of a BPXP whose name matches a glob pattern.
+///
+/// In the pattern, `*` matches any sequence of characters except `/` and `**` matches any
+/// sequence of characters including `/`; a `**/` prefix may also match no directory at all.
+/// All other characters must match exactly.
+///
+/// **This function prints some information to standard output as a way
+/// to debug a broken or incorrectly packed BPXP unless the `debug-log`
+/// feature is disabled. Only extracted objects are printed.**
+///
+/// # Arguments
+///
+/// * `package`: the [Package](crate::package::Package) to use.
+/// * `target`: the target [Path](std::path::Path) to extract the content to.
+/// * `pattern`: the glob pattern object names must match.
+///
+/// returns: Result<UnpackSummary, Error>
+///
+/// # Errors
+///
+/// An [ReadError](crate::package::error::ReadError) is returned if some objects could not be unpacked.
+pub fn unpack_matching<T: Read + Seek>(
+    package: &mut Package<T>,
+    target: &Path,
+    pattern: &str
+) -> Result<UnpackSummary, ReadError>
+{
+    unpack_filtered(package, target, |name| {
+        glob_match(pattern.as_bytes(), name.as_bytes())
+    })
+}
+
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool
+{
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => match rest.strip_prefix(b"*") {
+            Some(rest) => {
+                if let Some(after) = rest.strip_prefix(b"/") {
+                    if glob_match(after, name) {
+                        return true;
+                    }
+                }
+                (0..=name.len()).any(|i| glob_match(rest, &name[i..]))
+            },
+            None => (0..=name.len())
+                .take_while(|&i| i == 0 || name[i - 1] != b'/')
+                .any(|i| glob_match(rest, &name[i..]))
+        },
+        Some((c, rest)) => name.first() == Some(c) && glob_match(rest, &name[1..])
+    }
+}
+
+fn unpack_objects<T: Read + Seek, F: FnMut(&str) -> bool, P: FnMut(ProgressEvent)>(
+    package: &mut Package<T>,
+    target: &Path,
+    mut filter: F,
+    mut progress: P
+) -> Result<UnpackSummary, ReadError>
+{
+    let policy = package.get_duplicate_policy();
+    let duplicates = package.duplicates()?;
+    if policy == DuplicatePolicy::Error {
+        if let Some(name) = duplicates.first() {
+            return Err(ReadError::DuplicateObject(name.clone()));
+        }
+    }
+    let mut selected = Vec::new();
+    let mut total = 0;
+    for mut v in package.objects()? {
+        let accept = filter(v.load_name()?);
+        if accept {
+            total += v.size();
+        }
+        selected.push(accept);
+    }
+    let mut summary = UnpackSummary::default();
+    let mut bytes = 0;
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for (mut v, accept) in package.objects()?.zip(selected) {
+        if !accept {
+            summary.skipped += 1;
+            continue;
+        }
+        let size = v.size();
+        let mut path = v.load_name()?.to_string();
+        if path.is_empty() {
+            return Err(ReadError::BlankString);
+        }
+        bytes += size;
+        if duplicates.contains(&path) && !path.ends_with(DIRECTORY_MARKER) {
+            let count = seen.entry(path.clone()).or_insert(0);
+            *count += 1;
+            if *count > 1 {
+                match policy {
+                    DuplicatePolicy::FirstWins => {
+                        summary.skipped += 1;
+                        progress(ProgressEvent {
+                            name: &path,
+                            size,
+                            bytes,
+                            total
+                        });
+                        continue;
+                    },
+                    DuplicatePolicy::KeepBoth => path = format!("{}.{}", path, *count - 1),
+                    _ => ()
+                }
+            }
+        }
+        let relative = safe_relative_path(&path)?;
+        if path.ends_with(DIRECTORY_MARKER) {
+            prepare_destination(target, &relative, &path)?;
+            std::fs::create_dir_all(target.join(relative))?;
+        } else if v.is_symlink() {
+            let dest = prepare_destination(target, &relative, &path)?;
+            let mut target = Vec::new();
+            let s = v.unpack(&mut target)?;
+            if size != s {
+                return Err(ReadError::Eos(EosContext::Object));
+            }
+            create_symlink(&target, &dest)?;
+        } else {
+            let dest = prepare_destination(target, &relative, &path)?;
+            let mut f = File::create(&dest)?;
+            let s = v.unpack(&mut f)?;
+            if size != s {
+                return Err(ReadError::Eos(EosContext::Object));
+            }
+            if let Some(mtime) = v.mtime() {
+                apply_mtime(&f, mtime)?;
+            }
+            if let Some(mode) = v.mode() {
+                apply_mode(&dest, mode)?;
+            }
+        }
+        summary.extracted += 1;
+        progress(ProgressEvent {
+            name: &path,
+            size,
+            bytes,
+            total
+        });
+    }
+    Ok(summary)
+}
+
+/// The compression method to use for entries of an exported zip archive.
+#[cfg(feature = "zip")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ZipCompression
+{
+    /// Store objects without compression.
+    Stored,
+
+    /// Compress objects with the deflate algorithm.
+    Deflated
+}
+
+/// Exports all objects of a BPXP to a zip archive.
+///
+/// Each object is written to an entry named after its virtual name; objects are streamed one
+/// at a time. Empty directories recorded in the package are exported as directory entries.
+///
+/// # Arguments
+///
+/// * `package`: the [Package](crate::package::Package) to export.
+/// * `out`: the [Write](std::io::Write) to write the zip archive to.
+/// * `compression`: the [ZipCompression](self::ZipCompression) to use for zip entries.
+///
+/// returns: Result<(), Error>
+///
+/// # Errors
+///
+/// A [ReadError](crate::package::error::ReadError) is returned if some objects could not be
+/// unpacked or if the zip archive could not be written.
+#[cfg(feature = "zip")]
+pub fn export_zip<T: Read + Seek, W: Write + Seek>(
+    package: &mut Package<T>,
+    out: W,
+    compression: ZipCompression
+) -> Result<(), ReadError>
+{
+    use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+    let options = FileOptions::default()
+        .compression_method(match compression {
+            ZipCompression::Stored => CompressionMethod::Stored,
+            ZipCompression::Deflated => CompressionMethod::Deflated
+        })
+        .large_file(false);
+    let mut zip = ZipWriter::new(out);
     for mut v in package.objects()? {
         let size = v.size();
         let path = v.load_name()?;
         if path.is_empty() {
             return Err(ReadError::BlankString);
         }
-        #[cfg(feature = "debug-log")]
-        println!("Reading {} with {} byte(s)...", path, size);
-        let mut dest = PathBuf::new();
-        dest.push(target);
-        dest.push(Path::new(path));
-        if let Some(v) = dest.parent() {
-            std::fs::create_dir_all(v)?;
+        if path.ends_with(DIRECTORY_MARKER) {
+            zip.add_directory(path, options)?;
+            continue;
         }
-        let f = File::create(dest)?;
-        let s = v.unpack(f)?;
+        zip.start_file(path, options.large_file(size >= u32::MAX as u64))?;
+        let s = v.unpack(&mut zip)?;
         if size != s {
             return Err(ReadError::Eos(EosContext::Object));
         }
     }
+    zip.finish()?;
+    Ok(())
+}
+
+/// Imports all entries of a zip archive in a BPXP.
+///
+/// Each file is packed as an object named after its path in the archive; entries are
+/// streamed one at a time. Directory entries without any other entry inside are packed as
+/// empty directories.
+///
+/// # Arguments
+///
+/// * `package`: the [Package](crate::package::Package) to pack the entries in.
+/// * `input`: the [Read](std::io::Read) to read the zip archive from.
+///
+/// returns: Result<(), Error>
+///
+/// # Errors
+///
+/// A [WriteError](crate::package::error::WriteError) is returned if the zip archive could
+/// not be read or if some objects could not be packed.
+#[cfg(feature = "zip")]
+pub fn import_zip<T: Write + Seek, R: Read + Seek>(
+    package: &mut Package<T>,
+    input: R
+) -> Result<(), WriteError>
+{
+    let mut zip = zip::ZipArchive::new(input)?;
+    let names: Vec<String> = zip.file_names().map(String::from).collect();
+    for i in 0..zip.len() {
+        let entry = zip.by_index(i)?;
+        let name = entry.name().to_string();
+        if entry.is_dir() {
+            let dir = name.trim_end_matches(DIRECTORY_MARKER);
+            let prefix = format!("{}{}", dir, DIRECTORY_MARKER);
+            if names.iter().all(|v| v == &name || !v.starts_with(&prefix)) {
+                package.pack(&prefix, std::io::empty())?;
+            }
+            continue;
+        }
+        package.pack(&name, entry)?;
+    }
     Ok(())
 }
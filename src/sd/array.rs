@@ -87,15 +87,12 @@ impl Array
     }
 
     /// Removes a value from the array.
+    /// Returns None if pos is out of bounds.
     ///
     /// # Arguments
     ///
     /// * `pos`: the position of the item in the array to remove.
     ///
-    /// # Panics
-    ///
-    /// Panics if pos is out of bounds.
-    ///
     /// # Examples
     ///
     /// ```
@@ -109,7 +106,7 @@ impl Array
     /// ```
     pub fn remove_at(&mut self, pos: usize) -> Option<Value>
     {
-        if pos > self.data.len() {
+        if pos >= self.data.len() {
             None
         } else {
             Some(self.data.remove(pos))
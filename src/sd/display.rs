@@ -0,0 +1,126 @@
+// Copyright (c) 2021, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+//! Human-readable representation of BPXSD values.
+
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter}
+};
+
+use crate::{
+    sd::{Array, Object, Value},
+    utils::hash
+};
+
+/// Formats a property hash which has no known name.
+fn format_hash(hash: u64) -> String
+{
+    format!("#{:016X}", hash)
+}
+
+/// Returns the properties of an object sorted by name.
+///
+/// Names are taken from the `__debug__` symbol list of the object when available, otherwise
+/// the property hash is formatted with [format_hash]. The `__debug__` property itself is
+/// not returned.
+pub fn named_props(obj: &Object) -> Vec<(String, &Value)>
+{
+    let debug = hash("__debug__");
+    let mut symbols = HashMap::new();
+    if let Some(Value::Array(arr)) = obj.raw_get(debug) {
+        for v in arr.iter() {
+            if let Value::String(name) = v {
+                symbols.insert(hash(name), name.as_str());
+            }
+        }
+    }
+    let mut props: Vec<(String, &Value)> = obj
+        .iter()
+        .filter(|(k, _)| *k != debug)
+        .map(|(k, v)| match symbols.get(&k) {
+            Some(name) => (String::from(*name), v),
+            None => (format_hash(k), v)
+        })
+        .collect();
+    props.sort_by(|(a, _), (b, _)| a.cmp(b));
+    props
+}
+
+impl Display for Value
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Uint8(v) => write!(f, "{}u8", v),
+            Value::Uint16(v) => write!(f, "{}u16", v),
+            Value::Uint32(v) => write!(f, "{}u32", v),
+            Value::Uint64(v) => write!(f, "{}u64", v),
+            Value::Int8(v) => write!(f, "{}i8", v),
+            Value::Int16(v) => write!(f, "{}i16", v),
+            Value::Int32(v) => write!(f, "{}i32", v),
+            Value::Int64(v) => write!(f, "{}i64", v),
+            Value::Float(v) => write!(f, "{:?}f32", v),
+            Value::Double(v) => write!(f, "{:?}f64", v),
+            Value::String(v) => write!(f, "{:?}", v),
+            Value::Array(v) => write!(f, "{}", v),
+            Value::Object(v) => write!(f, "{}", v)
+        }
+    }
+}
+
+impl Display for Array
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        f.write_str("[")?;
+        for (i, v) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", v)?;
+        }
+        f.write_str("]")
+    }
+}
+
+impl Display for Object
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        f.write_str("{")?;
+        for (i, (name, v)) in named_props(self).into_iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}: {}", name, v)?;
+        }
+        f.write_str("}")
+    }
+}
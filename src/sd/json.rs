@@ -0,0 +1,503 @@
+// Copyright (c) 2021, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+//! Provides JSON export and import for [Object](crate::sd::Object).
+//!
+//! *This is only available when the `json` cargo feature is enabled.*
+//!
+//! BPXSD values map onto JSON as follows:
+//! - [Null](crate::sd::Value::Null), booleans and strings map to the matching JSON value,
+//! - integers are JSON numbers, except 64 bits integers whose magnitude is above 2^53 which
+//!   are written as a decimal string as they cannot be represented exactly by most JSON
+//!   implementations,
+//! - finite floats are JSON numbers, NaN and infinities are `null`,
+//! - [Array](crate::sd::Array) is a JSON array,
+//! - [Object](crate::sd::Object) is a JSON object whose keys are sorted. Property names are
+//!   taken from the `__debug__` symbol list (see [Debugger](crate::sd::Debugger)), properties
+//!   without a known name use their hash formatted as `#` followed by 16 hexadecimal digits.
+//!
+//! When importing:
+//! - JSON integers become [Uint64](crate::sd::Value::Uint64) when positive or
+//!   [Int64](crate::sd::Value::Int64) when negative, other numbers become
+//!   [Double](crate::sd::Value::Double),
+//! - strings made only of an optional `-` and decimal digits whose magnitude is above 2^53
+//!   become [Uint64](crate::sd::Value::Uint64) or [Int64](crate::sd::Value::Int64) when they
+//!   fit, other strings are kept as is,
+//! - keys formatted as a hash are set with [raw_set](crate::sd::Object::raw_set), other keys
+//!   are hashed and recorded in the `__debug__` symbol list of the object so that the names
+//!   are preserved when exporting again.
+
+use std::fmt::{Display, Formatter, Write};
+
+use crate::sd::{display::named_props, Array, Object, Value};
+
+/// The largest integer magnitude written as a JSON number (2^53).
+pub const MAX_SAFE_INTEGER: u64 = 1 << 53;
+
+/// The maximum nesting level of arrays and objects accepted when importing.
+pub const MAX_DEPTH: usize = 128;
+
+/// Represents a JSON import error.
+#[derive(Debug)]
+pub enum Error
+{
+    /// Describes an unexpected end of input.
+    Eof,
+
+    /// Describes an unexpected character.
+    ///
+    /// # Arguments
+    /// * the byte offset of the character in the input.
+    Syntax(usize),
+
+    /// Describes an invalid escape sequence in a string.
+    ///
+    /// # Arguments
+    /// * the byte offset of the escape sequence in the input.
+    Escape(usize),
+
+    /// Describes arrays or objects nested deeper than [MAX_DEPTH](crate::sd::json::MAX_DEPTH).
+    Depth,
+
+    /// Describes a root value which is not a JSON object.
+    NotAnObject
+}
+
+impl Display for Error
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self {
+            Error::Eof => f.write_str("unexpected end of input"),
+            Error::Syntax(pos) => write!(f, "unexpected character at offset {}", pos),
+            Error::Escape(pos) => write!(f, "invalid escape sequence at offset {}", pos),
+            Error::Depth => write!(f, "maximum nesting depth exceeded ({})", MAX_DEPTH),
+            Error::NotAnObject => f.write_str("root value is not an object")
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str)
+{
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            },
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+}
+
+fn write_value(out: &mut String, value: &Value)
+{
+    // Writing to a String never fails.
+    let _ = match value {
+        Value::Null => write!(out, "null"),
+        Value::Bool(v) => write!(out, "{}", v),
+        Value::Uint8(v) => write!(out, "{}", v),
+        Value::Uint16(v) => write!(out, "{}", v),
+        Value::Uint32(v) => write!(out, "{}", v),
+        Value::Uint64(v) if *v > MAX_SAFE_INTEGER => write!(out, "\"{}\"", v),
+        Value::Uint64(v) => write!(out, "{}", v),
+        Value::Int8(v) => write!(out, "{}", v),
+        Value::Int16(v) => write!(out, "{}", v),
+        Value::Int32(v) => write!(out, "{}", v),
+        Value::Int64(v) if v.unsigned_abs() > MAX_SAFE_INTEGER => write!(out, "\"{}\"", v),
+        Value::Int64(v) => write!(out, "{}", v),
+        Value::Float(v) if v.is_finite() => write!(out, "{:?}", v),
+        Value::Double(v) if v.is_finite() => write!(out, "{:?}", v),
+        Value::Float(_) | Value::Double(_) => write!(out, "null"),
+        Value::String(v) => {
+            write_string(out, v);
+            Ok(())
+        },
+        Value::Array(v) => {
+            out.push('[');
+            for (i, v) in v.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, v);
+            }
+            out.push(']');
+            Ok(())
+        },
+        Value::Object(v) => {
+            write_object(out, v);
+            Ok(())
+        }
+    };
+}
+
+fn write_object(out: &mut String, obj: &Object)
+{
+    out.push('{');
+    for (i, (name, v)) in named_props(obj).into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_string(out, &name);
+        out.push(':');
+        write_value(out, v);
+    }
+    out.push('}');
+}
+
+fn parse_hash(key: &str) -> Option<u64>
+{
+    let hex = key.strip_prefix('#')?;
+    if hex.len() != 16 || !hex.bytes().all(|v| v.is_ascii_hexdigit()) {
+        return None;
+    }
+    u64::from_str_radix(hex, 16).ok()
+}
+
+fn parse_big_integer(s: &str) -> Option<Value>
+{
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s)
+    };
+    if digits.is_empty() || !digits.bytes().all(|v| v.is_ascii_digit()) {
+        return None;
+    }
+    if negative {
+        let v: i64 = s.parse().ok()?;
+        (v.unsigned_abs() > MAX_SAFE_INTEGER).then_some(Value::Int64(v))
+    } else {
+        let v: u64 = digits.parse().ok()?;
+        (v > MAX_SAFE_INTEGER).then_some(Value::Uint64(v))
+    }
+}
+
+struct Parser<'a>
+{
+    input: &'a str,
+    pos: usize
+}
+
+impl<'a> Parser<'a>
+{
+    fn skip_whitespace(&mut self)
+    {
+        let bytes = self.input.as_bytes();
+        while self.pos < bytes.len() && matches!(bytes[self.pos], b' ' | b'\t' | b'\n' | b'\r') {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Result<u8, Error>
+    {
+        self.skip_whitespace();
+        self.input
+            .as_bytes()
+            .get(self.pos)
+            .copied()
+            .ok_or(Error::Eof)
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), Error>
+    {
+        if self.peek()? != c {
+            return Err(Error::Syntax(self.pos));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, Error>
+    {
+        let rest = &self.input[self.pos..];
+        if !rest.starts_with(word) {
+            return match word.starts_with(rest) {
+                true => Err(Error::Eof),
+                false => Err(Error::Syntax(self.pos))
+            };
+        }
+        self.pos += word.len();
+        Ok(value)
+    }
+
+    fn hex4(&mut self, start: usize) -> Result<u32, Error>
+    {
+        let hex = self.input.get(self.pos..self.pos + 4).ok_or(Error::Eof)?;
+        if !hex.bytes().all(|v| v.is_ascii_hexdigit()) {
+            return Err(Error::Escape(start));
+        }
+        self.pos += 4;
+        u32::from_str_radix(hex, 16).map_err(|_| Error::Escape(start))
+    }
+
+    fn string(&mut self) -> Result<String, Error>
+    {
+        self.expect(b'"')?;
+        let mut s = String::new();
+        loop {
+            let rest = &self.input[self.pos..];
+            let end = rest.find(['"', '\\']).ok_or(Error::Eof)?;
+            if let Some(pos) = rest[..end].find(|c: char| (c as u32) < 0x20) {
+                return Err(Error::Syntax(self.pos + pos));
+            }
+            s.push_str(&rest[..end]);
+            self.pos += end + 1;
+            if rest.as_bytes()[end] == b'"' {
+                return Ok(s);
+            }
+            let start = self.pos - 1;
+            let c = *self.input.as_bytes().get(self.pos).ok_or(Error::Eof)?;
+            self.pos += 1;
+            match c {
+                b'"' => s.push('"'),
+                b'\\' => s.push('\\'),
+                b'/' => s.push('/'),
+                b'b' => s.push('\u{8}'),
+                b'f' => s.push('\u{c}'),
+                b'n' => s.push('\n'),
+                b'r' => s.push('\r'),
+                b't' => s.push('\t'),
+                b'u' => {
+                    let mut code = self.hex4(start)?;
+                    if (0xD800..0xDC00).contains(&code) {
+                        if !self.input[self.pos..].starts_with("\\u") {
+                            return Err(Error::Escape(start));
+                        }
+                        self.pos += 2;
+                        let low = self.hex4(start)?;
+                        if !(0xDC00..0xE000).contains(&low) {
+                            return Err(Error::Escape(start));
+                        }
+                        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                    }
+                    s.push(char::from_u32(code).ok_or(Error::Escape(start))?);
+                },
+                _ => return Err(Error::Escape(start))
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, Error>
+    {
+        let start = self.pos;
+        let bytes = self.input.as_bytes();
+        let mut integer = true;
+        if bytes.get(self.pos) == Some(&b'-') {
+            self.pos += 1;
+        }
+        let digits = |pos: &mut usize| {
+            let begin = *pos;
+            while *pos < bytes.len() && bytes[*pos].is_ascii_digit() {
+                *pos += 1;
+            }
+            *pos > begin
+        };
+        if !digits(&mut self.pos) {
+            return Err(Error::Syntax(self.pos));
+        }
+        if bytes.get(self.pos) == Some(&b'.') {
+            integer = false;
+            self.pos += 1;
+            if !digits(&mut self.pos) {
+                return Err(Error::Syntax(self.pos));
+            }
+        }
+        if matches!(bytes.get(self.pos), Some(b'e' | b'E')) {
+            integer = false;
+            self.pos += 1;
+            if matches!(bytes.get(self.pos), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if !digits(&mut self.pos) {
+                return Err(Error::Syntax(self.pos));
+            }
+        }
+        let s = &self.input[start..self.pos];
+        if integer {
+            if let Ok(v) = s.parse::<u64>() {
+                return Ok(Value::Uint64(v));
+            }
+            if let Ok(v) = s.parse::<i64>() {
+                return Ok(Value::Int64(v));
+            }
+        }
+        s.parse::<f64>()
+            .map(Value::Double)
+            .map_err(|_| Error::Syntax(start))
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Array, Error>
+    {
+        self.expect(b'[')?;
+        let mut arr = Array::new();
+        if self.peek()? == b']' {
+            self.pos += 1;
+            return Ok(arr);
+        }
+        loop {
+            arr.add(self.value(depth)?);
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b']' => {
+                    self.pos += 1;
+                    return Ok(arr);
+                },
+                _ => return Err(Error::Syntax(self.pos))
+            }
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Object, Error>
+    {
+        self.expect(b'{')?;
+        let mut obj = Object::new();
+        let mut names = Array::new();
+        if self.peek()? == b'}' {
+            self.pos += 1;
+            return Ok(obj);
+        }
+        loop {
+            let key = self.string()?;
+            self.expect(b':')?;
+            let value = self.value(depth)?;
+            match parse_hash(&key) {
+                Some(hash) => obj.raw_set(hash, value),
+                None => {
+                    obj.set(&key, value);
+                    names.add(key.into());
+                }
+            }
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b'}' => {
+                    self.pos += 1;
+                    break;
+                },
+                _ => return Err(Error::Syntax(self.pos))
+            }
+        }
+        if !names.is_empty() {
+            obj.set("__debug__", names.into());
+        }
+        Ok(obj)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error>
+    {
+        if depth >= MAX_DEPTH {
+            return Err(Error::Depth);
+        }
+        match self.peek()? {
+            b'n' => self.keyword("null", Value::Null),
+            b't' => self.keyword("true", Value::Bool(true)),
+            b'f' => self.keyword("false", Value::Bool(false)),
+            b'"' => {
+                let s = self.string()?;
+                Ok(parse_big_integer(&s).unwrap_or(Value::String(s)))
+            },
+            b'[' => self.array(depth + 1).map(Value::Array),
+            b'{' => self.object(depth + 1).map(Value::Object),
+            b'-' | b'0'..=b'9' => self.number(),
+            _ => Err(Error::Syntax(self.pos))
+        }
+    }
+}
+
+impl Object
+{
+    /// Exports this object as a JSON string.
+    ///
+    /// See the [json](crate::sd::json) module for the conventions used to map BPXSD values
+    /// to JSON.
+    ///
+    /// returns: String
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::sd::Debugger;
+    /// use bpx::sd::Object;
+    ///
+    /// let mut obj = Debugger::attach(Object::new()).unwrap();
+    /// obj.set("Test", 12u8.into());
+    /// obj.set("Big", u64::MAX.into());
+    /// let json = obj.detach().to_json_string();
+    /// assert_eq!(json, "{\"Big\":\"18446744073709551615\",\"Test\":12}");
+    /// ```
+    pub fn to_json_string(&self) -> String
+    {
+        let mut out = String::new();
+        write_object(&mut out, self);
+        out
+    }
+
+    /// Imports an object from a JSON string.
+    ///
+    /// See the [json](crate::sd::json) module for the conventions used to map JSON to BPXSD
+    /// values.
+    ///
+    /// # Arguments
+    ///
+    /// * `s`: the JSON string to parse.
+    ///
+    /// returns: Result<Object, Error>
+    ///
+    /// # Errors
+    ///
+    /// An [Error](crate::sd::json::Error) is returned if the string is not valid JSON or
+    /// its root value is not an object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::sd::Object;
+    ///
+    /// let obj = Object::from_json_str("{\"props\": {\"vsync\": true, \"fps\": 60}}").unwrap();
+    /// assert_eq!(obj.get_path("props.vsync").unwrap().as_bool(), Some(true));
+    /// assert_eq!(obj.get_path("props.fps").unwrap().as_u64(), Some(60));
+    /// assert!(Object::from_json_str("[1, 2]").is_err());
+    /// ```
+    pub fn from_json_str(s: &str) -> Result<Object, Error>
+    {
+        let mut parser = Parser { input: s, pos: 0 };
+        if parser.peek()? != b'{' {
+            return Err(Error::NotAnObject);
+        }
+        let obj = parser.object(1)?;
+        parser.skip_whitespace();
+        if parser.pos < s.len() {
+            return Err(Error::Syntax(parser.pos));
+        }
+        Ok(obj)
+    }
+}
@@ -31,6 +31,7 @@
 mod array;
 pub mod debug;
 mod decoder;
+mod display;
 mod encoder;
 pub mod error;
 pub mod object;
@@ -43,3 +44,6 @@ pub use value::Value;
 
 #[cfg(feature = "serde")]
 pub mod serde;
+
+#[cfg(feature = "json")]
+pub mod json;
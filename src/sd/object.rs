@@ -181,6 +181,40 @@ impl Object
         self.raw_get(utils::hash(name))
     }
 
+    /// Gets a property in a nested object by its dotted path.
+    /// Returns None if a property along the path does not exist or is not an object.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the names of the properties to walk, separated by '.'.
+    ///
+    /// returns: Option<&Value>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::sd::Object;
+    ///
+    /// let mut graphics = Object::new();
+    /// graphics.set("vsync", true.into());
+    /// let mut props = Object::new();
+    /// props.set("graphics", graphics.into());
+    /// let mut obj = Object::new();
+    /// obj.set("props", props.into());
+    /// assert_eq!(obj.get_path("props.graphics.vsync").unwrap().as_bool(), Some(true));
+    /// assert!(obj.get_path("props.audio.volume").is_none());
+    /// assert!(obj.get_path("props.graphics.vsync.value").is_none());
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<&Value>
+    {
+        let mut names = path.split('.');
+        let mut value = self.get(names.next()?)?;
+        for name in names {
+            value = value.as_object()?.get(name)?;
+        }
+        Some(value)
+    }
+
     /// Returns the number of properties in the object.
     pub fn len(&self) -> usize
     {
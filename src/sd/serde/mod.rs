@@ -29,15 +29,28 @@
 //! Provides support for `serde` based serialization and deserialization for [Value](crate::sd::Value).
 //!
 //! *This is only available when the `serde` cargo feature is enabled.*
+//!
+//! Rust types map onto BPXSD as follows:
+//! - unit and `None` are [Null](crate::sd::Value::Null), `Some(v)` is the value of `v`,
+//! - booleans, integers up to 64 bits, floats and strings map to the matching
+//!   [Value](crate::sd::Value) variant, 128 bits integers are not supported,
+//! - sequences and tuples are [Array](crate::sd::Array),
+//! - structs are [Object](crate::sd::Object) with one property per field,
+//! - maps are arrays of objects with a `__key__` and a `__value__` property,
+//! - unit enum variants are the variant index as an unsigned integer sized according to
+//!   [EnumSize](crate::sd::serde::EnumSize),
+//! - newtype and tuple enum variants are arrays starting with the variant index,
+//! - struct enum variants are objects with one property per field and a `__variant__`
+//!   property holding the variant index.
 
 mod deserialize;
 mod serialize;
 
 use std::fmt::{Display, Formatter};
 
-use serde::ser::StdError;
+use serde::{de::DeserializeOwned, ser::StdError, Serialize};
 
-use crate::sd::error::TypeError;
+use crate::sd::{error::TypeError, Object};
 
 /// The size of an enum variant index when serializing/deserializing Rust enums.
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -121,3 +134,158 @@ impl StdError for Error {}
 
 pub use deserialize::Deserializer;
 pub use serialize::Serializer;
+
+/// Serializes a Rust value into a BPXSD [Object](crate::sd::Object).
+///
+/// *Enum variant indices are encoded with a single byte.*
+///
+/// # Arguments
+///
+/// * `value`: the value to serialize.
+///
+/// returns: Result<Object, Error>
+///
+/// # Errors
+///
+/// An [Error](crate::sd::serde::Error) is returned if the value contains an unsupported type
+/// or if it does not serialize to an [Object](crate::sd::Object), for example when `value` is
+/// an integer.
+///
+/// # Examples
+///
+/// ```
+/// use bpx::sd::serde::{from_object, to_object};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// struct Config
+/// {
+///     name: String,
+///     level: u8
+/// }
+///
+/// let config = Config { name: "test".into(), level: 3 };
+/// let obj = to_object(&config).unwrap();
+/// let level: u8 = obj.get("level").unwrap().try_into().unwrap();
+/// assert_eq!(level, 3);
+/// assert_eq!(from_object::<Config>(&obj).unwrap(), config);
+/// ```
+pub fn to_object<T: Serialize>(value: &T) -> Result<Object, Error>
+{
+    let value = value.serialize(Serializer::new(EnumSize::U8, false))?;
+    let obj = Object::try_from(value)?;
+    Ok(obj)
+}
+
+/// Deserializes a Rust value from a BPXSD [Object](crate::sd::Object).
+///
+/// *Enum variant indices are expected to be encoded with a single byte, as done by
+/// [to_object](crate::sd::serde::to_object).*
+///
+/// # Arguments
+///
+/// * `obj`: the object to deserialize.
+///
+/// returns: Result<T, Error>
+///
+/// # Errors
+///
+/// An [Error](crate::sd::serde::Error) is returned if the object does not match the shape of
+/// `T`.
+pub fn from_object<T: DeserializeOwned>(obj: &Object) -> Result<T, Error>
+{
+    T::deserialize(Deserializer::new(EnumSize::U8, obj.clone()))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Deserialize, Serialize, PartialEq, Debug)]
+    enum Shape
+    {
+        Point,
+        Circle(f32),
+        Rect
+        {
+            width: u32,
+            height: u32
+        }
+    }
+
+    #[derive(Deserialize, Serialize, PartialEq, Debug)]
+    struct Layer
+    {
+        name: String,
+        visible: bool,
+        opacity: Option<f64>,
+        shapes: Vec<Shape>
+    }
+
+    #[derive(Deserialize, Serialize, PartialEq, Debug)]
+    struct Document
+    {
+        id: u64,
+        offset: i32,
+        title: Option<String>,
+        layers: Vec<Layer>,
+        tags: HashMap<u32, String>
+    }
+
+    #[test]
+    fn object_round_trip()
+    {
+        let doc = Document {
+            id: 42,
+            offset: -12,
+            title: None,
+            layers: vec![
+                Layer {
+                    name: "background".into(),
+                    visible: true,
+                    opacity: Some(0.5),
+                    shapes: vec![Shape::Point, Shape::Circle(2.0)]
+                },
+                Layer {
+                    name: "foreground".into(),
+                    visible: false,
+                    opacity: None,
+                    shapes: vec![Shape::Rect {
+                        width: 4,
+                        height: 3
+                    }]
+                },
+            ],
+            tags: HashMap::from([(1, "one".into()), (2, "two".into())])
+        };
+        let obj = to_object(&doc).unwrap();
+        let id: u64 = obj.get("id").unwrap().try_into().unwrap();
+        assert_eq!(id, 42);
+        let doc1: Document = from_object(&obj).unwrap();
+        assert_eq!(doc1, doc);
+    }
+
+    #[test]
+    fn object_errors()
+    {
+        #[derive(Serialize)]
+        struct Big
+        {
+            value: u128
+        }
+        assert!(matches!(
+            to_object(&Big { value: 1 }),
+            Err(Error::Message(_))
+        ));
+        assert!(matches!(to_object(&42u32), Err(Error::TypeMismatch(_))));
+        assert!(matches!(
+            from_object::<Layer>(&Object::new()),
+            Err(Error::MissingStructKey("name"))
+        ));
+    }
+}
@@ -113,6 +113,134 @@ impl Value
             Value::Object(_) => "object"
         }
     }
+
+    /// Returns the boolean stored in this value, None if this value is not a bool.
+    pub fn as_bool(&self) -> Option<bool>
+    {
+        match self {
+            Value::Bool(v) => Some(*v),
+            _ => None
+        }
+    }
+
+    /// Returns the integer stored in this value as an i64.
+    ///
+    /// Signed integers and unsigned integers up to 32 bits are widened losslessly. An unsigned
+    /// 64 bits integer is only returned if it is not greater than [i64::MAX]. Returns None if
+    /// this value is not an integer or does not fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::sd::Value;
+    ///
+    /// assert_eq!(Value::from(42u8).as_i64(), Some(42));
+    /// assert_eq!(Value::from(-1i16).as_i64(), Some(-1));
+    /// assert_eq!(Value::from(u64::MAX).as_i64(), None);
+    /// assert_eq!(Value::from(1.0).as_i64(), None);
+    /// ```
+    pub fn as_i64(&self) -> Option<i64>
+    {
+        match self {
+            Value::Uint8(v) => Some(*v as i64),
+            Value::Uint16(v) => Some(*v as i64),
+            Value::Uint32(v) => Some(*v as i64),
+            Value::Uint64(v) => i64::try_from(*v).ok(),
+            Value::Int8(v) => Some(*v as i64),
+            Value::Int16(v) => Some(*v as i64),
+            Value::Int32(v) => Some(*v as i64),
+            Value::Int64(v) => Some(*v),
+            _ => None
+        }
+    }
+
+    /// Returns the integer stored in this value as an u64.
+    ///
+    /// Unsigned integers are widened losslessly. A signed integer is only returned if it is not
+    /// negative. Returns None if this value is not an integer or does not fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::sd::Value;
+    ///
+    /// assert_eq!(Value::from(u64::MAX).as_u64(), Some(u64::MAX));
+    /// assert_eq!(Value::from(12i32).as_u64(), Some(12));
+    /// assert_eq!(Value::from(-1i8).as_u64(), None);
+    /// ```
+    pub fn as_u64(&self) -> Option<u64>
+    {
+        match self {
+            Value::Uint8(v) => Some(*v as u64),
+            Value::Uint16(v) => Some(*v as u64),
+            Value::Uint32(v) => Some(*v as u64),
+            Value::Uint64(v) => Some(*v),
+            Value::Int8(v) => u64::try_from(*v).ok(),
+            Value::Int16(v) => u64::try_from(*v).ok(),
+            Value::Int32(v) => u64::try_from(*v).ok(),
+            Value::Int64(v) => u64::try_from(*v).ok(),
+            _ => None
+        }
+    }
+
+    /// Returns the number stored in this value as an f64.
+    ///
+    /// Floats and integers up to 32 bits are widened losslessly. 64 bits integers are not
+    /// converted as they may not be represented exactly. Returns None if this value is not a
+    /// float or an integer up to 32 bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::sd::Value;
+    ///
+    /// assert_eq!(Value::from(0.5f32).as_f64(), Some(0.5));
+    /// assert_eq!(Value::from(-3i32).as_f64(), Some(-3.0));
+    /// assert_eq!(Value::from(3u64).as_f64(), None);
+    /// ```
+    pub fn as_f64(&self) -> Option<f64>
+    {
+        match self {
+            Value::Float(v) => Some(*v as f64),
+            Value::Double(v) => Some(*v),
+            Value::Uint8(v) => Some(*v as f64),
+            Value::Uint16(v) => Some(*v as f64),
+            Value::Uint32(v) => Some(*v as f64),
+            Value::Int8(v) => Some(*v as f64),
+            Value::Int16(v) => Some(*v as f64),
+            Value::Int32(v) => Some(*v as f64),
+            _ => None
+        }
+    }
+
+    /// Returns the string stored in this value, None if this value is not a string.
+    pub fn as_str(&self) -> Option<&str>
+    {
+        match self {
+            Value::String(v) => Some(v),
+            _ => None
+        }
+    }
+
+    /// Returns the [Array](crate::sd::Array) stored in this value, None if this value is not
+    /// an array.
+    pub fn as_array(&self) -> Option<&Array>
+    {
+        match self {
+            Value::Array(v) => Some(v),
+            _ => None
+        }
+    }
+
+    /// Returns the [Object](crate::sd::Object) stored in this value, None if this value is not
+    /// an object.
+    pub fn as_object(&self) -> Option<&Object>
+    {
+        match self {
+            Value::Object(v) => Some(v),
+            _ => None
+        }
+    }
 }
 
 impl_err_conversion!(
@@ -26,7 +26,22 @@
 // NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::shader::{Target, Type};
+use crate::{
+    core::{builder::SectionOptions, header::SECTION_TYPE_STRING},
+    shader::{
+        encoder::{
+            default_extended_data_header,
+            default_shader_header,
+            default_string_header,
+            default_symbol_table_header
+        },
+        Target,
+        Type,
+        SECTION_TYPE_EXTENDED_DATA,
+        SECTION_TYPE_SHADER,
+        SECTION_TYPE_SYMBOL_TABLE
+    }
+};
 
 /// The required settings to create a new BPXS.
 ///
@@ -41,7 +56,23 @@ pub struct Settings
     pub target: Target,
 
     /// The type of the shader package (Assembly or Pipeline).
-    pub ty: Type
+    pub ty: Type,
+
+    /// The header settings of shader sections.
+    pub shader_header: SectionOptions,
+
+    /// The header settings of the symbol table section.
+    pub symbol_table_header: SectionOptions,
+
+    /// The header settings of the string section.
+    pub string_header: SectionOptions,
+
+    /// The header settings of the extended data section.
+    pub extended_data_header: SectionOptions,
+
+    /// True to replace the shader of a stage when adding a second shader for the same stage,
+    /// false to return an error instead.
+    pub replace_stages: bool
 }
 
 /// Utility to simplify generation of [Settings](crate::shader::Settings) required when creating a new BPXS.
@@ -67,7 +98,12 @@ impl Builder
             settings: Settings {
                 assembly_hash: 0,
                 target: Target::Any,
-                ty: Type::Pipeline
+                ty: Type::Pipeline,
+                shader_header: default_shader_header(),
+                symbol_table_header: default_symbol_table_header(),
+                string_header: default_string_header(),
+                extended_data_header: default_extended_data_header(),
+                replace_stages: false
             }
         }
     }
@@ -117,6 +153,111 @@ impl Builder
         self
     }
 
+    /// Defines the header to use when creating shader sections.
+    ///
+    /// *By default, shader sections use xz compression with a CRC32 checksum.*
+    ///
+    /// *The type byte is always replaced by
+    /// [SECTION_TYPE_SHADER](crate::shader::SECTION_TYPE_SHADER) and the size is always
+    /// replaced by the size of each shader.*
+    ///
+    /// # Arguments
+    ///
+    /// * `header`: the section header to use.
+    ///
+    /// returns: ShaderPackBuilder
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::builder::{Checksum, SectionHeaderBuilder};
+    /// use bpx::shader::{Builder, SECTION_TYPE_SHADER};
+    ///
+    /// let settings = Builder::new()
+    ///     .shader_header(SectionHeaderBuilder::new().ty(42).checksum(Checksum::Weak))
+    ///     .build();
+    /// assert_eq!(settings.shader_header.header.ty, SECTION_TYPE_SHADER);
+    /// ```
+    pub fn shader_header<H: Into<SectionOptions>>(mut self, header: H) -> Self
+    {
+        self.settings.shader_header = header.into();
+        self.settings.shader_header.header.ty = SECTION_TYPE_SHADER;
+        self
+    }
+
+    /// Defines the header to use when creating the symbol table section.
+    ///
+    /// *By default, the symbol table uses zlib compression with a weak checksum.*
+    ///
+    /// *The type byte is always replaced by
+    /// [SECTION_TYPE_SYMBOL_TABLE](crate::shader::SECTION_TYPE_SYMBOL_TABLE).*
+    ///
+    /// # Arguments
+    ///
+    /// * `header`: the section header to use.
+    ///
+    /// returns: ShaderPackBuilder
+    pub fn symbol_table_header<H: Into<SectionOptions>>(mut self, header: H) -> Self
+    {
+        self.settings.symbol_table_header = header.into();
+        self.settings.symbol_table_header.header.ty = SECTION_TYPE_SYMBOL_TABLE;
+        self
+    }
+
+    /// Defines the header to use when creating the string section.
+    ///
+    /// *By default, the string section uses zlib compression with a weak checksum.*
+    ///
+    /// *The type byte is always replaced by
+    /// [SECTION_TYPE_STRING](crate::core::header::SECTION_TYPE_STRING).*
+    ///
+    /// # Arguments
+    ///
+    /// * `header`: the section header to use.
+    ///
+    /// returns: ShaderPackBuilder
+    pub fn string_header<H: Into<SectionOptions>>(mut self, header: H) -> Self
+    {
+        self.settings.string_header = header.into();
+        self.settings.string_header.header.ty = SECTION_TYPE_STRING;
+        self
+    }
+
+    /// Defines the header to use when creating the extended data section.
+    ///
+    /// *By default, the extended data section uses zlib compression with a CRC32 checksum.*
+    ///
+    /// *The type byte is always replaced by
+    /// [SECTION_TYPE_EXTENDED_DATA](crate::shader::SECTION_TYPE_EXTENDED_DATA).*
+    ///
+    /// # Arguments
+    ///
+    /// * `header`: the section header to use.
+    ///
+    /// returns: ShaderPackBuilder
+    pub fn extended_data_header<H: Into<SectionOptions>>(mut self, header: H) -> Self
+    {
+        self.settings.extended_data_header = header.into();
+        self.settings.extended_data_header.header.ty = SECTION_TYPE_EXTENDED_DATA;
+        self
+    }
+
+    /// Defines what happens when adding a second shader for the same stage.
+    ///
+    /// *By default, adding a second shader for the same stage returns a
+    /// [DuplicateStage](crate::shader::error::WriteError::DuplicateStage) error.*
+    ///
+    /// # Arguments
+    ///
+    /// * `replace`: true to replace the existing shader, false to return an error.
+    ///
+    /// returns: ShaderPackBuilder
+    pub fn replace_stages(mut self, replace: bool) -> Self
+    {
+        self.settings.replace_stages = replace;
+        self
+    }
+
     /// Returns the built settings.
     pub fn build(&self) -> Settings
     {
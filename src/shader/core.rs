@@ -27,6 +27,7 @@
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use std::{
+    collections::HashMap,
     io::{Read, Seek, SeekFrom, Write},
     ops::Deref,
     slice::Iter
@@ -36,16 +37,23 @@ use byteorder::{ByteOrder, LittleEndian};
 
 use crate::{
     core::{
-        builder::{Checksum, CompressionMethod, MainHeaderBuilder, SectionHeaderBuilder},
+        builder::MainHeaderBuilder,
         header::{Struct, SECTION_TYPE_STRING},
         Container,
         SectionData
     },
     sd::Object,
     shader::{
-        decoder::{get_stage_from_code, get_target_type_from_code, read_symbol_table},
-        encoder::get_type_ext,
-        error::{EosContext, ReadError, Section, WriteError},
+        decoder::{get_target_type_from_code, read_shader, read_stage, read_symbol_table},
+        encoder::{
+            default_extended_data_header,
+            default_shader_header,
+            default_string_header,
+            default_symbol_table_header,
+            get_stage_code,
+            get_type_ext
+        },
+        error::{ReadError, Section, WriteError},
         symbol::{Settings as SymbolSettings, Symbol, FLAG_EXTENDED_DATA},
         Settings,
         Shader,
@@ -57,6 +65,7 @@ use crate::{
         SECTION_TYPE_SYMBOL_TABLE,
         SUPPORTED_VERSION
     },
+    spec::bpxs,
     strings::{load_string_section, StringSection},
     table::ItemTable,
     utils::OptionExtension,
@@ -157,6 +166,20 @@ impl<'a, T> Iterator for SymbolIter<'a, T>
     }
 }
 
+/// Represents the shader of a stage loaded from a shader package.
+#[derive(Clone, Debug)]
+pub struct StageRef
+{
+    /// The shader stage.
+    pub stage: Stage,
+
+    /// A handle to the shader section.
+    pub handle: Handle,
+
+    /// The shader code.
+    pub code: Vec<u8>
+}
+
 /// A BPXS (ShaderPack).
 ///
 /// # Examples
@@ -196,9 +219,10 @@ pub struct ShaderPack<T>
     container: Container<T>,
     strings: StringSection,
     symbol_table: Handle,
-    symbols: Vec<Symbol>,
-    table: Option<ItemTable<Symbol>>,
+    symbols: ItemTable<Symbol>,
+    symbols_loaded: bool,
     extended_data: Option<Handle>,
+    stages: HashMap<Stage, Handle>,
     num_symbols: u16
 }
 
@@ -219,7 +243,12 @@ impl<T> ShaderPack<T>
     /// Returns the number of symbols contained in that BPX.
     pub fn get_symbol_count(&self) -> u16
     {
-        self.num_symbols
+        if self.symbols_loaded {
+            //The symbol table may have been edited through symbols_mut
+            self.symbols.len() as u16
+        } else {
+            self.num_symbols
+        }
     }
 
     /// Returns the hash of the shader assembly this pipeline is linked to.
@@ -233,6 +262,17 @@ impl<T> ShaderPack<T>
     {
         self.container
     }
+
+    fn patch_extended_data(&mut self)
+    {
+        self.num_symbols = self.get_symbol_count();
+        let mut header = *self.container.get_main_header();
+        LittleEndian::write_u16(
+            &mut header.type_ext[bpxs::TYPE_EXT_NUM_SYMBOLS_OFFSET..],
+            self.num_symbols
+        );
+        self.container.set_main_header(header);
+    }
 }
 
 impl<T: Write + Seek> ShaderPack<T>
@@ -263,31 +303,22 @@ impl<T: Write + Seek> ShaderPack<T>
         let mut container = Container::create(
             backend,
             MainHeaderBuilder::new()
-                .ty(b'S')
+                .ty(bpxs::TYPE_BYTE)
                 .type_ext(get_type_ext(&settings))
                 .version(SUPPORTED_VERSION)
         );
-        let string_section = container.create_section(
-            SectionHeaderBuilder::new()
-                .checksum(Checksum::Weak)
-                .compression(CompressionMethod::Zlib)
-                .ty(SECTION_TYPE_STRING)
-        );
-        let symbol_table = container.create_section(
-            SectionHeaderBuilder::new()
-                .checksum(Checksum::Weak)
-                .compression(CompressionMethod::Zlib)
-                .ty(SECTION_TYPE_SYMBOL_TABLE)
-        );
+        let string_section = container.create_section(settings.string_header);
+        let symbol_table = container.create_section(settings.symbol_table_header);
         let strings = StringSection::new(string_section);
         ShaderPack {
             container,
             strings,
             settings,
             symbol_table,
-            symbols: Vec::new(),
-            table: None,
+            symbols: ItemTable::empty(),
+            symbols_loaded: true,
             extended_data: None,
+            stages: HashMap::new(),
             num_symbols: 0
         }
     }
@@ -295,32 +326,33 @@ impl<T: Write + Seek> ShaderPack<T>
     fn write_extended_data(&mut self, extended_data: Option<Object>) -> Result<u32, WriteError>
     {
         if let Some(obj) = extended_data {
-            let handle = *self.extended_data.get_or_insert_with(|| {
-                self.container.create_section(
-                    SectionHeaderBuilder::new()
-                        .ty(SECTION_TYPE_EXTENDED_DATA)
-                        .checksum(Checksum::Crc32)
-                        .compression(CompressionMethod::Zlib)
-                )
-            });
+            let handle = match self.extended_data.or_else(|| {
+                self.container
+                    .find_section_by_type(SECTION_TYPE_EXTENDED_DATA)
+            }) {
+                Some(v) => v,
+                None => self
+                    .container
+                    .create_section(self.settings.extended_data_header)
+            };
+            self.extended_data = Some(handle);
             let mut section = self.container.get_mut(handle);
             let data = section.open().ok_or(WriteError::SectionNotLoaded)?;
-            let offset = data.size();
+            let offset = data.seek(SeekFrom::End(0))?;
             obj.write(data)?;
             return Ok(offset as u32);
         }
-        Ok(0xFFFFFF)
-    }
-
-    fn patch_extended_data(&mut self)
-    {
-        let mut header = *self.container.get_main_header();
-        LittleEndian::write_u16(&mut header.type_ext[8..10], self.num_symbols);
-        self.container.set_main_header(header);
+        Ok(bpxs::NO_EXTENDED_DATA)
     }
 
     /// Adds a symbol into this BPXS.
     ///
+    /// *Symbols without extended data do not use any space in the extended data section.*
+    ///
+    /// *When this shader package was opened from a backend, call
+    /// [symbols_mut](ShaderPack::symbols_mut) first to load the sections required to add
+    /// symbols; previously saved symbols are kept.*
+    ///
     /// # Arguments
     ///
     /// * `name`: The name of the symbols.
@@ -344,8 +376,12 @@ impl<T: Write + Seek> ShaderPack<T>
             ty: settings.ty,
             register: settings.register
         };
-        self.symbols.push(buf);
-        self.num_symbols += 1;
+        self.symbols
+            .push(&mut self.container, &mut self.strings, buf)
+            .map_err(crate::strings::WriteError::from)?;
+        if !self.symbols_loaded {
+            self.num_symbols += 1;
+        }
         self.patch_extended_data();
         Ok(())
     }
@@ -361,32 +397,65 @@ impl<T: Write + Seek> ShaderPack<T>
     /// # Errors
     ///
     /// A [WriteError](crate::shader::error::WriteError) is returned if the shader could not be
-    /// written.
+    /// written or if a shader already exists for the same stage, see
+    /// [add_stage](ShaderPack::add_stage).
     pub fn add_shader(&mut self, shader: Shader) -> Result<(), WriteError>
     {
-        let section = self.container.create_section(
-            SectionHeaderBuilder::new()
-                .ty(SECTION_TYPE_SHADER)
-                .checksum(Checksum::Crc32)
-                .compression(CompressionMethod::Xz)
-                .size(shader.data.len() as u32 + 1)
-        );
-        let mut section = self.container.get_mut(section);
-        let mut buf = shader.data;
-        match shader.stage {
-            Stage::Vertex => buf.insert(0, 0x0),
-            Stage::Hull => buf.insert(0, 0x1),
-            Stage::Domain => buf.insert(0, 0x2),
-            Stage::Geometry => buf.insert(0, 0x3),
-            Stage::Pixel => buf.insert(0, 0x4)
-        };
-        section
-            .open()
-            .ok_or(WriteError::SectionNotLoaded)?
-            .write_all(&buf)?;
+        self.add_stage(shader.stage, &shader.data)?;
         Ok(())
     }
 
+    /// Adds the shader of a stage into this BPXS and returns a handle to its section.
+    ///
+    /// *If a shader already exists for the same stage, it is replaced when
+    /// [replace_stages](crate::shader::Builder::replace_stages) is enabled.*
+    ///
+    /// # Arguments
+    ///
+    /// * `stage`: the shader stage.
+    /// * `code`: the shader code.
+    ///
+    /// returns: Result<Handle, Error>
+    ///
+    /// # Errors
+    ///
+    /// A [WriteError](crate::shader::error::WriteError) is returned if the shader could not be
+    /// written or if a shader already exists for the same stage and
+    /// [replace_stages](crate::shader::Builder::replace_stages) is disabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::shader::{Builder, ShaderPack, Stage};
+    /// use bpx::shader::error::WriteError;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxs = ShaderPack::create(new_byte_buf(0), Builder::new());
+    /// bpxs.add_stage(Stage::Vertex, b"vertex").unwrap();
+    /// let res = bpxs.add_stage(Stage::Vertex, b"other vertex");
+    /// assert!(matches!(res, Err(WriteError::DuplicateStage(Stage::Vertex))));
+    /// ```
+    pub fn add_stage(&mut self, stage: Stage, code: &[u8]) -> Result<Handle, WriteError>
+    {
+        if let Some(old) = self.stages.get(&stage) {
+            if !self.settings.replace_stages {
+                return Err(WriteError::DuplicateStage(stage));
+            }
+            self.container.remove_section(*old);
+        }
+        let mut header = self.settings.shader_header;
+        header.header.size = code.len() as u32 + 1;
+        let handle = self.container.create_section(header);
+        {
+            let mut section = self.container.get_mut(handle);
+            let data = section.open().ok_or(WriteError::SectionNotLoaded)?;
+            data.write_all(&[get_stage_code(stage)])?;
+            data.write_all(code)?;
+        }
+        self.stages.insert(stage, handle);
+        Ok(handle)
+    }
+
     /// Saves this shader package.
     ///
     /// # Errors
@@ -397,12 +466,24 @@ impl<T: Write + Seek> ShaderPack<T>
     {
         {
             let mut section = self.container.get_mut(self.symbol_table);
-            let data = section.open().ok_or(WriteError::SectionNotLoaded)?;
-            data.seek(SeekFrom::Start(0))?;
-            for v in &self.symbols {
-                v.write(data)?;
+            if self.symbols_loaded {
+                let data = section.open().ok_or(WriteError::SectionNotLoaded)?;
+                data.truncate(0)?;
+                for v in &self.symbols {
+                    v.write(data)?;
+                }
+            } else if !self.symbols.is_empty() {
+                //The symbol table was never read: only append new symbols
+                let data = section.open().ok_or(WriteError::SectionNotLoaded)?;
+                data.seek(SeekFrom::End(0))?;
+                for v in &self.symbols {
+                    v.write(data)?;
+                }
             }
         }
+        if self.num_symbols != self.get_symbol_count() {
+            self.patch_extended_data();
+        }
         self.container.save()?;
         Ok(())
     }
@@ -421,7 +502,10 @@ impl<T: Read + Seek> ShaderPack<T>
     /// # Errors
     ///
     /// A [ReadError](crate::shader::error::ReadError) is returned if some
-    /// sections/headers could not be loaded.
+    /// sections/headers could not be loaded or if a shader section has an unknown stage.
+    ///
+    /// *Only the stage byte of each shader section is read: shader code is not loaded until
+    /// requested.*
     ///
     /// # Examples
     ///
@@ -439,18 +523,22 @@ impl<T: Read + Seek> ShaderPack<T>
     /// ```
     pub fn open(backend: T) -> Result<ShaderPack<T>, ReadError>
     {
-        let container = Container::open(backend)?;
-        if container.get_main_header().ty != b'S' {
+        let mut container = Container::open(backend)?;
+        if container.get_main_header().ty != bpxs::TYPE_BYTE {
             return Err(ReadError::BadType(container.get_main_header().ty));
         }
         if container.get_main_header().version != SUPPORTED_VERSION {
             return Err(ReadError::BadVersion(container.get_main_header().version));
         }
-        let assembly_hash = LittleEndian::read_u64(&container.get_main_header().type_ext[0..8]);
-        let num_symbols = LittleEndian::read_u16(&container.get_main_header().type_ext[8..10]);
+        let assembly_hash = LittleEndian::read_u64(
+            &container.get_main_header().type_ext[bpxs::TYPE_EXT_ASSEMBLY_HASH_OFFSET..]
+        );
+        let num_symbols = LittleEndian::read_u16(
+            &container.get_main_header().type_ext[bpxs::TYPE_EXT_NUM_SYMBOLS_OFFSET..]
+        );
         let (target, ty) = get_target_type_from_code(
-            container.get_main_header().type_ext[10],
-            container.get_main_header().type_ext[11]
+            container.get_main_header().type_ext[bpxs::TYPE_EXT_TARGET_OFFSET],
+            container.get_main_header().type_ext[bpxs::TYPE_EXT_TYPE_OFFSET]
         )?;
         let string_section = match container.find_section_by_type(SECTION_TYPE_STRING) {
             Some(v) => v,
@@ -461,19 +549,36 @@ impl<T: Read + Seek> ShaderPack<T>
             None => return Err(ReadError::MissingSection(Section::SymbolTable))
         };
         let strings = StringSection::new(string_section);
+        let mut stages = HashMap::new();
+        let shaders: Vec<Handle> = container
+            .iter()
+            .filter(|v| v.ty == SECTION_TYPE_SHADER)
+            .map(|v| v.handle())
+            .collect();
+        for handle in shaders {
+            stages.insert(read_stage(&mut container, handle)?, handle);
+        }
+        //Encrypted shader sections can't be streamed and are loaded to read their stage
+        container.unload_type(SECTION_TYPE_SHADER);
         Ok(Self {
             settings: Settings {
                 assembly_hash,
                 target,
-                ty
+                ty,
+                shader_header: default_shader_header(),
+                symbol_table_header: default_symbol_table_header(),
+                string_header: default_string_header(),
+                extended_data_header: default_extended_data_header(),
+                replace_stages: false
             },
             num_symbols,
             symbol_table,
             strings,
             extended_data: None,
+            stages,
             container,
-            symbols: Vec::with_capacity(num_symbols as _),
-            table: None
+            symbols: ItemTable::new(Vec::new()),
+            symbols_loaded: false
         })
     }
 
@@ -485,15 +590,8 @@ impl<T: Read + Seek> ShaderPack<T>
     /// or if the symbol table is truncated.
     pub fn symbols(&mut self) -> Result<SymbolIter<T>, ReadError>
     {
-        let table = self.table.get_or_insert_with_err(|| {
-            read_symbol_table(
-                &mut self.container,
-                &mut self.symbols,
-                self.num_symbols,
-                self.symbol_table
-            )
-        })?;
-        let iter = table.iter();
+        self.load_symbols()?;
+        let iter = self.symbols.iter();
         Ok(SymbolIter {
             extended_data: &mut self.extended_data,
             container: &mut self.container,
@@ -502,6 +600,96 @@ impl<T: Read + Seek> ShaderPack<T>
         })
     }
 
+    fn load_symbols(&mut self) -> Result<(), ReadError>
+    {
+        if !self.symbols_loaded {
+            let pending: Vec<Symbol> = self.symbols.iter().copied().collect();
+            let mut symbols = read_symbol_table(
+                &mut self.container,
+                self.num_symbols - pending.len() as u16,
+                self.symbol_table
+            )?;
+            //Symbols added before the symbol table was read are appended after existing ones
+            symbols.extend(pending);
+            self.symbols = ItemTable::new(symbols);
+            self.symbols_loaded = true;
+        }
+        Ok(())
+    }
+
+    /// Gets the symbol table of this shader package for editing, with its lookup table built.
+    ///
+    /// *All sections are loaded so that this shader package can be saved after editing.*
+    ///
+    /// # Errors
+    ///
+    /// Returns a [ReadError](crate::shader::error::ReadError) if a section or the symbol names
+    /// couldn't be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::shader::{symbol, Builder, ShaderPack};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxs = ShaderPack::create(new_byte_buf(0), Builder::new());
+    /// bpxs.add_symbol(symbol::Builder::new("test").register(2)).unwrap();
+    /// let symbols = bpxs.symbols_mut().unwrap();
+    /// assert_eq!(symbols.try_lookup("test").unwrap().unwrap().register, 2);
+    /// ```
+    pub fn symbols_mut(&mut self) -> Result<&mut ItemTable<Symbol>, ReadError>
+    {
+        self.load_symbols()?;
+        for mut section in self.container.iter_mut() {
+            section.load()?;
+        }
+        self.symbols
+            .build_lookup_table(&mut self.container, &mut self.strings)?;
+        Ok(&mut self.symbols)
+    }
+
+    /// Removes a symbol from this shader package.
+    ///
+    /// Returns true if the symbol exists and was removed, false otherwise.
+    ///
+    /// *The extended data of the symbol is not removed from the extended data section.*
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the symbol to remove.
+    ///
+    /// returns: Result<bool, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [ReadError](crate::shader::error::ReadError) if a section or the symbol names
+    /// couldn't be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::shader::{symbol, Builder, ShaderPack};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxs = ShaderPack::create(new_byte_buf(0), Builder::new());
+    /// bpxs.add_symbol(symbol::Builder::new("test")).unwrap();
+    /// assert!(bpxs.remove_symbol("test").unwrap());
+    /// assert!(!bpxs.remove_symbol("test").unwrap());
+    /// assert_eq!(bpxs.get_symbol_count(), 0);
+    /// ```
+    pub fn remove_symbol(&mut self, name: &str) -> Result<bool, ReadError>
+    {
+        let removed = self
+            .symbols_mut()?
+            .remove_by_name(name)
+            .unwrap_or(None)
+            .is_some();
+        if removed {
+            self.patch_extended_data();
+        }
+        Ok(removed)
+    }
+
     /// Lists all shaders contained in this shader package.
     pub fn list_shaders(&self) -> Vec<Handle>
     {
@@ -530,13 +718,48 @@ impl<T: Read + Seek> ShaderPack<T>
     /// An [ReadError](crate::shader::error::ReadError) is returned if the shader could not be loaded.
     pub fn load_shader(&mut self, handle: Handle) -> Result<Shader, ReadError>
     {
-        let mut section = self.container.get_mut(handle);
-        if section.size < 1 {
-            //We must at least find a stage byte
-            return Err(ReadError::Eos(EosContext::Shader));
-        }
-        let mut buf = section.load()?.load_in_memory()?;
-        let stage = get_stage_from_code(buf.remove(0))?;
-        Ok(Shader { stage, data: buf })
+        read_shader(&mut self.container, handle)
+    }
+
+    /// Loads the shader of a stage into memory.
+    /// Returns None if this shader package has no shader for this stage.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage`: the shader stage.
+    ///
+    /// returns: Result<Option<StageRef>, ReadError>
+    ///
+    /// # Errors
+    ///
+    /// An [ReadError](crate::shader::error::ReadError) is returned if the shader could not be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::shader::{Builder, ShaderPack, Stage};
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut bpxs = ShaderPack::create(new_byte_buf(0), Builder::new());
+    /// bpxs.add_stage(Stage::Compute, b"compute").unwrap();
+    /// bpxs.save().unwrap();
+    /// let mut buf = bpxs.into_inner().into_inner();
+    /// buf.set_position(0);
+    /// let mut bpxs = ShaderPack::open(buf).unwrap();
+    /// assert_eq!(bpxs.stage(Stage::Compute).unwrap().unwrap().code, b"compute");
+    /// assert!(bpxs.stage(Stage::Pixel).unwrap().is_none());
+    /// ```
+    pub fn stage(&mut self, stage: Stage) -> Result<Option<StageRef>, ReadError>
+    {
+        let handle = match self.stages.get(&stage) {
+            Some(v) => *v,
+            None => return Ok(None)
+        };
+        let shader = read_shader(&mut self.container, handle)?;
+        Ok(Some(StageRef {
+            stage: shader.stage,
+            handle,
+            code: shader.data
+        }))
     }
 }
@@ -29,15 +29,16 @@
 use std::io::{Read, Seek};
 
 use crate::{
-    core::{header::Struct, Container},
+    core::{header::Struct, Container, SectionData},
     shader::{
         error::{EosContext, InvalidCodeContext, ReadError},
         symbol::{Symbol, SIZE_SYMBOL_STRUCTURE},
+        Shader,
         Stage,
         Target,
         Type
     },
-    table::ItemTable,
+    spec::bpxs,
     Handle
 };
 
@@ -67,10 +68,10 @@ pub fn get_target_type_from_code(acode: u8, tcode: u8) -> Result<(Target, Type),
         0xFF => target = Target::Any,
         _ => return Err(ReadError::InvalidCode(InvalidCodeContext::Target, acode))
     }
-    if tcode == b'A' {
+    if tcode == bpxs::TYPE_ASSEMBLY {
         //Rust refuses to parse match properly so use if/else-if blocks
         ty = Type::Assembly;
-    } else if tcode == b'P' {
+    } else if tcode == bpxs::TYPE_PIPELINE {
         ty = Type::Pipeline;
     } else {
         return Err(ReadError::InvalidCode(InvalidCodeContext::Type, tcode));
@@ -81,31 +82,82 @@ pub fn get_target_type_from_code(acode: u8, tcode: u8) -> Result<(Target, Type),
 pub fn get_stage_from_code(code: u8) -> Result<Stage, ReadError>
 {
     match code {
-        0x0 => Ok(Stage::Vertex),
-        0x1 => Ok(Stage::Hull),
-        0x2 => Ok(Stage::Domain),
-        0x3 => Ok(Stage::Geometry),
-        0x4 => Ok(Stage::Pixel),
+        bpxs::STAGE_VERTEX => Ok(Stage::Vertex),
+        bpxs::STAGE_HULL => Ok(Stage::Hull),
+        bpxs::STAGE_DOMAIN => Ok(Stage::Domain),
+        bpxs::STAGE_GEOMETRY => Ok(Stage::Geometry),
+        bpxs::STAGE_PIXEL => Ok(Stage::Pixel),
+        bpxs::STAGE_COMPUTE => Ok(Stage::Compute),
         _ => Err(ReadError::InvalidCode(InvalidCodeContext::Stage, code))
     }
 }
 
+pub fn read_stage<T: Read + Seek>(
+    container: &mut Container<T>,
+    handle: Handle
+) -> Result<Stage, ReadError>
+{
+    if container.get(handle).size < 1 {
+        //We must at least find a stage byte
+        return Err(ReadError::Eos(EosContext::Shader));
+    }
+    //Stream the stage byte so that the shader section is neither loaded nor marked modified
+    let mut reader = container.section_reader(handle)?;
+    let mut code: [u8; 1] = [0; 1];
+    reader.read_exact(&mut code)?;
+    get_stage_from_code(code[0])
+}
+
+pub fn read_shader<T: Read + Seek>(
+    container: &mut Container<T>,
+    handle: Handle
+) -> Result<Shader, ReadError>
+{
+    let mut section = container.get_mut(handle);
+    if section.size < 1 {
+        //We must at least find a stage byte
+        return Err(ReadError::Eos(EosContext::Shader));
+    }
+    let data = section.load()?;
+    let (stage, data) = match data.as_slice() {
+        Some(slice) => match slice.split_first() {
+            Some((stage, data)) => (*stage, data.to_vec()),
+            None => return Err(ReadError::Eos(EosContext::Shader))
+        },
+        None => {
+            let mut buf = data.load_in_memory()?;
+            (buf.remove(0), buf)
+        }
+    };
+    let stage = get_stage_from_code(stage)?;
+    Ok(Shader { stage, data })
+}
+
 pub fn read_symbol_table<T: Read + Seek>(
     container: &mut Container<T>,
-    symbols: &mut Vec<Symbol>,
     num_symbols: u16,
     symbol_table: Handle
-) -> Result<ItemTable<Symbol>, ReadError>
+) -> Result<Vec<Symbol>, ReadError>
 {
     let mut section = container.get_mut(symbol_table);
-    let count = section.size as u32 / SIZE_SYMBOL_STRUCTURE as u32;
+    let count = section.size / SIZE_SYMBOL_STRUCTURE as u32;
 
     if count != num_symbols as u32 {
         return Err(ReadError::Eos(EosContext::SymbolTable));
     }
-    for _ in 0..count {
-        let header = Symbol::read(section.load()?)?;
-        symbols.push(header);
+    let data = section.load()?;
+    let mut symbols = Vec::with_capacity(count as _);
+    match data.as_slice() {
+        Some(mut slice) => {
+            for _ in 0..count {
+                symbols.push(Symbol::read(&mut slice)?);
+            }
+        },
+        None => {
+            for _ in 0..count {
+                symbols.push(Symbol::read(&mut *data)?);
+            }
+        },
     }
-    Ok(ItemTable::new(symbols.clone()))
+    Ok(symbols)
 }
@@ -28,35 +28,102 @@
 
 use byteorder::{ByteOrder, LittleEndian};
 
-use crate::shader::{Settings, Target, Type};
+use crate::{
+    core::{
+        builder::{Checksum, CompressionMethod, SectionHeaderBuilder, SectionOptions},
+        header::SECTION_TYPE_STRING
+    },
+    shader::{
+        Settings,
+        Stage,
+        Target,
+        Type,
+        SECTION_TYPE_EXTENDED_DATA,
+        SECTION_TYPE_SHADER,
+        SECTION_TYPE_SYMBOL_TABLE
+    },
+    spec::{bpxs, core as bpx}
+};
+
+pub fn default_string_header() -> SectionOptions
+{
+    SectionHeaderBuilder::new()
+        .checksum(Checksum::Weak)
+        .compression(CompressionMethod::Zlib)
+        .ty(SECTION_TYPE_STRING)
+        .options()
+}
+
+pub fn default_symbol_table_header() -> SectionOptions
+{
+    SectionHeaderBuilder::new()
+        .checksum(Checksum::Weak)
+        .compression(CompressionMethod::Zlib)
+        .ty(SECTION_TYPE_SYMBOL_TABLE)
+        .options()
+}
+
+pub fn default_extended_data_header() -> SectionOptions
+{
+    SectionHeaderBuilder::new()
+        .ty(SECTION_TYPE_EXTENDED_DATA)
+        .checksum(Checksum::Crc32)
+        .compression(CompressionMethod::Zlib)
+        .options()
+}
+
+pub fn default_shader_header() -> SectionOptions
+{
+    SectionHeaderBuilder::new()
+        .ty(SECTION_TYPE_SHADER)
+        .checksum(Checksum::Crc32)
+        .compression(CompressionMethod::Xz)
+        .options()
+}
+
+pub fn get_stage_code(stage: Stage) -> u8
+{
+    match stage {
+        Stage::Vertex => bpxs::STAGE_VERTEX,
+        Stage::Hull => bpxs::STAGE_HULL,
+        Stage::Domain => bpxs::STAGE_DOMAIN,
+        Stage::Geometry => bpxs::STAGE_GEOMETRY,
+        Stage::Pixel => bpxs::STAGE_PIXEL,
+        Stage::Compute => bpxs::STAGE_COMPUTE
+    }
+}
 
 pub fn get_type_ext(settings: &Settings) -> [u8; 16]
 {
-    let mut type_ext: [u8; 16] = [0; 16];
+    let mut type_ext: [u8; 16] = [0; bpx::TYPE_EXT_SIZE];
+    let target = &mut type_ext[bpxs::TYPE_EXT_TARGET_OFFSET];
     match settings.target {
-        Target::DX11 => type_ext[10] = 0x1,
-        Target::DX12 => type_ext[10] = 0x2,
-        Target::GL33 => type_ext[10] = 0x3,
-        Target::GL40 => type_ext[10] = 0x4,
-        Target::GL41 => type_ext[10] = 0x5,
-        Target::GL42 => type_ext[10] = 0x6,
-        Target::GL43 => type_ext[10] = 0x7,
-        Target::GL44 => type_ext[10] = 0x8,
-        Target::GL45 => type_ext[10] = 0x9,
-        Target::GL46 => type_ext[10] = 0xA,
-        Target::ES30 => type_ext[10] = 0xB,
-        Target::ES31 => type_ext[10] = 0xC,
-        Target::ES32 => type_ext[10] = 0xD,
-        Target::VK10 => type_ext[10] = 0xE,
-        Target::VK11 => type_ext[10] = 0xF,
-        Target::VK12 => type_ext[10] = 0x10,
-        Target::MT => type_ext[10] = 0x11,
-        Target::Any => type_ext[10] = 0xFF
+        Target::DX11 => *target = 0x1,
+        Target::DX12 => *target = 0x2,
+        Target::GL33 => *target = 0x3,
+        Target::GL40 => *target = 0x4,
+        Target::GL41 => *target = 0x5,
+        Target::GL42 => *target = 0x6,
+        Target::GL43 => *target = 0x7,
+        Target::GL44 => *target = 0x8,
+        Target::GL45 => *target = 0x9,
+        Target::GL46 => *target = 0xA,
+        Target::ES30 => *target = 0xB,
+        Target::ES31 => *target = 0xC,
+        Target::ES32 => *target = 0xD,
+        Target::VK10 => *target = 0xE,
+        Target::VK11 => *target = 0xF,
+        Target::VK12 => *target = 0x10,
+        Target::MT => *target = 0x11,
+        Target::Any => *target = 0xFF
     };
     match settings.ty {
-        Type::Assembly => type_ext[11] = b'A',
-        Type::Pipeline => type_ext[11] = b'P'
+        Type::Assembly => type_ext[bpxs::TYPE_EXT_TYPE_OFFSET] = bpxs::TYPE_ASSEMBLY,
+        Type::Pipeline => type_ext[bpxs::TYPE_EXT_TYPE_OFFSET] = bpxs::TYPE_PIPELINE
     };
-    LittleEndian::write_u64(&mut type_ext[0..8], settings.assembly_hash);
+    LittleEndian::write_u64(
+        &mut type_ext[bpxs::TYPE_EXT_ASSEMBLY_HASH_OFFSET..],
+        settings.assembly_hash
+    );
     type_ext
 }
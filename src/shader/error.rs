@@ -100,7 +100,13 @@ variant_error!(
         Sd(crate::sd::error::WriteError),
 
         /// Indicates a section wasn't loaded.
-        SectionNotLoaded
+        SectionNotLoaded,
+
+        /// Indicates a shader already exists for a stage.
+        ///
+        /// # Arguments
+        /// * the duplicate stage.
+        DuplicateStage(crate::shader::Stage)
     }
 );
 
@@ -147,7 +153,8 @@ impl Display for WriteError
             WriteError::Io(e) => write!(f, "io error: {}", e),
             WriteError::Strings(e) => write!(f, "strings error: {}", e),
             WriteError::Sd(e) => write!(f, "BPXSD error: {}", e),
-            WriteError::SectionNotLoaded => f.write_str("section not loaded")
+            WriteError::SectionNotLoaded => f.write_str("section not loaded"),
+            WriteError::DuplicateStage(s) => write!(f, "duplicate shader for stage {:?}", s)
         }
     }
 }
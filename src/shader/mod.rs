@@ -40,16 +40,16 @@ pub use builder::*;
 pub use self::core::*;
 
 /// The supported BPX version for this shader variant decoder/encoder.
-pub const SUPPORTED_VERSION: u32 = 0x2;
+pub const SUPPORTED_VERSION: u32 = crate::spec::bpxs::VERSION;
 
 /// The standard type for a shader section in a BPX Shader Package (type S).
-pub const SECTION_TYPE_SHADER: u8 = 0x1;
+pub const SECTION_TYPE_SHADER: u8 = crate::core::reserved::SHADER_SHADER.section_type;
 
 /// The standard type for a symbol table section in a BPX Shader Package (type S).
-pub const SECTION_TYPE_SYMBOL_TABLE: u8 = 0x2;
+pub const SECTION_TYPE_SYMBOL_TABLE: u8 = crate::core::reserved::SHADER_SYMBOL_TABLE.section_type;
 
 /// The standard type for an extended data section in a BPX Shader Package (type S).
-pub const SECTION_TYPE_EXTENDED_DATA: u8 = 0x3;
+pub const SECTION_TYPE_EXTENDED_DATA: u8 = crate::core::reserved::SHADER_EXTENDED_DATA.section_type;
 
 /// Represents a shader in a BPXS.
 #[derive(Clone, Debug)]
@@ -149,5 +149,8 @@ pub enum Stage
     Geometry,
 
     /// Pixel/fragment shader stage.
-    Pixel
+    Pixel,
+
+    /// Compute shader stage.
+    Compute
 }
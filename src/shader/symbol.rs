@@ -37,7 +37,8 @@ use crate::{
         error::{EosContext, InvalidCodeContext, ReadError},
         Stage
     },
-    table::Item
+    spec::bpxs,
+    table::{Item, ItemMut}
 };
 
 /// Indicates this symbol is used on the vertex stage.
@@ -70,11 +71,21 @@ pub const FLAG_EXTENDED_DATA: u16 = 0x100;
 /// Indicates this symbol has a register number.
 pub const FLAG_REGISTER: u16 = 0x200;
 
+/// Indicates this symbol is used on the compute stage.
+pub const FLAG_COMPUTE_STAGE: u16 = 0x400;
+
 /// Size in bytes of a symbol structure.
-pub const SIZE_SYMBOL_STRUCTURE: usize = 12;
+pub const SIZE_SYMBOL_STRUCTURE: usize = bpxs::SYMBOL_SIZE;
+
+const _: () = assert!(bpxs::SYMBOL_EXTENDED_DATA_OFFSET == bpxs::SYMBOL_NAME_OFFSET + 4);
+const _: () = assert!(bpxs::SYMBOL_FLAGS_OFFSET == bpxs::SYMBOL_EXTENDED_DATA_OFFSET + 4);
+const _: () = assert!(bpxs::SYMBOL_TYPE_OFFSET == bpxs::SYMBOL_FLAGS_OFFSET + 2);
+const _: () = assert!(bpxs::SYMBOL_REGISTER_OFFSET == bpxs::SYMBOL_TYPE_OFFSET + 1);
+const _: () = assert!(bpxs::SYMBOL_REGISTER_OFFSET + 1 == SIZE_SYMBOL_STRUCTURE);
 
 /// The type of a symbol.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type
 {
     /// A texture symbol.
@@ -118,6 +129,7 @@ fn get_symbol_type_from_code(scode: u8) -> Result<Type, ReadError>
 
 /// Represents the structure of a symbol.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Symbol
 {
     /// The pointer to the name of the symbol.
@@ -145,10 +157,10 @@ impl Struct<SIZE_SYMBOL_STRUCTURE> for Symbol
     {
         Symbol {
             name: 0,
-            extended_data: 0xFFFFFF,
+            extended_data: bpxs::NO_EXTENDED_DATA,
             flags: 0,
             ty: Type::Constant,
-            register: 0xFF
+            register: bpxs::NO_REGISTER
         }
     }
 
@@ -159,11 +171,11 @@ impl Struct<SIZE_SYMBOL_STRUCTURE> for Symbol
 
     fn from_bytes(buffer: [u8; SIZE_SYMBOL_STRUCTURE]) -> Result<Self::Output, Self::Error>
     {
-        let name = LittleEndian::read_u32(&buffer[0..4]);
-        let extended_data = LittleEndian::read_u32(&buffer[4..8]);
-        let flags = LittleEndian::read_u16(&buffer[8..10]);
-        let ty = get_symbol_type_from_code(buffer[10])?;
-        let register = buffer[11];
+        let name = LittleEndian::read_u32(&buffer[bpxs::SYMBOL_NAME_OFFSET..]);
+        let extended_data = LittleEndian::read_u32(&buffer[bpxs::SYMBOL_EXTENDED_DATA_OFFSET..]);
+        let flags = LittleEndian::read_u16(&buffer[bpxs::SYMBOL_FLAGS_OFFSET..]);
+        let ty = get_symbol_type_from_code(buffer[bpxs::SYMBOL_TYPE_OFFSET])?;
+        let register = buffer[bpxs::SYMBOL_REGISTER_OFFSET];
         Ok(Symbol {
             name,
             extended_data,
@@ -176,19 +188,22 @@ impl Struct<SIZE_SYMBOL_STRUCTURE> for Symbol
     fn to_bytes(&self) -> [u8; SIZE_SYMBOL_STRUCTURE]
     {
         let mut buf = [0; SIZE_SYMBOL_STRUCTURE];
-        LittleEndian::write_u32(&mut buf[0..4], self.name);
-        LittleEndian::write_u32(&mut buf[4..8], self.extended_data);
-        LittleEndian::write_u16(&mut buf[8..10], self.flags);
+        LittleEndian::write_u32(&mut buf[bpxs::SYMBOL_NAME_OFFSET..], self.name);
+        LittleEndian::write_u32(
+            &mut buf[bpxs::SYMBOL_EXTENDED_DATA_OFFSET..],
+            self.extended_data
+        );
+        LittleEndian::write_u16(&mut buf[bpxs::SYMBOL_FLAGS_OFFSET..], self.flags);
         match self.ty {
-            Type::Texture => buf[10] = 0x0,
-            Type::Sampler => buf[10] = 0x1,
-            Type::ConstantBuffer => buf[10] = 0x2,
-            Type::Constant => buf[10] = 0x3,
-            Type::VertexFormat => buf[10] = 0x4,
-            Type::Pipeline => buf[10] = 0x5,
-            Type::Output => buf[10] = 0x6
+            Type::Texture => buf[bpxs::SYMBOL_TYPE_OFFSET] = 0x0,
+            Type::Sampler => buf[bpxs::SYMBOL_TYPE_OFFSET] = 0x1,
+            Type::ConstantBuffer => buf[bpxs::SYMBOL_TYPE_OFFSET] = 0x2,
+            Type::Constant => buf[bpxs::SYMBOL_TYPE_OFFSET] = 0x3,
+            Type::VertexFormat => buf[bpxs::SYMBOL_TYPE_OFFSET] = 0x4,
+            Type::Pipeline => buf[bpxs::SYMBOL_TYPE_OFFSET] = 0x5,
+            Type::Output => buf[bpxs::SYMBOL_TYPE_OFFSET] = 0x6
         };
-        buf[11] = self.register;
+        buf[bpxs::SYMBOL_REGISTER_OFFSET] = self.register;
         buf
     }
 }
@@ -201,6 +216,14 @@ impl Item for Symbol
     }
 }
 
+impl ItemMut for Symbol
+{
+    fn set_name_address(&mut self, address: u32)
+    {
+        self.name = address;
+    }
+}
+
 /// The required settings to create a new symbol.
 ///
 /// *This is intended to be generated with help of [Builder](crate::shader::symbol::Builder).*
@@ -240,7 +263,7 @@ impl Builder
                 extended_data: None,
                 ty: Type::Constant,
                 flags: 0,
-                register: 0xFF
+                register: bpxs::NO_REGISTER
             }
         }
     }
@@ -336,7 +359,8 @@ impl Builder
             Stage::Hull => self.sym.flags |= FLAG_HULL_STAGE,
             Stage::Domain => self.sym.flags |= FLAG_DOMAIN_STAGE,
             Stage::Geometry => self.sym.flags |= FLAG_GEOMETRY_STAGE,
-            Stage::Pixel => self.sym.flags |= FLAG_PIXEL_STAGE
+            Stage::Pixel => self.sym.flags |= FLAG_PIXEL_STAGE,
+            Stage::Compute => self.sym.flags |= FLAG_COMPUTE_STAGE
         }
         self
     }
@@ -0,0 +1,275 @@
+// Copyright (c) 2021, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Versioned on-disk conventions of the BPX format and its official variants.
+//!
+//! Every magic number, structure size, field offset and version used by the encoders and
+//! decoders of this crate is declared here. The implementation only consumes these
+//! constants so that this module is the single reference for the binary layout.
+//!
+//! All multi-byte integers are stored in little endian. Offsets are in bytes from the start
+//! of the structure they belong to.
+
+/// Layout of the BPX core: main header, section headers and trailer.
+pub mod core
+{
+    /// The signature found at the start of every BPX file.
+    pub const SIGNATURE: [u8; 3] = *b"BPX";
+
+    /// The BPX version written by this implementation.
+    pub const VERSION: u32 = 0x2;
+
+    /// All BPX versions this implementation can read.
+    pub const KNOWN_VERSIONS: &[u32] = &[0x1, 0x2];
+
+    /// Size in bytes of the main header.
+    pub const MAIN_HEADER_SIZE: usize = 40;
+
+    /// Offset of the signature in the main header (3 bytes).
+    pub const MAIN_HEADER_SIGNATURE_OFFSET: usize = 0;
+
+    /// Offset of the type byte in the main header.
+    pub const MAIN_HEADER_TYPE_OFFSET: usize = 3;
+
+    /// Offset of the checksum in the main header (u32).
+    pub const MAIN_HEADER_CHECKSUM_OFFSET: usize = 4;
+
+    /// Offset of the total file size in the main header (u64).
+    pub const MAIN_HEADER_FILE_SIZE_OFFSET: usize = 8;
+
+    /// Offset of the number of sections in the main header (u32).
+    pub const MAIN_HEADER_SECTION_NUM_OFFSET: usize = 16;
+
+    /// Offset of the version in the main header (u32).
+    pub const MAIN_HEADER_VERSION_OFFSET: usize = 20;
+
+    /// Offset of the variant specific extended type information in the main header.
+    pub const MAIN_HEADER_TYPE_EXT_OFFSET: usize = 24;
+
+    /// Size in bytes of the extended type information.
+    pub const TYPE_EXT_SIZE: usize = 16;
+
+    /// Size in bytes of a section header.
+    pub const SECTION_HEADER_SIZE: usize = 24;
+
+    /// Offset of the data pointer in a section header (u64).
+    pub const SECTION_HEADER_POINTER_OFFSET: usize = 0;
+
+    /// Offset of the compressed size in a section header (u32).
+    pub const SECTION_HEADER_CSIZE_OFFSET: usize = 8;
+
+    /// Offset of the uncompressed size in a section header (u32).
+    pub const SECTION_HEADER_SIZE_OFFSET: usize = 12;
+
+    /// Offset of the checksum in a section header (u32).
+    pub const SECTION_HEADER_CHECKSUM_OFFSET: usize = 16;
+
+    /// Offset of the type byte in a section header.
+    pub const SECTION_HEADER_TYPE_OFFSET: usize = 20;
+
+    /// Offset of the flags byte in a section header.
+    pub const SECTION_HEADER_FLAGS_OFFSET: usize = 21;
+
+    /// Section flag: the section is compressed with zlib.
+    pub const FLAG_COMPRESS_ZLIB: u8 = 0x1;
+
+    /// Section flag: the section is compressed with xz.
+    pub const FLAG_COMPRESS_XZ: u8 = 0x2;
+
+    /// Section flag: the section is checked with crc32.
+    pub const FLAG_CHECK_CRC32: u8 = 0x4;
+
+    /// Section flag: the section is checked with the weak checksum.
+    pub const FLAG_CHECK_WEAK: u8 = 0x8;
+
+    /// Section flag: the section is encrypted with AES-256-GCM.
+    pub const FLAG_ENCRYPT_AES_GCM: u8 = 0x10;
+
+    /// Section type of a string section, common to every variant.
+    pub const SECTION_TYPE_STRING: u8 = 0xFF;
+
+    /// Section type of a BPXSD metadata section, common to every variant.
+    pub const SECTION_TYPE_SD: u8 = 0xFE;
+
+    /// Magic bytes of the optional whole-file trailer.
+    pub const TRAILER_MAGIC: [u8; 4] = *b"BPXT";
+
+    /// Size in bytes of the optional whole-file trailer (magic followed by a crc32).
+    pub const TRAILER_SIZE: usize = 8;
+}
+
+/// Layout of a BPX Package (type P).
+pub mod bpxp
+{
+    /// The type byte of the main header.
+    pub const TYPE_BYTE: u8 = b'P';
+
+    /// The BPX version supported by the package encoder/decoder.
+    pub const VERSION: u32 = 0x2;
+
+    /// Section type of the data sections.
+    pub const DATA_TYPE: u8 = 0x1;
+
+    /// Section type of the object table section.
+    pub const OBJECT_TABLE_TYPE: u8 = 0x2;
+
+    /// Offset of the architecture code in the extended type information.
+    pub const TYPE_EXT_ARCHITECTURE_OFFSET: usize = 0;
+
+    /// Offset of the platform code in the extended type information.
+    pub const TYPE_EXT_PLATFORM_OFFSET: usize = 1;
+
+    /// Offset of the 2 bytes type code in the extended type information.
+    pub const TYPE_EXT_TYPE_CODE_OFFSET: usize = 2;
+
+    /// The default type code ("PH").
+    pub const DEFAULT_TYPE_CODE: [u8; 2] = [0x50, 0x48];
+
+    /// Size in bytes of an object header in the object table.
+    pub const OBJECT_HEADER_SIZE: usize = 20;
+
+    /// Offset of the object size in an object header (u64).
+    pub const OBJECT_HEADER_SIZE_OFFSET: usize = 0;
+
+    /// Offset of the name pointer in an object header (u32).
+    pub const OBJECT_HEADER_NAME_OFFSET: usize = 8;
+
+    /// Offset of the start data section index in an object header (u32).
+    pub const OBJECT_HEADER_START_OFFSET: usize = 12;
+
+    /// Offset of the offset in the start data section in an object header (u32).
+    pub const OBJECT_HEADER_DATA_OFFSET: usize = 16;
+
+    /// Section type of the optional object attribute table section.
+    pub const ATTRIBUTE_TABLE_TYPE: u8 = 0x3;
+
+    /// Size in bytes of a record in the object attribute table.
+    ///
+    /// *Bytes not covered by a field are reserved and written as zero.*
+    pub const OBJECT_ATTRIBUTES_SIZE: usize = 24;
+
+    /// Offset of the name pointer of the object in an attribute record (u32).
+    pub const OBJECT_ATTRIBUTES_NAME_OFFSET: usize = 0;
+
+    /// Offset of the flags indicating which attributes are present in an attribute record (u32).
+    pub const OBJECT_ATTRIBUTES_FLAGS_OFFSET: usize = 4;
+
+    /// Offset of the Unix permissions in an attribute record (u32).
+    pub const OBJECT_ATTRIBUTES_MODE_OFFSET: usize = 8;
+
+    /// Offset of the modification time in an attribute record (u64).
+    pub const OBJECT_ATTRIBUTES_MTIME_OFFSET: usize = 16;
+
+    /// Flag indicating the Unix permissions are present in an attribute record.
+    pub const ATTRIBUTE_FLAG_MODE: u32 = 0x1;
+
+    /// Flag indicating the modification time is present in an attribute record.
+    pub const ATTRIBUTE_FLAG_MTIME: u32 = 0x2;
+
+    /// Flag indicating the object is a symbolic link; the object data is the link target.
+    pub const ATTRIBUTE_FLAG_SYMLINK: u32 = 0x4;
+}
+
+/// Layout of a BPX Shader Package (type S).
+pub mod bpxs
+{
+    /// The type byte of the main header.
+    pub const TYPE_BYTE: u8 = b'S';
+
+    /// The BPX version supported by the shader encoder/decoder.
+    pub const VERSION: u32 = 0x2;
+
+    /// Section type of the shader sections.
+    pub const SHADER_TYPE: u8 = 0x1;
+
+    /// Section type of the symbol table section.
+    pub const SYMBOL_TABLE_TYPE: u8 = 0x2;
+
+    /// Section type of the extended data section.
+    pub const EXTENDED_DATA_TYPE: u8 = 0x3;
+
+    /// Offset of the assembly hash in the extended type information (u64).
+    pub const TYPE_EXT_ASSEMBLY_HASH_OFFSET: usize = 0;
+
+    /// Offset of the number of symbols in the extended type information (u16).
+    pub const TYPE_EXT_NUM_SYMBOLS_OFFSET: usize = 8;
+
+    /// Offset of the target code in the extended type information.
+    pub const TYPE_EXT_TARGET_OFFSET: usize = 10;
+
+    /// Offset of the package type byte in the extended type information.
+    pub const TYPE_EXT_TYPE_OFFSET: usize = 11;
+
+    /// Package type byte of an assembly.
+    pub const TYPE_ASSEMBLY: u8 = b'A';
+
+    /// Package type byte of a pipeline.
+    pub const TYPE_PIPELINE: u8 = b'P';
+
+    /// Stage byte of a vertex shader.
+    pub const STAGE_VERTEX: u8 = 0x0;
+
+    /// Stage byte of a hull shader.
+    pub const STAGE_HULL: u8 = 0x1;
+
+    /// Stage byte of a domain shader.
+    pub const STAGE_DOMAIN: u8 = 0x2;
+
+    /// Stage byte of a geometry shader.
+    pub const STAGE_GEOMETRY: u8 = 0x3;
+
+    /// Stage byte of a pixel shader.
+    pub const STAGE_PIXEL: u8 = 0x4;
+
+    /// Stage byte of a compute shader.
+    pub const STAGE_COMPUTE: u8 = 0x5;
+
+    /// Size in bytes of a symbol structure in the symbol table.
+    pub const SYMBOL_SIZE: usize = 12;
+
+    /// Offset of the name pointer in a symbol structure (u32).
+    pub const SYMBOL_NAME_OFFSET: usize = 0;
+
+    /// Offset of the extended data pointer in a symbol structure (u32).
+    pub const SYMBOL_EXTENDED_DATA_OFFSET: usize = 4;
+
+    /// Offset of the flags in a symbol structure (u16).
+    pub const SYMBOL_FLAGS_OFFSET: usize = 8;
+
+    /// Offset of the symbol type byte in a symbol structure.
+    pub const SYMBOL_TYPE_OFFSET: usize = 10;
+
+    /// Offset of the register byte in a symbol structure.
+    pub const SYMBOL_REGISTER_OFFSET: usize = 11;
+
+    /// Extended data pointer of a symbol without extended data.
+    pub const NO_EXTENDED_DATA: u32 = 0xFFFFFF;
+
+    /// Register byte of a symbol without register.
+    pub const NO_REGISTER: u8 = 0xFF;
+}
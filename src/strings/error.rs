@@ -74,12 +74,16 @@ pub enum WriteError
     Io(std::io::Error),
 
     /// Indicates the section is not loaded.
-    SectionNotLoaded
+    SectionNotLoaded,
+
+    /// Describes an error while reading existing strings.
+    Read(ReadError)
 }
 
 impl_err_conversion!(
     WriteError {
-        std::io::Error => Io
+        std::io::Error => Io,
+        ReadError => Read
     }
 );
 
@@ -89,7 +93,8 @@ impl Display for WriteError
     {
         match self {
             WriteError::SectionNotLoaded => f.write_str("section not loaded"),
-            WriteError::Io(e) => write!(f, "io error: {}", e)
+            WriteError::Io(e) => write!(f, "io error: {}", e),
+            WriteError::Read(e) => write!(f, "read error: {}", e)
         }
     }
 }
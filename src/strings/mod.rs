@@ -33,7 +33,7 @@ mod error;
 use std::{
     collections::{hash_map::Entry, HashMap},
     fs::DirEntry,
-    io::{Read, Seek, SeekFrom},
+    io::{Read, Seek, SeekFrom, Write},
     path::Path,
     string::String
 };
@@ -65,7 +65,8 @@ use crate::{
 pub struct StringSection
 {
     section: Handle,
-    cache: HashMap<u32, String>
+    cache: HashMap<u32, String>,
+    reverse: Option<HashMap<String, u32>>
 }
 
 impl StringSection
@@ -81,7 +82,8 @@ impl StringSection
     {
         StringSection {
             section,
-            cache: HashMap::new()
+            cache: HashMap::new(),
+            reverse: None
         }
     }
 
@@ -94,6 +96,8 @@ impl StringSection
     ///
     /// returns: Result<&str, Error>
     ///
+    /// *The cursor of the section is left unchanged by this function.*
+    ///
     /// # Errors
     ///
     /// Returns a [ReadError](crate::strings::ReadError) if the string could not be read or the
@@ -104,12 +108,10 @@ impl StringSection
         let res = match self.cache.entry(address) {
             Entry::Occupied(o) => o.into_mut(),
             Entry::Vacant(o) => {
-                let mut section = container.get_mut(self.section);
-                let s = low_level_read_string(
-                    address,
-                    section.open().ok_or(ReadError::SectionNotLoaded)?
-                )?;
-                o.insert(s)
+                let mut cursor = container
+                    .open_cursor(self.section)
+                    .ok_or(ReadError::SectionNotLoaded)?;
+                o.insert(low_level_read_string(address, &mut cursor)?)
             }
         };
         Ok(res)
@@ -133,9 +135,217 @@ impl StringSection
         let address =
             low_level_write_string(s, section.open().ok_or(WriteError::SectionNotLoaded)?)?;
         self.cache.insert(address, String::from(s));
+        if let Some(reverse) = &mut self.reverse {
+            reverse.entry(String::from(s)).or_insert(address);
+        }
         Ok(address)
     }
 
+    /// Writes a string into the section unless the same string already exists in the section.
+    ///
+    /// Returns the offset of the existing string if any, otherwise the offset of the newly
+    /// written string.
+    ///
+    /// *The first call scans all strings already in the section; the section must be loaded.*
+    ///
+    /// # Arguments
+    ///
+    /// * `container`: the BPX container.
+    /// * `s`: the string to write.
+    ///
+    /// returns: Result<u32, Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [WriteError](crate::strings::WriteError) if the existing strings could not be
+    /// scanned or the string could not be written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::Container;
+    /// use bpx::core::header::{MainHeader, SectionHeader, Struct};
+    /// use bpx::strings::StringSection;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeader::new());
+    /// let section = file.create_section(SectionHeader::new());
+    /// let mut strings = StringSection::new(section);
+    /// let first = strings.put_dedup(&mut file, "Test").unwrap();
+    /// let second = strings.put_dedup(&mut file, "Test").unwrap();
+    /// assert_eq!(first, second);
+    /// ```
+    pub fn put_dedup<T>(&mut self, container: &mut Container<T>, s: &str)
+        -> Result<u32, WriteError>
+    {
+        if self.reverse.is_none() {
+            let mut section = container.get_mut(self.section);
+            let data = section.open().ok_or(WriteError::SectionNotLoaded)?;
+            self.reverse = Some(scan_strings(data)?);
+        }
+        if let Some(address) = self.reverse.as_ref().and_then(|v| v.get(s)) {
+            return Ok(*address);
+        }
+        self.put(container, s)
+    }
+
+    /// Rewrites the section so that it only contains the strings starting at the given offsets.
+    ///
+    /// Strings are kept in the order of their original offsets and duplicate offsets are
+    /// written only once. Returns the new offset of each string indexed by its old offset so
+    /// that callers can update their references.
+    ///
+    /// *The section must be loaded. The cursor of the section is left at its end so that new
+    /// strings can be written.*
+    ///
+    /// # Arguments
+    ///
+    /// * `container`: the BPX container.
+    /// * `live_offsets`: the offsets of the strings to keep.
+    ///
+    /// returns: Result<HashMap<u32, u32>, Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [WriteError](crate::strings::WriteError) if a string to keep could not be read
+    /// or if the section could not be written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::{Container, SectionData};
+    /// use bpx::core::header::{MainHeader, SectionHeader, Struct};
+    /// use bpx::strings::StringSection;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeader::new());
+    /// let section = file.create_section(SectionHeader::new());
+    /// let mut strings = StringSection::new(section);
+    /// strings.put(&mut file, "Dead").unwrap();
+    /// let live = strings.put(&mut file, "Live").unwrap();
+    /// let remap = strings.compact(&mut file, &[live]).unwrap();
+    /// assert_eq!(remap[&live], 0);
+    /// assert_eq!(strings.get(&mut file, 0).unwrap(), "Live");
+    /// assert_eq!(file.get(section).open().unwrap().size(), 5);
+    /// ```
+    pub fn compact<T>(
+        &mut self,
+        container: &mut Container<T>,
+        live_offsets: &[u32]
+    ) -> Result<HashMap<u32, u32>, WriteError>
+    {
+        let mut offsets = live_offsets.to_vec();
+        offsets.sort_unstable();
+        offsets.dedup();
+        let mut section = container.get_mut(self.section);
+        let data = section.open().ok_or(WriteError::SectionNotLoaded)?;
+        let mut strings = Vec::with_capacity(offsets.len());
+        for address in offsets {
+            strings.push((address, low_level_read_bytes(address, data)?));
+        }
+        data.truncate(0)?;
+        data.seek(SeekFrom::Start(0))?;
+        self.cache.clear();
+        self.reverse = None;
+        let mut remap = HashMap::with_capacity(strings.len());
+        for (address, bytes) in strings {
+            let new_address = data.size() as u32;
+            data.write_all(&bytes)?;
+            data.write_all(&[0x0])?;
+            if let Ok(s) = String::from_utf8(bytes) {
+                self.cache.insert(new_address, s);
+            }
+            remap.insert(address, new_address);
+        }
+        Ok(remap)
+    }
+
+    /// Creates an iterator over all strings stored in the section, in order, along with their
+    /// offsets.
+    ///
+    /// Iteration stops at the end of the section; strings read are added to the cache.
+    ///
+    /// *The section must be loaded and its cursor is left unchanged by this function.*
+    ///
+    /// # Arguments
+    ///
+    /// * `container`: the BPX container.
+    ///
+    /// returns: Iter<T>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::Container;
+    /// use bpx::core::header::{MainHeader, SectionHeader, Struct};
+    /// use bpx::strings::StringSection;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeader::new());
+    /// let section = file.create_section(SectionHeader::new());
+    /// let mut strings = StringSection::new(section);
+    /// strings.put(&mut file, "Hello").unwrap();
+    /// strings.put(&mut file, "world").unwrap();
+    /// let all: Vec<(u32, String)> = strings.iter(&mut file).map(|v| v.unwrap()).collect();
+    /// assert_eq!(all, vec![(0, "Hello".into()), (6, "world".into())]);
+    /// ```
+    pub fn iter<'a, T>(&'a mut self, container: &'a mut Container<T>) -> Iter<'a, T>
+    {
+        Iter {
+            strings: self,
+            container,
+            address: 0,
+            done: false
+        }
+    }
+
+    /// Reads the whole section once and caches all its strings.
+    ///
+    /// Subsequent calls to [get](StringSection::get) for any string of the section are then
+    /// served from memory instead of reading the section byte by byte.
+    ///
+    /// *Invalid UTF-8 strings are not cached; [get](StringSection::get) still reports them as
+    /// errors. The cursor of the section is left unchanged by this function.*
+    ///
+    /// # Arguments
+    ///
+    /// * `container`: the BPX container.
+    ///
+    /// returns: Result<(), ReadError>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [ReadError](crate::strings::ReadError) if the section could not be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bpx::core::Container;
+    /// use bpx::core::header::{MainHeader, SectionHeader, Struct};
+    /// use bpx::strings::StringSection;
+    /// use bpx::utils::new_byte_buf;
+    ///
+    /// let mut file = Container::create(new_byte_buf(0), MainHeader::new());
+    /// let section = file.create_section(SectionHeader::new());
+    /// let mut strings = StringSection::new(section);
+    /// let offset = strings.put(&mut file, "Test").unwrap();
+    /// let mut strings = StringSection::new(section);
+    /// strings.preload(&mut file).unwrap();
+    /// assert_eq!(strings.get(&mut file, offset).unwrap(), "Test");
+    /// ```
+    pub fn preload<T>(&mut self, container: &mut Container<T>) -> Result<(), ReadError>
+    {
+        let mut section = container.get_mut(self.section);
+        let data = section.open().ok_or(ReadError::SectionNotLoaded)?;
+        let buf = read_all(data)?;
+        for (address, s) in split_strings(&buf) {
+            if let Ok(s) = std::str::from_utf8(s) {
+                self.cache.entry(address).or_insert_with(|| s.into());
+            }
+        }
+        Ok(())
+    }
+
     /// Returns the section handle.
     pub fn handle(&self) -> Handle
     {
@@ -143,6 +353,55 @@ impl StringSection
     }
 }
 
+/// An iterator over all strings of a [StringSection](crate::strings::StringSection).
+///
+/// Yields the offset of each string along with the string itself. Iteration continues after
+/// an invalid UTF-8 string and stops after any other error.
+pub struct Iter<'a, T>
+{
+    strings: &'a mut StringSection,
+    container: &'a mut Container<T>,
+    address: u32,
+    done: bool
+}
+
+impl<'a, T> Iter<'a, T>
+{
+    fn next_string(&mut self) -> Result<Option<(u32, String)>, ReadError>
+    {
+        let mut cursor = self
+            .container
+            .open_cursor(self.strings.section)
+            .ok_or(ReadError::SectionNotLoaded)?;
+        if self.address as usize >= cursor.size() {
+            return Ok(None);
+        }
+        let bytes = low_level_read_bytes(self.address, &mut cursor)?;
+        let address = self.address;
+        self.address += bytes.len() as u32 + 1;
+        let s = String::from_utf8(bytes).map_err(|_| ReadError::Utf8)?;
+        self.strings.cache.insert(address, s.clone());
+        Ok(Some((address, s)))
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T>
+{
+    type Item = Result<(u32, String), ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        if self.done {
+            return None;
+        }
+        let res = self.next_string().transpose();
+        if !matches!(res, Some(Ok(_)) | Some(Err(ReadError::Utf8))) {
+            self.done = true;
+        }
+        res
+    }
+}
+
 /// Ensures string section is loaded. This is used to enable lazy loading on BPX types.
 ///
 /// # Arguments
@@ -161,10 +420,58 @@ pub fn load_string_section<T: Read + Seek>(
     Ok(())
 }
 
-fn low_level_read_string(
+fn read_all(string_section: &mut AutoSectionData) -> std::io::Result<Vec<u8>>
+{
+    let pos = string_section.stream_position()?;
+    let mut buf = Vec::new();
+    string_section.seek(SeekFrom::Start(0))?;
+    let res = string_section.read_to_end(&mut buf);
+    string_section.seek(SeekFrom::Start(pos))?;
+    res?;
+    Ok(buf)
+}
+
+/// Splits raw string section bytes into null terminated strings along with their address.
+fn split_strings(buf: &[u8]) -> impl Iterator<Item = (u32, &[u8])>
+{
+    let mut segments: Vec<&[u8]> = buf.split(|v| *v == 0x0).collect();
+    //The last segment is never null terminated
+    segments.pop();
+    segments.into_iter().scan(0, |address, s| {
+        let cur = *address;
+        *address += s.len() as u32 + 1;
+        Some((cur, s))
+    })
+}
+
+fn scan_strings(string_section: &mut AutoSectionData) -> std::io::Result<HashMap<String, u32>>
+{
+    let buf = read_all(string_section)?;
+    let mut strings = HashMap::new();
+    for (address, s) in split_strings(&buf) {
+        if let Ok(s) = std::str::from_utf8(s) {
+            strings.entry(String::from(s)).or_insert(address);
+        }
+    }
+    Ok(strings)
+}
+
+fn low_level_read_string<R: Read + Seek>(
     ptr: u32,
-    string_section: &mut AutoSectionData
+    string_section: &mut R
 ) -> Result<String, ReadError>
+{
+    let curs = low_level_read_bytes(ptr, string_section)?;
+    match String::from_utf8(curs) {
+        Err(_) => Err(ReadError::Utf8),
+        Ok(v) => Ok(v)
+    }
+}
+
+fn low_level_read_bytes<R: Read + Seek>(
+    ptr: u32,
+    string_section: &mut R
+) -> Result<Vec<u8>, ReadError>
 {
     let mut curs: Vec<u8> = Vec::new();
     let mut chr: [u8; 1] = [0; 1]; //read char by char with a buffer
@@ -180,10 +487,7 @@ fn low_level_read_string(
             return Err(ReadError::Eos);
         }
     }
-    match String::from_utf8(curs) {
-        Err(_) => Err(ReadError::Utf8),
-        Ok(v) => Ok(v)
-    }
+    Ok(curs)
 }
 
 fn low_level_write_string(
@@ -191,7 +495,7 @@ fn low_level_write_string(
     string_section: &mut dyn SectionData
 ) -> Result<u32, std::io::Error>
 {
-    let ptr = string_section.size() as u32;
+    let ptr = string_section.seek(SeekFrom::End(0))? as u32;
     string_section.write_all(s.as_bytes())?;
     string_section.write_all(&[0x0])?;
     Ok(ptr)
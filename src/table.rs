@@ -27,11 +27,37 @@
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 //! This module provides a lookup-table style implementation.
+//!
+//! *With the `serde` cargo feature, an [ItemTable](crate::table::ItemTable) serializes as the
+//! sequence of its items.*
 
-use std::{collections::HashMap, ops::Index, slice::Iter};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    ops::Index,
+    slice::Iter
+};
 
 use crate::{core::Container, strings::StringSection};
 
+/// Represents an error when looking up an item by name.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LookupError
+{
+    /// Indicates the lookup table has not yet been built.
+    NotBuilt
+}
+
+impl Display for LookupError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self {
+            LookupError::NotBuilt => f.write_str("lookup table has not yet been built")
+        }
+    }
+}
+
 /// Represents an item to be stored in an ItemTable.
 pub trait Item
 {
@@ -39,11 +65,43 @@ pub trait Item
     fn get_name_address(&self) -> u32;
 }
 
+/// Represents an item whose name can be changed.
+pub trait ItemMut: Item
+{
+    /// Sets the address of the name of this item in its string section.
+    ///
+    /// # Arguments
+    ///
+    /// * `address`: the new address of the name.
+    fn set_name_address(&mut self, address: u32);
+}
+
+struct Lookup
+{
+    names: Vec<String>,
+    map: HashMap<String, usize>,
+    keep_first: bool
+}
+
+impl Lookup
+{
+    fn rebuild(&mut self)
+    {
+        self.map.clear();
+        for (i, name) in self.names.iter().enumerate() {
+            if self.keep_first && self.map.contains_key(name) {
+                continue;
+            }
+            self.map.insert(name.clone(), i);
+        }
+    }
+}
+
 /// Represents an item table with on demand lookup capability (the lookup function only works after you've built it).
 pub struct ItemTable<T: Item>
 {
     list: Vec<T>,
-    map: Option<HashMap<String, T>>
+    lookup: Option<Lookup>
 }
 
 impl<T: Item> ItemTable<T>
@@ -57,7 +115,25 @@ impl<T: Item> ItemTable<T>
     /// returns: ItemTable<T>
     pub fn new(list: Vec<T>) -> Self
     {
-        Self { list, map: None }
+        Self { list, lookup: None }
+    }
+
+    /// Constructs a new empty ItemTable.
+    ///
+    /// *The lookup table of an empty ItemTable is already built, items added with
+    /// [push](ItemTable::push) can be looked up right away.*
+    ///
+    /// returns: ItemTable<T>
+    pub fn empty() -> Self
+    {
+        Self {
+            list: Vec::new(),
+            lookup: Some(Lookup {
+                names: Vec::new(),
+                map: HashMap::new(),
+                keep_first: false
+            })
+        }
     }
 
     /// Gets all items in this table.
@@ -90,14 +166,197 @@ impl<T: Item> ItemTable<T>
     /// # Panics
     ///
     /// Panics if the lookup table is not yet built.
+    #[deprecated(
+        since = "4.0.0",
+        note = "use try_lookup or get_or_build_lookup which do not panic"
+    )]
     pub fn lookup(&self, name: &str) -> Option<&T>
     {
-        if let Some(map) = &self.map {
-            map.get(name)
-        } else {
-            panic!("Lookup table has not yet been initialized, please call build_lookup_table");
+        match self.try_lookup(name) {
+            Ok(v) => v,
+            Err(_) => {
+                panic!("Lookup table has not yet been initialized, please call build_lookup_table")
+            }
         }
     }
+
+    /// Lookup an item by its name.
+    /// Returns None if the item does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the item to search for.
+    ///
+    /// returns: Result<Option<&T>, LookupError>
+    ///
+    /// # Errors
+    ///
+    /// Returns [NotBuilt](LookupError::NotBuilt) if the lookup table is not yet built.
+    pub fn try_lookup(&self, name: &str) -> Result<Option<&T>, LookupError>
+    {
+        let lookup = self.lookup.as_ref().ok_or(LookupError::NotBuilt)?;
+        Ok(lookup.map.get(name).map(|v| &self.list[*v]))
+    }
+
+    /// Removes an item from this table and returns it.
+    ///
+    /// *If the lookup table is built, it is updated so that the removed item can no longer be
+    /// found by name.*
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: the index of the item to remove.
+    ///
+    /// returns: T
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> T
+    {
+        let item = self.list.remove(index);
+        if let Some(lookup) = &mut self.lookup {
+            lookup.names.remove(index);
+            lookup.rebuild();
+        }
+        item
+    }
+
+    /// Removes an item by its name and returns it.
+    /// Returns None if no item has this name.
+    ///
+    /// *When several items share the same name, the item returned by
+    /// [try_lookup](ItemTable::try_lookup) is removed.*
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the item to remove.
+    ///
+    /// returns: Result<Option<T>, LookupError>
+    ///
+    /// # Errors
+    ///
+    /// Returns [NotBuilt](LookupError::NotBuilt) if the lookup table is not yet built.
+    pub fn remove_by_name(&mut self, name: &str) -> Result<Option<T>, LookupError>
+    {
+        let lookup = self.lookup.as_ref().ok_or(LookupError::NotBuilt)?;
+        Ok(lookup
+            .map
+            .get(name)
+            .copied()
+            .map(|index| self.remove(index)))
+    }
+}
+
+impl<T: Item> ItemTable<T>
+{
+    /// Retains only the items for which `f` returns true, keeping the order of the remaining
+    /// items.
+    ///
+    /// *If the lookup table is built, it is updated so that removed items can no longer be
+    /// found by name.*
+    ///
+    /// *The string section is not modified: names of removed items are left in place.*
+    ///
+    /// # Arguments
+    ///
+    /// * `f`: the predicate which returns true for items to keep.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F)
+    {
+        let keep: Vec<bool> = self.list.iter().map(&mut f).collect();
+        let mut iter = keep.iter();
+        self.list.retain(|_| *iter.next().unwrap_or(&true));
+        if let Some(lookup) = &mut self.lookup {
+            let mut iter = keep.iter();
+            lookup.names.retain(|_| *iter.next().unwrap_or(&true));
+            lookup.rebuild();
+        }
+    }
+
+    /// Adds an item at the end of this table.
+    ///
+    /// *If the lookup table is built, the name of the item is loaded and the item can be
+    /// looked up right away.*
+    ///
+    /// # Arguments
+    ///
+    /// * `container`: the container of the string section.
+    /// * `names`: the NameTable to load the name of the item from.
+    /// * `item`: the item to add.
+    ///
+    /// returns: Result<(), Error>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::strings::ReadError) is returned if the name of the item could not
+    /// be loaded.
+    pub fn push<T1>(
+        &mut self,
+        container: &mut Container<T1>,
+        names: &mut StringSection,
+        item: T
+    ) -> Result<(), crate::strings::ReadError>
+    {
+        if let Some(lookup) = &mut self.lookup {
+            let name: String = names.get(container, item.get_name_address())?.into();
+            if !lookup.keep_first || !lookup.map.contains_key(&name) {
+                lookup.map.insert(name.clone(), self.list.len());
+            }
+            lookup.names.push(name);
+        }
+        self.list.push(item);
+        Ok(())
+    }
+}
+
+impl<T: ItemMut> ItemTable<T>
+{
+    /// Renames an item.
+    ///
+    /// The new name is written to the string section, the name address of the item is
+    /// updated and the lookup table is fixed to find the item under its new name.
+    /// Returns false if no item is named `old`.
+    ///
+    /// # Arguments
+    ///
+    /// * `container`: the container of the string section.
+    /// * `names`: the string section to write the new name to.
+    /// * `old`: the current name of the item.
+    /// * `new`: the new name of the item.
+    ///
+    /// returns: Result<bool, WriteError>
+    ///
+    /// # Errors
+    ///
+    /// A [WriteError](crate::strings::WriteError) is returned if the new name could not be
+    /// written.
+    ///
+    /// *The lookup table is built if needed.*
+    pub fn rename<T1>(
+        &mut self,
+        container: &mut Container<T1>,
+        names: &mut StringSection,
+        old: &str,
+        new: &str
+    ) -> Result<bool, crate::strings::WriteError>
+    {
+        if self.lookup.is_none() {
+            self.build_lookup_table(container, names)?;
+        }
+        let lookup = match &mut self.lookup {
+            Some(v) => v,
+            None => return Ok(false)
+        };
+        let index = match lookup.map.get(old) {
+            Some(v) => *v,
+            None => return Ok(false)
+        };
+        let address = names.put(container, new)?;
+        self.list[index].set_name_address(address);
+        lookup.names[index] = new.into();
+        lookup.rebuild();
+        Ok(true)
+    }
 }
 
 impl<'a, T: Item> IntoIterator for &'a ItemTable<T>
@@ -111,6 +370,25 @@ impl<'a, T: Item> IntoIterator for &'a ItemTable<T>
     }
 }
 
+#[cfg(feature = "serde")]
+impl<T: Item + serde::Serialize> serde::Serialize for ItemTable<T>
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        //Only the items are serialized: the lookup table is built again on demand
+        self.list.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Item + serde::Deserialize<'de>> serde::Deserialize<'de> for ItemTable<T>
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        Vec::deserialize(deserializer).map(ItemTable::new)
+    }
+}
+
 impl<T: Item> Index<usize> for ItemTable<T>
 {
     type Output = T;
@@ -121,7 +399,7 @@ impl<T: Item> Index<usize> for ItemTable<T>
     }
 }
 
-impl<T: Item + Clone> ItemTable<T>
+impl<T: Item> ItemTable<T>
 {
     /// Builds the item map for easy and efficient lookup of items by name.
     ///
@@ -142,12 +420,75 @@ impl<T: Item + Clone> ItemTable<T>
         names: &mut StringSection
     ) -> Result<(), crate::strings::ReadError>
     {
-        let mut map: HashMap<String, T> = HashMap::new();
-        for v in &self.list {
-            let name = names.get(container, v.get_name_address())?.into();
-            map.insert(name, v.clone());
-        }
-        self.map = Some(map);
+        self.build_lookup_table_checked(container, names, false, |v| v.into())?;
         Ok(())
     }
+
+    /// Looks up an item by its name, building the lookup table first if it is not yet built.
+    /// Returns None if the item does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `container`: the container of the string section.
+    /// * `names`: the NameTable to load the names from.
+    /// * `name`: the name of the item to search for.
+    ///
+    /// returns: Result<Option<&T>, Error>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::strings::ReadError) is returned if the strings could not be loaded.
+    pub fn get_or_build_lookup<T1>(
+        &mut self,
+        container: &mut Container<T1>,
+        names: &mut StringSection,
+        name: &str
+    ) -> Result<Option<&T>, crate::strings::ReadError>
+    {
+        if self.lookup.is_none() {
+            self.build_lookup_table(container, names)?;
+        }
+        Ok(self.try_lookup(name).unwrap_or(None))
+    }
+
+    /// Builds the item map like [build_lookup_table](ItemTable::build_lookup_table) and
+    /// returns the names which are shared by more than one item.
+    ///
+    /// # Arguments
+    ///
+    /// * `names`: the NameTable to load the names from.
+    /// * `keep_first`: true to map a duplicate name to the first item with that name, false to
+    ///   map it to the last one.
+    /// * `key`: a function computing the name to use in the item map from the stored name.
+    ///
+    /// returns: Result<Vec<String>, Error>
+    ///
+    /// # Errors
+    ///
+    /// A [ReadError](crate::strings::ReadError) is returned if the strings could not be loaded.
+    pub fn build_lookup_table_checked<T1, F: Fn(&str) -> String>(
+        &mut self,
+        container: &mut Container<T1>,
+        names: &mut StringSection,
+        keep_first: bool,
+        key: F
+    ) -> Result<Vec<String>, crate::strings::ReadError>
+    {
+        let mut lookup = Lookup {
+            names: Vec::with_capacity(self.list.len()),
+            map: HashMap::new(),
+            keep_first
+        };
+        let mut duplicates = Vec::new();
+        for (i, v) in self.list.iter().enumerate() {
+            let name = key(names.get(container, v.get_name_address())?);
+            if lookup.map.insert(name.clone(), i).is_some() && !duplicates.contains(&name) {
+                duplicates.push(name.clone());
+            }
+            lookup.names.push(name);
+        }
+        lookup.rebuild();
+        self.lookup = Some(lookup);
+        Ok(duplicates)
+    }
 }
@@ -0,0 +1,116 @@
+#![cfg(feature = "async")]
+
+use std::io::Write;
+
+use bpx::{
+    core::{
+        builder::{Checksum, CompressionMethod, MainHeaderBuilder, SectionHeaderBuilder},
+        error::{CorruptionContext, ReadError},
+        header::SIZE_MAIN_HEADER,
+        AsyncContainer,
+        Container,
+        SectionData
+    },
+    utils::new_byte_buf
+};
+
+fn sample_container() -> Vec<u8>
+{
+    let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new().ty(b'A'));
+    let raw = file.create_section(SectionHeaderBuilder::new().ty(1).checksum(Checksum::Weak));
+    let compressed = file.create_section(
+        SectionHeaderBuilder::new()
+            .ty(2)
+            .compression(CompressionMethod::Xz)
+            .checksum(Checksum::Crc32)
+            .threshold(0)
+    );
+    file.get_mut(raw)
+        .open()
+        .unwrap()
+        .write_all(b"raw section")
+        .unwrap();
+    let mut data = file.get_mut(compressed);
+    let data = data.open().unwrap();
+    for _ in 0..1000 {
+        data.write_all(b"compressed section ").unwrap();
+    }
+    file.save().unwrap();
+    file.into_inner().into_inner()
+}
+
+#[tokio::test]
+async fn async_load_sections()
+{
+    let buf = sample_container();
+    let mut file = AsyncContainer::open(std::io::Cursor::new(buf))
+        .await
+        .unwrap();
+    assert_eq!(file.get_main_header().ty, b'A');
+    assert_eq!(file.len(), 2);
+
+    let raw = file.find_section_by_type(1).unwrap();
+    let mut data = file.load(raw).await.unwrap();
+    assert_eq!(data.load_in_memory().unwrap(), b"raw section");
+
+    let compressed = file.find_section_by_type(2).unwrap();
+    assert!(file.get_section_header(compressed).csize < file.get_section_header(compressed).size);
+    let mut data = file.load(compressed).await.unwrap();
+    let data = data.load_in_memory().unwrap();
+    assert_eq!(data.len(), 19000);
+    assert!(data.starts_with(b"compressed section compressed"));
+}
+
+#[tokio::test]
+async fn async_checksum_mismatch()
+{
+    let mut buf = sample_container();
+    let file = AsyncContainer::open(std::io::Cursor::new(&buf))
+        .await
+        .unwrap();
+    let handle = file.find_section_by_type(1).unwrap();
+    let pointer = file.get_section_header(handle).pointer as usize;
+    buf[pointer] = b'R';
+
+    let mut file = AsyncContainer::open(std::io::Cursor::new(buf))
+        .await
+        .unwrap();
+    assert!(matches!(
+        file.load(handle).await,
+        Err(ReadError::Checksum(_, _))
+    ));
+    file.set_skip_checksum(true);
+    let mut data = file.load(handle).await.unwrap();
+    assert_eq!(data.load_in_memory().unwrap(), b"Raw section");
+}
+
+/// Makes the first section claim `csize` bytes, keeping the header checksum valid.
+fn lie_about_csize(buf: &mut [u8], csize: u32)
+{
+    let start = SIZE_MAIN_HEADER + bpx::spec::core::SECTION_HEADER_CSIZE_OFFSET;
+    let sum = |bytes: &[u8]| bytes.iter().map(|v| *v as u32).sum::<u32>();
+    let old = sum(&buf[start..start + 4]);
+    buf[start..start + 4].copy_from_slice(&csize.to_le_bytes());
+    let chksum = u32::from_le_bytes(buf[4..8].try_into().unwrap())
+        .wrapping_add(sum(&buf[start..start + 4]))
+        .wrapping_sub(old);
+    buf[4..8].copy_from_slice(&chksum.to_le_bytes());
+}
+
+#[tokio::test]
+async fn async_rejects_header_lies()
+{
+    let mut buf = sample_container();
+    lie_about_csize(&mut buf, u32::MAX);
+    assert!(matches!(
+        AsyncContainer::open(std::io::Cursor::new(buf)).await,
+        Err(ReadError::Corrupted(CorruptionContext::SectionPastEnd(0)))
+    ));
+
+    let mut buf = sample_container();
+    buf[16..20].copy_from_slice(&0xFFFFu32.to_le_bytes());
+    assert!(matches!(
+        AsyncContainer::open(std::io::Cursor::new(buf)).await,
+        Err(ReadError::Corrupted(CorruptionContext::SectionTable))
+    ));
+}
@@ -1,8 +1,28 @@
-use std::{fs::File, path::Path};
+use std::{
+    fs::File,
+    io::{Seek, SeekFrom, Write},
+    path::Path
+};
 
-use bpx::core::{
-    header::{MainHeader, Struct, BPX_CURRENT_VERSION},
-    Container
+use bpx::{
+    core::{
+        builder::{CompressionMethod, MainHeaderBuilder, OpenOptions, SectionHeaderBuilder},
+        error::{CorruptionContext, ReadError},
+        header::{
+            MainHeader,
+            SectionHeader,
+            Struct,
+            BPX_CURRENT_VERSION,
+            SIZE_MAIN_HEADER,
+            SIZE_SECTION_HEADER,
+            SIZE_TRAILER
+        },
+        Container,
+        SectionData,
+        DEFAULT_MAX_SECTIONS,
+        UNTRUSTED_MAX_SECTION_SIZE
+    },
+    utils::new_byte_buf
 };
 
 /*use bpx::{decoder::Decoder, encoder::Encoder, header::BPX_CURRENT_VERSION, Interface};*/
@@ -24,6 +44,100 @@ fn attempt_write_empty_bpxp()
     }
 }
 
+#[test]
+fn save_to_round_trip()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let first = container.create_section(SectionHeaderBuilder::new().ty(1));
+    let second = container.create_section(
+        SectionHeaderBuilder::new()
+            .ty(2)
+            .compression(CompressionMethod::Xz)
+            .threshold(0)
+    );
+    container
+        .get_mut(first)
+        .open()
+        .unwrap()
+        .write_all(b"first")
+        .unwrap();
+    container
+        .get_mut(second)
+        .open()
+        .unwrap()
+        .write_all(b"second")
+        .unwrap();
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let original = buf.get_ref().clone();
+
+    let mut container = Container::open(buf).unwrap();
+    let first = container.find_section_by_type(1).unwrap();
+    {
+        let mut section = container.get_mut(first);
+        let data = section.load().unwrap();
+        data.seek(SeekFrom::End(0)).unwrap();
+        data.write_all(b" modified").unwrap();
+    }
+    let mut out = new_byte_buf(0);
+    container.save_to(&mut out).unwrap();
+    assert_eq!(container.into_inner().into_inner(), original);
+
+    out.set_position(0);
+    let mut container = Container::open(out).unwrap();
+    assert_eq!(container.get_main_header().section_num, 2);
+    let handle = container.find_section_by_type(1).unwrap();
+    let mut section = container.get_mut(handle);
+    assert_eq!(
+        section.load().unwrap().load_in_memory().unwrap(),
+        b"first modified"
+    );
+    let handle = container.find_section_by_type(2).unwrap();
+    let mut section = container.get_mut(handle);
+    assert_eq!(section.load().unwrap().load_in_memory().unwrap(), b"second");
+}
+
+#[test]
+fn create_section_at_index()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    for ty in [1, 2, 3] {
+        let handle = container.create_section(SectionHeaderBuilder::new().ty(ty));
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&[ty])
+            .unwrap();
+    }
+    let handle = container.create_section_at(1, SectionHeaderBuilder::new().ty(4));
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(&[4])
+        .unwrap();
+    let types: Vec<u8> = container.iter().map(|v| v.ty).collect();
+    assert_eq!(types, [1, 4, 2, 3]);
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+
+    let mut container = Container::open(buf).unwrap();
+    for (index, ty) in [1, 4, 2, 3].into_iter().enumerate() {
+        let handle = container.find_section_by_index(index as u32).unwrap();
+        let mut section = container.get_mut(handle);
+        assert_eq!(section.ty, ty);
+        assert_eq!(section.load().unwrap().load_in_memory().unwrap(), [ty]);
+    }
+    // Removing a section shifts the following ones back.
+    let handle = container.find_section_by_index(1).unwrap();
+    container.remove_section(handle);
+    let indices: Vec<(u32, u8)> = container.iter().map(|v| (v.index(), v.ty)).collect();
+    assert_eq!(indices, [(0, 1), (1, 2), (2, 3)]);
+}
+
 #[test]
 #[cfg(feature = "sd")]
 fn sd_api_test()
@@ -43,3 +157,704 @@ fn sd_api_test()
     assert_eq!(v1u, String::from("test"));
     assert_eq!(v2u, Some(0));
 }
+
+#[test]
+fn inflated_section_num()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    container.save().unwrap();
+    let mut buf = container.into_inner().into_inner();
+    // Only the main header exists: any section header read would fail with an io error.
+    buf[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+    let res = Container::open(std::io::Cursor::new(buf.clone()));
+    assert!(matches!(
+        res,
+        Err(ReadError::TooManySections(u32::MAX, DEFAULT_MAX_SECTIONS))
+    ));
+    buf[16..20].copy_from_slice(&8u32.to_le_bytes());
+    let res = Container::open_with_max_sections(std::io::Cursor::new(buf), 4);
+    assert!(matches!(res, Err(ReadError::TooManySections(8, 4))));
+}
+
+#[test]
+fn merge_containers()
+{
+    let mut first = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let a = first.create_section(SectionHeaderBuilder::new().ty(1));
+    first
+        .get_mut(a)
+        .open()
+        .unwrap()
+        .write_all(b"first")
+        .unwrap();
+
+    let mut second = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    for (ty, data) in [(2, &b"second"[..]), (3, &b"third"[..])] {
+        let handle = second.create_section(
+            SectionHeaderBuilder::new()
+                .ty(ty)
+                .compression(CompressionMethod::Zlib)
+                .threshold(0)
+        );
+        second
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(data)
+            .unwrap();
+    }
+    second.save().unwrap();
+    let mut buf = second.into_inner();
+    buf.set_position(0);
+    // Sections of the reopened container are not loaded.
+    let second = Container::open(buf).unwrap();
+
+    let handles = first.merge(second).unwrap();
+    assert_eq!(handles.len(), 2);
+    assert!(!handles.contains(&a));
+    assert_eq!(first.find_section_by_index(1), Some(handles[0]));
+    assert_eq!(first.find_section_by_index(2), Some(handles[1]));
+    first.save().unwrap();
+
+    let mut buf = first.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    assert_eq!(container.get_main_header().section_num, 3);
+    for (index, (ty, data)) in [(1, &b"first"[..]), (2, b"second"), (3, b"third")]
+        .into_iter()
+        .enumerate()
+    {
+        let handle = container.find_section_by_index(index as u32).unwrap();
+        let mut section = container.get_mut(handle);
+        assert_eq!(section.ty, ty);
+        assert_eq!(section.load().unwrap().load_in_memory().unwrap(), data);
+    }
+}
+
+/// A backend recording the furthest offset read.
+struct ReadTracker
+{
+    inner: std::io::Cursor<Vec<u8>>,
+    max_read: u64
+}
+
+impl std::io::Read for ReadTracker
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize>
+    {
+        let len = self.inner.read(buf)?;
+        self.max_read = self.max_read.max(self.inner.position());
+        Ok(len)
+    }
+}
+
+impl Seek for ReadTracker
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64>
+    {
+        self.inner.seek(pos)
+    }
+}
+
+fn write_three_sections() -> Vec<u8>
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    for ty in 1..4 {
+        let handle = container.create_section(SectionHeaderBuilder::new().ty(ty));
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&[ty; 128])
+            .unwrap();
+    }
+    container.save().unwrap();
+    container.into_inner().into_inner()
+}
+
+#[test]
+fn open_reads_headers_only()
+{
+    let buf = write_three_sections();
+    let mut container = Container::open(ReadTracker {
+        inner: std::io::Cursor::new(buf),
+        max_read: 0
+    })
+    .unwrap();
+    let headers_end = (SIZE_MAIN_HEADER + 3 * SIZE_SECTION_HEADER) as u64;
+    assert_eq!(container.get_main_header().section_num, 3);
+    assert_eq!(container.iter().count(), 3);
+    assert!(container.iter().all(|v| v.open().is_none()));
+    container.load_all().unwrap();
+    let backend = container.into_inner();
+    assert!(backend.max_read > headers_end);
+
+    let buf = backend.inner.into_inner();
+    let container = Container::open(ReadTracker {
+        inner: std::io::Cursor::new(buf),
+        max_read: 0
+    })
+    .unwrap();
+    assert!(container.into_inner().max_read <= headers_end);
+}
+
+#[test]
+fn load_all_reports_failing_section()
+{
+    let mut buf = write_three_sections();
+    // Truncate the file in the middle of the second section.
+    let container = Container::open(std::io::Cursor::new(buf.clone())).unwrap();
+    let second = container.find_section_by_type(2).unwrap();
+    let third = container.find_section_by_type(3).unwrap();
+    buf.truncate(container.get(second).pointer as usize + 64);
+    assert!(matches!(
+        Container::open(std::io::Cursor::new(buf.clone())),
+        Err(ReadError::Corrupted(CorruptionContext::SectionPastEnd(1)))
+    ));
+
+    let mut options = OpenOptions::new();
+    options.skip_validation(true);
+    let mut container =
+        Container::open_with_options(std::io::Cursor::new(buf.clone()), &options).unwrap();
+    match container.load_all() {
+        Err(ReadError::Section(handle, _)) => assert_eq!(handle, second),
+        _ => panic!("expected a section error")
+    }
+
+    let mut container = Container::open_with_options(std::io::Cursor::new(buf), &options).unwrap();
+    let errors = container.load_all_tolerant();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].0, second);
+    assert_eq!(errors[1].0, third);
+    let first = container.find_section_by_type(1).unwrap();
+    let data = container
+        .get_mut(first)
+        .open()
+        .unwrap()
+        .load_in_memory()
+        .unwrap();
+    assert_eq!(data, [1; 128]);
+}
+
+fn trailer_container(trailer: bool) -> Vec<u8>
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    for data in [&b"first section"[..], &b"last section without checksum"[..]] {
+        let handle = container.create_section(SectionHeaderBuilder::new());
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(data)
+            .unwrap();
+    }
+    match trailer {
+        true => container.save_with_trailer().unwrap(),
+        false => container.save().unwrap()
+    }
+    container.into_inner().into_inner()
+}
+
+#[test]
+fn whole_file_trailer()
+{
+    let buf = trailer_container(true);
+    let mut container = Container::open(std::io::Cursor::new(buf.clone())).unwrap();
+    assert!(container.has_valid_trailer().unwrap());
+
+    // Files without a trailer keep working.
+    let mut container = Container::open(std::io::Cursor::new(trailer_container(false))).unwrap();
+    assert!(!container.has_valid_trailer().unwrap());
+    container.load_all().unwrap();
+
+    // Truncated file: the trailer is missing some bytes.
+    let mut truncated = buf.clone();
+    truncated.truncate(buf.len() - 3);
+    let mut container = Container::open(std::io::Cursor::new(truncated)).unwrap();
+    assert!(!container.has_valid_trailer().unwrap());
+
+    // Truncated last section.
+    let mut truncated = buf.clone();
+    truncated.truncate(buf.len() - SIZE_TRAILER - 4);
+    let mut options = OpenOptions::new();
+    options.skip_validation(true);
+    let mut container =
+        Container::open_with_options(std::io::Cursor::new(truncated), &options).unwrap();
+    assert!(!container.has_valid_trailer().unwrap());
+
+    // Bit flip in the last section which has no checksum.
+    let mut flipped = buf.clone();
+    flipped[buf.len() - SIZE_TRAILER - 1] ^= 0x1;
+    assert!(matches!(
+        Container::open(std::io::Cursor::new(flipped.clone())),
+        Err(ReadError::Corrupted(CorruptionContext::Trailer))
+    ));
+    let mut options = OpenOptions::new();
+    options.verify_trailer(false);
+    let mut container =
+        Container::open_with_options(std::io::Cursor::new(flipped), &options).unwrap();
+    container.load_all().unwrap();
+    assert!(!container.has_valid_trailer().unwrap());
+}
+
+#[test]
+fn plain_save_removes_trailer()
+{
+    let buf = trailer_container(true);
+    let mut container = Container::open(std::io::Cursor::new(buf)).unwrap();
+    let last = container.find_section_by_index(1).unwrap();
+    // Same size rewrite of the last section: the stale trailer is right after the data.
+    {
+        let mut section = container.get_mut(last);
+        let data = section.load().unwrap();
+        data.seek(SeekFrom::Start(0)).unwrap();
+        data.write_all(b"LAST").unwrap();
+    }
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    assert!(!container.has_valid_trailer().unwrap());
+    let last = container.find_section_by_index(1).unwrap();
+    let data = container.get_mut(last).load().unwrap().load_in_memory().unwrap();
+    assert_eq!(data, b"LAST section without checksum");
+
+    // Saving with a trailer again restores it.
+    container.get_mut(last).load().unwrap().write_all(b"!").unwrap();
+    container.save_with_trailer().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    assert!(container.has_valid_trailer().unwrap());
+}
+
+#[test]
+fn reject_trailing_data()
+{
+    let strict = || {
+        let mut options = OpenOptions::new();
+        options.reject_trailing_data(true);
+        options
+    };
+    let buf = trailer_container(false);
+    assert!(Container::open_with_options(std::io::Cursor::new(buf.clone()), &strict()).is_ok());
+
+    let mut junk = buf.clone();
+    junk.extend_from_slice(b"appended payload");
+    assert!(Container::open(std::io::Cursor::new(junk.clone())).is_ok());
+    assert!(matches!(
+        Container::open_with_options(std::io::Cursor::new(junk), &strict()),
+        Err(ReadError::TrailingData(16))
+    ));
+
+    // A whole-file trailer is not trailing data, but anything after it is.
+    let mut buf = trailer_container(true);
+    assert!(Container::open_with_options(std::io::Cursor::new(buf.clone()), &strict()).is_ok());
+    buf.push(0);
+    assert!(matches!(
+        Container::open_with_options(std::io::Cursor::new(buf), &strict()),
+        Err(ReadError::TrailingData(1))
+    ));
+}
+
+#[test]
+fn reject_trailing_data_after_shrinking_save()
+{
+    let strict = || {
+        let mut options = OpenOptions::new();
+        options.reject_trailing_data(true);
+        options
+    };
+    let mut container = Container::create(tempfile::tempfile().unwrap(), MainHeaderBuilder::new());
+    let first = container.create_section(SectionHeaderBuilder::new().ty(1));
+    let last = container.create_section(SectionHeaderBuilder::new().ty(2));
+    for handle in [first, last] {
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&[0x42; 512])
+            .unwrap();
+    }
+    container.save().unwrap();
+    let mut file = container.into_inner();
+    let len = file.metadata().unwrap().len();
+
+    // Shrinking in place leaves the end of the previous version in the backend.
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut container = Container::open_with_options(file, &strict()).unwrap();
+    let first = container.find_section_by_type(1).unwrap();
+    let last = container.find_section_by_type(2).unwrap();
+    container.get_mut(first).load().unwrap().truncate(10).unwrap();
+    container.get_mut(last).load().unwrap();
+    container.save().unwrap();
+    let file_size = container.get_main_header().file_size;
+    assert_eq!(file_size, len - 502);
+    let mut out = new_byte_buf(0);
+    container.save_to(&mut out).unwrap();
+    let mut file = container.into_inner();
+    assert_eq!(file.metadata().unwrap().len(), len);
+    file.seek(SeekFrom::Start(0)).unwrap();
+    assert!(matches!(
+        Container::open_with_options(file, &strict()),
+        Err(ReadError::TrailingData(502))
+    ));
+
+    // The same container written to a new backend has no trailing data.
+    out.set_position(0);
+    let mut container = Container::open_with_options(out, &strict()).unwrap();
+    assert_eq!(container.get_main_header().file_size, file_size);
+    let first = container.find_section_by_type(1).unwrap();
+    assert_eq!(container.get_mut(first).load().unwrap().size(), 10);
+}
+
+#[test]
+fn find_all_sections_by_type_in_index_order()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let a = container.create_section(SectionHeaderBuilder::new().ty(1));
+    container.create_section(SectionHeaderBuilder::new().ty(2));
+    let b = container.create_section(SectionHeaderBuilder::new().ty(1));
+    let c = container.create_section(SectionHeaderBuilder::new().ty(3));
+    // Inserted last but placed first in the file.
+    let d = container.create_section_at(0, SectionHeaderBuilder::new().ty(1));
+    let all: Vec<_> = container.find_all_sections_by_type(1).collect();
+    assert_eq!(all, vec![d, a, b]);
+    assert_eq!(
+        container.find_all_sections_by_type(3).collect::<Vec<_>>(),
+        vec![c]
+    );
+    assert_eq!(container.find_all_sections_by_type(4).count(), 0);
+
+    container.remove_section(a);
+    let all: Vec<_> = container.find_all_sections_by_type(1).collect();
+    assert_eq!(all, vec![d, b]);
+    for handle in container.find_all_sections_by_type(1) {
+        assert_eq!(container.get(handle).ty, 1);
+    }
+}
+
+#[test]
+fn remove_section_reports_old_index()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let a = container.create_section(SectionHeaderBuilder::new().ty(1));
+    let b = container.create_section(SectionHeaderBuilder::new().ty(2));
+    let c = container.create_section(SectionHeaderBuilder::new().ty(3));
+    assert_eq!(container.remove_section(b).old_index, 1);
+    assert_eq!(container.get(a).index(), 0);
+    assert_eq!(container.get(c).index(), 1);
+    assert_eq!(container.remove_section(a).old_index, 0);
+    assert_eq!(container.get(c).index(), 0);
+}
+
+/// Rewrites the section headers of a saved container, updating the main header checksum.
+fn patch_section_headers<F: FnOnce(&mut [SectionHeader])>(buf: &mut [u8], f: F)
+{
+    let (_, mut main_header) =
+        MainHeader::from_bytes(buf[..SIZE_MAIN_HEADER].try_into().unwrap()).unwrap();
+    let mut sections: Vec<SectionHeader> = (0..main_header.section_num as usize)
+        .map(|i| {
+            let start = SIZE_MAIN_HEADER + i * SIZE_SECTION_HEADER;
+            let bytes = buf[start..start + SIZE_SECTION_HEADER].try_into().unwrap();
+            SectionHeader::from_bytes(bytes).unwrap().1
+        })
+        .collect();
+    f(&mut sections);
+    main_header.chksum = 0;
+    let sum = |bytes: &[u8]| bytes.iter().map(|v| *v as u32).sum::<u32>();
+    main_header.chksum =
+        sum(&main_header.to_bytes()) + sections.iter().map(|v| sum(&v.to_bytes())).sum::<u32>();
+    buf[..SIZE_MAIN_HEADER].copy_from_slice(&main_header.to_bytes());
+    for (i, section) in sections.iter().enumerate() {
+        let start = SIZE_MAIN_HEADER + i * SIZE_SECTION_HEADER;
+        buf[start..start + SIZE_SECTION_HEADER].copy_from_slice(&section.to_bytes());
+    }
+}
+
+#[test]
+fn corrupted_section_table()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    container.save().unwrap();
+    let mut buf = container.into_inner().into_inner();
+    buf[16..20].copy_from_slice(&0xFFFFu32.to_le_bytes());
+    assert!(matches!(
+        Container::open(std::io::Cursor::new(buf)),
+        Err(ReadError::Corrupted(CorruptionContext::SectionTable))
+    ));
+}
+
+#[test]
+fn corrupted_section_bounds()
+{
+    let buf = write_three_sections();
+    let open = |buf: &Vec<u8>| Container::open(std::io::Cursor::new(buf.clone()));
+    assert!(open(&buf).is_ok());
+    let len = buf.len() as u64;
+
+    let mut past_end = buf.clone();
+    patch_section_headers(&mut past_end, |sections| sections[1].pointer = len - 2);
+    assert!(matches!(
+        open(&past_end),
+        Err(ReadError::Corrupted(CorruptionContext::SectionPastEnd(1)))
+    ));
+
+    let mut overflow = buf.clone();
+    patch_section_headers(&mut overflow, |sections| sections[2].pointer = u64::MAX);
+    assert!(matches!(
+        open(&overflow),
+        Err(ReadError::Corrupted(CorruptionContext::SectionPastEnd(2)))
+    ));
+
+    let mut csize = buf.clone();
+    patch_section_headers(&mut csize, |sections| sections[0].csize = len as u32);
+    assert!(matches!(
+        open(&csize),
+        Err(ReadError::Corrupted(CorruptionContext::SectionPastEnd(0)))
+    ));
+
+    let mut overlap = buf.clone();
+    patch_section_headers(&mut overlap, |sections| sections[0].pointer = 8);
+    assert!(matches!(
+        open(&overlap),
+        Err(ReadError::Corrupted(
+            CorruptionContext::SectionOverlapsHeaders(0)
+        ))
+    ));
+
+    // Recovery tools can still open the file and access the intact sections.
+    let mut options = OpenOptions::new();
+    options.skip_validation(true);
+    let mut container =
+        Container::open_with_options(std::io::Cursor::new(past_end), &options).unwrap();
+    let first = container.find_section_by_index(0).unwrap();
+    assert!(container.get_mut(first).load().is_ok());
+}
+
+#[test]
+fn section_size_limit()
+{
+    let mut buf = write_three_sections();
+    // The header claims almost 4GB over a payload of 128 bytes.
+    patch_section_headers(&mut buf, |sections| sections[0].size = u32::MAX - 1);
+    let mut container = Container::open_untrusted(std::io::Cursor::new(buf.clone())).unwrap();
+    // Even a fully in-memory section would fail before allocating.
+    container.set_memory_threshold(usize::MAX);
+    let first = container.find_section_by_index(0).unwrap();
+    assert!(matches!(
+        container.get_mut(first).load(),
+        Err(ReadError::SectionTooLarge(size, UNTRUSTED_MAX_SECTION_SIZE)) if size == u32::MAX - 1
+    ));
+    let second = container.find_section_by_index(1).unwrap();
+    assert!(container.get_mut(second).load().is_ok());
+
+    let mut options = OpenOptions::new();
+    options.max_section_size(Some(64));
+    let mut container = Container::open_with_options(std::io::Cursor::new(buf), &options).unwrap();
+    let second = container.find_section_by_index(1).unwrap();
+    assert!(matches!(
+        container.get_mut(second).load(),
+        Err(ReadError::SectionTooLarge(128, 64))
+    ));
+    container.set_max_section_size(None);
+    assert!(container.get_mut(second).load().is_ok());
+}
+
+#[test]
+fn section_decoding_past_declared_size()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = container.create_section(
+        SectionHeaderBuilder::new()
+            .compression(CompressionMethod::Zlib)
+            .threshold(0)
+    );
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(&[0; 65536])
+        .unwrap();
+    container.save().unwrap();
+    let mut buf = container.into_inner().into_inner();
+    patch_section_headers(&mut buf, |sections| sections[0].size = 16);
+
+    // Without limits, the section is decoded anyway.
+    let mut container = Container::open(std::io::Cursor::new(buf.clone())).unwrap();
+    let handle = container.find_section_by_index(0).unwrap();
+    assert_eq!(container.get_mut(handle).load().unwrap().size(), 65536);
+
+    let mut container = Container::open_untrusted(std::io::Cursor::new(buf)).unwrap();
+    let handle = container.find_section_by_index(0).unwrap();
+    assert!(container.get_mut(handle).load().is_err());
+    assert!(container.get(handle).open().is_none());
+}
+
+#[test]
+fn total_memory_limit()
+{
+    let buf = write_three_sections();
+    let mut options = OpenOptions::new();
+    options.max_total_memory(Some(300));
+    let mut container = Container::open_with_options(std::io::Cursor::new(buf), &options).unwrap();
+    let third = container.find_section_by_index(2).unwrap();
+    match container.load_all() {
+        Err(ReadError::Section(handle, e)) => {
+            assert_eq!(handle, third);
+            assert!(matches!(*e, ReadError::SectionTooLarge(128, 44)));
+        },
+        _ => panic!("expected a section error")
+    }
+    container.set_max_total_memory(Some(384));
+    container.load_all().unwrap();
+    assert_eq!(container.get(third).open().unwrap().size(), 128);
+}
+
+#[test]
+fn invalid_handles_are_recoverable()
+{
+    let mut other = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    for _ in 0..3 {
+        other.create_section(SectionHeaderBuilder::new());
+    }
+    let foreign = other.find_section_by_index(2).unwrap();
+
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let removed = container.create_section(SectionHeaderBuilder::new());
+    container.remove_section(removed);
+    let kept = container.create_section(SectionHeaderBuilder::new().ty(1));
+    assert!(matches!(
+        container.try_get(removed),
+        Err(ReadError::InvalidHandle(h)) if h == removed
+    ));
+    assert!(matches!(
+        container.try_get_mut(removed),
+        Err(ReadError::InvalidHandle(_))
+    ));
+    assert!(matches!(
+        container.try_get(foreign),
+        Err(ReadError::InvalidHandle(_))
+    ));
+    assert_eq!(container.try_get(kept).unwrap().ty, 1);
+    container
+        .try_get_mut(kept)
+        .unwrap()
+        .open()
+        .unwrap()
+        .write_all(b"data")
+        .unwrap();
+    assert_eq!(
+        container
+            .get_mut(kept)
+            .load()
+            .unwrap()
+            .load_in_memory()
+            .unwrap(),
+        b"data"
+    );
+}
+
+#[test]
+fn try_accessors_after_remove()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let first = container.create_section(SectionHeaderBuilder::new().ty(1));
+    let second = container.create_section(SectionHeaderBuilder::new().ty(2));
+    assert_eq!(container.try_index(second).unwrap(), 1);
+    container.remove_section(first);
+    assert!(matches!(
+        container.try_header(first),
+        Err(ReadError::InvalidHandle(_))
+    ));
+    assert!(matches!(
+        container.try_index(first),
+        Err(ReadError::InvalidHandle(_))
+    ));
+    assert_eq!(container.try_header(second).unwrap().ty, 2);
+    assert_eq!(container.try_index(second).unwrap(), 0);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn handle_used_on_second_container()
+{
+    let mut first = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let mut second = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = first.create_section(SectionHeaderBuilder::new().ty(1));
+    // Same key as `handle`.
+    second.create_section(SectionHeaderBuilder::new().ty(2));
+    assert!(matches!(
+        second.try_get(handle),
+        Err(ReadError::InvalidHandle(h)) if h == handle
+    ));
+    assert!(matches!(
+        second.try_get_mut(handle),
+        Err(ReadError::InvalidHandle(_))
+    ));
+    assert!(second.try_header(handle).is_err());
+    assert!(second.try_index(handle).is_err());
+    assert_eq!(first.get(handle).ty, 1);
+
+    // The raw key can still be carried over explicitly.
+    let raw = unsafe { bpx::Handle::from_raw(handle.into_raw()) };
+    assert_eq!(second.get(raw).ty, 2);
+    assert_eq!(first.get(raw).ty, 1);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "attempt to use invalid handle")]
+fn get_mut_with_handle_from_second_container()
+{
+    let mut first = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let mut second = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = first.create_section(SectionHeaderBuilder::new());
+    second.create_section(SectionHeaderBuilder::new());
+    second.get_mut(handle);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "attempt to use invalid handle")]
+fn remove_section_with_handle_from_second_container()
+{
+    let mut first = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let mut second = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = first.create_section(SectionHeaderBuilder::new());
+    second.create_section(SectionHeaderBuilder::new());
+    second.remove_section(handle);
+}
+
+#[test]
+fn save_with_progress_reports_sections()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let sizes = [10, 2000, 300];
+    for size in sizes {
+        let handle = container.create_section(
+            SectionHeaderBuilder::new()
+                .compression(CompressionMethod::Zlib)
+                .threshold(0)
+        );
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&vec![1; size])
+            .unwrap();
+    }
+    let mut events = Vec::new();
+    container
+        .save_with_progress(|event| events.push(event))
+        .unwrap();
+    assert_eq!(events.len(), 3);
+    let mut bytes = 0;
+    for (i, event) in events.iter().enumerate() {
+        assert_eq!(event.index, i as u32);
+        assert_eq!(event.size as usize, sizes[i]);
+        bytes += sizes[i] as u64;
+        assert_eq!(event.bytes, bytes);
+        assert_eq!(event.total, 2310);
+    }
+}
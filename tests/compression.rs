@@ -0,0 +1,887 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path
+};
+
+use bpx::{
+    core::{
+        builder::{
+            Checksum,
+            CompressionLevel,
+            CompressionMethod,
+            MainHeaderBuilder,
+            RepackOptions,
+            SectionHeaderBuilder
+        },
+        error::ReadError,
+        header::{
+            SectionHeader,
+            FLAG_CHECK_CRC32,
+            FLAG_CHECK_WEAK,
+            FLAG_COMPRESS_XZ,
+            FLAG_COMPRESS_ZLIB
+        },
+        ChecksumStatus,
+        CompressionKind,
+        Container,
+        SectionData,
+        DEFAULT_COMPRESSION_THRESHOLD
+    },
+    utils::new_byte_buf
+};
+
+const SIZE_BELOW: usize = 1024;
+const SIZE_ABOVE: usize = DEFAULT_COMPRESSION_THRESHOLD as usize + 4096;
+
+fn generate_data(size: usize) -> Vec<u8>
+{
+    // Simple LCG mixed with a repeating pattern to get data which is both compressible and
+    // not trivially made of zeros.
+    let mut state: u32 = 0x12345678;
+    (0..size)
+        .map(|i| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            if i % 4 == 0 {
+                (state >> 24) as u8
+            } else {
+                b"BPX-"[i % 4]
+            }
+        })
+        .collect()
+}
+
+fn weak_checksum(data: &[u8]) -> u32
+{
+    data.iter().fold(0u32, |acc, v| acc.wrapping_add(*v as u32))
+}
+
+fn crc32_checksum(data: &[u8]) -> u32
+{
+    // Bitwise reference implementation of the standard CRC32 (IEEE 802.3).
+    let mut crc: u32 = 0xFFFFFFFF;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            if crc & 1 != 0 {
+                crc = (crc >> 1) ^ 0xEDB88320;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    !crc
+}
+
+fn expected_flags(
+    compression: Option<CompressionMethod>,
+    checksum: Option<Checksum>,
+    size: usize,
+    threshold: u32
+) -> u8
+{
+    let mut flags = 0;
+    match checksum {
+        Some(Checksum::Weak) => flags |= FLAG_CHECK_WEAK,
+        Some(Checksum::Crc32) => flags |= FLAG_CHECK_CRC32,
+        None => ()
+    }
+    if size > threshold as usize {
+        match compression {
+            Some(CompressionMethod::Xz) => flags |= FLAG_COMPRESS_XZ,
+            Some(CompressionMethod::Zlib) => flags |= FLAG_COMPRESS_ZLIB,
+            None => ()
+        }
+    }
+    flags
+}
+
+fn write_container(
+    compression: Option<CompressionMethod>,
+    checksum: Option<Checksum>,
+    threshold: u32,
+    data: &[u8]
+) -> Vec<u8>
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let mut builder = SectionHeaderBuilder::new();
+    builder.ty(1);
+    if let Some(method) = compression {
+        builder.compression(method).threshold(threshold);
+    }
+    if let Some(chksum) = checksum {
+        builder.checksum(chksum);
+    }
+    let handle = container.create_section(&mut builder);
+    {
+        let mut section = container.get_mut(handle);
+        section.open().unwrap().write_all(data).unwrap();
+    }
+    container.save().unwrap();
+    container.into_inner().into_inner()
+}
+
+fn check_header(header: &SectionHeader, flags: u8, data: &[u8], file_size: u64)
+{
+    assert_eq!(header.flags, flags);
+    assert_eq!(header.size as usize, data.len());
+    assert!(header.pointer + header.csize as u64 <= file_size);
+    if flags & (FLAG_COMPRESS_XZ | FLAG_COMPRESS_ZLIB) == 0 {
+        assert_eq!(header.csize, header.size);
+    } else {
+        assert!(header.csize < header.size);
+    }
+    if flags & FLAG_CHECK_WEAK != 0 {
+        assert_eq!(header.chksum, weak_checksum(data));
+    } else if flags & FLAG_CHECK_CRC32 != 0 {
+        assert_eq!(header.chksum, crc32_checksum(data));
+    } else {
+        assert_eq!(header.chksum, 0);
+    }
+}
+
+fn check_cell(compression: Option<CompressionMethod>, checksum: Option<Checksum>, size: usize)
+{
+    let data = generate_data(size);
+    let buf = write_container(compression, checksum, DEFAULT_COMPRESSION_THRESHOLD, &data);
+    let file_size = buf.len() as u64;
+    let mut container = Container::open(std::io::Cursor::new(buf)).unwrap();
+    assert_eq!(container.get_main_header().file_size, file_size);
+    let handle = container.find_section_by_type(1).unwrap();
+    let flags = expected_flags(compression, checksum, size, DEFAULT_COMPRESSION_THRESHOLD);
+    check_header(&container.get(handle), flags, &data, file_size);
+    let mut section = container.get_mut(handle);
+    let loaded = section.load().unwrap().load_in_memory().unwrap();
+    assert_eq!(
+        loaded, data,
+        "content mismatch with {:?}/{:?}/{}",
+        compression, checksum, size
+    );
+}
+
+#[test]
+fn compression_checksum_threshold_matrix()
+{
+    let compressions = [
+        None,
+        Some(CompressionMethod::Zlib),
+        Some(CompressionMethod::Xz)
+    ];
+    let checksums = [None, Some(Checksum::Weak), Some(Checksum::Crc32)];
+    for compression in compressions {
+        for checksum in checksums {
+            for size in [SIZE_BELOW, SIZE_ABOVE] {
+                check_cell(compression, checksum, size);
+            }
+        }
+    }
+}
+
+#[test]
+fn crc32_reference()
+{
+    assert_eq!(crc32_checksum(b"123456789"), 0xCBF43926);
+}
+
+fn legacy_crc32_checksum(data: &[u8]) -> u32
+{
+    // Older revisions built their CRC32 table with a single round instead of 8.
+    let table: Vec<u32> = (0..256u32)
+        .map(|i| match i & 1 != 0 {
+            true => (i >> 1) ^ 0xEDB88320,
+            false => i >> 1
+        })
+        .collect();
+    let mut crc: u32 = 0xFFFFFFFF;
+    for byte in data {
+        crc = (crc >> 8) ^ table[((crc ^ *byte as u32) & 0xFF) as usize];
+    }
+    !crc
+}
+
+#[test]
+fn legacy_crc32_accepted()
+{
+    let data = generate_data(SIZE_BELOW);
+    let mut buf = write_container(
+        None,
+        Some(Checksum::Crc32),
+        DEFAULT_COMPRESSION_THRESHOLD,
+        &data
+    );
+    // Replace the section checksum by the legacy one, keeping the header checksum valid.
+    let start =
+        bpx::spec::core::MAIN_HEADER_SIZE + bpx::spec::core::SECTION_HEADER_CHECKSUM_OFFSET;
+    let sum = |bytes: &[u8]| bytes.iter().map(|v| *v as u32).sum::<u32>();
+    let prev = sum(&buf[start..start + 4]);
+    buf[start..start + 4].copy_from_slice(&legacy_crc32_checksum(&data).to_le_bytes());
+    let chksum = u32::from_le_bytes(buf[4..8].try_into().unwrap())
+        .wrapping_add(sum(&buf[start..start + 4]))
+        .wrapping_sub(prev);
+    buf[4..8].copy_from_slice(&chksum.to_le_bytes());
+
+    let mut container = Container::open(std::io::Cursor::new(buf)).unwrap();
+    let handle = container.find_section_by_type(1).unwrap();
+    assert!(container.verify_section(handle).is_ok());
+    let report = container.verify().unwrap();
+    assert!(report.is_valid());
+    assert_eq!(report.sections[0], (handle, ChecksumStatus::Legacy));
+    let mut loaded = Vec::new();
+    container
+        .section_reader(handle)
+        .unwrap()
+        .read_to_end(&mut loaded)
+        .unwrap();
+    assert_eq!(loaded, data);
+    let mut section = container.get_mut(handle);
+    assert_eq!(section.load().unwrap().load_in_memory().unwrap(), data);
+}
+
+#[test]
+fn resave_last_section()
+{
+    let data = generate_data(SIZE_ABOVE);
+    let buf = write_container(
+        Some(CompressionMethod::Zlib),
+        Some(Checksum::Crc32),
+        DEFAULT_COMPRESSION_THRESHOLD,
+        &data
+    );
+    let mut container = Container::open(std::io::Cursor::new(buf)).unwrap();
+    let handle = container.find_section_by_type(1).unwrap();
+    {
+        let mut section = container.get_mut(handle);
+        let data = section.load().unwrap();
+        data.seek(SeekFrom::End(0)).unwrap();
+        data.write_all(b"appended").unwrap();
+    }
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    let handle = container.find_section_by_type(1).unwrap();
+    let mut section = container.get_mut(handle);
+    let loaded = section.load().unwrap().load_in_memory().unwrap();
+    assert_eq!(&loaded[..data.len()], &data[..]);
+    assert_eq!(&loaded[data.len()..], b"appended");
+}
+
+fn golden_path(name: &str) -> String
+{
+    format!("{}/tests/data/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+const GOLDEN_CELLS: &[(&str, Option<CompressionMethod>, Option<Checksum>)] = &[
+    ("golden_none.bpx", None, Some(Checksum::Weak)),
+    (
+        "golden_zlib.bpx",
+        Some(CompressionMethod::Zlib),
+        Some(Checksum::Crc32)
+    ),
+    (
+        "golden_xz.bpx",
+        Some(CompressionMethod::Xz),
+        Some(Checksum::Crc32)
+    )
+];
+
+// Golden files force compression through a null threshold to keep the fixtures small.
+const GOLDEN_SIZE: usize = 4096;
+
+#[test]
+fn golden_files()
+{
+    let data = generate_data(GOLDEN_SIZE);
+    for (name, compression, checksum) in GOLDEN_CELLS {
+        let file = File::open(golden_path(name)).unwrap();
+        let file_size = file.metadata().unwrap().len();
+        let mut container = Container::open(file).unwrap();
+        let handle = container.find_section_by_type(1).unwrap();
+        let flags = expected_flags(*compression, *checksum, data.len(), 0);
+        check_header(&container.get(handle), flags, &data, file_size);
+        let mut section = container.get_mut(handle);
+        let loaded = section.load().unwrap().load_in_memory().unwrap();
+        assert_eq!(loaded, data, "golden file {} does not decode", name);
+    }
+}
+
+/// Re-generates the golden files; only run this when the on-disk format intentionally changes.
+#[test]
+#[ignore]
+fn regenerate_golden_files()
+{
+    let data = generate_data(GOLDEN_SIZE);
+    for (name, compression, checksum) in GOLDEN_CELLS {
+        let buf = write_container(*compression, *checksum, 0, &data);
+        let mut file = File::create(Path::new(&golden_path(name))).unwrap();
+        file.write_all(&buf).unwrap();
+    }
+}
+
+#[test]
+fn verify_detects_corruption()
+{
+    let data = generate_data(SIZE_ABOVE);
+    for compression in [None, Some(CompressionMethod::Zlib)] {
+        let buf = write_container(
+            compression,
+            Some(Checksum::Crc32),
+            DEFAULT_COMPRESSION_THRESHOLD,
+            &data
+        );
+        let mut container = Container::open(std::io::Cursor::new(buf.clone())).unwrap();
+        let report = container.verify().unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.main_header, ChecksumStatus::Valid);
+        let handle = container.find_section_by_type(1).unwrap();
+        assert_eq!(report.sections, vec![(handle, ChecksumStatus::Valid)]);
+
+        // Corrupt the last byte of section data.
+        let mut corrupted = buf.clone();
+        *corrupted.last_mut().unwrap() ^= 0xFF;
+        let mut container = Container::open(std::io::Cursor::new(corrupted)).unwrap();
+        match container.verify() {
+            Ok(report) => {
+                assert!(!report.is_valid());
+                assert_eq!(report.main_header, ChecksumStatus::Valid);
+                assert!(matches!(
+                    report.sections[0].1,
+                    ChecksumStatus::Mismatch(_, _)
+                ));
+            },
+            // Corrupted compressed streams may also fail to decompress.
+            Err(_) => assert!(compression.is_some())
+        }
+    }
+}
+
+#[test]
+fn skip_checksum_loads_corrupted_section()
+{
+    let data = generate_data(SIZE_BELOW);
+    let mut buf = write_container(
+        None,
+        Some(Checksum::Crc32),
+        DEFAULT_COMPRESSION_THRESHOLD,
+        &data
+    );
+    *buf.last_mut().unwrap() ^= 0xFF;
+    let mut container = Container::open(std::io::Cursor::new(buf.clone())).unwrap();
+    let handle = container.find_section_by_type(1).unwrap();
+    assert!(matches!(
+        container.get_mut(handle).load(),
+        Err(ReadError::Checksum(_, _))
+    ));
+
+    let mut container = Container::open(std::io::Cursor::new(buf)).unwrap();
+    container.set_skip_checksum(true);
+    let handle = container.find_section_by_type(1).unwrap();
+    assert!(matches!(
+        container.verify_section(handle),
+        Err(ReadError::Checksum(_, _))
+    ));
+    let mut loaded = Vec::new();
+    container
+        .get_mut(handle)
+        .load()
+        .unwrap()
+        .read_to_end(&mut loaded)
+        .unwrap();
+    assert_eq!(loaded.len(), data.len());
+    assert_eq!(&loaded[..data.len() - 1], &data[..data.len() - 1]);
+    assert_ne!(loaded.last(), data.last());
+}
+
+#[test]
+fn section_reader_streams()
+{
+    let compressions = [
+        None,
+        Some(CompressionMethod::Zlib),
+        Some(CompressionMethod::Xz)
+    ];
+    let data = generate_data(SIZE_ABOVE);
+    for compression in compressions {
+        let buf = write_container(
+            compression,
+            Some(Checksum::Crc32),
+            DEFAULT_COMPRESSION_THRESHOLD,
+            &data
+        );
+        let mut container = Container::open(std::io::Cursor::new(buf.clone())).unwrap();
+        let handle = container.find_section_by_type(1).unwrap();
+        let mut reader = container.section_reader(handle).unwrap();
+        // Small reads to go through the decoders block by block.
+        let mut streamed = Vec::new();
+        let mut block = [0; 1000];
+        loop {
+            let len = reader.read(&mut block).unwrap();
+            if len == 0 {
+                break;
+            }
+            streamed.extend_from_slice(&block[..len]);
+        }
+        assert_eq!(streamed, data, "content mismatch with {:?}", compression);
+        assert!(container.get(handle).open().is_none());
+
+        if compression.is_none() {
+            // The checksum is verified at the end of the section.
+            let mut corrupted = buf.clone();
+            *corrupted.last_mut().unwrap() ^= 0xFF;
+            let mut container = Container::open(std::io::Cursor::new(corrupted)).unwrap();
+            let mut reader = container.section_reader(handle).unwrap();
+            let mut streamed = Vec::new();
+            assert!(reader.read_to_end(&mut streamed).is_err());
+        }
+    }
+}
+
+#[test]
+fn compression_summary_counts()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let data = generate_data(SIZE_BELOW);
+    let methods = [
+        Some(CompressionMethod::Xz),
+        Some(CompressionMethod::Zlib),
+        Some(CompressionMethod::Zlib),
+        None,
+        None,
+        None
+    ];
+    for method in methods {
+        let mut builder = SectionHeaderBuilder::new();
+        if let Some(method) = method {
+            builder.compression(method).threshold(0);
+        }
+        let handle = container.create_section(&mut builder);
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&data)
+            .unwrap();
+    }
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let container = Container::open(buf).unwrap();
+    let summary = container.compression_summary();
+    assert_eq!(summary.len(), 3);
+    assert_eq!(summary[&CompressionKind::Xz], 1);
+    assert_eq!(summary[&CompressionKind::Zlib], 2);
+    assert_eq!(summary[&CompressionKind::None], 3);
+}
+
+#[test]
+fn sections_by_size_largest_first()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let tiny = container.create_section(SectionHeaderBuilder::new());
+    let compressed = container.create_section(
+        SectionHeaderBuilder::new()
+            .compression(CompressionMethod::Zlib)
+            .threshold(0)
+    );
+    let raw = container.create_section(SectionHeaderBuilder::new());
+    container
+        .get_mut(tiny)
+        .open()
+        .unwrap()
+        .write_all(b"tiny")
+        .unwrap();
+    container
+        .get_mut(compressed)
+        .open()
+        .unwrap()
+        .write_all(&[0; 65536])
+        .unwrap();
+    container
+        .get_mut(raw)
+        .open()
+        .unwrap()
+        .write_all(&generate_data(4096))
+        .unwrap();
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let container = Container::open(buf).unwrap();
+    let sizes = container.sections_by_size();
+    assert_eq!(sizes.len(), 3);
+    assert_eq!(sizes[0], (raw, 4096));
+    assert_eq!(sizes[1].0, compressed);
+    assert_eq!(sizes[2], (tiny, 4));
+    assert!(sizes[1].1 < 4096 && sizes[1].1 > 4);
+}
+
+fn compressed_size(method: CompressionMethod, level: Option<CompressionLevel>, data: &[u8]) -> u32
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let mut builder = SectionHeaderBuilder::new();
+    builder.ty(1).compression(method).checksum(Checksum::Crc32);
+    if let Some(level) = level {
+        builder.level(level);
+    }
+    let handle = container.create_section(&mut builder);
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(data)
+        .unwrap();
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    let handle = container.find_section_by_index(0).unwrap();
+    let csize = container.get(handle).csize;
+    let loaded = container
+        .get_mut(handle)
+        .load()
+        .unwrap()
+        .load_in_memory()
+        .unwrap();
+    assert_eq!(
+        loaded, data,
+        "content mismatch with {:?}/{:?}",
+        method, level
+    );
+    csize
+}
+
+#[test]
+fn compression_levels()
+{
+    let data = generate_data(SIZE_ABOVE);
+    let levels = [
+        None,
+        Some(CompressionLevel::Fast),
+        Some(CompressionLevel::Balanced),
+        Some(CompressionLevel::Best),
+        Some(CompressionLevel::Preset(0)),
+        Some(CompressionLevel::Preset(42))
+    ];
+    for method in [CompressionMethod::Zlib, CompressionMethod::Xz] {
+        for level in levels {
+            compressed_size(method, level, &data);
+        }
+        let fast = compressed_size(method, Some(CompressionLevel::Fast), &data);
+        let best = compressed_size(method, Some(CompressionLevel::Best), &data);
+        // The level must reach the compressor.
+        assert_ne!(best, fast, "{:?}", method);
+    }
+    // Not setting a level keeps the default zlib level.
+    assert_eq!(
+        compressed_size(CompressionMethod::Zlib, None, &data),
+        compressed_size(
+            CompressionMethod::Zlib,
+            Some(CompressionLevel::Preset(6)),
+            &data
+        )
+    );
+}
+
+#[test]
+fn checksum_of_matches_manual_hash()
+{
+    let data = generate_data(SIZE_ABOVE);
+    for compression in [
+        None,
+        Some(CompressionMethod::Zlib),
+        Some(CompressionMethod::Xz)
+    ] {
+        let buf = write_container(compression, None, DEFAULT_COMPRESSION_THRESHOLD, &data);
+        let mut container = Container::open(std::io::Cursor::new(buf)).unwrap();
+        let handle = container.find_section_by_type(1).unwrap();
+        let crc32 = container.checksum_of(handle, Checksum::Crc32).unwrap();
+        let weak = container.checksum_of(handle, Checksum::Weak).unwrap();
+        assert!(container.get(handle).open().is_none());
+
+        // The long way: load then hash manually.
+        let loaded = container
+            .get_mut(handle)
+            .load()
+            .unwrap()
+            .load_in_memory()
+            .unwrap();
+        assert_eq!(crc32, crc32_checksum(&loaded));
+        assert_eq!(weak, weak_checksum(&loaded));
+        // Loaded sections are hashed from memory.
+        assert_eq!(
+            container.checksum_of(handle, Checksum::Crc32).unwrap(),
+            crc32
+        );
+    }
+}
+
+#[test]
+fn recompress_existing_section()
+{
+    let data = generate_data(SIZE_ABOVE);
+    let buf = write_container(
+        None,
+        Some(Checksum::Crc32),
+        DEFAULT_COMPRESSION_THRESHOLD,
+        &data
+    );
+    let mut container = Container::open(std::io::Cursor::new(buf)).unwrap();
+    let handle = container.find_section_by_type(1).unwrap();
+    assert_eq!(container.get(handle).csize, container.get(handle).size);
+    let policy = container.get_section_policy(handle);
+    assert_eq!(policy.flags, FLAG_CHECK_CRC32);
+    container.set_flags(handle, policy.flags | FLAG_COMPRESS_ZLIB).unwrap();
+    container.set_compression_threshold(handle, 0).unwrap();
+    assert_eq!(container.get_section_policy(handle).threshold, 0);
+    container.get_mut(handle).load().unwrap();
+    container.save().unwrap();
+
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    let handle = container.find_section_by_type(1).unwrap();
+    let header = *container.get(handle);
+    assert_eq!(header.flags, FLAG_CHECK_CRC32 | FLAG_COMPRESS_ZLIB);
+    assert!(header.csize < header.size);
+    let loaded = container
+        .get_mut(handle)
+        .load()
+        .unwrap()
+        .load_in_memory()
+        .unwrap();
+    assert_eq!(loaded, data);
+}
+
+#[test]
+fn set_flags_on_unloaded_section()
+{
+    let data = generate_data(SIZE_ABOVE);
+    let buf = write_container(None, None, DEFAULT_COMPRESSION_THRESHOLD, &data);
+    let mut container = Container::open(std::io::Cursor::new(buf)).unwrap();
+    let handle = container.find_section_by_type(1).unwrap();
+    container.set_flags(handle, FLAG_COMPRESS_XZ).unwrap();
+    container.set_compression_threshold(handle, 0).unwrap();
+    container.save().unwrap();
+
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    let handle = container.find_section_by_type(1).unwrap();
+    assert_eq!(container.get(handle).flags, FLAG_COMPRESS_XZ);
+    let loaded = container
+        .get_mut(handle)
+        .load()
+        .unwrap()
+        .load_in_memory()
+        .unwrap();
+    assert_eq!(loaded, data);
+}
+
+#[test]
+fn copy_compressed_section()
+{
+    let data = generate_data(SIZE_ABOVE);
+    let buf = write_container(
+        Some(CompressionMethod::Xz),
+        Some(Checksum::Weak),
+        DEFAULT_COMPRESSION_THRESHOLD,
+        &data
+    );
+    let mut src = Container::open(std::io::Cursor::new(buf)).unwrap();
+    let handle = src.find_section_by_type(1).unwrap();
+    assert!(src.get(handle).flags & FLAG_COMPRESS_XZ != 0);
+
+    let mut dst = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let xz = dst.copy_section_from(&mut src, handle).unwrap();
+    let zlib = dst.copy_section_from(&mut src, handle).unwrap();
+    assert_eq!(dst.get(xz).ty, 1);
+    assert_eq!(dst.get(xz).flags, src.get(handle).flags);
+    dst.set_flags(zlib, FLAG_CHECK_WEAK | FLAG_COMPRESS_ZLIB).unwrap();
+    dst.save().unwrap();
+
+    let mut buf = dst.into_inner();
+    buf.set_position(0);
+    let mut dst = Container::open(buf).unwrap();
+    let xz = dst.find_section_by_index(0).unwrap();
+    let zlib = dst.find_section_by_index(1).unwrap();
+    assert_eq!(dst.get(xz).flags, FLAG_CHECK_WEAK | FLAG_COMPRESS_XZ);
+    assert_eq!(dst.get(zlib).flags, FLAG_CHECK_WEAK | FLAG_COMPRESS_ZLIB);
+    for handle in [xz, zlib] {
+        let loaded = dst
+            .get_mut(handle)
+            .load()
+            .unwrap()
+            .load_in_memory()
+            .unwrap();
+        assert_eq!(loaded, data);
+    }
+}
+
+#[test]
+fn repack_xz_to_zlib()
+{
+    let data = generate_data(SIZE_ABOVE);
+    let buf = write_container(
+        Some(CompressionMethod::Xz),
+        Some(Checksum::Weak),
+        DEFAULT_COMPRESSION_THRESHOLD,
+        &data
+    );
+    let mut container = Container::open(std::io::Cursor::new(buf)).unwrap();
+    let other = container.create_section(SectionHeaderBuilder::new().ty(2));
+    container
+        .get_mut(other)
+        .open()
+        .unwrap()
+        .write_all(&data)
+        .unwrap();
+    container
+        .repack(
+            RepackOptions::new()
+                .all(
+                    SectionHeaderBuilder::new()
+                        .compression(CompressionMethod::Zlib)
+                        .checksum(Checksum::Crc32)
+                )
+                .ty(2, SectionHeaderBuilder::new().checksum(Checksum::Weak))
+        )
+        .unwrap();
+
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    let handle = container.find_section_by_type(1).unwrap();
+    let other = container.find_section_by_type(2).unwrap();
+    assert_eq!(
+        container.get(handle).flags,
+        FLAG_COMPRESS_ZLIB | FLAG_CHECK_CRC32
+    );
+    assert_eq!(container.get(other).flags, FLAG_CHECK_WEAK);
+    for handle in [handle, other] {
+        let loaded = container
+            .get_mut(handle)
+            .load()
+            .unwrap()
+            .load_in_memory()
+            .unwrap();
+        assert_eq!(loaded, data);
+    }
+    assert!(container.verify().unwrap().is_valid());
+}
+
+#[test]
+fn verify_pinpoints_corrupted_section()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let first = container.create_section(SectionHeaderBuilder::new().checksum(Checksum::Crc32));
+    let second = container.create_section(SectionHeaderBuilder::new().checksum(Checksum::Weak));
+    let third = container.create_section(SectionHeaderBuilder::new());
+    for (handle, byte) in [(first, 1), (second, 2), (third, 3)] {
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&[byte; 64])
+            .unwrap();
+    }
+    container.save().unwrap();
+    let mut buf = container.into_inner().into_inner();
+    // Corrupt the first byte of the second section.
+    let pos = buf.windows(64).position(|v| v == [2; 64]).unwrap();
+    buf[pos] = 0xFF;
+
+    let mut container = Container::open(std::io::Cursor::new(buf)).unwrap();
+    let second = container.find_section_by_index(1).unwrap();
+    let report = container.verify().unwrap();
+    assert!(!report.is_valid());
+    assert_eq!(report.main_header, ChecksumStatus::Valid);
+    assert_eq!(report.sections[0], (first, ChecksumStatus::Valid));
+    assert!(matches!(
+        report.sections[1],
+        (h, ChecksumStatus::Mismatch(expected, actual)) if h == second && expected != actual
+    ));
+    assert_eq!(report.sections[2], (third, ChecksumStatus::None));
+    // Verifying does not load any section.
+    assert!(container.get(second).open().is_none());
+}
+
+#[test]
+fn section_sizes_without_loading()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = container.create_section(
+        SectionHeaderBuilder::new()
+            .compression(CompressionMethod::Zlib)
+            .threshold(0)
+    );
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(&[0; 4096])
+        .unwrap();
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+
+    let mut container = Container::open(buf).unwrap();
+    let handle = container.find_section_by_index(0).unwrap();
+    assert_eq!(container.decoded_size(handle), 4096);
+    let csize = container.compressed_size(handle);
+    assert!(csize > 0 && csize < 4096);
+    assert!(container.get(handle).open().is_none());
+
+    // Unsaved changes are reflected by the decoded size only.
+    let mut section = container.get_mut(handle);
+    let data = section.load().unwrap();
+    data.seek(std::io::SeekFrom::End(0)).unwrap();
+    data.write_all(&[1; 100]).unwrap();
+    assert_eq!(container.decoded_size(handle), 4196);
+    assert_eq!(container.compressed_size(handle), csize);
+}
+
+#[test]
+fn default_compression_threshold_override()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    container.set_default_compression_threshold(1 << 20);
+    let small = container.create_section(
+        SectionHeaderBuilder::new()
+            .compression(CompressionMethod::Zlib)
+            .ty(1)
+    );
+    let explicit = container.create_section(
+        SectionHeaderBuilder::new()
+            .compression(CompressionMethod::Zlib)
+            .threshold(0)
+            .ty(2)
+    );
+    //An explicit threshold equal to the default must not be replaced by the container default
+    let explicit_default = container.create_section(
+        SectionHeaderBuilder::new()
+            .compression(CompressionMethod::Zlib)
+            .threshold(DEFAULT_COMPRESSION_THRESHOLD)
+            .ty(3)
+    );
+    for handle in [small, explicit, explicit_default] {
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&[0; 100000])
+            .unwrap();
+    }
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+
+    let container = Container::open(buf).unwrap();
+    let small = container.find_section_by_type(1).unwrap();
+    let explicit = container.find_section_by_type(2).unwrap();
+    assert_eq!(container.get(small).flags & FLAG_COMPRESS_ZLIB, 0);
+    assert_eq!(container.compressed_size(small), 100000);
+    assert_ne!(container.get(explicit).flags & FLAG_COMPRESS_ZLIB, 0);
+    assert!(container.compressed_size(explicit) < 100000);
+    let explicit_default = container.find_section_by_type(3).unwrap();
+    assert_ne!(container.get(explicit_default).flags & FLAG_COMPRESS_ZLIB, 0);
+    assert!(container.compressed_size(explicit_default) < 100000);
+}
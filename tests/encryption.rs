@@ -0,0 +1,234 @@
+#![cfg(feature = "encryption")]
+
+use std::io::{Cursor, Read, Write};
+
+use bpx::{
+    core::{
+        builder::{
+            Checksum,
+            CompressionMethod,
+            EncryptionMethod,
+            MainHeaderBuilder,
+            SectionHeaderBuilder
+        },
+        error::{ReadError, WriteError},
+        header::{FLAG_CHECK_CRC32, FLAG_COMPRESS_ZLIB, FLAG_ENCRYPT_AES_GCM},
+        Container,
+        SectionData
+    },
+    utils::new_byte_buf
+};
+
+const KEY: [u8; 32] = [7; 32];
+
+fn generate_data(size: usize) -> Vec<u8>
+{
+    (0..size).map(|i| (i % 251) as u8).collect()
+}
+
+fn write_encrypted(data: &[u8]) -> Vec<u8>
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    container.set_encryption_key(KEY);
+    let handle = container.create_section(
+        SectionHeaderBuilder::new()
+            .ty(1)
+            .compression(CompressionMethod::Zlib)
+            .threshold(0)
+            .checksum(Checksum::Crc32)
+            .encryption(EncryptionMethod::Aes256Gcm)
+    );
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(data)
+        .unwrap();
+    let plain = container.create_section(SectionHeaderBuilder::new().ty(2));
+    container
+        .get_mut(plain)
+        .open()
+        .unwrap()
+        .write_all(data)
+        .unwrap();
+    container.save().unwrap();
+    container.into_inner().into_inner()
+}
+
+#[test]
+fn encrypted_round_trip()
+{
+    let data = generate_data(10000);
+    let buf = write_encrypted(&data);
+    let mut container = Container::open(Cursor::new(buf)).unwrap();
+    container.set_encryption_key(KEY);
+    let handle = container.find_section_by_type(1).unwrap();
+    let header = *container.get(handle);
+    assert_eq!(
+        header.flags,
+        FLAG_COMPRESS_ZLIB | FLAG_CHECK_CRC32 | FLAG_ENCRYPT_AES_GCM
+    );
+    assert!(container.verify().unwrap().is_valid());
+    let mut streamed = Vec::new();
+    container
+        .section_reader(handle)
+        .unwrap()
+        .read_to_end(&mut streamed)
+        .unwrap();
+    assert_eq!(streamed, data);
+    let loaded = container
+        .get_mut(handle)
+        .load()
+        .unwrap()
+        .load_in_memory()
+        .unwrap();
+    assert_eq!(loaded, data);
+}
+
+#[test]
+fn missing_or_wrong_key()
+{
+    let data = generate_data(1000);
+    let buf = write_encrypted(&data);
+
+    let mut container = Container::open(Cursor::new(buf.clone())).unwrap();
+    let handle = container.find_section_by_type(1).unwrap();
+    let plain = container.find_section_by_type(2).unwrap();
+    assert!(matches!(
+        container.get_mut(handle).load(),
+        Err(ReadError::Decryption)
+    ));
+    // Unencrypted sections do not need the key.
+    container.get_mut(plain).load().unwrap();
+
+    let mut container = Container::open(Cursor::new(buf)).unwrap();
+    container.set_encryption_key([8; 32]);
+    let handle = container.find_section_by_type(1).unwrap();
+    assert!(matches!(
+        container.get_mut(handle).load(),
+        Err(ReadError::Decryption)
+    ));
+}
+
+#[test]
+fn tampered_section()
+{
+    let data = generate_data(1000);
+    let mut buf = write_encrypted(&data);
+    let container = Container::open(Cursor::new(buf.clone())).unwrap();
+    let header = *container.get(container.find_section_by_type(1).unwrap());
+    buf[header.pointer as usize + 20] ^= 1;
+
+    let mut container = Container::open(Cursor::new(buf)).unwrap();
+    container.set_encryption_key(KEY);
+    let handle = container.find_section_by_type(1).unwrap();
+    assert!(matches!(
+        container.get_mut(handle).load(),
+        Err(ReadError::Decryption)
+    ));
+}
+
+#[test]
+fn save_without_key()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = container.create_section(
+        SectionHeaderBuilder::new()
+            .ty(1)
+            .encryption(EncryptionMethod::Aes256Gcm)
+    );
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(b"test")
+        .unwrap();
+    assert!(matches!(container.save(), Err(WriteError::Encryption)));
+}
+
+#[test]
+fn checksum_covers_encrypted_data()
+{
+    let data = generate_data(1000);
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    container.set_encryption_key(KEY);
+    let handle = container.create_section(
+        SectionHeaderBuilder::new()
+            .ty(1)
+            .checksum(Checksum::Weak)
+            .encryption(EncryptionMethod::Aes256Gcm)
+    );
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(&data)
+        .unwrap();
+    container.save().unwrap();
+    let header = *container.get(handle);
+    let buf = container.into_inner().into_inner();
+    let start = header.pointer as usize;
+    let stored = &buf[start..start + header.csize as usize];
+    assert_eq!(header.chksum, stored.iter().map(|v| *v as u32).sum::<u32>());
+
+    let mut container = Container::open(Cursor::new(buf)).unwrap();
+    container.set_encryption_key(KEY);
+    let handle = container.find_section_by_type(1).unwrap();
+    assert!(container.verify().unwrap().is_valid());
+    let decoded = data.iter().map(|v| *v as u32).sum::<u32>();
+    assert_eq!(container.checksum_of(handle, Checksum::Weak).unwrap(), decoded);
+    container.get_mut(handle).load().unwrap();
+    assert_eq!(container.checksum_of(handle, Checksum::Weak).unwrap(), decoded);
+}
+
+#[test]
+fn encrypted_in_chunks()
+{
+    const CHUNK_SIZE: usize = 65536;
+    for (size, chunks) in [(0, 1), (CHUNK_SIZE, 1), (CHUNK_SIZE * 2 + 5, 3)] {
+        let data = generate_data(size);
+        let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+        container.set_encryption_key(KEY);
+        let handle = container.create_section(
+            SectionHeaderBuilder::new()
+                .checksum(Checksum::Crc32)
+                .encryption(EncryptionMethod::Aes256Gcm)
+        );
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&data)
+            .unwrap();
+        container.save().unwrap();
+        //Nonce then each chunk followed by its tag
+        assert_eq!(container.get(handle).csize as usize, 7 + size + chunks * 16);
+
+        let buf = container.into_inner().into_inner();
+        let mut container = Container::open(Cursor::new(buf.clone())).unwrap();
+        container.set_encryption_key(KEY);
+        let handle = container.find_section_by_index(0).unwrap();
+        let loaded = container
+            .get_mut(handle)
+            .load()
+            .unwrap()
+            .load_in_memory()
+            .unwrap();
+        assert_eq!(loaded, data);
+
+        //Chunks are bound to their position in the section
+        if chunks > 2 {
+            let start = container.get(handle).pointer as usize + 7;
+            let mut buf = buf;
+            let (first, second) = buf[start..].split_at_mut(CHUNK_SIZE + 16);
+            first.swap_with_slice(&mut second[..CHUNK_SIZE + 16]);
+            let mut container = Container::open(Cursor::new(buf)).unwrap();
+            container.set_encryption_key(KEY);
+            let handle = container.find_section_by_index(0).unwrap();
+            assert!(matches!(
+                container.get_mut(handle).load(),
+                Err(ReadError::Decryption)
+            ));
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    io::{Read, Seek, SeekFrom, Write},
+    sync::atomic::{AtomicUsize, Ordering}
+};
+
+use bpx::core::{
+    builder::{MainHeaderBuilder, SectionHeaderBuilder},
+    Container
+};
+
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator
+{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8
+    {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout)
+    {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const SECTION_SIZE: usize = 4 * 1024 * 1024;
+const SECTIONS: usize = 6;
+
+fn generated_byte(section: usize, pos: usize) -> u8
+{
+    (pos.wrapping_mul(31) ^ section) as u8
+}
+
+// Packs several large generated sections and returns the peak of allocated memory while
+// writing and saving them.
+fn pack_sections(budget: Option<usize>) -> (usize, std::fs::File)
+{
+    let baseline = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let mut container = Container::create(tempfile::tempfile().unwrap(), MainHeaderBuilder::new());
+    container.set_memory_threshold(usize::MAX);
+    if let Some(budget) = budget {
+        container.set_save_memory_budget(budget);
+    }
+    let mut chunk = vec![0; 65536];
+    for section in 0..SECTIONS {
+        let handle = container.create_section(SectionHeaderBuilder::new());
+        let mut section_mut = container.get_mut(handle);
+        let data = section_mut.open().unwrap();
+        for start in (0..SECTION_SIZE).step_by(chunk.len()) {
+            for (i, v) in chunk.iter_mut().enumerate() {
+                *v = generated_byte(section, start + i);
+            }
+            data.write_all(&chunk).unwrap();
+        }
+        container.spill_sections().unwrap();
+    }
+    container.save().unwrap();
+    let peak = PEAK.load(Ordering::SeqCst) - baseline;
+    (peak, container.into_inner())
+}
+
+#[test]
+fn save_memory_budget_bounds_peak_memory()
+{
+    let (peak, _) = pack_sections(None);
+    assert!(peak >= SECTIONS * SECTION_SIZE);
+
+    let budget = SECTION_SIZE;
+    let (peak, mut file) = pack_sections(Some(budget));
+    // The budget plus the section being written (including its buffer growth).
+    assert!(peak < budget + 2 * SECTION_SIZE + 1024 * 1024);
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut container = Container::open(file).unwrap();
+    let mut buf = vec![0; 4096];
+    for section in 0..SECTIONS {
+        let handle = container.find_section_by_index(section as u32).unwrap();
+        let mut section_mut = container.get_mut(handle);
+        let data = section_mut.load().unwrap();
+        data.seek(SeekFrom::Start(SECTION_SIZE as u64 - 4096))
+            .unwrap();
+        data.read_exact(&mut buf).unwrap();
+        for (i, v) in buf.iter().enumerate() {
+            assert_eq!(*v, generated_byte(section, SECTION_SIZE - 4096 + i));
+        }
+    }
+}
+
+#[test]
+fn save_releases_written_sections()
+{
+    let mut container = Container::create(tempfile::tempfile().unwrap(), MainHeaderBuilder::new());
+    container.set_memory_threshold(usize::MAX);
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let handle = container.create_section(SectionHeaderBuilder::new());
+            container
+                .get_mut(handle)
+                .open()
+                .unwrap()
+                .write_all(&[0x42; 1024])
+                .unwrap();
+            handle
+        })
+        .collect();
+    // Without spill_sections, sections stay in memory until saved.
+    container.set_save_memory_budget(2048);
+    assert!(handles
+        .iter()
+        .all(|v| container.get(*v).open().unwrap().is_in_memory()));
+    container.save().unwrap();
+    let in_memory: Vec<bool> = handles
+        .iter()
+        .map(|v| container.get(*v).open().unwrap().is_in_memory())
+        .collect();
+    assert_eq!(in_memory, vec![false, false, true, true]);
+}
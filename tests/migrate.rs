@@ -0,0 +1,92 @@
+use std::{fs::File, io::Write};
+
+use bpx::{
+    core::{
+        builder::{MainHeaderBuilder, SectionHeaderBuilder},
+        error::{CorruptionContext, MigrateError, ReadError},
+        header::{
+            BPX_CURRENT_VERSION,
+            FLAG_CHECK_CRC32,
+            FLAG_CHECK_WEAK,
+            FLAG_COMPRESS_ZLIB,
+            SIZE_MAIN_HEADER
+        },
+        migrate::{upgrade, ChecksumStatus},
+        Container,
+        SectionData
+    },
+    utils::new_byte_buf
+};
+
+#[test]
+fn upgrade_legacy_rev1()
+{
+    let old = File::open(format!(
+        "{}/tests/data/legacy_rev1.bpx",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap();
+    let mut new = new_byte_buf(0);
+    let report = upgrade(old, &mut new).unwrap();
+    assert_eq!(report.version, 1);
+    assert_eq!(report.sections.len(), 3);
+    assert_eq!(report.sections[0].checksum, ChecksumStatus::Valid);
+    //Rev1 CRC32 checksums are verified with the legacy table
+    assert_eq!(report.sections[1].checksum, ChecksumStatus::Legacy);
+    assert_eq!(report.sections[2].checksum, ChecksumStatus::Valid);
+    assert!(report.is_verified());
+    assert_eq!(report.sections[0].new_flags, FLAG_CHECK_WEAK);
+    assert_eq!(report.sections[1].new_flags, FLAG_CHECK_CRC32);
+    assert_eq!(
+        report.sections[2].new_flags,
+        FLAG_COMPRESS_ZLIB | FLAG_CHECK_WEAK
+    );
+
+    new.set_position(0);
+    let mut container = Container::open(new).unwrap();
+    assert_eq!(container.get_main_header().version, BPX_CURRENT_VERSION);
+    assert_eq!(container.get_main_header().section_num, 3);
+    let expected: [&[u8]; 3] = [
+        b"legacy uncompressed section with a null csize\0",
+        b"legacy section with a rev1 crc32 checksum\0",
+        &b"legacy zlib compressed section ".repeat(16)
+    ];
+    for (i, data) in expected.iter().enumerate() {
+        let handle = container.find_section_by_index(i as u32).unwrap();
+        let mut section = container.get_mut(handle);
+        assert_eq!(section.load().unwrap().load_in_memory().unwrap(), *data);
+        assert_eq!(section.ty, i as u8 + 1);
+    }
+    let handle = container.find_section_by_index(0).unwrap();
+    let section = container.get(handle);
+    assert_eq!(section.csize, section.size);
+}
+
+#[test]
+fn upgrade_rejects_header_lies()
+{
+    let mut old = Container::create(new_byte_buf(0), MainHeaderBuilder::new().version(1));
+    let handle = old.create_section(SectionHeaderBuilder::new());
+    old.get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(b"data")
+        .unwrap();
+    old.save().unwrap();
+    let mut buf = old.into_inner().into_inner();
+    //Make the section claim more data than the file has, keeping the header checksum valid
+    let start = SIZE_MAIN_HEADER + bpx::spec::core::SECTION_HEADER_CSIZE_OFFSET;
+    let sum = |bytes: &[u8]| bytes.iter().map(|v| *v as u32).sum::<u32>();
+    let prev = sum(&buf[start..start + 4]);
+    buf[start..start + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    let chksum = u32::from_le_bytes(buf[4..8].try_into().unwrap())
+        .wrapping_add(sum(&buf[start..start + 4]))
+        .wrapping_sub(prev);
+    buf[4..8].copy_from_slice(&chksum.to_le_bytes());
+    assert!(matches!(
+        upgrade(std::io::Cursor::new(buf), new_byte_buf(0)),
+        Err(MigrateError::Read(ReadError::Corrupted(
+            CorruptionContext::SectionPastEnd(0)
+        )))
+    ));
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "mmap")]
+
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write}
+};
+
+use bpx::core::{
+    builder::{Checksum, CompressionMethod, MainHeaderBuilder, OpenOptions, SectionHeaderBuilder},
+    error::ReadError,
+    Container,
+    SectionData
+};
+
+fn write_file(path: &std::path::Path)
+{
+    let mut container = Container::create(File::create(path).unwrap(), MainHeaderBuilder::new());
+    let raw = container.create_section(SectionHeaderBuilder::new().ty(1));
+    let compressed = container.create_section(
+        SectionHeaderBuilder::new()
+            .ty(2)
+            .compression(CompressionMethod::Zlib)
+            .threshold(0)
+    );
+    container
+        .get_mut(raw)
+        .open()
+        .unwrap()
+        .write_all(b"mapped data")
+        .unwrap();
+    container
+        .get_mut(compressed)
+        .open()
+        .unwrap()
+        .write_all(b"compressed data")
+        .unwrap();
+    container.save().unwrap();
+}
+
+#[test]
+fn mmap_read_and_copy_on_write()
+{
+    let path = std::env::temp_dir().join(format!("bpx_mmap_{}.bpx", std::process::id()));
+    write_file(&path);
+    {
+        let mut container = unsafe { Container::open_mmap(File::open(&path).unwrap()) }.unwrap();
+        let raw = container.find_section_by_type(1).unwrap();
+        let compressed = container.find_section_by_type(2).unwrap();
+        let mut section = container.get_mut(compressed);
+        assert_eq!(
+            section.load().unwrap().load_in_memory().unwrap(),
+            b"compressed data"
+        );
+        let mut section = container.get_mut(raw);
+        let data = section.load().unwrap();
+        let mut buf = [0; 6];
+        data.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"mapped");
+        data.write_all(b"!").unwrap();
+        data.seek(SeekFrom::Start(0)).unwrap();
+        assert_eq!(data.load_in_memory().unwrap(), b"mapped!data");
+        assert_eq!(data.size(), 11);
+    }
+    let mut container = Container::open(File::open(&path).unwrap()).unwrap();
+    let raw = container.find_section_by_type(1).unwrap();
+    let mut section = container.get_mut(raw);
+    assert_eq!(
+        section.load().unwrap().load_in_memory().unwrap(),
+        b"mapped data"
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn mmap_verifies_checksums()
+{
+    let path = std::env::temp_dir().join(format!("bpx_mmap_chksum_{}.bpx", std::process::id()));
+    {
+        let mut container =
+            Container::create(File::create(&path).unwrap(), MainHeaderBuilder::new());
+        let handle =
+            container.create_section(SectionHeaderBuilder::new().checksum(Checksum::Crc32));
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(b"mapped data")
+            .unwrap();
+        container.save().unwrap();
+    }
+    let pointer = {
+        let container = Container::open(File::open(&path).unwrap()).unwrap();
+        container.get(container.find_section_by_index(0).unwrap()).pointer
+    };
+    {
+        let mut file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(pointer)).unwrap();
+        file.write_all(b"M").unwrap();
+    }
+    let res = unsafe { Container::open_mmap(File::open(&path).unwrap()) };
+    assert!(matches!(res, Err(ReadError::Checksum(_, _))));
+    let mut container = unsafe {
+        Container::open_mmap_with_options(
+            File::open(&path).unwrap(),
+            OpenOptions::new().skip_checksum(true)
+        )
+    }
+    .unwrap();
+    let handle = container.find_section_by_index(0).unwrap();
+    let mut section = container.get_mut(handle);
+    assert_eq!(
+        section.load().unwrap().load_in_memory().unwrap(),
+        b"Mapped data"
+    );
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,183 @@
+#![cfg(feature = "multithreading")]
+
+use std::io::Write;
+
+use bpx::{
+    core::{
+        builder::{Checksum, CompressionMethod, MainHeaderBuilder, SectionHeaderBuilder},
+        Container,
+        SectionData
+    },
+    utils::new_byte_buf
+};
+
+fn create() -> Container<std::io::Cursor<Vec<u8>>>
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let methods = [
+        None,
+        Some(CompressionMethod::Zlib),
+        Some(CompressionMethod::Xz)
+    ];
+    for (i, method) in methods.iter().cycle().take(9).enumerate() {
+        let mut builder = SectionHeaderBuilder::new();
+        builder.ty(i as u8).checksum(Checksum::Crc32);
+        if let Some(method) = method {
+            builder.compression(*method).threshold(0);
+        }
+        let handle = container.create_section(&mut builder);
+        let data: Vec<u8> = (0..20000 * (i + 1)).map(|v| (v % 251) as u8).collect();
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&data)
+            .unwrap();
+    }
+    container
+}
+
+fn build(parallel: bool) -> Vec<u8>
+{
+    let mut container = create();
+    match parallel {
+        true => container.save_parallel().unwrap(),
+        false => container.save().unwrap()
+    }
+    container.into_inner().into_inner()
+}
+
+#[test]
+fn parallel_save_matches_save()
+{
+    let expected = build(false);
+    for threads in [1, 2, 4] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let actual = pool.install(|| build(true));
+        assert_eq!(actual, expected, "output differs with {} threads", threads);
+    }
+    let mut container = Container::open(std::io::Cursor::new(expected)).unwrap();
+    assert!(container.verify().unwrap().is_valid());
+}
+
+#[cfg(feature = "package")]
+#[test]
+fn pack_file_parallel_is_deterministic()
+{
+    use bpx::package::{utils::pack_file_parallel, Builder, Package};
+
+    let dir = std::env::temp_dir().join(format!("bpx_parallel_{}", std::process::id()));
+    let root = dir.join("root");
+    std::fs::create_dir_all(root.join("empty")).unwrap();
+    for i in 0..12 {
+        let sub = root.join(format!("dir{}", i % 3));
+        std::fs::create_dir_all(&sub).unwrap();
+        let data: Vec<u8> = (0..1000 * (i + 1)).map(|v| ((v + i) % 251) as u8).collect();
+        std::fs::write(sub.join(format!("file{}.bin", i)), data).unwrap();
+    }
+    let build = |threads| {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        pool.install(|| {
+            let mut package = Package::create(
+                new_byte_buf(0),
+                Builder::new()
+                    .max_section_size(4096)
+                    .data_header(SectionHeaderBuilder::new().compression(CompressionMethod::Xz))
+            )
+            .unwrap();
+            pack_file_parallel(&mut package, "root", &root).unwrap();
+            package.save_parallel().unwrap();
+            package.into_inner().into_inner().into_inner()
+        })
+    };
+    let expected = build(1);
+    assert_eq!(build(4), expected);
+
+    let mut package = Package::open(std::io::Cursor::new(expected)).unwrap();
+    let names = package.object_names().unwrap();
+    assert_eq!(names[0], "root/dir0/file0.bin");
+    assert_eq!(names[1], "root/dir0/file3.bin");
+    assert_eq!(names.last().unwrap(), "root/empty/");
+    assert_eq!(names.len(), 13);
+    let mut data = Vec::new();
+    package.unpack("root/dir1/file10.bin", &mut data).unwrap();
+    assert_eq!(data, std::fs::read(root.join("dir1/file10.bin")).unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(all(feature = "package", unix))]
+#[test]
+fn pack_file_parallel_keeps_symlinks()
+{
+    use bpx::package::{utils::pack_file_parallel, Builder, ObjectKind, Package};
+
+    let dir = std::env::temp_dir().join(format!("bpx_parallel_links_{}", std::process::id()));
+    let root = dir.join("root");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("file.txt"), b"content").unwrap();
+    std::os::unix::fs::symlink(".", root.join("self")).unwrap();
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    pack_file_parallel(&mut package, "root", &root).unwrap();
+    assert_eq!(package.stat("root/self").unwrap().kind, ObjectKind::Symlink);
+    assert_eq!(package.unpack_to_vec("root/self").unwrap(), b".");
+    assert_eq!(package.objects().unwrap().count(), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn parallel_save_copies_staged_sections()
+{
+    let mut buf = std::io::Cursor::new(build(false));
+    let mut container = Container::open(&mut buf).unwrap();
+    container.stage_unloaded().unwrap();
+    let handle = container.create_section(SectionHeaderBuilder::new().ty(42));
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(b"appended")
+        .unwrap();
+    container.save_parallel().unwrap();
+    drop(container);
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    assert_eq!(container.get_main_header().section_num, 10);
+    assert!(container.verify().unwrap().is_valid());
+    container.load_all().unwrap();
+}
+
+#[test]
+fn parallel_save_reports_progress_within_budget()
+{
+    let expected = build(false);
+    let mut container = create();
+    container.set_save_memory_budget(50000);
+    let mut events = Vec::new();
+    container
+        .save_parallel_with_progress(|event| events.push((event.index, event.bytes, event.total)))
+        .unwrap();
+    let sizes: Vec<u64> = (1..=9).map(|i| 20000 * i).collect();
+    let total: u64 = sizes.iter().sum();
+    assert_eq!(events.len(), 9);
+    let mut bytes = 0;
+    for (i, event) in events.iter().enumerate() {
+        bytes += sizes[i];
+        assert_eq!(*event, (i as u32, bytes, total));
+    }
+    // Written sections are moved out of memory until the remaining ones fit in the budget.
+    let mut in_memory = 0;
+    for section in container.iter() {
+        let data = section.open().unwrap();
+        if data.is_in_memory() {
+            in_memory += data.size();
+        }
+    }
+    assert!(in_memory <= 50000);
+    assert_eq!(container.into_inner().into_inner(), expected);
+}
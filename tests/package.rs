@@ -0,0 +1,1489 @@
+#![cfg(feature = "package")]
+
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use bpx::{
+    core::{
+        builder::{
+            Checksum,
+            CompressionMethod,
+            MainHeaderBuilder,
+            OpenOptions,
+            SectionHeaderBuilder
+        },
+        header::{
+            Struct,
+            FLAG_CHECK_CRC32,
+            FLAG_COMPRESS_XZ,
+            FLAG_COMPRESS_ZLIB,
+            SECTION_TYPE_STRING
+        },
+        Container,
+        SectionData
+    },
+    package::{
+        error::ReadError,
+        object::{ObjectHeader, SIZE_OBJECT_HEADER},
+        Builder,
+        DuplicatePolicy,
+        ObjectCompression,
+        ObjectKind,
+        ObjectOptions,
+        Package,
+        SECTION_TYPE_DATA,
+        SECTION_TYPE_OBJECT_TABLE,
+        SUPPORTED_VERSION
+    },
+    strings::StringSection,
+    utils::new_byte_buf
+};
+
+/// Builds a package by hand with a single object split over two data sections.
+fn split_object_package(first: &[u8], second: &[u8]) -> std::io::Cursor<Vec<u8>>
+{
+    let mut type_ext = [0; 16];
+    type_ext[0] = 0x4;
+    type_ext[1] = 0x4;
+    let mut container = Container::create(
+        new_byte_buf(0),
+        MainHeaderBuilder::new()
+            .ty(b'P')
+            .type_ext(type_ext)
+            .version(SUPPORTED_VERSION)
+    );
+    let table = container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_OBJECT_TABLE));
+    let strings = container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_STRING));
+    let data1 = container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_DATA));
+    let data2 = container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_DATA));
+    let mut strings = StringSection::new(strings);
+    let name = strings.put(&mut container, "split").unwrap();
+    // Some unrelated bytes before the object in the first section.
+    let mut section = container.get_mut(data1);
+    let data = section.open().unwrap();
+    data.write_all(b"junk").unwrap();
+    data.write_all(first).unwrap();
+    container
+        .get_mut(data2)
+        .open()
+        .unwrap()
+        .write_all(second)
+        .unwrap();
+    let header = ObjectHeader {
+        size: (first.len() + second.len()) as u64,
+        name,
+        start: container.get(data1).index(),
+        offset: 4
+    };
+    header
+        .write(container.get_mut(table).open().unwrap())
+        .unwrap();
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    buf
+}
+
+#[test]
+fn open_object_across_sections()
+{
+    let mut package = Package::open(split_object_package(b"Hello ", b"world!")).unwrap();
+    let mut reader = package.open_object("split").unwrap();
+    assert_eq!(reader.size(), 12);
+    let mut s = String::new();
+    reader.read_to_string(&mut s).unwrap();
+    assert_eq!(s, "Hello world!");
+
+    // Reads crossing the section boundary.
+    reader.seek(SeekFrom::Start(4)).unwrap();
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"o wo");
+    reader.seek(SeekFrom::End(-1)).unwrap();
+    let mut buf = [0; 4];
+    assert_eq!(reader.read(&mut buf).unwrap(), 1);
+    assert_eq!(buf[0], b'!');
+
+    // Seeking past the end behaves like a file.
+    assert_eq!(reader.seek(SeekFrom::End(10)).unwrap(), 22);
+    assert_eq!(reader.read(&mut buf).unwrap(), 0);
+    assert!(reader.seek(SeekFrom::Current(-23)).is_err());
+    assert!(package.open_object("missing").is_err());
+}
+
+#[test]
+fn custom_section_headers()
+{
+    let mut package = Package::create(
+        new_byte_buf(0),
+        Builder::new()
+            .object_table_header(
+                SectionHeaderBuilder::new()
+                    .ty(42)
+                    .compression(CompressionMethod::Zlib)
+                    .threshold(0)
+                    .checksum(Checksum::Crc32)
+            )
+            .string_header(SectionHeaderBuilder::new().checksum(Checksum::Crc32))
+            .data_header(
+                SectionHeaderBuilder::new()
+                    .compression(CompressionMethod::Zlib)
+                    .threshold(0)
+                    .checksum(Checksum::Crc32)
+            )
+    )
+    .unwrap();
+    package.pack("test", &b"custom headers"[..]).unwrap();
+    package.save().unwrap();
+    let mut buf = package.into_inner().into_inner();
+    buf.set_position(0);
+
+    let container = Container::open(&mut buf).unwrap();
+    let flags = |ty| {
+        let handle = container.find_section_by_type(ty).unwrap();
+        container.get(handle).flags
+    };
+    assert_eq!(
+        flags(SECTION_TYPE_OBJECT_TABLE),
+        FLAG_COMPRESS_ZLIB | FLAG_CHECK_CRC32
+    );
+    assert_eq!(flags(SECTION_TYPE_STRING), FLAG_CHECK_CRC32);
+    assert_eq!(
+        flags(SECTION_TYPE_DATA),
+        FLAG_COMPRESS_ZLIB | FLAG_CHECK_CRC32
+    );
+    assert_eq!(flags(SECTION_TYPE_DATA) & FLAG_COMPRESS_XZ, 0);
+
+    buf.set_position(0);
+    let mut package = Package::open(buf).unwrap();
+    let mut data = Vec::new();
+    package.unpack("test", &mut data).unwrap();
+    assert_eq!(data, b"custom headers");
+}
+
+fn reopen(package: Package<std::io::Cursor<Vec<u8>>>) -> Package<std::io::Cursor<Vec<u8>>>
+{
+    let mut buf = package.into_inner().into_inner();
+    buf.set_position(0);
+    Package::open(buf).unwrap()
+}
+
+#[test]
+fn remove_object()
+{
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.pack("a", &b"first object"[..]).unwrap();
+    package.pack("b", &b"second object"[..]).unwrap();
+    package.pack("c", &b"third object"[..]).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    assert!(package.remove("b").unwrap());
+    assert!(!package.remove("b").unwrap());
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    assert_eq!(package.objects().unwrap().count(), 2);
+    let mut data = Vec::new();
+    assert!(package.unpack("b", &mut data).unwrap().is_none());
+    package.unpack("a", &mut data).unwrap();
+    assert_eq!(data, b"first object");
+    data.clear();
+    package.unpack("c", &mut data).unwrap();
+    assert_eq!(data, b"third object");
+}
+
+#[test]
+fn stat_object()
+{
+    let dir = std::env::temp_dir().join(format!("bpx_stat_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("file.txt");
+    std::fs::write(&path, b"packed file content").unwrap();
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    bpx::package::utils::pack_file(&mut package, &path).unwrap();
+    package.save().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mut package = reopen(package);
+    let stat = package.stat("file.txt").unwrap();
+    assert_eq!(stat.size, 19);
+    assert_eq!(stat.kind, ObjectKind::File);
+    assert_eq!(stat.mode, None);
+    assert_eq!(stat.mtime, None);
+    assert_eq!(stat.sections, 1);
+    assert!(package.stat("missing").is_err());
+
+    let mut package = Package::open(split_object_package(b"Hello ", b"world!")).unwrap();
+    let stat = package.stat("split").unwrap();
+    assert_eq!(stat.size, 12);
+    assert_eq!(stat.sections, 2);
+}
+
+fn unpack_all(package: &mut Package<std::io::Cursor<Vec<u8>>>, name: &str) -> Vec<u8>
+{
+    let mut data = Vec::new();
+    package.unpack(name, &mut data).unwrap().unwrap();
+    data
+}
+
+fn check_untouched(package: &mut Package<std::io::Cursor<Vec<u8>>>)
+{
+    assert_eq!(unpack_all(package, "a"), b"first object");
+    assert_eq!(unpack_all(package, "c"), b"third object");
+    assert_eq!(package.objects().unwrap().count(), 3);
+}
+
+#[test]
+fn replace_object()
+{
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.pack("a", &b"first object"[..]).unwrap();
+    package.pack("b", &b"second object"[..]).unwrap();
+    package.pack("c", &b"third object"[..]).unwrap();
+    package.save().unwrap();
+    let container = package.into_inner();
+    let handle = container.find_section_by_type(SECTION_TYPE_STRING).unwrap();
+    let strings_size = container.get(handle).size;
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut package = Package::open(buf).unwrap();
+
+    // Same size
+    package.replace("b", &b"SECOND OBJECT"[..]).unwrap();
+    package.save().unwrap();
+    let mut package = reopen(package);
+    assert_eq!(unpack_all(&mut package, "b"), b"SECOND OBJECT");
+    check_untouched(&mut package);
+
+    // Smaller
+    package.replace("b", &b"2nd"[..]).unwrap();
+    package.save().unwrap();
+    let mut package = reopen(package);
+    assert_eq!(unpack_all(&mut package, "b"), b"2nd");
+    check_untouched(&mut package);
+
+    // Larger
+    let larger = vec![0x42; 100000];
+    package.replace("b", &larger[..]).unwrap();
+    package.save().unwrap();
+    let mut package = reopen(package);
+    assert_eq!(unpack_all(&mut package, "b"), larger);
+    check_untouched(&mut package);
+    assert!(package.replace("missing", &b""[..]).is_err());
+
+    let container = package.into_inner();
+    let data_sections = container
+        .iter()
+        .filter(|v| v.ty == SECTION_TYPE_DATA)
+        .count();
+    assert_eq!(data_sections, 1);
+    let handle = container.find_section_by_type(SECTION_TYPE_STRING).unwrap();
+    assert_eq!(container.get(handle).size, strings_size);
+}
+
+#[test]
+fn recover_truncated_object_table()
+{
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.pack("a", &b"first object"[..]).unwrap();
+    package.pack("b", &b"second object"[..]).unwrap();
+    package.pack("c", &b"third object"[..]).unwrap();
+    package.save().unwrap();
+
+    // Move the object table to the end of the file so that it can be truncated.
+    let mut container = package.into_inner();
+    let table = container
+        .find_section_by_type(SECTION_TYPE_OBJECT_TABLE)
+        .unwrap();
+    let mut data = container
+        .get_mut(table)
+        .open()
+        .unwrap()
+        .load_in_memory()
+        .unwrap();
+    assert_eq!(data.len(), 60);
+    // Removing the table shifts the index of the data section down by one.
+    for record in data.chunks_mut(SIZE_OBJECT_HEADER) {
+        let mut header = ObjectHeader::from_bytes(record.try_into().unwrap()).unwrap();
+        header.start -= 1;
+        record.copy_from_slice(&header.to_bytes());
+    }
+    container.remove_section(table);
+    let table = container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_OBJECT_TABLE));
+    container
+        .get_mut(table)
+        .open()
+        .unwrap()
+        .write_all(&data)
+        .unwrap();
+    container.save().unwrap();
+    let mut buf = container.into_inner().into_inner();
+    // Cut the object table in the middle of the second record.
+    buf.truncate(buf.len() - 30);
+
+    assert!(matches!(
+        Package::open(std::io::Cursor::new(buf.clone())),
+        Err(ReadError::Bpx(bpx::core::error::ReadError::Corrupted(_)))
+    ));
+    let mut options = OpenOptions::new();
+    options.skip_validation(true);
+    let mut package =
+        Package::open_with_options(std::io::Cursor::new(buf.clone()), &options).unwrap();
+    assert!(package.objects().is_err());
+
+    let mut package = Package::open_with_options(std::io::Cursor::new(buf), &options).unwrap();
+    assert_eq!(package.recover_objects().unwrap(), 1);
+    assert_eq!(package.objects().unwrap().count(), 1);
+    let mut data = Vec::new();
+    package.unpack("a", &mut data).unwrap();
+    assert_eq!(data, b"first object");
+    assert!(package.unpack("b", &mut data).unwrap().is_none());
+}
+
+#[test]
+fn empty_directories_round_trip()
+{
+    let dir = std::env::temp_dir().join(format!("bpx_dirs_{}", std::process::id()));
+    let root = dir.join("root");
+    std::fs::create_dir_all(root.join("empty")).unwrap();
+    std::fs::create_dir_all(root.join("nested/inner")).unwrap();
+    std::fs::create_dir_all(root.join("full")).unwrap();
+    std::fs::write(root.join("full/file.txt"), b"content").unwrap();
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    bpx::package::utils::pack_file(&mut package, &root).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    assert_eq!(package.objects().unwrap().count(), 3);
+    assert_eq!(
+        package.stat("root/empty/").unwrap().kind,
+        ObjectKind::Directory
+    );
+    assert_eq!(
+        package.stat("root/nested/inner/").unwrap().kind,
+        ObjectKind::Directory
+    );
+    assert_eq!(
+        package.stat("root/full/file.txt").unwrap().kind,
+        ObjectKind::File
+    );
+    let out = dir.join("out");
+    bpx::package::utils::unpack(&mut package, &out).unwrap();
+    assert!(out.join("root/empty").is_dir());
+    assert!(out.join("root/nested/inner").is_dir());
+    assert_eq!(
+        std::fs::read(out.join("root/full/file.txt")).unwrap(),
+        b"content"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Two objects referencing the same name, as written by a buggy third-party writer.
+fn duplicate_name_package() -> std::io::Cursor<Vec<u8>>
+{
+    let mut type_ext = [0; 16];
+    type_ext[0] = 0x4;
+    type_ext[1] = 0x4;
+    let mut container = Container::create(
+        new_byte_buf(0),
+        MainHeaderBuilder::new()
+            .ty(b'P')
+            .type_ext(type_ext)
+            .version(SUPPORTED_VERSION)
+    );
+    let table = container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_OBJECT_TABLE));
+    let strings = container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_STRING));
+    let data = container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_DATA));
+    let mut strings = StringSection::new(strings);
+    let name = strings.put(&mut container, "dup.txt").unwrap();
+    let other = strings.put(&mut container, "other.txt").unwrap();
+    container
+        .get_mut(data)
+        .open()
+        .unwrap()
+        .write_all(b"firstsecondother")
+        .unwrap();
+    let start = container.get(data).index();
+    for (name, offset, size) in [(name, 0, 5), (other, 11, 5), (name, 5, 6)] {
+        let header = ObjectHeader {
+            size,
+            name,
+            start,
+            offset
+        };
+        header
+            .write(container.get_mut(table).open().unwrap())
+            .unwrap();
+    }
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    buf
+}
+
+fn unpack_with_policy(policy: DuplicatePolicy) -> Result<std::path::PathBuf, ReadError>
+{
+    let mut package = Package::open(duplicate_name_package()).unwrap();
+    package.set_duplicate_policy(policy);
+    assert_eq!(package.objects().unwrap().count(), 3);
+    let dir = std::env::temp_dir().join(format!("bpx_dup_{:?}_{}", policy, std::process::id()));
+    bpx::package::utils::unpack(&mut package, &dir)?;
+    Ok(dir)
+}
+
+#[test]
+fn duplicate_names_policies()
+{
+    let mut package = Package::open(duplicate_name_package()).unwrap();
+    assert_eq!(package.duplicates().unwrap(), vec!["dup.txt"]);
+    assert_eq!(unpack_all(&mut package, "dup.txt"), b"second");
+    package.set_duplicate_policy(DuplicatePolicy::FirstWins);
+    assert_eq!(unpack_all(&mut package, "dup.txt"), b"first");
+    assert_eq!(unpack_all(&mut package, "other.txt"), b"other");
+    package.set_duplicate_policy(DuplicatePolicy::Error);
+    assert!(matches!(
+        package.stat("other.txt"),
+        Err(ReadError::DuplicateObject(name)) if name == "dup.txt"
+    ));
+    assert_eq!(package.objects().unwrap().count(), 3);
+
+    assert!(matches!(
+        unpack_with_policy(DuplicatePolicy::Error),
+        Err(ReadError::DuplicateObject(_))
+    ));
+    let dir = unpack_with_policy(DuplicatePolicy::FirstWins).unwrap();
+    assert_eq!(std::fs::read(dir.join("dup.txt")).unwrap(), b"first");
+    assert!(!dir.join("dup.txt.1").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+    let dir = unpack_with_policy(DuplicatePolicy::LastWins).unwrap();
+    assert_eq!(std::fs::read(dir.join("dup.txt")).unwrap(), b"second");
+    assert!(!dir.join("dup.txt.1").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+    let dir = unpack_with_policy(DuplicatePolicy::KeepBoth).unwrap();
+    assert_eq!(std::fs::read(dir.join("dup.txt")).unwrap(), b"first");
+    assert_eq!(std::fs::read(dir.join("dup.txt.1")).unwrap(), b"second");
+    assert_eq!(std::fs::read(dir.join("other.txt")).unwrap(), b"other");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn normalized_object_names()
+{
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.set_name_normalization(true);
+    package.pack("./foo/bar", "normalized".as_bytes()).unwrap();
+    package.save().unwrap();
+    let mut package = reopen(package);
+    package.set_name_normalization(true);
+    assert_eq!(unpack_all(&mut package, "foo/bar"), b"normalized");
+    assert_eq!(unpack_all(&mut package, "foo\\bar"), b"normalized");
+
+    // Names stored without normalization are still found once normalization is enabled.
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.pack("./foo/bar", "raw".as_bytes()).unwrap();
+    package.save().unwrap();
+    let mut package = reopen(package);
+    assert!(package.stat("foo/bar").is_err());
+    package.set_name_normalization(true);
+    assert_eq!(unpack_all(&mut package, "foo/bar"), b"raw");
+}
+
+#[test]
+fn pack_rolls_over_max_section_size()
+{
+    let mut package =
+        Package::create(new_byte_buf(0), Builder::new().max_section_size(4096)).unwrap();
+    let data: Vec<u8> = (0..10000u32).map(|v| (v % 251) as u8).collect();
+    package.pack("big", &data[..]).unwrap();
+    package.pack("small", &b"tail"[..]).unwrap();
+    package.save().unwrap();
+    let mut buf = package.into_inner().into_inner();
+    buf.set_position(0);
+
+    let container = Container::open(&mut buf).unwrap();
+    let sizes: Vec<u32> = container
+        .iter()
+        .filter(|v| v.ty == SECTION_TYPE_DATA)
+        .map(|v| v.size)
+        .collect();
+    assert_eq!(sizes, vec![4096, 4096, 1812]);
+    drop(container);
+
+    buf.set_position(0);
+    let mut package = Package::open(buf).unwrap();
+    let mut out = Vec::new();
+    package.unpack("big", &mut out).unwrap();
+    assert_eq!(out, data);
+    let mut out = Vec::new();
+    package.unpack("small", &mut out).unwrap();
+    assert_eq!(out, b"tail");
+}
+
+#[test]
+fn expected_objects_hint()
+{
+    let mut package =
+        Package::create(new_byte_buf(0), Builder::new().expected_objects(64)).unwrap();
+    for i in 0..100 {
+        package
+            .pack(&format!("file{}", i), format!("content {}", i).as_bytes())
+            .unwrap();
+    }
+    package.save().unwrap();
+    let mut buf = package.into_inner().into_inner();
+    buf.set_position(0);
+
+    let mut package = Package::open(buf).unwrap();
+    for i in 0..100 {
+        let mut out = Vec::new();
+        package.unpack(&format!("file{}", i), &mut out).unwrap();
+        assert_eq!(out, format!("content {}", i).as_bytes());
+    }
+}
+
+#[test]
+fn list_object_names()
+{
+    let mut package = Package::open(duplicate_name_package()).unwrap();
+    assert_eq!(
+        package.object_names().unwrap(),
+        vec!["dup.txt", "other.txt", "dup.txt"]
+    );
+    package.pack("new.txt", &b"new"[..]).unwrap();
+    assert_eq!(
+        package.object_names().unwrap(),
+        vec!["dup.txt", "other.txt", "dup.txt", "new.txt"]
+    );
+    assert!(package.contains("dup.txt").unwrap());
+    assert!(package.contains("new.txt").unwrap());
+    assert!(!package.contains("missing.txt").unwrap());
+    package.set_duplicate_policy(DuplicatePolicy::Error);
+    assert!(matches!(
+        package.contains("other.txt"),
+        Err(ReadError::DuplicateObject(_))
+    ));
+}
+
+#[test]
+fn iter_streaming_objects()
+{
+    let mut package = Package::create(
+        new_byte_buf(0),
+        Builder::new()
+            .max_section_size(4096)
+            .data_header(SectionHeaderBuilder::new().compression(CompressionMethod::Zlib))
+    )
+    .unwrap();
+    let objects: Vec<(String, Vec<u8>)> = (0..20u32)
+        .map(|i| {
+            let data = (0..i * 500).map(|v| ((v + i) % 251) as u8).collect();
+            (format!("file{}", i), data)
+        })
+        .collect();
+    for (name, data) in &objects {
+        package.pack(name, &data[..]).unwrap();
+    }
+    package.save().unwrap();
+    let mut buf = package.into_inner().into_inner();
+    buf.set_position(0);
+
+    let mut package = Package::open(buf).unwrap();
+    let mut count = 0;
+    for (mut object, (name, data)) in package.iter_streaming().unwrap().zip(&objects) {
+        assert_eq!(object.name(), name);
+        assert_eq!(object.size(), data.len() as u64);
+        // Skip some objects entirely to check the stream catches up.
+        if count % 3 == 1 {
+            count += 1;
+            continue;
+        }
+        let mut out = Vec::new();
+        object.read_to_end(&mut out).unwrap();
+        assert_eq!(&out, data);
+        count += 1;
+    }
+    assert_eq!(count, objects.len());
+    let container = package.into_inner();
+    assert!(container
+        .iter()
+        .filter(|v| v.ty == SECTION_TYPE_DATA || v.ty == SECTION_TYPE_OBJECT_TABLE)
+        .all(|v| v.open().is_none()));
+}
+
+#[test]
+fn iter_streaming_stale_object()
+{
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.pack("a", &b"first"[..]).unwrap();
+    package.pack("b", &b"second"[..]).unwrap();
+    package.save().unwrap();
+    let mut buf = package.into_inner().into_inner();
+    buf.set_position(0);
+
+    let mut package = Package::open(buf).unwrap();
+    let mut iter = package.iter_streaming().unwrap();
+    let mut a = iter.next().unwrap();
+    let mut b = iter.next().unwrap();
+    let mut out = Vec::new();
+    assert!(a.read_to_end(&mut out).is_err());
+    b.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"second");
+}
+
+#[test]
+fn pack_file_filtered_skips_excluded_folder()
+{
+    let root = std::env::temp_dir().join(format!("bpx_filtered_{}", std::process::id()));
+    std::fs::create_dir_all(root.join("target/debug")).unwrap();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("target/debug/out.bin"), b"artifact").unwrap();
+    std::fs::write(root.join("src/main.rs"), b"fn main() {}").unwrap();
+    std::fs::write(root.join("notes.tmp"), b"temp").unwrap();
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    bpx::package::utils::pack_file_vname_filtered(&mut package, "root", &root, |path| {
+        let name = path.file_name().unwrap().to_str().unwrap();
+        name != "target" && !name.ends_with(".tmp")
+    })
+    .unwrap();
+    package.save().unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+
+    let mut package = reopen(package);
+    assert_eq!(package.objects().unwrap().count(), 1);
+    assert_eq!(package.object_names().unwrap(), vec!["root/src/main.rs"]);
+}
+
+#[test]
+fn metadata_round_trip()
+{
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.pack("a", &b"data"[..]).unwrap();
+    assert!(package.metadata().unwrap().is_none());
+    let mut obj = bpx::sd::Object::new();
+    obj.set("Version", 1u8.into());
+    package.set_metadata(&obj).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    let metadata = package.metadata().unwrap().unwrap();
+    assert_eq!(metadata.get("Version").unwrap().as_u64(), Some(1));
+    let mut obj = bpx::sd::Object::new();
+    obj.set("Version", 2u8.into());
+    obj.set("Description", "a much longer metadata object".into());
+    package.set_metadata(&obj).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    let metadata = package.metadata().unwrap().unwrap();
+    assert_eq!(metadata.get("Version").unwrap().as_u64(), Some(2));
+    assert_eq!(
+        metadata.get("Description").unwrap().as_str(),
+        Some("a much longer metadata object")
+    );
+    assert_eq!(
+        package
+            .into_inner()
+            .iter()
+            .filter(|v| v.ty == bpx::core::header::SECTION_TYPE_SD)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn pack_and_unpack_with_progress()
+{
+    let dir = std::env::temp_dir().join(format!("bpx_progress_{}", std::process::id()));
+    let root = dir.join("root");
+    std::fs::create_dir_all(root.join("empty")).unwrap();
+    std::fs::create_dir_all(root.join("sub")).unwrap();
+    std::fs::write(root.join("a.txt"), b"12345").unwrap();
+    std::fs::write(root.join("sub/b.txt"), b"1234567890").unwrap();
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    let mut events = Vec::new();
+    bpx::package::utils::pack_file_with_progress(&mut package, &root, |event| {
+        events.push((event.name.to_string(), event.size, event.bytes, event.total))
+    })
+    .unwrap();
+    package.save().unwrap();
+    events.sort();
+    assert_eq!(events.len(), 3);
+    assert!(events.iter().all(|v| v.3 == 15));
+    let sizes: Vec<(String, u64)> = events.iter().map(|v| (v.0.clone(), v.1)).collect();
+    assert_eq!(
+        sizes,
+        vec![
+            ("root/a.txt".into(), 5),
+            ("root/empty/".into(), 0),
+            ("root/sub/b.txt".into(), 10)
+        ]
+    );
+    assert_eq!(events.iter().map(|v| v.2).max(), Some(15));
+
+    let mut package = reopen(package);
+    let mut events = Vec::new();
+    bpx::package::utils::unpack_with_progress(&mut package, &dir.join("out"), |event| {
+        events.push((event.name.to_string(), event.bytes, event.total))
+    })
+    .unwrap();
+    assert_eq!(events.len(), 3);
+    assert!(events.iter().all(|v| v.2 == 15));
+    assert_eq!(events.last().unwrap().1, 15);
+    assert!(events.windows(2).all(|v| v[0].1 <= v[1].1));
+    assert_eq!(
+        std::fs::read(dir.join("out/root/sub/b.txt")).unwrap(),
+        b"1234567890"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn retarget_package()
+{
+    use bpx::package::{Architecture, Platform};
+
+    let mut package = Package::create(new_byte_buf(0), Builder::new().type_code(*b"TX")).unwrap();
+    package.pack("a", &b"data"[..]).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    assert_eq!(package.settings().architecture, Architecture::Any);
+    assert_eq!(package.settings().platform, Platform::Any);
+    package.set_architecture(Architecture::X86_64).unwrap();
+    package.set_platform(Platform::Linux).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    assert_eq!(package.settings().architecture, Architecture::X86_64);
+    assert_eq!(package.settings().platform, Platform::Linux);
+    assert_eq!(package.get_type_code(), *b"TX");
+    let mut data = Vec::new();
+    package.unpack("a", &mut data).unwrap();
+    assert_eq!(data, b"data");
+}
+
+#[test]
+fn unpack_one_object()
+{
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.pack("dir/a", &b"first"[..]).unwrap();
+    package.pack("dir/b", std::io::empty()).unwrap();
+    package.pack("c", &b"third object"[..]).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    let mut data = Vec::new();
+    assert_eq!(package.unpack_one("c", &mut data).unwrap(), 12);
+    assert_eq!(data, b"third object");
+    data.clear();
+    assert_eq!(package.unpack_one("dir/b", &mut data).unwrap(), 0);
+    assert!(data.is_empty());
+    match package.unpack_one("dir/c", &mut data) {
+        Err(ReadError::MissingObject(name)) => assert_eq!(name, "dir/c"),
+        _ => panic!("expected a missing object error")
+    }
+    assert_eq!(package.unpack_one("dir/a", &mut data).unwrap(), 5);
+    assert_eq!(data, b"first");
+}
+
+#[test]
+fn unpack_to_vec_matches_unpack()
+{
+    let big: Vec<u8> = (0..100000u32).map(|v| (v % 251) as u8).collect();
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.pack("dir/big.bin", &big[..]).unwrap();
+    package.pack("dir/empty.bin", std::io::empty()).unwrap();
+    package.pack("empty/", std::io::empty()).unwrap();
+    package.pack("small.txt", &b"small"[..]).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    assert_eq!(package.unpack_to_vec("dir/big.bin").unwrap(), big);
+    assert!(package.unpack_to_vec("dir/empty.bin").unwrap().is_empty());
+    assert!(matches!(
+        package.unpack_to_vec("dir/missing.bin"),
+        Err(ReadError::MissingObject(_))
+    ));
+
+    let dir = std::env::temp_dir().join(format!("bpx_to_vec_{}", std::process::id()));
+    bpx::package::utils::unpack(&mut package, &dir).unwrap();
+    let objects: Vec<(String, Vec<u8>)> = package
+        .unpack_all_to_vec()
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let names: Vec<&str> = objects.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["dir/big.bin", "dir/empty.bin", "empty/", "small.txt"]
+    );
+    for (name, data) in &objects {
+        if name.ends_with('/') {
+            assert!(data.is_empty());
+            assert!(dir.join(name).is_dir());
+        } else {
+            assert_eq!(&std::fs::read(dir.join(name)).unwrap(), data);
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn preserve_permissions()
+{
+    use std::os::unix::fs::PermissionsExt;
+
+    use bpx::package::SECTION_TYPE_ATTRIBUTE_TABLE;
+
+    let dir = std::env::temp_dir().join(format!("bpx_permissions_{}", std::process::id()));
+    let root = dir.join("root");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("run.sh"), b"#!/bin/sh").unwrap();
+    std::fs::write(root.join("data.txt"), b"data").unwrap();
+    std::fs::set_permissions(root.join("run.sh"), std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::fs::set_permissions(
+        root.join("data.txt"),
+        std::fs::Permissions::from_mode(0o640)
+    )
+    .unwrap();
+
+    // Permissions are not recorded by default.
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    bpx::package::utils::pack_file(&mut package, &root).unwrap();
+    package.save().unwrap();
+    let mut package = reopen(package);
+    assert_eq!(package.stat("root/run.sh").unwrap().mode, None);
+    assert!(package
+        .into_inner()
+        .find_section_by_type(SECTION_TYPE_ATTRIBUTE_TABLE)
+        .is_none());
+
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.set_preserve_permissions(true);
+    bpx::package::utils::pack_file(&mut package, &root).unwrap();
+    package.pack("extra", &b"no mode"[..]).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    assert_eq!(
+        package.stat("root/run.sh").unwrap().mode.map(|v| v & 0o777),
+        Some(0o755)
+    );
+    assert_eq!(
+        package
+            .stat("root/data.txt")
+            .unwrap()
+            .mode
+            .map(|v| v & 0o777),
+        Some(0o640)
+    );
+    assert_eq!(package.stat("extra").unwrap().mode, None);
+    let out = dir.join("out");
+    bpx::package::utils::unpack(&mut package, &out).unwrap();
+    let mode = |path: &str| {
+        std::fs::metadata(out.join(path))
+            .unwrap()
+            .permissions()
+            .mode()
+            & 0o777
+    };
+    assert_eq!(mode("root/run.sh"), 0o755);
+    assert_eq!(mode("root/data.txt"), 0o640);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn object_modes_survive_edits()
+{
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.pack_with_mode("a", &b"first"[..], 0o644).unwrap();
+    package.pack_with_mode("b", &b"second"[..], 0o755).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    assert!(package.remove("a").unwrap());
+    package.pack_with_mode("c", &b"third"[..], 0o600).unwrap();
+    package.replace("b", &b"replaced content"[..]).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    assert_eq!(package.stat("b").unwrap().mode, Some(0o755));
+    assert_eq!(package.stat("c").unwrap().mode, Some(0o600));
+    let modes: Vec<Option<u32>> = package.objects().unwrap().map(|v| v.mode()).collect();
+    assert_eq!(modes, vec![Some(0o755), Some(0o600)]);
+    // The attributes of the removed object are dropped.
+    let container = package.into_inner();
+    let table = container
+        .find_section_by_type(bpx::package::SECTION_TYPE_ATTRIBUTE_TABLE)
+        .unwrap();
+    assert_eq!(
+        container.get(table).size as usize,
+        2 * bpx::package::object::SIZE_OBJECT_ATTRIBUTES
+    );
+}
+
+#[test]
+fn compact_data_sections_keeps_objects_readable()
+{
+    let mut package =
+        Package::create(new_byte_buf(0), Builder::new().max_section_size(16)).unwrap();
+    package.pack("a", &[1; 16][..]).unwrap();
+    package.pack("b", &[2; 40][..]).unwrap();
+    package.pack("c", &[3; 20][..]).unwrap();
+    package.pack("empty", std::io::empty()).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    assert!(package.remove("b").unwrap());
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    // "b" filled 2 data sections of its own and shared its last one with "c".
+    assert_eq!(package.compact_data_sections().unwrap(), 2);
+    assert_eq!(package.compact_data_sections().unwrap(), 0);
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    assert_eq!(package.unpack_to_vec("a").unwrap(), vec![1; 16]);
+    assert_eq!(package.unpack_to_vec("c").unwrap(), vec![3; 20]);
+    assert!(package.unpack_to_vec("empty").unwrap().is_empty());
+    let data_sections = package
+        .into_inner()
+        .iter()
+        .filter(|v| v.ty == SECTION_TYPE_DATA)
+        .count();
+    assert_eq!(data_sections, 3);
+}
+
+#[test]
+fn preserve_timestamps()
+{
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let dir = std::env::temp_dir().join(format!("bpx_timestamps_{}", std::process::id()));
+    let root = dir.join("root");
+    std::fs::create_dir_all(&root).unwrap();
+    let file = std::fs::File::create(root.join("file.txt")).unwrap();
+    file.set_modified(UNIX_EPOCH + Duration::from_secs(1600000000))
+        .unwrap();
+    drop(file);
+
+    // Timestamps are not recorded by default.
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    assert!(!package.get_preserve_timestamps());
+    bpx::package::utils::pack_file(&mut package, &root).unwrap();
+    package.save().unwrap();
+    let mut package = reopen(package);
+    assert_eq!(package.stat("root/file.txt").unwrap().mtime, None);
+
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.set_preserve_timestamps(true);
+    bpx::package::utils::pack_file(&mut package, &root).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    assert_eq!(
+        package.stat("root/file.txt").unwrap().mtime,
+        Some(1600000000)
+    );
+    assert_eq!(package.stat("root/file.txt").unwrap().mode, None);
+    let mtimes: Vec<Option<u64>> = package.objects().unwrap().map(|v| v.mtime()).collect();
+    assert_eq!(mtimes, vec![Some(1600000000)]);
+    let out = dir.join("out");
+    bpx::package::utils::unpack(&mut package, &out).unwrap();
+    let modified = std::fs::metadata(out.join("root/file.txt"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    assert_eq!(modified, UNIX_EPOCH + Duration::from_secs(1600000000));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn pack_and_unpack_symlinks()
+{
+    let dir = std::env::temp_dir().join(format!("bpx_symlinks_{}", std::process::id()));
+    let root = dir.join("root");
+    std::fs::create_dir_all(root.join("sub")).unwrap();
+    std::fs::write(root.join("sub/file.txt"), b"content").unwrap();
+    std::os::unix::fs::symlink("sub/file.txt", root.join("link.txt")).unwrap();
+    // A cyclic directory link must not be followed.
+    std::os::unix::fs::symlink("..", root.join("sub/parent")).unwrap();
+
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.set_preserve_permissions(true);
+    bpx::package::utils::pack_file(&mut package, &root).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    assert_eq!(package.objects().unwrap().count(), 3);
+    let stat = package.stat("root/link.txt").unwrap();
+    assert_eq!(stat.kind, ObjectKind::Symlink);
+    assert_eq!(stat.mode, None);
+    assert_eq!(stat.size, 12);
+    assert_eq!(
+        package.stat("root/sub/parent").unwrap().kind,
+        ObjectKind::Symlink
+    );
+    assert_eq!(
+        package.stat("root/sub/file.txt").unwrap().kind,
+        ObjectKind::File
+    );
+    assert_eq!(package.unpack_to_vec("root/sub/parent").unwrap(), b"..");
+
+    let out = dir.join("out");
+    bpx::package::utils::unpack(&mut package, &out).unwrap();
+    assert_eq!(
+        std::fs::read_link(out.join("root/link.txt")).unwrap(),
+        std::path::Path::new("sub/file.txt")
+    );
+    assert_eq!(
+        std::fs::read_link(out.join("root/sub/parent")).unwrap(),
+        std::path::Path::new("..")
+    );
+    assert_eq!(
+        std::fs::read(out.join("root/link.txt")).unwrap(),
+        b"content"
+    );
+    // Unpacking again replaces the existing links.
+    bpx::package::utils::unpack(&mut package, &out).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn deduplicate_identical_contents()
+{
+    let data_size = |package: Package<std::io::Cursor<Vec<u8>>>| {
+        let container = package.into_inner();
+        let size: u32 = container
+            .iter()
+            .filter(|v| v.ty == SECTION_TYPE_DATA)
+            .map(|v| v.size)
+            .sum();
+        size
+    };
+    let texture: Vec<u8> = (0..10000).map(|v| (v % 251) as u8).collect();
+
+    let dir = std::env::temp_dir().join(format!("bpx_dedup_{}", std::process::id()));
+    let root = dir.join("root");
+    std::fs::create_dir_all(root.join("a")).unwrap();
+    std::fs::create_dir_all(root.join("b")).unwrap();
+    std::fs::write(root.join("a/texture.bin"), &texture).unwrap();
+    std::fs::write(root.join("b/texture.bin"), &texture).unwrap();
+    std::fs::write(root.join("b/other.bin"), b"other").unwrap();
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    assert!(!package.get_deduplicate());
+    package.set_deduplicate(true);
+    bpx::package::utils::pack_file(&mut package, &root).unwrap();
+    package.save().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let mut package = reopen(package);
+    assert_eq!(
+        package.unpack_to_vec("root/a/texture.bin").unwrap(),
+        texture
+    );
+    assert_eq!(
+        package.unpack_to_vec("root/b/texture.bin").unwrap(),
+        texture
+    );
+    assert_eq!(data_size(package), 10005);
+
+    // Objects spanning several sections are deduplicated as well.
+    let mut package =
+        Package::create(new_byte_buf(0), Builder::new().max_section_size(4096)).unwrap();
+    package.set_deduplicate(true);
+    package.pack("first", &texture[..]).unwrap();
+    package.pack("same prefix", &texture[..9999]).unwrap();
+    package.pack("second", &texture[..]).unwrap();
+    package.save().unwrap();
+    let mut package = reopen(package);
+    assert_eq!(package.unpack_to_vec("second").unwrap(), texture);
+    assert_eq!(
+        package.unpack_to_vec("same prefix").unwrap(),
+        &texture[..9999]
+    );
+
+    // Replacing a deduplicated object does not modify the shared data.
+    package.replace("second", &b"new"[..]).unwrap();
+    package.save().unwrap();
+    let mut package = reopen(package);
+    assert_eq!(package.unpack_to_vec("first").unwrap(), texture);
+    assert_eq!(package.unpack_to_vec("second").unwrap(), b"new");
+    assert_eq!(data_size(package), 10000 + 9999 + 3);
+}
+
+#[test]
+fn list_does_not_load_data()
+{
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.pack("a", &b"first object"[..]).unwrap();
+    package.pack_with_mode("b", &b"second"[..], 0o644).unwrap();
+    package.pack("dir/", std::io::empty()).unwrap();
+    package.save().unwrap();
+    let buf = package.into_inner().into_inner().into_inner();
+
+    // A read-only backend is enough.
+    let mut package = Package::open(std::io::Cursor::new(&buf[..])).unwrap();
+    let list = package.list().unwrap();
+    let summary: Vec<(&str, u64, ObjectKind)> = list
+        .iter()
+        .map(|v| (v.name.as_str(), v.size, v.kind))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("a", 12, ObjectKind::File),
+            ("b", 6, ObjectKind::File),
+            ("dir/", 0, ObjectKind::Directory)
+        ]
+    );
+    let container = package.into_inner();
+    let data = container.find_section_by_type(SECTION_TYPE_DATA).unwrap();
+    assert!(container.get(data).open().is_none());
+}
+
+#[test]
+fn estimate_size_matches_pack()
+{
+    let dir = std::env::temp_dir().join(format!("bpx_estimate_{}", std::process::id()));
+    let root = dir.join("root");
+    std::fs::create_dir_all(root.join("empty")).unwrap();
+    std::fs::write(root.join("zeros.bin"), vec![0u8; 200000]).unwrap();
+    std::fs::write(root.join("small.txt"), b"content").unwrap();
+
+    let mut builder = Builder::new();
+    builder.data_header(SectionHeaderBuilder::new());
+    let mut package = Package::create(new_byte_buf(0), builder).unwrap();
+    let estimate = package.estimate_size(&root).unwrap();
+    assert_eq!(estimate.objects, 3);
+    assert_eq!(estimate.size, 200007);
+    assert_eq!(estimate.compressed_size, 200007);
+    assert_eq!(
+        estimate.name_bytes,
+        ("root/empty/".len() + "root/zeros.bin".len() + "root/small.txt".len() + 3) as u64
+    );
+    // Estimating does not pack anything.
+    assert_eq!(package.objects().unwrap().count(), 0);
+
+    let package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    let estimate = package.estimate_size(&root).unwrap();
+    assert_eq!(estimate.size, 200007);
+    assert!(estimate.compressed_size < estimate.size / 10);
+    assert!(estimate.total() > estimate.compressed_size);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn per_object_compression()
+{
+    let data = vec![0u8; 100000];
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    let options = ObjectOptions::from_extension(std::path::Path::new("image.png"));
+    assert_eq!(options.compression, ObjectCompression::None);
+    package.pack("a.bin", &data[..]).unwrap();
+    package
+        .pack_with_options("b.png", &data[..], options)
+        .unwrap();
+    package.pack("c.bin", &data[..]).unwrap();
+    package
+        .pack_with_options("d.png", &data[..], options)
+        .unwrap();
+    let zlib = ObjectOptions {
+        compression: ObjectCompression::Zlib,
+        ..Default::default()
+    };
+    package.pack_with_options("e.bin", &data[..], zlib).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    for name in ["a.bin", "b.png", "c.bin", "d.png", "e.bin"] {
+        assert_eq!(package.unpack_to_vec(name).unwrap(), data);
+    }
+    let container = package.into_inner();
+    let flags: Vec<u8> = container
+        .iter()
+        .filter(|v| v.ty == SECTION_TYPE_DATA)
+        .map(|v| v.flags & (FLAG_COMPRESS_XZ | FLAG_COMPRESS_ZLIB))
+        .collect();
+    // Objects sharing the same compression share data sections.
+    assert_eq!(flags, vec![FLAG_COMPRESS_XZ, 0, FLAG_COMPRESS_ZLIB]);
+}
+
+#[test]
+fn pack_with_options_metadata()
+{
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let dir = std::env::temp_dir().join(format!("bpx_options_{}", std::process::id()));
+    let options = ObjectOptions {
+        mtime: Some(1500000000),
+        mode: Some(0o640),
+        ..Default::default()
+    };
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package
+        .pack_with_options("root/file.txt", "content".as_bytes(), options)
+        .unwrap();
+    package.pack("root/plain.txt", "plain".as_bytes()).unwrap();
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    let stat = package.stat("root/file.txt").unwrap();
+    assert_eq!(stat.mtime, Some(1500000000));
+    assert_eq!(stat.mode, Some(0o640));
+    let stat = package.stat("root/plain.txt").unwrap();
+    assert_eq!(stat.mtime, None);
+    assert_eq!(stat.mode, None);
+    bpx::package::utils::unpack(&mut package, &dir).unwrap();
+    let md = std::fs::metadata(dir.join("root/file.txt")).unwrap();
+    assert_eq!(
+        md.modified().unwrap(),
+        UNIX_EPOCH + Duration::from_secs(1500000000)
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(md.permissions().mode() & 0o777, 0o640);
+    }
+    assert_eq!(std::fs::read(dir.join("root/plain.txt")).unwrap(), b"plain");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn append_to_existing_package()
+{
+    fn data_sections(buf: &[u8]) -> Vec<Vec<u8>>
+    {
+        let container = Container::open(std::io::Cursor::new(buf)).unwrap();
+        container
+            .iter()
+            .filter(|v| v.ty == SECTION_TYPE_DATA)
+            .map(|v| buf[v.pointer as usize..(v.pointer + v.csize as u64) as usize].to_vec())
+            .collect()
+    }
+
+    let data: Vec<u8> = (0..200000).map(|i| (i % 251) as u8).collect();
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.pack("a.bin", &data[..]).unwrap();
+    package
+        .pack_with_mode("b.txt", "b".as_bytes(), 0o600)
+        .unwrap();
+    package.save().unwrap();
+    let buf = package.into_inner().into_inner().into_inner();
+    let before = data_sections(&buf);
+    let sections = Container::open(std::io::Cursor::new(&buf))
+        .unwrap()
+        .get_main_header()
+        .section_num;
+
+    let mut package = Package::open_append(std::io::Cursor::new(buf)).unwrap();
+    package.pack("c.txt", "c".as_bytes()).unwrap();
+    package.save().unwrap();
+    let buf = package.into_inner().into_inner().into_inner();
+    let after = data_sections(&buf);
+    // Existing data sections are copied verbatim and new objects go to a new section.
+    assert_eq!(after.len(), before.len() + 1);
+    assert_eq!(&after[..before.len()], &before[..]);
+    assert_eq!(
+        Container::open(std::io::Cursor::new(&buf))
+            .unwrap()
+            .get_main_header()
+            .section_num,
+        sections + 1
+    );
+
+    let mut package = Package::open(std::io::Cursor::new(buf)).unwrap();
+    assert_eq!(package.objects().unwrap().count(), 3);
+    assert_eq!(package.unpack_to_vec("a.bin").unwrap(), data);
+    assert_eq!(package.unpack_to_vec("b.txt").unwrap(), b"b");
+    assert_eq!(package.unpack_to_vec("c.txt").unwrap(), b"c");
+    assert_eq!(package.stat("b.txt").unwrap().mode, Some(0o600));
+}
+
+fn unpack_names(dir: &std::path::Path, objects: &[(&str, &[u8])]) -> Result<(), ReadError>
+{
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    for (name, data) in objects {
+        package.pack(name, *data).unwrap();
+    }
+    package.save().unwrap();
+    let mut package = reopen(package);
+    bpx::package::utils::unpack(&mut package, &dir.join("out"))
+}
+
+#[test]
+fn unpack_rejects_unsafe_paths()
+{
+    let dir = std::env::temp_dir().join(format!("bpx_unsafe_{}", std::process::id()));
+    for name in ["../evil.txt", "a/../../evil.txt", "..\\evil.txt"] {
+        assert!(matches!(
+            unpack_names(&dir, &[(name, b"evil")]),
+            Err(ReadError::UnsafePath(v)) if v == name
+        ));
+        assert!(!dir.join("evil.txt").exists());
+        assert!(!std::env::temp_dir().join("evil.txt").exists());
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+
+    unpack_names(
+        &dir,
+        &[
+            ("/abs/file.txt", b"abs"),
+            ("C:\\win\\file.txt", b"win"),
+            ("dir\\nested\\file.txt", b"backslash"),
+            ("nested/ok.txt", b"ok")
+        ]
+    )
+    .unwrap();
+    let out = dir.join("out");
+    assert_eq!(std::fs::read(out.join("abs/file.txt")).unwrap(), b"abs");
+    assert_eq!(std::fs::read(out.join("win/file.txt")).unwrap(), b"win");
+    assert_eq!(
+        std::fs::read(out.join("dir/nested/file.txt")).unwrap(),
+        b"backslash"
+    );
+    assert_eq!(std::fs::read(out.join("nested/ok.txt")).unwrap(), b"ok");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn unpack_rejects_paths_through_symlinks()
+{
+    use bpx::package::object::ObjectAttributes;
+
+    let dir = std::env::temp_dir().join(format!("bpx_unsafe_link_{}", std::process::id()));
+    let outside = dir.join("outside");
+    std::fs::create_dir_all(&outside).unwrap();
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    let attributes = ObjectAttributes {
+        symlink: true,
+        ..Default::default()
+    };
+    package
+        .pack_with_attributes("link", outside.to_str().unwrap().as_bytes(), attributes)
+        .unwrap();
+    package.pack("link/file.txt", "evil".as_bytes()).unwrap();
+    package.save().unwrap();
+    let mut package = reopen(package);
+    assert!(matches!(
+        bpx::package::utils::unpack(&mut package, &dir.join("out")),
+        Err(ReadError::UnsafePath(v)) if v == "link/file.txt"
+    ));
+    assert!(!outside.join("file.txt").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn unpack_matching_glob()
+{
+    let dir = std::env::temp_dir().join(format!("bpx_matching_{}", std::process::id()));
+    let root = dir.join("root");
+    std::fs::create_dir_all(root.join("textures/ui")).unwrap();
+    std::fs::create_dir_all(root.join("models")).unwrap();
+    std::fs::write(root.join("textures/wall.png"), b"wall").unwrap();
+    std::fs::write(root.join("textures/ui/button.png"), b"button").unwrap();
+    std::fs::write(root.join("models/cube.obj"), b"cube").unwrap();
+    std::fs::write(root.join("textures.txt"), b"not a texture").unwrap();
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    for name in ["textures", "models", "textures.txt"] {
+        bpx::package::utils::pack_file_vname(&mut package, name, &root.join(name)).unwrap();
+    }
+    package.save().unwrap();
+
+    let mut package = reopen(package);
+    let out = dir.join("out");
+    let summary = bpx::package::utils::unpack_matching(&mut package, &out, "textures/**").unwrap();
+    assert_eq!(summary.extracted, 2);
+    assert_eq!(summary.skipped, 2);
+    assert_eq!(
+        std::fs::read(out.join("textures/wall.png")).unwrap(),
+        b"wall"
+    );
+    assert_eq!(
+        std::fs::read(out.join("textures/ui/button.png")).unwrap(),
+        b"button"
+    );
+    assert!(!out.join("models").exists());
+    assert!(!out.join("textures.txt").exists());
+
+    let out = dir.join("out_png");
+    let summary = bpx::package::utils::unpack_matching(&mut package, &out, "**/*.png").unwrap();
+    assert_eq!(summary.extracted, 2);
+    let summary = bpx::package::utils::unpack_matching(&mut package, &out, "*/*.png").unwrap();
+    assert_eq!(summary.extracted, 1);
+    let summary =
+        bpx::package::utils::unpack_filtered(&mut package, &dir.join("none"), |_| false).unwrap();
+    assert_eq!(summary.extracted, 0);
+    assert_eq!(summary.skipped, 4);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn pack_with_progress_reports()
+{
+    let data: Vec<u8> = (0..50000u32).map(|v| (v % 251) as u8).collect();
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    let mut reports = Vec::new();
+    package
+        .pack_with_progress("big", &data[..], Some(data.len() as u64), |bytes, total| {
+            reports.push((bytes, total))
+        })
+        .unwrap();
+    assert!(reports.len() >= data.len() / 8192);
+    assert!(reports
+        .windows(2)
+        .all(|v| v[0].0 < v[1].0 && v[1].0 - v[0].0 <= 8192));
+    assert_eq!(reports.last().unwrap(), &(50000, Some(50000)));
+    package.save().unwrap();
+    let mut package = reopen(package);
+    assert_eq!(unpack_all(&mut package, "big"), data);
+}
+
+#[test]
+fn verify_package_object_sizes()
+{
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.pack("ok", "Hello".as_bytes()).unwrap();
+    package.save().unwrap();
+    let mut package = reopen(package);
+    let report = package.verify().unwrap();
+    assert!(report.is_valid());
+    assert!(report.inconsistent_objects.is_empty());
+
+    // An object table claiming more data than the data section holds.
+    let mut container = package.into_inner();
+    let table = container
+        .find_section_by_type(SECTION_TYPE_OBJECT_TABLE)
+        .unwrap();
+    let data = container.find_section_by_type(SECTION_TYPE_DATA).unwrap();
+    let name = {
+        let mut section = container.get_mut(table);
+        let mut bytes = Vec::new();
+        section.load().unwrap().read_to_end(&mut bytes).unwrap();
+        ObjectHeader::read(&bytes[..]).unwrap().name
+    };
+    let header = ObjectHeader {
+        size: 1000,
+        name,
+        start: container.get(data).index(),
+        offset: 0
+    };
+    {
+        let mut section = container.get_mut(table);
+        let section = section.load().unwrap();
+        section.seek(SeekFrom::Start(0)).unwrap();
+        header.write(section).unwrap();
+    }
+    container.stage_unloaded().unwrap();
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut package = Package::open(buf).unwrap();
+    let report = package.verify().unwrap();
+    assert!(report.container.is_valid());
+    assert!(!report.is_valid());
+    assert_eq!(report.inconsistent_objects, vec!["ok"]);
+}
@@ -0,0 +1,143 @@
+use bpx::{
+    core::{
+        builder::{MainHeaderBuilder, SectionHeaderBuilder},
+        header::{SectionType, SECTION_TYPE_SD, SECTION_TYPE_STRING},
+        reserved::{
+            is_reserved,
+            lookup,
+            MAIN_TYPE_PACKAGE,
+            MAIN_TYPE_SHADER,
+            PACKAGE_DATA,
+            PACKAGE_OBJECT_TABLE,
+            RESERVED_SECTION_TYPES,
+            SHADER_SHADER,
+            SHADER_SYMBOL_TABLE
+        },
+        Container
+    },
+    utils::new_byte_buf
+};
+
+#[test]
+fn registry_has_no_duplicates()
+{
+    for (i, a) in RESERVED_SECTION_TYPES.iter().enumerate() {
+        for b in &RESERVED_SECTION_TYPES[i + 1..] {
+            assert!(a.main_type != b.main_type || a.section_type != b.section_type);
+        }
+        assert_ne!(a.section_type, SECTION_TYPE_STRING);
+        assert_ne!(a.section_type, SECTION_TYPE_SD);
+    }
+    assert_eq!(lookup(MAIN_TYPE_PACKAGE, 0x1), Some(&PACKAGE_DATA));
+    assert_eq!(lookup(MAIN_TYPE_PACKAGE, 0x2), Some(&PACKAGE_OBJECT_TABLE));
+    assert_eq!(lookup(MAIN_TYPE_SHADER, 0x1), Some(&SHADER_SHADER));
+}
+
+#[test]
+fn deliberate_collision()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new().ty(b'X'));
+    let symbols = container.create_section(SectionHeaderBuilder::new().ty(0x2));
+    container.create_section(SectionHeaderBuilder::new().ty(0x10));
+    container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_STRING));
+
+    let conflicts = container.check_reserved_conflicts(b'X');
+    assert_eq!(conflicts.len(), 2);
+    assert!(conflicts.iter().all(|v| v.handle == symbols));
+    assert!(conflicts.iter().any(|v| v.reserved == SHADER_SYMBOL_TABLE));
+    assert!(conflicts.iter().any(|v| v.reserved == PACKAGE_OBJECT_TABLE));
+
+    // A shader package owns its symbol table type; only the package meaning collides.
+    let conflicts = container.check_reserved_conflicts(MAIN_TYPE_SHADER);
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].reserved, PACKAGE_OBJECT_TABLE);
+}
+
+#[test]
+fn no_conflict_in_unrelated_types()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new().ty(b'X'));
+    container.create_section(SectionHeaderBuilder::new().ty(0x40));
+    container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_SD));
+    assert!(container.check_reserved_conflicts(b'X').is_empty());
+}
+
+#[cfg(feature = "package")]
+#[test]
+fn registry_matches_package()
+{
+    assert_eq!(bpx::package::SECTION_TYPE_DATA, PACKAGE_DATA.section_type);
+    assert_eq!(
+        bpx::package::SECTION_TYPE_OBJECT_TABLE,
+        PACKAGE_OBJECT_TABLE.section_type
+    );
+    assert_eq!(
+        u8::from(SectionType::PackageData),
+        bpx::package::SECTION_TYPE_DATA
+    );
+    assert_eq!(
+        u8::from(SectionType::PackageObjectTable),
+        bpx::package::SECTION_TYPE_OBJECT_TABLE
+    );
+    assert_eq!(
+        u8::from(SectionType::PackageAttributeTable),
+        bpx::package::SECTION_TYPE_ATTRIBUTE_TABLE
+    );
+}
+
+#[cfg(feature = "shader")]
+#[test]
+fn registry_matches_shader()
+{
+    assert_eq!(bpx::shader::SECTION_TYPE_SHADER, SHADER_SHADER.section_type);
+    assert_eq!(
+        bpx::shader::SECTION_TYPE_SYMBOL_TABLE,
+        SHADER_SYMBOL_TABLE.section_type
+    );
+    assert_eq!(
+        u8::from(SectionType::Shader),
+        bpx::shader::SECTION_TYPE_SHADER
+    );
+    assert_eq!(
+        u8::from(SectionType::ShaderSymbolTable),
+        bpx::shader::SECTION_TYPE_SYMBOL_TABLE
+    );
+    assert_eq!(
+        u8::from(SectionType::ShaderExtendedData),
+        bpx::shader::SECTION_TYPE_EXTENDED_DATA
+    );
+}
+
+#[test]
+fn section_type_round_trip()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new().ty(b'X'));
+    for reserved in RESERVED_SECTION_TYPES {
+        let ty = SectionType::from_raw(reserved.main_type, reserved.section_type);
+        assert!(!matches!(ty, SectionType::Custom(_)));
+        let handle = container.create_section(SectionHeaderBuilder::new().section_type(ty));
+        let header = container.get(handle);
+        assert_eq!(header.ty, reserved.section_type);
+        assert_eq!(SectionType::from_raw(reserved.main_type, header.ty), ty);
+        assert!(container.is_reserved_type(header.ty));
+        // Under another main type a variant section type is just a custom type.
+        assert_eq!(
+            SectionType::from_raw(b'X', header.ty),
+            SectionType::Custom(reserved.section_type)
+        );
+    }
+    for (ty, byte) in [
+        (SectionType::Strings, SECTION_TYPE_STRING),
+        (SectionType::Sd, SECTION_TYPE_SD)
+    ] {
+        assert_eq!(u8::from(ty), byte);
+        assert_eq!(SectionType::from_raw(b'X', byte), ty);
+        assert!(is_reserved(byte));
+    }
+    let custom = container.create_section(SectionHeaderBuilder::new().ty(0x40));
+    assert!(!container.is_reserved_type(container.get(custom).ty));
+    assert_eq!(
+        container.find_section_by_type(SectionType::Custom(0x40).into()),
+        Some(custom)
+    );
+}
@@ -0,0 +1,238 @@
+#![cfg(feature = "sd")]
+
+use bpx::sd::{Array, Debugger, Object, Value};
+
+#[test]
+fn integer_widening()
+{
+    let values: [Value; 8] = [
+        u8::MAX.into(),
+        u16::MAX.into(),
+        u32::MAX.into(),
+        (i64::MAX as u64).into(),
+        i8::MIN.into(),
+        i16::MIN.into(),
+        i32::MIN.into(),
+        i64::MIN.into()
+    ];
+    let expected = [
+        u8::MAX as i64,
+        u16::MAX as i64,
+        u32::MAX as i64,
+        i64::MAX,
+        i8::MIN as i64,
+        i16::MIN as i64,
+        i32::MIN as i64,
+        i64::MIN
+    ];
+    for (value, expected) in values.iter().zip(expected) {
+        assert_eq!(value.as_i64(), Some(expected));
+    }
+    assert_eq!(Value::from(i64::MAX as u64 + 1).as_i64(), None);
+    assert_eq!(
+        Value::from(i64::MAX as u64 + 1).as_u64(),
+        Some(i64::MAX as u64 + 1)
+    );
+    assert_eq!(Value::from(i64::MIN).as_u64(), None);
+    assert_eq!(Value::from(7i64).as_u64(), Some(7));
+    assert_eq!(Value::from(true).as_i64(), None);
+    assert_eq!(Value::from("12").as_u64(), None);
+}
+
+#[test]
+fn float_widening()
+{
+    assert_eq!(Value::from(1.5f32).as_f64(), Some(1.5));
+    assert_eq!(Value::from(2.25f64).as_f64(), Some(2.25));
+    assert_eq!(Value::from(u32::MAX).as_f64(), Some(u32::MAX as f64));
+    assert_eq!(Value::from(i32::MIN).as_f64(), Some(i32::MIN as f64));
+    assert_eq!(Value::from(u64::MAX).as_f64(), None);
+    assert_eq!(Value::from(1i64).as_f64(), None);
+    assert_eq!(Value::Null.as_f64(), None);
+}
+
+#[test]
+fn typed_getters()
+{
+    let mut arr = Array::new();
+    arr.add(1u8.into());
+    let mut obj = Object::new();
+    obj.set("name", "test".into());
+    obj.set("flag", false.into());
+    obj.set("list", arr.into());
+    let value = Value::from(obj);
+    let obj = value.as_object().unwrap();
+    assert_eq!(obj.get("name").unwrap().as_str(), Some("test"));
+    assert_eq!(obj.get("flag").unwrap().as_bool(), Some(false));
+    assert_eq!(obj.get("list").unwrap().as_array().unwrap().len(), 1);
+    assert!(obj.get("name").unwrap().as_bool().is_none());
+    assert!(obj.get("flag").unwrap().as_str().is_none());
+    assert!(obj.get("name").unwrap().as_array().is_none());
+    assert!(obj.get("list").unwrap().as_object().is_none());
+}
+
+#[test]
+fn nested_path()
+{
+    let mut inner = Object::new();
+    inner.set("vsync", true.into());
+    inner.set("fps", 60u16.into());
+    let mut props = Object::new();
+    props.set("graphics", inner.into());
+    props.set("name", "config".into());
+    let mut obj = Object::new();
+    obj.set("props", props.into());
+
+    assert_eq!(
+        obj.get_path("props.graphics.vsync").unwrap().as_bool(),
+        Some(true)
+    );
+    assert_eq!(
+        obj.get_path("props.graphics.fps").unwrap().as_i64(),
+        Some(60)
+    );
+    assert!(obj.get_path("props").unwrap().as_object().is_some());
+    assert!(obj.get_path("props.name.value").is_none());
+    assert!(obj.get_path("props.missing").is_none());
+    assert!(obj.get_path("").is_none());
+}
+
+#[test]
+fn display_value()
+{
+    let mut arr = Array::new();
+    arr.add(1u8.into());
+    arr.add((-2i64).into());
+    arr.add(1.5f32.into());
+    arr.add(2.0f64.into());
+    arr.add("a \"b\"".into());
+    arr.add(Value::Null);
+    arr.add(true.into());
+    assert_eq!(
+        Value::from(arr).to_string(),
+        "[1u8, -2i64, 1.5f32, 2.0f64, \"a \\\"b\\\"\", null, true]"
+    );
+}
+
+#[test]
+fn display_object()
+{
+    let mut obj = Debugger::attach(Object::new()).unwrap();
+    obj.set("zeta", 1u32.into());
+    obj.set("alpha", Object::new().into());
+    let mut obj = obj.detach();
+    obj.raw_set(0xAB, Value::Null);
+    assert_eq!(
+        obj.to_string(),
+        "{#00000000000000AB: null, alpha: {}, zeta: 1u32}"
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_round_trip()
+{
+    let mut inner = Debugger::attach(Object::new()).unwrap();
+    inner.set("vsync", true.into());
+    inner.set("scale", 0.5f64.into());
+    let mut arr = Array::new();
+    arr.add("line\nbreak \u{1F600}".into());
+    arr.add(Value::Null);
+    let mut obj = Debugger::attach(Object::new()).unwrap();
+    obj.set("graphics", inner.detach().into());
+    obj.set("list", arr.into());
+    obj.set("count", 42u8.into());
+    obj.set("negative", (-7i32).into());
+    let obj = obj.detach();
+
+    let json = obj.to_json_string();
+    assert_eq!(
+        json,
+        "{\"count\":42,\"graphics\":{\"scale\":0.5,\"vsync\":true},\
+         \"list\":[\"line\\nbreak \u{1F600}\",null],\"negative\":-7}"
+    );
+    let copy = Object::from_json_str(&json).unwrap();
+    assert_eq!(copy.to_json_string(), json);
+    assert_eq!(
+        copy.get_path("graphics.vsync").unwrap().as_bool(),
+        Some(true)
+    );
+    assert_eq!(copy.get_path("graphics.scale").unwrap().as_f64(), Some(0.5));
+    assert_eq!(copy.get("count").unwrap().as_u64(), Some(42));
+    assert_eq!(copy.get("negative").unwrap().as_i64(), Some(-7));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_large_integers()
+{
+    let above = (1u64 << 53) + 1;
+    let mut obj = Debugger::attach(Object::new()).unwrap();
+    obj.set("above", above.into());
+    obj.set("max", u64::MAX.into());
+    obj.set("min", i64::MIN.into());
+    obj.set("safe", (1u64 << 53).into());
+    let obj = obj.detach();
+
+    let json = obj.to_json_string();
+    assert_eq!(
+        json,
+        "{\"above\":\"9007199254740993\",\"max\":\"18446744073709551615\",\
+         \"min\":\"-9223372036854775808\",\"safe\":9007199254740992}"
+    );
+    let copy = Object::from_json_str(&json).unwrap();
+    assert!(copy.get("above").unwrap() == &Value::Uint64(above));
+    assert!(copy.get("max").unwrap() == &Value::Uint64(u64::MAX));
+    assert!(copy.get("min").unwrap() == &Value::Int64(i64::MIN));
+    assert!(copy.get("safe").unwrap() == &Value::Uint64(1 << 53));
+    assert_eq!(copy.to_json_string(), json);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_unnamed_properties()
+{
+    let mut obj = Object::new();
+    obj.set("name", "value".into());
+    let json = obj.to_json_string();
+    assert_eq!(
+        json,
+        format!("{{\"#{:016X}\":\"value\"}}", bpx::utils::hash("name"))
+    );
+    let copy = Object::from_json_str(&json).unwrap();
+    assert!(copy == obj);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_errors()
+{
+    use bpx::sd::json::Error;
+
+    assert!(matches!(
+        Object::from_json_str("[]"),
+        Err(Error::NotAnObject)
+    ));
+    assert!(matches!(
+        Object::from_json_str("{\"a\": tru"),
+        Err(Error::Eof)
+    ));
+    assert!(matches!(
+        Object::from_json_str("{\"a\" 1}"),
+        Err(Error::Syntax(5))
+    ));
+    assert!(matches!(
+        Object::from_json_str("{} x"),
+        Err(Error::Syntax(3))
+    ));
+    assert!(matches!(
+        Object::from_json_str("{\"a\": \"\\q\"}"),
+        Err(Error::Escape(7))
+    ));
+    assert!(matches!(
+        Object::from_json_str("{\"a\": \"\\ud800\"}"),
+        Err(Error::Escape(7))
+    ));
+    let deep = format!("{{\"a\":{}{}}}", "[".repeat(200), "]".repeat(200));
+    assert!(matches!(Object::from_json_str(&deep), Err(Error::Depth)));
+}
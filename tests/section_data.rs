@@ -0,0 +1,540 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use bpx::{
+    core::{
+        builder::{CompressionMethod, MainHeaderBuilder, SectionHeaderBuilder},
+        compare,
+        AutoSectionData,
+        BorrowedSection,
+        Container,
+        SectionData
+    },
+    utils::new_byte_buf
+};
+
+fn generate_data(size: usize) -> Vec<u8>
+{
+    (0..size).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn spill_to_file_above_threshold()
+{
+    let data = generate_data(4096);
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let raw = container.create_section(SectionHeaderBuilder::new().ty(1));
+    let compressed = container.create_section(
+        SectionHeaderBuilder::new()
+            .ty(2)
+            .compression(CompressionMethod::Zlib)
+            .threshold(0)
+    );
+    for handle in [raw, compressed] {
+        let mut section = container.get_mut(handle);
+        let section = section.open().unwrap();
+        // Small writes to cross the threshold while writing.
+        for chunk in data.chunks(100) {
+            section.write_all(chunk).unwrap();
+        }
+        assert_eq!(section.size(), data.len());
+        section.seek(SeekFrom::Start(10)).unwrap();
+        let mut buf = [0; 20];
+        section.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, &data[10..30]);
+        section.seek(SeekFrom::Start(0)).unwrap();
+        assert_eq!(section.load_in_memory().unwrap(), data);
+    }
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    container.set_memory_threshold(1024);
+    for ty in [1, 2] {
+        let handle = container.find_section_by_type(ty).unwrap();
+        let mut section = container.get_mut(handle);
+        assert_eq!(section.load().unwrap().load_in_memory().unwrap(), data);
+    }
+}
+
+#[test]
+fn spilled_seek_past_end()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    container.set_memory_threshold(16);
+    let handle = container.create_section(SectionHeaderBuilder::new());
+    let mut section = container.get_mut(handle);
+    let section = section.open().unwrap();
+    section.write_all(&[1; 32]).unwrap();
+    assert_eq!(section.seek(SeekFrom::End(8)).unwrap(), 40);
+    assert_eq!(section.size(), 32);
+    section.write_all(&[2; 4]).unwrap();
+    assert_eq!(section.size(), 44);
+    section.seek(SeekFrom::Start(0)).unwrap();
+    let mut expected = vec![1; 32];
+    expected.extend_from_slice(&[0; 8]);
+    expected.extend_from_slice(&[2; 4]);
+    assert_eq!(section.load_in_memory().unwrap(), expected);
+    // Overwrite in the middle then keep writing past the end.
+    section.seek(SeekFrom::Start(40)).unwrap();
+    section.write_all(&[3; 8]).unwrap();
+    assert_eq!(section.size(), 48);
+    assert!(section.seek(SeekFrom::Current(-49)).is_err());
+}
+
+#[test]
+fn clear_goes_back_to_memory()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    container.set_memory_threshold(16);
+    let handle = container.create_section(SectionHeaderBuilder::new());
+    let mut section = container.get_mut(handle);
+    let section = section.open().unwrap();
+    section.write_all(&[1; 32]).unwrap();
+    section.clear();
+    assert_eq!(section.size(), 0);
+    section.write_all(b"small").unwrap();
+    section.seek(SeekFrom::Start(0)).unwrap();
+    assert_eq!(section.load_in_memory().unwrap(), b"small");
+}
+
+#[test]
+fn borrowed_read_only_section()
+{
+    let data = generate_data(300);
+    let mut section = BorrowedSection::new(&data);
+    assert_eq!(section.size(), 300);
+    section.seek(SeekFrom::Start(250)).unwrap();
+    let mut buf = Vec::new();
+    section.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, &data[250..]);
+    assert!(section.write_all(b"test").is_err());
+    assert_eq!(section.load_in_memory().unwrap(), data);
+}
+
+#[test]
+fn borrowed_read_write_section()
+{
+    let mut data = [0u8; 8];
+    {
+        let mut section = BorrowedSection::new_mut(&mut data);
+        section.write_all(b"abcd").unwrap();
+        section.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = [0; 4];
+        section.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"abcd");
+        section.seek(SeekFrom::End(-2)).unwrap();
+        assert!(section.write_all(b"xyz").is_err());
+    }
+    assert_eq!(&data[..4], b"abcd");
+}
+
+#[test]
+fn compare_section_data()
+{
+    let data = generate_data(20000);
+    let mut first = data.clone();
+    first[0] ^= 0xFF;
+    let mut last = data.clone();
+    last[19999] ^= 0xFF;
+    let mut a = BorrowedSection::new(&data);
+    a.seek(SeekFrom::Start(42)).unwrap();
+    assert!(compare(&mut a, &mut BorrowedSection::new(&data)).unwrap());
+    assert!(!compare(&mut a, &mut BorrowedSection::new(&first)).unwrap());
+    assert!(!compare(&mut a, &mut BorrowedSection::new(&last)).unwrap());
+    assert!(!compare(&mut a, &mut BorrowedSection::new(&data[..19999])).unwrap());
+    assert_eq!(a.stream_position().unwrap(), 42);
+}
+
+#[test]
+fn sections_equal_across_containers()
+{
+    let data = generate_data(10000);
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let h1 = container.create_section(SectionHeaderBuilder::new().ty(1));
+    let h2 = container.create_section(
+        SectionHeaderBuilder::new()
+            .ty(2)
+            .compression(CompressionMethod::Zlib)
+    );
+    for handle in [h1, h2] {
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&data)
+            .unwrap();
+    }
+    assert!(container.sections_equal(h1, h2).unwrap());
+    container.save().unwrap();
+
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut other = Container::open(buf).unwrap();
+    let h1 = other.find_section_by_type(1).unwrap();
+    let h2 = other.find_section_by_type(2).unwrap();
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let h3 = container.create_section(SectionHeaderBuilder::new().ty(1));
+    container
+        .get_mut(h3)
+        .open()
+        .unwrap()
+        .write_all(&data[..9999])
+        .unwrap();
+    assert!(!container.sections_equal_with(h3, &mut other, h2).unwrap());
+    container
+        .get_mut(h3)
+        .open()
+        .unwrap()
+        .write_all(&[1])
+        .unwrap();
+    assert!(!container.sections_equal_with(h3, &mut other, h2).unwrap());
+    container
+        .get_mut(h3)
+        .open()
+        .unwrap()
+        .seek(SeekFrom::Start(9999))
+        .unwrap();
+    container
+        .get_mut(h3)
+        .open()
+        .unwrap()
+        .write_all(&data[9999..])
+        .unwrap();
+    assert!(container.sections_equal_with(h3, &mut other, h2).unwrap());
+    assert!(other.sections_equal(h1, h2).unwrap());
+}
+
+#[test]
+fn truncate_section_data()
+{
+    let data = generate_data(4096);
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let memory = container.create_section(SectionHeaderBuilder::new().ty(1));
+    container.set_memory_threshold(1024);
+    let file = container.create_section(SectionHeaderBuilder::new().ty(2));
+    for handle in [memory, file] {
+        let mut section = container.get_mut(handle);
+        let section = section.open().unwrap();
+        section.write_all(&data).unwrap();
+
+        // Truncate to a middle position: the cursor is clamped to the new end.
+        section.truncate(2000).unwrap();
+        assert_eq!(section.size(), 2000);
+        assert_eq!(section.stream_position().unwrap(), 2000);
+        section.seek(SeekFrom::Start(0)).unwrap();
+        assert_eq!(section.load_in_memory().unwrap(), &data[..2000]);
+
+        // Truncating past the end does nothing.
+        section.seek(SeekFrom::Start(10)).unwrap();
+        section.truncate(3000).unwrap();
+        assert_eq!(section.size(), 2000);
+        assert_eq!(section.stream_position().unwrap(), 10);
+
+        // Write after truncation.
+        section.truncate(1000).unwrap();
+        assert_eq!(section.stream_position().unwrap(), 10);
+        section.seek(SeekFrom::End(0)).unwrap();
+        section.write_all(b"end").unwrap();
+        assert_eq!(section.size(), 1003);
+        section.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        section.read_to_end(&mut buf).unwrap();
+        assert_eq!(&buf[..1000], &data[..1000]);
+        assert_eq!(&buf[1000..], b"end");
+
+        // Truncate to zero.
+        section.truncate(0).unwrap();
+        assert_eq!(section.size(), 0);
+        assert_eq!(section.stream_position().unwrap(), 0);
+        buf.clear();
+        section.read_to_end(&mut buf).unwrap();
+        assert!(buf.is_empty());
+    }
+    container.save().unwrap();
+    assert_eq!(container.get(memory).size, 0);
+    assert_eq!(container.get(file).size, 0);
+}
+
+#[test]
+fn load_above_memory_threshold()
+{
+    let data = generate_data(8192);
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let raw = container.create_section(SectionHeaderBuilder::new().ty(1));
+    let compressed = container.create_section(
+        SectionHeaderBuilder::new()
+            .ty(2)
+            .compression(CompressionMethod::Xz)
+            .threshold(0)
+    );
+    for handle in [raw, compressed] {
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&data)
+            .unwrap();
+    }
+    container.save().unwrap();
+
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    container.set_memory_threshold(256);
+    let raw = container.find_section_by_type(1).unwrap();
+    let compressed = container.find_section_by_type(2).unwrap();
+    for handle in [raw, compressed] {
+        let mut section = container.get_mut(handle);
+        let section = section.load().unwrap();
+        assert_eq!(section.size(), data.len());
+        assert_eq!(section.load_in_memory().unwrap(), data);
+        // Writes past the threshold keep working on the loaded data.
+        section.seek(SeekFrom::End(0)).unwrap();
+        section.write_all(&data).unwrap();
+    }
+    container.save().unwrap();
+
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    let raw = container.find_section_by_type(1).unwrap();
+    let compressed = container.find_section_by_type(2).unwrap();
+    for handle in [raw, compressed] {
+        let loaded = container
+            .get_mut(handle)
+            .load()
+            .unwrap()
+            .load_in_memory()
+            .unwrap();
+        assert_eq!(&loaded[..8192], &data[..]);
+        assert_eq!(&loaded[8192..], &data[..]);
+    }
+}
+
+#[test]
+fn seek_past_end_after_truncate()
+{
+    let data = generate_data(4096);
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let memory = container.create_section(SectionHeaderBuilder::new().ty(1));
+    container.set_memory_threshold(1024);
+    let file = container.create_section(SectionHeaderBuilder::new().ty(2));
+    for handle in [memory, file] {
+        let mut section = container.get_mut(handle);
+        let section = section.open().unwrap();
+        section.write_all(&data).unwrap();
+        section.truncate(100).unwrap();
+        assert_eq!(section.size(), 100);
+        // Like std::io::Cursor, seeking past the end is allowed and reads return nothing.
+        assert_eq!(section.seek(SeekFrom::Start(200)).unwrap(), 200);
+        let mut buf = [0; 16];
+        assert_eq!(section.read(&mut buf).unwrap(), 0);
+        assert_eq!(section.seek(SeekFrom::End(10)).unwrap(), 110);
+        assert_eq!(section.stream_position().unwrap(), 110);
+        assert_eq!(section.size(), 100);
+    }
+}
+
+#[test]
+fn write_past_end_zero_fills()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let memory = container.create_section(SectionHeaderBuilder::new().ty(1));
+    container.set_memory_threshold(1024);
+    let file = container.create_section(SectionHeaderBuilder::new().ty(2));
+    for handle in [memory, file] {
+        let mut section = container.get_mut(handle);
+        let section = section.open().unwrap();
+        section.write_all(&[0xAB; 2048]).unwrap();
+        section.truncate(16).unwrap();
+        section.seek(SeekFrom::Start(1500)).unwrap();
+        section.write_all(b"end").unwrap();
+        assert_eq!(section.size(), 1503);
+        section.seek(SeekFrom::Start(0)).unwrap();
+        let data = section.load_in_memory().unwrap();
+        assert_eq!(data.len(), 1503);
+        assert_eq!(&data[..16], &[0xAB; 16]);
+        assert!(data[16..1500].iter().all(|v| *v == 0));
+        assert_eq!(&data[1500..], b"end");
+    }
+}
+
+#[test]
+fn append_to_unloaded_section()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = container.create_section(
+        SectionHeaderBuilder::new()
+            .compression(CompressionMethod::Zlib)
+            .threshold(0)
+    );
+    container.append_to_section(handle, b"line 1\n").unwrap();
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    let handle = container.find_section_by_index(0).unwrap();
+    assert!(container.get(handle).open().is_none());
+    container.append_to_section(handle, b"line 2\n").unwrap();
+    container.append_to_section(handle, b"line 3\n").unwrap();
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    let handle = container.find_section_by_index(0).unwrap();
+    let mut data = Vec::new();
+    container
+        .get_mut(handle)
+        .load()
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, b"line 1\nline 2\nline 3\n");
+}
+
+#[test]
+fn unload_sections_by_type()
+{
+    let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    for i in 0..3 {
+        let handle = file.create_section(
+            SectionHeaderBuilder::new()
+                .ty(if i == 2 { 2 } else { 1 })
+                .compression(CompressionMethod::Zlib)
+        );
+        file.get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&generate_data(1000 + i))
+            .unwrap();
+    }
+    file.save().unwrap();
+    let mut buf = file.into_inner();
+    buf.set_position(0);
+
+    let mut file = Container::open(buf).unwrap();
+    let handles: Vec<_> = (0..3)
+        .map(|i| file.find_section_by_index(i).unwrap())
+        .collect();
+    file.load_all().unwrap();
+    // Sections which were only read are identical to the backend and can be unloaded.
+    assert_eq!(file.unload_type(1), 2);
+    assert!(file.get(handles[0]).open().is_none());
+    assert!(file.get(handles[1]).open().is_none());
+    file.get_mut(handles[0]).load().unwrap();
+    let mut section = file.get_mut(handles[1]);
+    let data = section.load().unwrap();
+    data.seek(SeekFrom::End(0)).unwrap();
+    data.write_all(b"modified").unwrap();
+    // Sections with unsaved writes are kept.
+    assert_eq!(file.unload_type(1), 1);
+    assert!(file.get(handles[0]).open().is_none());
+    assert!(file.get(handles[1]).open().is_some());
+    assert!(file.get(handles[2]).open().is_some());
+
+    let data = file
+        .get_mut(handles[0])
+        .load()
+        .unwrap()
+        .load_in_memory()
+        .unwrap();
+    assert_eq!(data, generate_data(1000));
+    let data = file
+        .get_mut(handles[1])
+        .load()
+        .unwrap()
+        .load_in_memory()
+        .unwrap();
+    assert!(data.ends_with(b"modified"));
+}
+
+#[test]
+fn zero_copy_section_access()
+{
+    let data = generate_data(4096);
+    let mut section = AutoSectionData::new();
+    section.write_all(&data).unwrap();
+    let slice = section.as_slice().unwrap();
+    assert_eq!(slice, &data[..]);
+    // The returned buffer is the internal buffer, not a copy.
+    let ptr = slice.as_ptr();
+    let buf = section.into_vec().unwrap();
+    assert_eq!(buf.as_ptr(), ptr);
+    assert_eq!(buf, data);
+
+    let mut section = AutoSectionData::new_with_threshold(16);
+    section.write_all(&data).unwrap();
+    assert!(section.as_slice().is_none());
+    assert_eq!(section.into_vec().unwrap(), data);
+
+    let borrowed = BorrowedSection::new(&data);
+    assert_eq!(
+        SectionData::as_slice(&borrowed).unwrap().as_ptr(),
+        data.as_ptr()
+    );
+
+    // Tables are decoded directly from loaded sections.
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = container.create_section(SectionHeaderBuilder::new());
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(&data)
+        .unwrap();
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.seek(SeekFrom::Start(0)).unwrap();
+    let mut container = Container::open(buf).unwrap();
+    let handle = container.find_section_by_index(0).unwrap();
+    let mut section = container.get_mut(handle);
+    let loaded = section.load().unwrap();
+    assert_eq!(loaded.as_slice().unwrap(), &data[..]);
+}
+
+#[test]
+fn independent_section_cursors()
+{
+    let data = generate_data(20000);
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = container.create_section(SectionHeaderBuilder::new());
+    container.set_memory_threshold(1024);
+    let spilled = container.create_section(SectionHeaderBuilder::new());
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(&data)
+        .unwrap();
+    {
+        let mut section = container.get_mut(spilled);
+        let section = section.open().unwrap();
+        section.spill().unwrap();
+        section.write_all(&data).unwrap();
+        assert!(!section.is_in_memory());
+    }
+
+    for handle in [handle, spilled] {
+        let mut a = container.open_cursor(handle).unwrap();
+        let mut b = container.open_cursor(handle).unwrap();
+        b.seek(SeekFrom::Start(10000)).unwrap();
+        let mut buf = [0; 100];
+        for i in 0..10 {
+            a.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf[..], &data[i * 100..(i + 1) * 100]);
+            b.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf[..], &data[10000 + i * 100..10000 + (i + 1) * 100]);
+        }
+        assert_eq!(a.position(), 1000);
+        b.seek(SeekFrom::End(-10)).unwrap();
+        let mut end = Vec::new();
+        b.read_to_end(&mut end).unwrap();
+        assert_eq!(end, &data[data.len() - 10..]);
+    }
+    // Cursors do not move the cursor of the section itself.
+    let mut section = container.get_mut(handle);
+    assert_eq!(
+        section.open().unwrap().stream_position().unwrap(),
+        data.len() as u64
+    );
+}
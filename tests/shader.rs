@@ -0,0 +1,269 @@
+#![cfg(feature = "shader")]
+
+use std::io::Write;
+
+use bpx::{
+    core::{
+        builder::{Checksum, CompressionMethod, SectionHeaderBuilder},
+        header::{FLAG_CHECK_CRC32, FLAG_CHECK_WEAK, FLAG_COMPRESS_ZLIB, SECTION_TYPE_STRING},
+        Container
+    },
+    sd::{Object, Value},
+    shader::{
+        error::{InvalidCodeContext, ReadError, WriteError},
+        symbol,
+        Builder,
+        Shader,
+        ShaderPack,
+        Stage,
+        SECTION_TYPE_EXTENDED_DATA,
+        SECTION_TYPE_SHADER,
+        SECTION_TYPE_SYMBOL_TABLE
+    },
+    utils::new_byte_buf
+};
+
+#[test]
+fn custom_section_headers()
+{
+    let mut bpxs = ShaderPack::create(
+        new_byte_buf(0),
+        Builder::new()
+            .shader_header(
+                SectionHeaderBuilder::new()
+                    .compression(CompressionMethod::Zlib)
+                    .threshold(0)
+                    .checksum(Checksum::Weak)
+            )
+            .symbol_table_header(SectionHeaderBuilder::new().ty(7).checksum(Checksum::Crc32))
+            .string_header(SectionHeaderBuilder::new().checksum(Checksum::Crc32))
+    );
+    bpxs.add_symbol(symbol::Builder::new("test")).unwrap();
+    bpxs.add_shader(Shader {
+        stage: Stage::Pixel,
+        data: b"shader code".to_vec()
+    })
+    .unwrap();
+    bpxs.save().unwrap();
+    let mut buf = bpxs.into_inner().into_inner();
+    buf.set_position(0);
+
+    let container = Container::open(buf).unwrap();
+    let flags = |ty| {
+        let handle = container.find_section_by_type(ty).unwrap();
+        container.get(handle).flags
+    };
+    assert_eq!(
+        flags(SECTION_TYPE_SHADER),
+        FLAG_COMPRESS_ZLIB | FLAG_CHECK_WEAK
+    );
+    assert_eq!(flags(SECTION_TYPE_SYMBOL_TABLE), FLAG_CHECK_CRC32);
+    assert_eq!(flags(SECTION_TYPE_STRING), FLAG_CHECK_CRC32);
+}
+
+const STAGES: [Stage; 6] = [
+    Stage::Vertex,
+    Stage::Hull,
+    Stage::Domain,
+    Stage::Geometry,
+    Stage::Pixel,
+    Stage::Compute
+];
+
+#[test]
+fn stage_round_trip()
+{
+    let mut bpxs = ShaderPack::create(new_byte_buf(0), Builder::new());
+    for (i, stage) in STAGES.iter().enumerate() {
+        bpxs.add_stage(*stage, &vec![i as u8; i + 1]).unwrap();
+    }
+    bpxs.save().unwrap();
+    let mut buf = bpxs.into_inner().into_inner();
+    buf.set_position(0);
+
+    let mut bpxs = ShaderPack::open(buf).unwrap();
+    assert_eq!(bpxs.list_shaders().len(), STAGES.len());
+    for (i, stage) in STAGES.iter().enumerate() {
+        let shader = bpxs.stage(*stage).unwrap().unwrap();
+        assert_eq!(shader.stage, *stage);
+        assert_eq!(shader.code, vec![i as u8; i + 1]);
+        assert_eq!(bpxs.load_shader(shader.handle).unwrap().data, shader.code);
+    }
+}
+
+#[test]
+fn duplicate_stage()
+{
+    let mut bpxs = ShaderPack::create(new_byte_buf(0), Builder::new());
+    bpxs.add_stage(Stage::Pixel, b"first").unwrap();
+    assert!(matches!(
+        bpxs.add_shader(Shader {
+            stage: Stage::Pixel,
+            data: b"second".to_vec()
+        }),
+        Err(WriteError::DuplicateStage(Stage::Pixel))
+    ));
+    assert_eq!(bpxs.list_shaders().len(), 1);
+
+    let mut bpxs = ShaderPack::create(new_byte_buf(0), Builder::new().replace_stages(true));
+    bpxs.add_stage(Stage::Pixel, b"first").unwrap();
+    bpxs.add_stage(Stage::Pixel, b"second").unwrap();
+    assert_eq!(bpxs.list_shaders().len(), 1);
+    bpxs.save().unwrap();
+    let mut buf = bpxs.into_inner().into_inner();
+    buf.set_position(0);
+    let mut bpxs = ShaderPack::open(buf).unwrap();
+    assert_eq!(bpxs.stage(Stage::Pixel).unwrap().unwrap().code, b"second");
+}
+
+#[test]
+fn unknown_stage_code()
+{
+    let mut bpxs = ShaderPack::create(new_byte_buf(0), Builder::new());
+    bpxs.save().unwrap();
+    let mut container = bpxs.into_inner();
+    let handle = container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_SHADER));
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(&[0x42, 0x0])
+        .unwrap();
+    container.save().unwrap();
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+
+    let err = ShaderPack::open(buf).err().unwrap();
+    assert!(matches!(
+        err,
+        ReadError::InvalidCode(InvalidCodeContext::Stage, 0x42)
+    ));
+    assert_eq!(err.to_string(), "invalid stage code (66)");
+}
+
+fn reopen(bpxs: ShaderPack<std::io::Cursor<Vec<u8>>>) -> ShaderPack<std::io::Cursor<Vec<u8>>>
+{
+    let mut buf = bpxs.into_inner().into_inner();
+    buf.set_position(0);
+    ShaderPack::open(buf).unwrap()
+}
+
+#[test]
+fn symbols_without_extended_data()
+{
+    let mut bpxs = ShaderPack::create(new_byte_buf(0), Builder::new());
+    bpxs.add_symbol(symbol::Builder::new("plain").register(1))
+        .unwrap();
+    bpxs.save().unwrap();
+    let container = bpxs.into_inner();
+    assert!(container
+        .find_section_by_type(SECTION_TYPE_EXTENDED_DATA)
+        .is_none());
+}
+
+#[test]
+fn edit_symbols()
+{
+    let mut bpxs = ShaderPack::create(new_byte_buf(0), Builder::new());
+    let mut obj = Object::new();
+    obj.set("semantic", Value::from("POSITION"));
+    bpxs.add_symbol(symbol::Builder::new("first").extended_data(obj))
+        .unwrap();
+    bpxs.add_symbol(symbol::Builder::new("second").register(3))
+        .unwrap();
+    bpxs.save().unwrap();
+
+    let mut bpxs = reopen(bpxs);
+    assert_eq!(bpxs.get_symbol_count(), 2);
+    let symbols = bpxs.symbols_mut().unwrap();
+    assert_eq!(symbols.try_lookup("second").unwrap().unwrap().register, 3);
+    let mut obj = Object::new();
+    obj.set("semantic", Value::from("COLOR"));
+    bpxs.add_symbol(symbol::Builder::new("third").extended_data(obj))
+        .unwrap();
+    bpxs.save().unwrap();
+
+    let mut bpxs = reopen(bpxs);
+    assert_eq!(bpxs.get_symbol_count(), 3);
+    let mut names = Vec::new();
+    let mut semantics = Vec::new();
+    for mut sym in bpxs.symbols().unwrap() {
+        names.push(sym.load_name().unwrap().to_string());
+        if sym.flags & symbol::FLAG_EXTENDED_DATA != 0 {
+            let obj = sym.load_extended_data().unwrap();
+            let semantic: &str = obj.get("semantic").unwrap().try_into().unwrap();
+            semantics.push(semantic.to_string());
+        }
+    }
+    assert_eq!(names, vec!["first", "second", "third"]);
+    assert_eq!(semantics, vec!["POSITION", "COLOR"]);
+    assert_eq!(
+        bpxs.symbols_mut()
+            .unwrap()
+            .try_lookup("third")
+            .unwrap()
+            .unwrap()
+            .flags
+            & symbol::FLAG_EXTENDED_DATA,
+        symbol::FLAG_EXTENDED_DATA
+    );
+
+    assert!(bpxs.remove_symbol("first").unwrap());
+    assert!(!bpxs.remove_symbol("missing").unwrap());
+    bpxs.save().unwrap();
+    let mut bpxs = reopen(bpxs);
+    assert_eq!(bpxs.get_symbol_count(), 2);
+    let symbols = bpxs.symbols_mut().unwrap();
+    assert!(symbols.try_lookup("first").unwrap().is_none());
+    assert!(symbols.try_lookup("third").unwrap().is_some());
+}
+
+#[test]
+fn shaders_stay_unloaded_after_open()
+{
+    let mut bpxs = ShaderPack::create(new_byte_buf(0), Builder::new());
+    bpxs.add_stage(Stage::Vertex, b"vertex").unwrap();
+    bpxs.add_stage(Stage::Pixel, b"pixel").unwrap();
+    bpxs.save().unwrap();
+
+    let bpxs = reopen(bpxs);
+    let shaders = bpxs.list_shaders();
+    assert_eq!(shaders.len(), 2);
+    let container = bpxs.into_inner();
+    for handle in shaders {
+        assert!(container.get(handle).open().is_none());
+    }
+}
+
+#[test]
+fn edit_symbols_through_table()
+{
+    let mut bpxs = ShaderPack::create(new_byte_buf(0), Builder::new());
+    for name in ["first", "second", "third"] {
+        bpxs.add_symbol(symbol::Builder::new(name)).unwrap();
+    }
+    bpxs.save().unwrap();
+
+    let mut bpxs = reopen(bpxs);
+    bpxs.symbols_mut()
+        .unwrap()
+        .remove_by_name("first")
+        .unwrap()
+        .unwrap();
+    assert_eq!(bpxs.get_symbol_count(), 2);
+    bpxs.save().unwrap();
+
+    let mut bpxs = reopen(bpxs);
+    assert_eq!(bpxs.get_symbol_count(), 2);
+    let mut names = Vec::new();
+    for mut sym in bpxs.symbols().unwrap() {
+        names.push(sym.load_name().unwrap().to_string());
+    }
+    assert_eq!(names, vec!["second", "third"]);
+    bpxs.symbols_mut().unwrap().retain(|_| false);
+    bpxs.save().unwrap();
+
+    let mut bpxs = reopen(bpxs);
+    assert_eq!(bpxs.get_symbol_count(), 0);
+    assert_eq!(bpxs.symbols().unwrap().count(), 0);
+}
@@ -0,0 +1,120 @@
+use bpx::{
+    core::header::{MainHeader, SectionHeader, Struct},
+    spec::{self, bpxp, bpxs}
+};
+
+#[test]
+fn core_constants()
+{
+    assert_eq!(spec::core::SIGNATURE, *b"BPX");
+    assert_eq!(spec::core::VERSION, 2);
+    assert_eq!(spec::core::KNOWN_VERSIONS, &[1, 2]);
+    assert_eq!(spec::core::MAIN_HEADER_SIZE, 40);
+    assert_eq!(spec::core::MAIN_HEADER_SIGNATURE_OFFSET, 0);
+    assert_eq!(spec::core::MAIN_HEADER_TYPE_OFFSET, 3);
+    assert_eq!(spec::core::MAIN_HEADER_CHECKSUM_OFFSET, 4);
+    assert_eq!(spec::core::MAIN_HEADER_FILE_SIZE_OFFSET, 8);
+    assert_eq!(spec::core::MAIN_HEADER_SECTION_NUM_OFFSET, 16);
+    assert_eq!(spec::core::MAIN_HEADER_VERSION_OFFSET, 20);
+    assert_eq!(spec::core::MAIN_HEADER_TYPE_EXT_OFFSET, 24);
+    assert_eq!(spec::core::TYPE_EXT_SIZE, 16);
+    assert_eq!(spec::core::SECTION_HEADER_SIZE, 24);
+    assert_eq!(spec::core::SECTION_HEADER_POINTER_OFFSET, 0);
+    assert_eq!(spec::core::SECTION_HEADER_CSIZE_OFFSET, 8);
+    assert_eq!(spec::core::SECTION_HEADER_SIZE_OFFSET, 12);
+    assert_eq!(spec::core::SECTION_HEADER_CHECKSUM_OFFSET, 16);
+    assert_eq!(spec::core::SECTION_HEADER_TYPE_OFFSET, 20);
+    assert_eq!(spec::core::SECTION_HEADER_FLAGS_OFFSET, 21);
+    assert_eq!(spec::core::FLAG_COMPRESS_ZLIB, 0x1);
+    assert_eq!(spec::core::FLAG_COMPRESS_XZ, 0x2);
+    assert_eq!(spec::core::FLAG_CHECK_CRC32, 0x4);
+    assert_eq!(spec::core::FLAG_CHECK_WEAK, 0x8);
+    assert_eq!(spec::core::FLAG_ENCRYPT_AES_GCM, 0x10);
+    assert_eq!(spec::core::SECTION_TYPE_STRING, 0xFF);
+    assert_eq!(spec::core::SECTION_TYPE_SD, 0xFE);
+    assert_eq!(spec::core::TRAILER_MAGIC, *b"BPXT");
+    assert_eq!(spec::core::TRAILER_SIZE, 8);
+}
+
+#[test]
+fn package_constants()
+{
+    assert_eq!(bpxp::TYPE_BYTE, b'P');
+    assert_eq!(bpxp::VERSION, 2);
+    assert_eq!(bpxp::DATA_TYPE, 0x1);
+    assert_eq!(bpxp::OBJECT_TABLE_TYPE, 0x2);
+    assert_eq!(bpxp::TYPE_EXT_ARCHITECTURE_OFFSET, 0);
+    assert_eq!(bpxp::TYPE_EXT_PLATFORM_OFFSET, 1);
+    assert_eq!(bpxp::TYPE_EXT_TYPE_CODE_OFFSET, 2);
+    assert_eq!(bpxp::DEFAULT_TYPE_CODE, *b"PH");
+    assert_eq!(bpxp::OBJECT_HEADER_SIZE, 20);
+    assert_eq!(bpxp::OBJECT_HEADER_SIZE_OFFSET, 0);
+    assert_eq!(bpxp::OBJECT_HEADER_NAME_OFFSET, 8);
+    assert_eq!(bpxp::OBJECT_HEADER_START_OFFSET, 12);
+    assert_eq!(bpxp::OBJECT_HEADER_DATA_OFFSET, 16);
+}
+
+#[test]
+fn shader_constants()
+{
+    assert_eq!(bpxs::TYPE_BYTE, b'S');
+    assert_eq!(bpxs::VERSION, 2);
+    assert_eq!(bpxs::SHADER_TYPE, 0x1);
+    assert_eq!(bpxs::SYMBOL_TABLE_TYPE, 0x2);
+    assert_eq!(bpxs::EXTENDED_DATA_TYPE, 0x3);
+    assert_eq!(bpxs::TYPE_EXT_ASSEMBLY_HASH_OFFSET, 0);
+    assert_eq!(bpxs::TYPE_EXT_NUM_SYMBOLS_OFFSET, 8);
+    assert_eq!(bpxs::TYPE_EXT_TARGET_OFFSET, 10);
+    assert_eq!(bpxs::TYPE_EXT_TYPE_OFFSET, 11);
+    assert_eq!(bpxs::TYPE_ASSEMBLY, b'A');
+    assert_eq!(bpxs::TYPE_PIPELINE, b'P');
+    assert_eq!(bpxs::SYMBOL_SIZE, 12);
+    assert_eq!(bpxs::SYMBOL_NAME_OFFSET, 0);
+    assert_eq!(bpxs::SYMBOL_EXTENDED_DATA_OFFSET, 4);
+    assert_eq!(bpxs::SYMBOL_FLAGS_OFFSET, 8);
+    assert_eq!(bpxs::SYMBOL_TYPE_OFFSET, 10);
+    assert_eq!(bpxs::SYMBOL_REGISTER_OFFSET, 11);
+    assert_eq!(bpxs::NO_EXTENDED_DATA, 0xFFFFFF);
+    assert_eq!(bpxs::NO_REGISTER, 0xFF);
+}
+
+#[test]
+fn headers_follow_spec_offsets()
+{
+    let mut header = MainHeader::new();
+    header.ty = bpxp::TYPE_BYTE;
+    header.file_size = 0x0102030405060708;
+    header.section_num = 3;
+    header.type_ext[bpxp::TYPE_EXT_TYPE_CODE_OFFSET] = 0x42;
+    let bytes = header.to_bytes();
+    assert_eq!(
+        bytes[..spec::core::MAIN_HEADER_TYPE_OFFSET],
+        spec::core::SIGNATURE
+    );
+    assert_eq!(bytes[spec::core::MAIN_HEADER_TYPE_OFFSET], bpxp::TYPE_BYTE);
+    assert_eq!(bytes[spec::core::MAIN_HEADER_FILE_SIZE_OFFSET], 0x08);
+    assert_eq!(bytes[spec::core::MAIN_HEADER_SECTION_NUM_OFFSET], 3);
+    assert_eq!(
+        bytes[spec::core::MAIN_HEADER_VERSION_OFFSET],
+        spec::core::VERSION as u8
+    );
+    assert_eq!(
+        bytes[spec::core::MAIN_HEADER_TYPE_EXT_OFFSET + bpxp::TYPE_EXT_TYPE_CODE_OFFSET],
+        0x42
+    );
+
+    let mut section = SectionHeader::new();
+    section.ty = spec::core::SECTION_TYPE_SD;
+    section.flags = spec::core::FLAG_CHECK_CRC32;
+    section.size = 7;
+    let bytes = section.to_bytes();
+    assert_eq!(bytes[spec::core::SECTION_HEADER_SIZE_OFFSET], 7);
+    assert_eq!(
+        bytes[spec::core::SECTION_HEADER_TYPE_OFFSET],
+        spec::core::SECTION_TYPE_SD
+    );
+    assert_eq!(
+        bytes[spec::core::SECTION_HEADER_FLAGS_OFFSET],
+        spec::core::FLAG_CHECK_CRC32
+    );
+}
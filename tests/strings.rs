@@ -0,0 +1,176 @@
+#![cfg(feature = "strings")]
+
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use bpx::{
+    core::{
+        builder::{MainHeaderBuilder, SectionHeaderBuilder},
+        Container,
+        SectionData
+    },
+    strings::{ReadError, StringSection},
+    utils::new_byte_buf
+};
+
+#[test]
+fn get_preserves_section_cursor()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = container.create_section(SectionHeaderBuilder::new());
+    let mut writer = StringSection::new(handle);
+    let first = writer.put(&mut container, "first").unwrap();
+    let second = writer.put(&mut container, "second").unwrap();
+
+    // Sequential reader positioned at the start of the second string.
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .seek(SeekFrom::Start(second as u64))
+        .unwrap();
+    let mut strings = StringSection::new(handle);
+    assert_eq!(strings.get(&mut container, first).unwrap(), "first");
+    let mut buf = [0; 6];
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .read_exact(&mut buf)
+        .unwrap();
+    assert_eq!(&buf, b"second");
+
+    // Interleave the other way around: the sequential reader is now on the first string.
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .seek(SeekFrom::Start(first as u64))
+        .unwrap();
+    assert_eq!(strings.get(&mut container, second).unwrap(), "second");
+    let mut buf = [0; 5];
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .read_exact(&mut buf)
+        .unwrap();
+    assert_eq!(&buf, b"first");
+}
+
+#[test]
+fn put_dedup_reuses_strings()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = container.create_section(SectionHeaderBuilder::new());
+    let mut strings = StringSection::new(handle);
+    let first = strings.put(&mut container, "first").unwrap();
+    let empty = strings.put(&mut container, "").unwrap();
+    let second = strings.put_dedup(&mut container, "second").unwrap();
+    assert_eq!(strings.put_dedup(&mut container, "first").unwrap(), first);
+    assert_eq!(strings.put_dedup(&mut container, "second").unwrap(), second);
+    assert_eq!(strings.put_dedup(&mut container, "").unwrap(), empty);
+    let third = strings.put(&mut container, "third").unwrap();
+    assert_eq!(strings.put_dedup(&mut container, "third").unwrap(), third);
+    let size = container.get_mut(handle).open().unwrap().size();
+    container.save().unwrap();
+
+    // Strings persisted by a previous session are also reused.
+    let mut buf = container.into_inner();
+    buf.set_position(0);
+    let mut container = Container::open(buf).unwrap();
+    let handle = container.find_section_by_index(0).unwrap();
+    container.get_mut(handle).load().unwrap();
+    let mut strings = StringSection::new(handle);
+    assert_eq!(strings.put_dedup(&mut container, "second").unwrap(), second);
+    assert_eq!(strings.put_dedup(&mut container, "third").unwrap(), third);
+    assert_eq!(container.get_mut(handle).open().unwrap().size(), size);
+    let fourth = strings.put_dedup(&mut container, "fourth").unwrap();
+    assert_eq!(fourth as usize, size);
+    assert_eq!(strings.get(&mut container, fourth).unwrap(), "fourth");
+}
+
+#[test]
+fn iter_strings()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = container.create_section(SectionHeaderBuilder::new());
+    let mut strings = StringSection::new(handle);
+    let first = strings.put(&mut container, "first").unwrap();
+    let empty = strings.put(&mut container, "").unwrap();
+    let mut section = container.get_mut(handle);
+    let data = section.open().unwrap();
+    data.write_all(&[0xFF, 0xFE, 0x0]).unwrap();
+    let last = strings.put(&mut container, "last").unwrap();
+    let all: Vec<_> = strings.iter(&mut container).collect();
+    assert_eq!(all.len(), 4);
+    assert_eq!(all[0].as_ref().unwrap(), &(first, "first".into()));
+    assert_eq!(all[1].as_ref().unwrap(), &(empty, "".into()));
+    assert!(matches!(all[2], Err(ReadError::Utf8)));
+    assert_eq!(all[3].as_ref().unwrap(), &(last, "last".into()));
+
+    // An unterminated string at the end of the section is reported once.
+    container
+        .get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(b"trunc")
+        .unwrap();
+    let all: Vec<_> = strings.iter(&mut container).collect();
+    assert_eq!(all.len(), 5);
+    assert!(matches!(all[4], Err(ReadError::Eos)));
+}
+
+#[test]
+fn compact_strings()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = container.create_section(SectionHeaderBuilder::new());
+    let mut strings = StringSection::new(handle);
+    let a = strings.put(&mut container, "a").unwrap();
+    let dead = strings.put(&mut container, "dead").unwrap();
+    let b = strings.put(&mut container, "bb").unwrap();
+    let c = strings.put(&mut container, "ccc").unwrap();
+    let remap = strings.compact(&mut container, &[c, a, c, b]).unwrap();
+    assert_eq!(remap.len(), 3);
+    assert!(!remap.contains_key(&dead));
+    assert_eq!(remap[&a], 0);
+    assert_eq!(remap[&b], 2);
+    assert_eq!(remap[&c], 5);
+    assert_eq!(container.get(handle).open().unwrap().size(), 9);
+    let mut fresh = StringSection::new(handle);
+    assert_eq!(fresh.get(&mut container, remap[&b]).unwrap(), "bb");
+    assert_eq!(fresh.get(&mut container, remap[&c]).unwrap(), "ccc");
+
+    // New strings are appended after the compacted ones.
+    let d = strings.put(&mut container, "d").unwrap();
+    assert_eq!(d, 9);
+    let all: Vec<_> = strings.iter(&mut container).map(|v| v.unwrap().1).collect();
+    assert_eq!(all, vec!["a", "bb", "ccc", "d"]);
+}
+
+#[test]
+fn preload_strings()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = container.create_section(SectionHeaderBuilder::new());
+    let mut strings = StringSection::new(handle);
+    let addresses: Vec<u32> = (0..1000)
+        .map(|i| strings.put(&mut container, &format!("name{}", i)).unwrap())
+        .collect();
+    let mut section = container.get_mut(handle);
+    let data = section.open().unwrap();
+    let bad = data.size() as u32;
+    data.write_all(&[0xFF, 0x0]).unwrap();
+
+    let mut strings = StringSection::new(handle);
+    strings.preload(&mut container).unwrap();
+    // Erase the section: strings must now be served from the cache.
+    container.get_mut(handle).open().unwrap().clear();
+    for (i, address) in addresses.into_iter().enumerate() {
+        assert_eq!(
+            strings.get(&mut container, address).unwrap(),
+            format!("name{}", i)
+        );
+    }
+    assert!(strings.get(&mut container, bad).is_err());
+}
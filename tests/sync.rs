@@ -0,0 +1,141 @@
+#![cfg(feature = "sync")]
+
+use std::io::{Read, Write};
+
+use bpx::{
+    core::{
+        builder::{CompressionMethod, MainHeaderBuilder, SectionHeaderBuilder},
+        error::{CorruptionContext, ReadError},
+        header::SIZE_MAIN_HEADER,
+        Container,
+        SyncContainer
+    },
+    utils::new_byte_buf
+};
+
+fn sample_container() -> SyncContainer<std::io::Cursor<Vec<u8>>>
+{
+    let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new().ty(b'S'));
+    let first = file.create_section(SectionHeaderBuilder::new().ty(1));
+    let second = file.create_section(
+        SectionHeaderBuilder::new()
+            .ty(2)
+            .compression(CompressionMethod::Zlib)
+            .threshold(0)
+    );
+    file.get_mut(first)
+        .open()
+        .unwrap()
+        .write_all(b"first section")
+        .unwrap();
+    file.get_mut(second)
+        .open()
+        .unwrap()
+        .write_all(&[42; 4096])
+        .unwrap();
+    file.save().unwrap();
+    let mut backend = file.into_inner();
+    backend.set_position(0);
+    SyncContainer::open(backend).unwrap()
+}
+
+fn read_section(file: &SyncContainer<std::io::Cursor<Vec<u8>>>, ty: u8) -> Vec<u8>
+{
+    let handle = file.find_section_by_type(ty).unwrap();
+    let mut section = file.load(handle).unwrap();
+    let mut buf = Vec::new();
+    section.read_to_end(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn load_sections_concurrently()
+{
+    let file = sample_container();
+    assert_eq!(file.len(), 2);
+    let types: Vec<u8> = file.iter().map(|(_, header)| header.ty).collect();
+    assert_eq!(types, vec![1, 2]);
+    std::thread::scope(|s| {
+        let first = s.spawn(|| read_section(&file, 1));
+        let second = s.spawn(|| read_section(&file, 2));
+        assert_eq!(first.join().unwrap(), b"first section");
+        assert_eq!(second.join().unwrap(), vec![42; 4096]);
+    });
+    let handle = file.find_section_by_type(1).unwrap();
+    assert!(file.get(handle).unwrap().is_some());
+    file.unload(handle).unwrap();
+    assert!(file.get(handle).unwrap().is_none());
+}
+
+#[test]
+fn same_section_in_use()
+{
+    let file = sample_container();
+    let handle = file.find_section_by_type(1).unwrap();
+    let section = file.load(handle).unwrap();
+    assert_eq!(section.handle(), handle);
+    assert_eq!(section.index(), 0);
+    std::thread::scope(|s| {
+        let res = s.spawn(|| file.load(handle).map(|_| ())).join().unwrap();
+        match res {
+            Err(ReadError::SectionInUse(h)) => assert_eq!(h, handle),
+            _ => panic!("expected SectionInUse")
+        }
+        assert!(matches!(
+            s.spawn(|| file.get(handle).map(|_| ())).join().unwrap(),
+            Err(ReadError::SectionInUse(_))
+        ));
+    });
+    drop(section);
+    assert!(file.load(handle).is_ok());
+}
+
+#[test]
+fn try_header_of_unknown_handle()
+{
+    let file = sample_container();
+    let first = file.find_section_by_type(1).unwrap();
+    assert_eq!(file.try_header(first).unwrap().ty, 1);
+    assert_eq!(file.try_index(first).unwrap(), 0);
+    let unknown = unsafe { bpx::Handle::from_raw(42) };
+    assert!(matches!(
+        file.try_header(unknown),
+        Err(ReadError::InvalidHandle(_))
+    ));
+    assert!(matches!(
+        file.try_index(unknown),
+        Err(ReadError::InvalidHandle(_))
+    ));
+}
+
+/// Makes the first section claim `csize` bytes, keeping the header checksum valid.
+fn lie_about_csize(buf: &mut [u8], csize: u32)
+{
+    let start = SIZE_MAIN_HEADER + bpx::spec::core::SECTION_HEADER_CSIZE_OFFSET;
+    let sum = |bytes: &[u8]| bytes.iter().map(|v| *v as u32).sum::<u32>();
+    let old = sum(&buf[start..start + 4]);
+    buf[start..start + 4].copy_from_slice(&csize.to_le_bytes());
+    let chksum = u32::from_le_bytes(buf[4..8].try_into().unwrap())
+        .wrapping_add(sum(&buf[start..start + 4]))
+        .wrapping_sub(old);
+    buf[4..8].copy_from_slice(&chksum.to_le_bytes());
+}
+
+#[test]
+fn sync_rejects_section_past_end()
+{
+    let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let handle = file.create_section(SectionHeaderBuilder::new());
+    file.get_mut(handle)
+        .open()
+        .unwrap()
+        .write_all(b"data")
+        .unwrap();
+    file.save().unwrap();
+    let mut buf = file.into_inner().into_inner();
+    lie_about_csize(&mut buf, u32::MAX);
+    assert!(matches!(
+        SyncContainer::open(std::io::Cursor::new(buf)),
+        Err(ReadError::Corrupted(CorruptionContext::SectionPastEnd(0)))
+    ));
+}
@@ -0,0 +1,191 @@
+#![cfg(feature = "sync")]
+
+use std::{
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Barrier
+    }
+};
+
+use bpx::{
+    core::{
+        builder::{Checksum, CompressionMethod, MainHeaderBuilder, SectionHeaderBuilder},
+        error::ReadError,
+        Container,
+        SectionData,
+        SyncContainer
+    },
+    utils::new_byte_buf
+};
+
+const SECTIONS: u8 = 16;
+const THREADS: usize = 8;
+
+fn section_data(ty: u8) -> Vec<u8>
+{
+    (0..1024 + ty as usize * 64)
+        .map(|i| (i as u8).wrapping_mul(ty))
+        .collect()
+}
+
+fn sample_container() -> SyncContainer<Cursor<Vec<u8>>>
+{
+    let mut file = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    for ty in 0..SECTIONS {
+        let mut builder = SectionHeaderBuilder::new();
+        builder.ty(ty).checksum(Checksum::Crc32);
+        if ty % 2 == 0 {
+            builder.compression(CompressionMethod::Zlib).threshold(0);
+        }
+        let handle = file.create_section(&mut builder);
+        file.get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&section_data(ty))
+            .unwrap();
+    }
+    file.save().unwrap();
+    let mut backend = file.into_inner();
+    backend.set_position(0);
+    SyncContainer::open(backend).unwrap()
+}
+
+fn read_section(file: &SyncContainer<Cursor<Vec<u8>>>, ty: u8) -> Result<Vec<u8>, ReadError>
+{
+    let handle = file.find_section_by_type(ty).unwrap();
+    let mut section = file.load(handle)?;
+    let mut buf = Vec::new();
+    section.seek(SeekFrom::Start(0)).unwrap();
+    section.read_to_end(&mut buf).unwrap();
+    Ok(buf)
+}
+
+#[test]
+fn threads_load_disjoint_sections()
+{
+    let file = sample_container();
+    let barrier = Barrier::new(THREADS);
+    std::thread::scope(|s| {
+        for t in 0..THREADS {
+            let (file, barrier) = (&file, &barrier);
+            s.spawn(move || {
+                barrier.wait();
+                for ty in (0..SECTIONS).filter(|ty| *ty as usize % THREADS == t) {
+                    assert_eq!(read_section(file, ty).unwrap(), section_data(ty));
+                }
+            });
+        }
+    });
+    for (handle, _) in file.iter() {
+        assert!(file.get(handle).unwrap().is_some());
+    }
+}
+
+#[test]
+fn threads_race_on_same_section()
+{
+    let file = sample_container();
+    let handle = file.find_section_by_type(3).unwrap();
+    let barrier = Barrier::new(THREADS);
+    let loaded = AtomicUsize::new(0);
+    std::thread::scope(|s| {
+        for _ in 0..THREADS {
+            let (file, barrier, loaded) = (&file, &barrier, &loaded);
+            s.spawn(move || {
+                barrier.wait();
+                for _ in 0..64 {
+                    match read_section(file, 3) {
+                        Ok(data) => {
+                            assert_eq!(data, section_data(3));
+                            loaded.fetch_add(1, Ordering::Relaxed);
+                        },
+                        Err(ReadError::SectionInUse(h)) => assert_eq!(h, handle),
+                        Err(e) => panic!("unexpected error: {}", e)
+                    }
+                }
+            });
+        }
+    });
+    assert!(loaded.load(Ordering::Relaxed) > 0);
+    //All locks are released once every thread is done
+    assert!(file.get(handle).unwrap().is_some());
+    file.unload(handle).unwrap();
+}
+
+#[test]
+fn load_and_unload_race()
+{
+    let file = sample_container();
+    let barrier = Barrier::new(THREADS);
+    std::thread::scope(|s| {
+        for t in 0..THREADS {
+            let (file, barrier) = (&file, &barrier);
+            s.spawn(move || {
+                barrier.wait();
+                for i in 0..64 {
+                    let ty = ((t + i) % SECTIONS as usize) as u8;
+                    let handle = file.find_section_by_type(ty).unwrap();
+                    if i % 3 == 0 {
+                        match file.unload(handle) {
+                            Ok(()) | Err(ReadError::SectionInUse(_)) => (),
+                            Err(e) => panic!("unexpected error: {}", e)
+                        }
+                        continue;
+                    }
+                    match read_section(file, ty) {
+                        Ok(data) => assert_eq!(data, section_data(ty)),
+                        Err(ReadError::SectionInUse(_)) => (),
+                        Err(e) => panic!("unexpected error: {}", e)
+                    }
+                }
+            });
+        }
+    });
+    for ty in 0..SECTIONS {
+        assert_eq!(read_section(&file, ty).unwrap(), section_data(ty));
+    }
+}
+
+#[test]
+fn headers_readable_while_sections_held()
+{
+    let file = sample_container();
+    //Hold every other section for the whole test: headers must stay readable.
+    let held: Vec<_> = file
+        .iter()
+        .filter(|(_, header)| header.ty % 2 == 0)
+        .map(|(handle, _)| file.load(handle).unwrap())
+        .collect();
+    let barrier = Barrier::new(2);
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            barrier.wait();
+            for _ in 0..256 {
+                let types: Vec<u8> = file.iter().map(|(_, header)| header.ty).collect();
+                assert_eq!(types, (0..SECTIONS).collect::<Vec<u8>>());
+                for (handle, header) in file.iter() {
+                    assert_eq!(file.try_header(handle).unwrap().ty, header.ty);
+                    assert_eq!(file.try_index(handle).unwrap(), header.ty as u32);
+                }
+            }
+        });
+        s.spawn(|| {
+            barrier.wait();
+            for ty in (0..SECTIONS).filter(|ty| ty % 2 == 1) {
+                assert_eq!(read_section(&file, ty).unwrap(), section_data(ty));
+            }
+            for ty in (0..SECTIONS).filter(|ty| ty % 2 == 0) {
+                assert!(matches!(
+                    read_section(&file, ty),
+                    Err(ReadError::SectionInUse(_))
+                ));
+            }
+        });
+    });
+    for section in &held {
+        assert_eq!(section.size(), section_data(section.header().ty).len());
+    }
+    drop(held);
+    assert_eq!(read_section(&file, 0).unwrap(), section_data(0));
+}
@@ -0,0 +1,267 @@
+#![cfg(feature = "table")]
+
+use bpx::{
+    core::{
+        builder::{MainHeaderBuilder, SectionHeaderBuilder},
+        header::SECTION_TYPE_STRING,
+        Container
+    },
+    strings::StringSection,
+    table::{Item, ItemMut, ItemTable, LookupError},
+    utils::new_byte_buf
+};
+
+#[derive(Clone, Debug, PartialEq)]
+struct Named
+{
+    name: u32,
+    value: u32
+}
+
+impl Item for Named
+{
+    fn get_name_address(&self) -> u32
+    {
+        self.name
+    }
+}
+
+impl ItemMut for Named
+{
+    fn set_name_address(&mut self, address: u32)
+    {
+        self.name = address;
+    }
+}
+
+#[test]
+fn remove_and_rename_items()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let mut strings = StringSection::new(
+        container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_STRING))
+    );
+    let items = ["a", "b", "c"]
+        .iter()
+        .enumerate()
+        .map(|(i, v)| Named {
+            name: strings.put(&mut container, v).unwrap(),
+            value: i as u32
+        })
+        .collect();
+    let mut table = ItemTable::new(items);
+    table
+        .build_lookup_table(&mut container, &mut strings)
+        .unwrap();
+    assert_eq!(table.try_lookup("b").unwrap().unwrap().value, 1);
+
+    assert_eq!(table.remove(1).value, 1);
+    assert!(table.try_lookup("b").unwrap().is_none());
+    assert_eq!(table.try_lookup("c").unwrap().unwrap().value, 2);
+    assert_eq!(table.len(), 2);
+
+    assert!(table
+        .rename(&mut container, &mut strings, "c", "d")
+        .unwrap());
+    assert!(table.try_lookup("c").unwrap().is_none());
+    let item = table.try_lookup("d").unwrap().unwrap();
+    assert_eq!(item.value, 2);
+    assert_eq!(strings.get(&mut container, item.name).unwrap(), "d");
+    assert!(!table
+        .rename(&mut container, &mut strings, "c", "e")
+        .unwrap());
+}
+
+#[test]
+fn remove_duplicate_falls_back()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let mut strings = StringSection::new(
+        container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_STRING))
+    );
+    let name = strings.put(&mut container, "dup").unwrap();
+    let mut table = ItemTable::new(vec![Named { name, value: 0 }, Named { name, value: 1 }]);
+    table
+        .build_lookup_table(&mut container, &mut strings)
+        .unwrap();
+    assert_eq!(table.try_lookup("dup").unwrap().unwrap().value, 1);
+    table.remove(1);
+    assert_eq!(table.try_lookup("dup").unwrap().unwrap().value, 0);
+}
+
+#[test]
+fn lookup_without_table()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let mut strings = StringSection::new(
+        container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_STRING))
+    );
+    let name = strings.put(&mut container, "item").unwrap();
+    let mut table = ItemTable::new(vec![Named { name, value: 42 }]);
+    assert_eq!(table.try_lookup("item").unwrap_err(), LookupError::NotBuilt);
+    let item = table
+        .get_or_build_lookup(&mut container, &mut strings, "item")
+        .unwrap();
+    assert_eq!(item.unwrap().value, 42);
+    assert!(table
+        .get_or_build_lookup(&mut container, &mut strings, "other")
+        .unwrap()
+        .is_none());
+    assert!(table.try_lookup("item").unwrap().is_some());
+}
+
+#[test]
+fn remove_items_by_name()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let mut strings = StringSection::new(
+        container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_STRING))
+    );
+    let items = ["first", "middle", "last"]
+        .iter()
+        .enumerate()
+        .map(|(i, v)| Named {
+            name: strings.put(&mut container, v).unwrap(),
+            value: i as u32
+        })
+        .collect();
+    let mut table = ItemTable::new(items);
+    assert_eq!(
+        table.remove_by_name("middle").unwrap_err(),
+        LookupError::NotBuilt
+    );
+    table
+        .build_lookup_table(&mut container, &mut strings)
+        .unwrap();
+    assert_eq!(table.remove_by_name("middle").unwrap().unwrap().value, 1);
+    assert!(table.remove_by_name("middle").unwrap().is_none());
+    assert!(table.try_lookup("middle").unwrap().is_none());
+    assert_eq!(table.try_lookup("first").unwrap().unwrap().value, 0);
+    assert_eq!(table.try_lookup("last").unwrap().unwrap().value, 2);
+    assert_eq!(table.len(), 2);
+    assert_eq!(table[1].value, 2);
+}
+
+#[test]
+fn push_to_empty_table()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let mut strings = StringSection::new(
+        container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_STRING))
+    );
+    let mut table = ItemTable::empty();
+    assert!(table.try_lookup("item").unwrap().is_none());
+    let name = strings.put(&mut container, "item").unwrap();
+    table
+        .push(&mut container, &mut strings, Named { name, value: 7 })
+        .unwrap();
+    assert_eq!(table.try_lookup("item").unwrap().unwrap().value, 7);
+    table
+        .push(&mut container, &mut strings, Named { name, value: 8 })
+        .unwrap();
+    assert_eq!(table.try_lookup("item").unwrap().unwrap().value, 8);
+    assert_eq!(table.len(), 2);
+}
+
+#[test]
+fn push_without_lookup()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let mut strings = StringSection::new(
+        container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_STRING))
+    );
+    let name = strings.put(&mut container, "item").unwrap();
+    let mut table = ItemTable::new(Vec::new());
+    table
+        .push(&mut container, &mut strings, Named { name, value: 1 })
+        .unwrap();
+    assert_eq!(table.try_lookup("item").unwrap_err(), LookupError::NotBuilt);
+    table
+        .build_lookup_table(&mut container, &mut strings)
+        .unwrap();
+    assert_eq!(table.try_lookup("item").unwrap().unwrap().value, 1);
+}
+
+#[test]
+fn retain_items()
+{
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let mut strings = StringSection::new(
+        container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_STRING))
+    );
+    let items = (0..10)
+        .map(|i| Named {
+            name: strings.put(&mut container, &format!("item{}", i)).unwrap(),
+            value: i
+        })
+        .collect();
+    let mut table = ItemTable::new(items);
+    table
+        .build_lookup_table(&mut container, &mut strings)
+        .unwrap();
+    table.retain(|v| v.value % 2 == 0);
+    assert_eq!(table.len(), 5);
+    let values: Vec<u32> = table.iter().map(|v| v.value).collect();
+    assert_eq!(values, vec![0, 2, 4, 6, 8]);
+    for i in 0..10 {
+        let item = table.try_lookup(&format!("item{}", i)).unwrap();
+        match i % 2 {
+            0 => assert_eq!(item.unwrap().value, i),
+            _ => assert!(item.is_none())
+        }
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "sd"))]
+#[test]
+fn serde_round_trip()
+{
+    use bpx::sd::serde::{Deserializer, EnumSize, Serializer};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Entry
+    {
+        name: u32,
+        value: String
+    }
+
+    impl Item for Entry
+    {
+        fn get_name_address(&self) -> u32
+        {
+            self.name
+        }
+    }
+
+    let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+    let mut strings = StringSection::new(
+        container.create_section(SectionHeaderBuilder::new().ty(SECTION_TYPE_STRING))
+    );
+    let items = ["a", "b"]
+        .iter()
+        .map(|v| Entry {
+            name: strings.put(&mut container, v).unwrap(),
+            value: v.to_uppercase()
+        })
+        .collect();
+    let mut table = ItemTable::new(items);
+    table
+        .build_lookup_table(&mut container, &mut strings)
+        .unwrap();
+    let value = table
+        .serialize(Serializer::new(EnumSize::U8, false))
+        .unwrap();
+    let array: &bpx::sd::Array = (&value).try_into().unwrap();
+    assert_eq!(array.len(), 2);
+
+    let mut table =
+        ItemTable::<Entry>::deserialize(Deserializer::new(EnumSize::U8, value)).unwrap();
+    assert_eq!(table.len(), 2);
+    assert_eq!(table[1].value, "B");
+    assert_eq!(table.try_lookup("a").unwrap_err(), LookupError::NotBuilt);
+    table
+        .build_lookup_table(&mut container, &mut strings)
+        .unwrap();
+    assert_eq!(table.try_lookup("a").unwrap().unwrap().value, "A");
+}
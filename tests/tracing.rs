@@ -0,0 +1,161 @@
+#![cfg(all(feature = "tracing", feature = "package"))]
+
+use std::{
+    fmt::Debug,
+    io::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+        Mutex
+    }
+};
+
+use bpx::{
+    core::{
+        builder::{CompressionMethod, MainHeaderBuilder, SectionHeaderBuilder},
+        Container
+    },
+    package::{Builder, Package},
+    utils::new_byte_buf
+};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event,
+    Metadata,
+    Subscriber
+};
+
+#[derive(Debug)]
+struct SpanRecord
+{
+    name: &'static str,
+    fields: Vec<(&'static str, String)>
+}
+
+struct FieldVisitor<'a>(&'a mut Vec<(&'static str, String)>);
+
+impl<'a> Visit for FieldVisitor<'a>
+{
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug)
+    {
+        self.0.push((field.name(), format!("{:?}", value)));
+    }
+}
+
+#[derive(Clone, Default)]
+struct Collector
+{
+    spans: Arc<Mutex<Vec<SpanRecord>>>,
+    next_id: Arc<AtomicU64>
+}
+
+impl Collector
+{
+    fn find(&self, name: &str) -> Vec<Vec<(&'static str, String)>>
+    {
+        self.spans
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|v| v.name == name)
+            .map(|v| v.fields.clone())
+            .collect()
+    }
+}
+
+impl Subscriber for Collector
+{
+    fn enabled(&self, _: &Metadata<'_>) -> bool
+    {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id
+    {
+        let mut fields = Vec::new();
+        span.record(&mut FieldVisitor(&mut fields));
+        self.spans.lock().unwrap().push(SpanRecord {
+            name: span.metadata().name(),
+            fields
+        });
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+fn field<'a>(fields: &'a [(&'static str, String)], name: &str) -> &'a str
+{
+    &fields.iter().find(|(k, _)| *k == name).unwrap().1
+}
+
+#[test]
+fn container_spans()
+{
+    let collector = Collector::default();
+    tracing::subscriber::with_default(collector.clone(), || {
+        let mut container = Container::create(new_byte_buf(0), MainHeaderBuilder::new());
+        let handle = container.create_section(
+            SectionHeaderBuilder::new()
+                .ty(1)
+                .compression(CompressionMethod::Zlib)
+                .threshold(0)
+        );
+        container
+            .get_mut(handle)
+            .open()
+            .unwrap()
+            .write_all(&[42; 1000])
+            .unwrap();
+        container.save().unwrap();
+        let mut buf = container.into_inner();
+        buf.set_position(0);
+        let mut container = Container::open(buf).unwrap();
+        let handle = container.find_section_by_type(1).unwrap();
+        container.get_mut(handle).load().unwrap();
+    });
+
+    let save = collector.find("bpx::save");
+    assert_eq!(save.len(), 1);
+    assert_eq!(field(&save[0], "sections"), "1");
+    let compress = collector.find("bpx::compress");
+    assert_eq!(compress.len(), 1);
+    assert_eq!(field(&compress[0], "size"), "1000");
+    assert_eq!(field(&compress[0], "compression"), "Zlib");
+    let load = collector.find("bpx::load");
+    assert_eq!(load.len(), 1);
+    assert_eq!(field(&load[0], "index"), "0");
+    assert_eq!(field(&load[0], "size"), "1000");
+    assert_eq!(field(&load[0], "compression"), "Zlib");
+    let decompress = collector.find("bpx::decompress");
+    assert_eq!(decompress.len(), 1);
+    assert_eq!(field(&decompress[0], "compression"), "Zlib");
+}
+
+#[test]
+fn package_spans()
+{
+    let collector = Collector::default();
+    tracing::subscriber::with_default(collector.clone(), || {
+        let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+        package.pack("test", &b"content"[..]).unwrap();
+        let mut out = Vec::new();
+        package.unpack("test", &mut out).unwrap();
+    });
+
+    let pack = collector.find("bpx::package::pack");
+    assert_eq!(pack.len(), 1);
+    assert_eq!(field(&pack[0], "name"), "\"test\"");
+    let unpack = collector.find("bpx::package::unpack");
+    assert_eq!(unpack.len(), 1);
+    assert_eq!(field(&unpack[0], "name"), "\"test\"");
+}
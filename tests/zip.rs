@@ -0,0 +1,67 @@
+#![cfg(feature = "zip")]
+
+use std::{collections::BTreeMap, io::Cursor};
+
+use bpx::{
+    package::{
+        utils::{export_zip, import_zip, ZipCompression},
+        Builder,
+        Package
+    },
+    utils::new_byte_buf
+};
+
+fn generate_data(size: usize) -> Vec<u8>
+{
+    (0..size).map(|i| (i % 251) as u8).collect()
+}
+
+fn read_all<T: std::io::Read + std::io::Seek>(package: &mut Package<T>)
+    -> BTreeMap<String, Vec<u8>>
+{
+    let mut objects = BTreeMap::new();
+    for mut v in package.objects().unwrap() {
+        let name = v.load_name().unwrap().to_string();
+        let mut data = Vec::new();
+        assert_eq!(v.unpack(&mut data).unwrap(), v.size());
+        objects.insert(name, data);
+    }
+    objects
+}
+
+fn round_trip(compression: ZipCompression)
+{
+    let mut package = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    package.pack("root/a.txt", &b"first object"[..]).unwrap();
+    package
+        .pack("root/nested/b.bin", &generate_data(100000)[..])
+        .unwrap();
+    package.pack("root/empty.txt", std::io::empty()).unwrap();
+    package.pack("root/empty/", std::io::empty()).unwrap();
+    package.save().unwrap();
+    let expected = read_all(&mut package);
+
+    let mut zip = Cursor::new(Vec::new());
+    export_zip(&mut package, &mut zip, compression).unwrap();
+    zip.set_position(0);
+
+    let mut imported = Package::create(new_byte_buf(0), Builder::new()).unwrap();
+    import_zip(&mut imported, zip).unwrap();
+    imported.save().unwrap();
+    let mut buf = imported.into_inner().into_inner();
+    buf.set_position(0);
+    let mut imported = Package::open(buf).unwrap();
+    assert_eq!(read_all(&mut imported), expected);
+}
+
+#[test]
+fn zip_round_trip_stored()
+{
+    round_trip(ZipCompression::Stored);
+}
+
+#[test]
+fn zip_round_trip_deflated()
+{
+    round_trip(ZipCompression::Deflated);
+}